@@ -107,6 +107,7 @@ pub async fn node_info(addr: SocketAddr) -> Result<()> {
         "Time since last restart: {:?}",
         Duration::from_secs(node_info.uptime_secs)
     );
+    println!("Peers in routing table: {}", node_info.peer_count);
 
     Ok(())
 }
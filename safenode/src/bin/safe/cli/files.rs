@@ -91,14 +91,16 @@ async fn upload_files(files_path: PathBuf, file_api: &Files, root_dir: &Path) ->
             println!("Storing file {file_name:?} of {} bytes..", bytes.len());
 
             match file_api.upload(bytes).await {
-                Ok(address) => {
-                    // Output address in hex string.
-                    println!(
-                        "Successfully stored file {:?} to {:64x}",
-                        entry.file_name(),
-                        address.name()
-                    );
-                    chunks_to_fetch.push((*address.name(), file_name));
+                Ok(data_map) => {
+                    if let Some(address) = data_map.address() {
+                        // Output address in hex string.
+                        println!(
+                            "Successfully stored file {:?} to {:64x}",
+                            entry.file_name(),
+                            address.name()
+                        );
+                        chunks_to_fetch.push((*address.name(), file_name));
+                    }
                 }
                 Err(error) => {
                     println!(
@@ -14,9 +14,12 @@ use safenode::{
 use sn_dbc::Token;
 
 use clap::Parser;
-use eyre::Result;
+use eyre::{eyre, Result};
 use std::path::Path;
 
+// Env var holding the passphrase the wallet is stored encrypted with, if it is.
+const WALLET_PASSPHRASE_ENV: &str = "SAFE_WALLET_PASSPHRASE";
+
 #[derive(Parser, Debug)]
 pub enum WalletCmds {
     /// Print the address of the wallet.
@@ -39,6 +42,10 @@ pub enum WalletCmds {
         #[clap(name = "to")]
         to: String,
     },
+    /// Encrypt the wallet, along with its main key, with the passphrase
+    /// set in the SAFE_WALLET_PASSPHRASE env var, removing them from disk in plaintext.
+    /// From then on, the wallet can only be used with that env var set.
+    Encrypt,
 }
 
 pub(crate) async fn wallet_cmds(cmds: WalletCmds, client: &Client, root_dir: &Path) -> Result<()> {
@@ -47,26 +54,46 @@ pub(crate) async fn wallet_cmds(cmds: WalletCmds, client: &Client, root_dir: &Pa
         WalletCmds::Balance => balance(root_dir).await?,
         WalletCmds::Deposit => deposit(root_dir).await?,
         WalletCmds::Send { amount, to } => send(amount, to, client, root_dir).await?,
+        WalletCmds::Encrypt => encrypt(root_dir).await?,
     }
     Ok(())
 }
 
+// Loads the wallet, decrypting it with the passphrase in the env var if it's set.
+async fn load_wallet(root_dir: &Path) -> Result<LocalWallet> {
+    let wallet = match std::env::var(WALLET_PASSPHRASE_ENV) {
+        Ok(passphrase) => LocalWallet::load_encrypted_from(root_dir, &passphrase).await?,
+        Err(_) => LocalWallet::load_from(root_dir).await?,
+    };
+    Ok(wallet)
+}
+
+async fn encrypt(root_dir: &Path) -> Result<()> {
+    let passphrase = std::env::var(WALLET_PASSPHRASE_ENV).map_err(|_| {
+        eyre!("The passphrase to encrypt the wallet with must be set in the {WALLET_PASSPHRASE_ENV} env var")
+    })?;
+    let mut wallet = LocalWallet::load_from(root_dir).await?;
+    wallet.store_encrypted(&passphrase).await?;
+    println!("Wallet encrypted.");
+    Ok(())
+}
+
 async fn address(root_dir: &Path) -> Result<()> {
-    let wallet = LocalWallet::load_from(root_dir).await?;
+    let wallet = load_wallet(root_dir).await?;
     let address_hex = hex::encode(wallet.address().to_bytes());
     println!("{address_hex}");
     Ok(())
 }
 
 async fn balance(root_dir: &Path) -> Result<()> {
-    let wallet = LocalWallet::load_from(root_dir).await?;
+    let wallet = load_wallet(root_dir).await?;
     let balance = wallet.balance();
     println!("{balance}");
     Ok(())
 }
 
 async fn deposit(root_dir: &Path) -> Result<()> {
-    let mut wallet = LocalWallet::load_from(root_dir).await?;
+    let mut wallet = load_wallet(root_dir).await?;
 
     let previous_balance = wallet.balance();
 
@@ -98,12 +125,15 @@ async fn send(amount: String, to: String, client: &Client, root_dir: &Path) -> R
         return Ok(());
     }
 
-    let wallet = LocalWallet::load_from(root_dir).await?;
+    let wallet = load_wallet(root_dir).await?;
     let mut wallet_client = WalletClient::new(client.clone(), wallet);
 
     match wallet_client.send(amount, address).await {
-        Ok(new_dbc) => {
+        Ok(receipt) => {
             println!("Sent {amount:?} to {address:?}");
+            if let Some(change_address) = receipt.change_address {
+                println!("Change returned to {change_address:?}");
+            }
             let mut wallet = wallet_client.into_wallet();
             let new_balance = wallet.balance();
 
@@ -113,7 +143,7 @@ async fn send(amount: String, to: String, client: &Client, root_dir: &Path) -> R
                 println!("Successfully stored wallet with new balance {new_balance:?}.");
             }
 
-            wallet.store_created_dbc(new_dbc).await?;
+            wallet.store_created_dbc(receipt.dbc).await?;
             println!("Successfully stored new dbc to wallet dir. It can now be sent to the recipient, using any channel of choice.");
         }
         Err(err) => {
@@ -9,6 +9,7 @@ mod rpc;
 
 use safenode::{
     log::init_node_logging,
+    network::RequestWeights,
     node::{Node, NodeEvent, NodeEventsReceiver},
     peers_acquisition::peers_from_opts_or_env,
 };
@@ -18,6 +19,7 @@ use eyre::{eyre, Error, Result};
 use libp2p::{Multiaddr, PeerId};
 use std::{
     net::{IpAddr, Ipv4Addr, SocketAddr},
+    num::NonZeroUsize,
     path::{Path, PathBuf},
     time::Duration,
 };
@@ -82,6 +84,84 @@ struct Opt {
     /// Enable the admin/ctrl RPC service by providing an IP and port for it to listen on.
     #[clap(long)]
     rpc: Option<SocketAddr>,
+
+    /// Sign the responses sent to peers with the node's key.
+    ///
+    /// It allows the clients to verify the responses have not been tampered with.
+    #[clap(long)]
+    sign_responses: bool,
+
+    /// Minimum number of nodes a chunk has to be stored by before acknowledging it to the uploader.
+    ///
+    /// Defaults to the majority of the close group.
+    #[clap(long)]
+    min_replicas: Option<NonZeroUsize>,
+
+    /// Relay to be reached through by the nodes which cannot connect to this one directly,
+    /// e.g. when it's behind a NAT.
+    ///
+    /// The relay's address must end with its PeerId.
+    #[clap(long, value_name = "MultiAddr")]
+    relay: Option<Multiaddr>,
+
+    /// Number of closest peers a Register cmd accepted by this node is forwarded to.
+    ///
+    /// Defaults to the size of the close group.
+    #[clap(long)]
+    register_replicas: Option<NonZeroUsize>,
+
+    /// Do not discover peers on the local network through mDNS.
+    ///
+    /// The node then relies solely on the provided peers to join the network.
+    #[clap(long)]
+    no_mdns: bool,
+
+    /// Maximum estimated size, in bytes, of all the Registers held in memory.
+    ///
+    /// The least recently used Registers are evicted once over it, and reloaded from disk when needed.
+    ///
+    /// If not provided, the Registers held in memory are not capped.
+    #[clap(long, value_name = "BYTES")]
+    register_cache_max_bytes: Option<usize>,
+
+    /// Load Registers skipping, and logging, any cmd of their log which can't be applied.
+    ///
+    /// Otherwise a single bad cmd makes the whole Register unreadable.
+    #[clap(long)]
+    lenient_register_load: bool,
+
+    /// Weights by which the requests received from peers are prioritised when they come in faster
+    /// than they are handled.
+    ///
+    /// Those are the number of reads, writes and replication requests, respectively, handled in
+    /// each round, e.g. 4,2,1.
+    #[clap(long, value_name = "READS,WRITES,REPLICATION")]
+    request_weights: Option<RequestWeights>,
+
+    /// Maximum number of inbound connections held at once.
+    ///
+    /// The connections coming in beyond it are rejected. If not provided, they are not capped.
+    #[clap(long)]
+    max_inbound_connections: Option<u32>,
+
+    /// Number of seconds a peer whose last connection closed is given to reconnect, before being
+    /// removed from the routing table.
+    ///
+    /// Defaults to 30 seconds.
+    #[clap(long, value_name = "SECONDS")]
+    dead_peer_grace_period: Option<u64>,
+
+    /// Maximum number of record and closest peers queries in flight at once.
+    ///
+    /// New queries are rejected beyond it until some of the pending ones complete. Defaults to 256.
+    #[clap(long)]
+    max_pending_queries: Option<usize>,
+
+    /// Maximum size, in bytes, of the msgs exchanged with peers.
+    ///
+    /// Any larger msg received is rejected before being decoded. Defaults to 500MB.
+    #[clap(long, value_name = "BYTES")]
+    max_msg_size: Option<usize>,
 }
 
 #[derive(Debug)]
@@ -135,6 +215,18 @@ fn main() -> Result<()> {
             opt.rpc,
             &log_dir,
             &root_dir,
+            opt.sign_responses,
+            opt.min_replicas,
+            opt.relay.clone(),
+            opt.register_replicas,
+            opt.no_mdns,
+            opt.register_cache_max_bytes,
+            opt.lenient_register_load,
+            opt.request_weights,
+            opt.max_inbound_connections,
+            opt.dead_peer_grace_period.map(Duration::from_secs),
+            opt.max_pending_queries,
+            opt.max_msg_size,
         ))?;
 
         // actively shut down the runtime
@@ -142,17 +234,47 @@ fn main() -> Result<()> {
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn start_node(
     node_socket_addr: SocketAddr,
     peers: Vec<(PeerId, Multiaddr)>,
     rpc: Option<SocketAddr>,
     log_dir: &str,
     root_dir: &Path,
+    sign_responses: bool,
+    min_replicas: Option<NonZeroUsize>,
+    relay: Option<Multiaddr>,
+    register_replicas: Option<NonZeroUsize>,
+    no_mdns: bool,
+    register_cache_max_bytes: Option<usize>,
+    lenient_register_load: bool,
+    request_weights: Option<RequestWeights>,
+    max_inbound_connections: Option<u32>,
+    dead_peer_grace_period: Option<Duration>,
+    max_pending_queries: Option<usize>,
+    max_msg_size: Option<usize>,
 ) -> Result<()> {
     let started_instant = std::time::Instant::now();
 
     info!("Starting node ...");
-    let running_node = Node::run(node_socket_addr, peers, root_dir).await?;
+    let running_node = Node::run(
+        node_socket_addr,
+        peers,
+        root_dir,
+        sign_responses,
+        min_replicas,
+        relay,
+        register_replicas,
+        no_mdns,
+        register_cache_max_bytes,
+        lenient_register_load,
+        request_weights,
+        max_inbound_connections,
+        dead_peer_grace_period,
+        max_pending_queries,
+        max_msg_size,
+    )
+    .await?;
 
     // Channel to receive node ctrl cmds from RPC service (if enabled), and events monitoring task
     let (ctrl_tx, mut ctrl_rx) = mpsc::channel::<NodeCtrl>(5);
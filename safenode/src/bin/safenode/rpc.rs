@@ -6,7 +6,7 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use safenode::node::RunningNode;
+use safenode::{node::RunningNode, protocol::storage::ChunkAddress};
 
 use super::NodeCtrl;
 
@@ -25,9 +25,11 @@ use tracing::{debug, info, trace};
 use safenode_proto::safe_node_server::{SafeNode, SafeNodeServer};
 use safenode_proto::{
     NetworkInfoRequest, NetworkInfoResponse, NodeEvent, NodeEventsRequest, NodeInfoRequest,
-    NodeInfoResponse, RestartRequest, RestartResponse, StopRequest, StopResponse, UpdateRequest,
+    NodeInfoResponse, PinChunkRequest, PinChunkResponse, RestartRequest, RestartResponse,
+    StopRequest, StopResponse, UnpinChunkRequest, UnpinChunkResponse, UpdateRequest,
     UpdateResponse,
 };
+use xor_name::XorName;
 
 // this includes code generated from .proto files
 mod safenode_proto {
@@ -58,12 +60,21 @@ impl SafeNode for SafeNodeRpcService {
             request.get_ref()
         );
 
+        let state = self
+            .running_node
+            .get_swarm_local_state()
+            .await
+            .map_err(|err| {
+                Status::new(Code::Internal, format!("Failed to get node state: {err}"))
+            })?;
+
         let resp = Response::new(NodeInfoResponse {
             peer_id: self.running_node.peer_id().to_bytes(),
             log_dir: self.log_dir.clone(),
             pid: process::id(),
             bin_version: env!("CARGO_PKG_VERSION").to_string(),
             uptime_secs: self.started_instant.elapsed().as_secs(),
+            peer_count: state.peers_in_routing_table as u64,
         });
 
         Ok(resp)
@@ -187,6 +198,58 @@ impl SafeNode for SafeNodeRpcService {
             )),
         }
     }
+
+    async fn pin_chunk(
+        &self,
+        request: Request<PinChunkRequest>,
+    ) -> Result<Response<PinChunkResponse>, Status> {
+        trace!(
+            "RPC request received at {}: {:?}",
+            self.addr,
+            request.get_ref()
+        );
+
+        let address = chunk_address(&request.get_ref().address)?;
+        match self.running_node.pin_chunk(address).await {
+            Ok(()) => Ok(Response::new(PinChunkResponse {})),
+            Err(err) => Err(Status::new(
+                Code::Internal,
+                format!("Failed to pin the chunk: {err}"),
+            )),
+        }
+    }
+
+    async fn unpin_chunk(
+        &self,
+        request: Request<UnpinChunkRequest>,
+    ) -> Result<Response<UnpinChunkResponse>, Status> {
+        trace!(
+            "RPC request received at {}: {:?}",
+            self.addr,
+            request.get_ref()
+        );
+
+        let address = chunk_address(&request.get_ref().address)?;
+        match self.running_node.unpin_chunk(address).await {
+            Ok(()) => Ok(Response::new(UnpinChunkResponse {})),
+            Err(err) => Err(Status::new(
+                Code::Internal,
+                format!("Failed to unpin the chunk: {err}"),
+            )),
+        }
+    }
+}
+
+// Parses the address of a chunk out of the bytes of its xorname, as sent in an RPC request.
+#[allow(clippy::result_large_err)]
+fn chunk_address(bytes: &[u8]) -> Result<ChunkAddress, Status> {
+    let xorname: [u8; 32] = bytes.try_into().map_err(|_| {
+        Status::new(
+            Code::InvalidArgument,
+            format!("Invalid chunk address of {} bytes", bytes.len()),
+        )
+    })?;
+    Ok(ChunkAddress::new(XorName(xorname)))
 }
 
 pub(super) fn start_rpc_service(
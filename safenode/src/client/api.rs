@@ -13,10 +13,20 @@ use super::{
 
 use crate::{
     domain::client_transfers::SpendRequest,
-    network::{close_group_majority, NetworkEvent, SwarmDriver},
+    network::{
+        close_group_majority, Error as NetworkError, NetworkEvent, SwarmDriver,
+        DEFAULT_MAX_MSG_SIZE,
+    },
     protocol::{
-        messages::{Cmd, CmdResponse, Query, QueryResponse, Request, Response, SpendQuery},
-        storage::{Chunk, ChunkAddress, DbcAddress},
+        error::{Error as ProtocolError, StorageError},
+        messages::{
+            decompress_register_entries, Cmd, CmdResponse, Query, QueryResponse, RegisterQuery,
+            Request, Response, SpendQuery,
+        },
+        storage::{
+            registers::{Entry, EntryHash},
+            Chunk, ChunkAddress, DbcAddress, RegisterAddress,
+        },
         NetworkAddress,
     },
 };
@@ -24,13 +34,17 @@ use crate::{
 use sn_dbc::{DbcId, SignedSpend};
 
 use bls::{PublicKey, SecretKey, Signature};
-use futures::future::select_all;
+use futures::future::{join_all, select_all};
 use itertools::Itertools;
 use libp2p::{kad::RecordKey, Multiaddr, PeerId};
+use std::{collections::BTreeSet, time::Duration};
 use tokio::task::spawn;
 use tracing::trace;
 use xor_name::XorName;
 
+// Default time to wait for the peers to answer with a Register, before giving up on them.
+const DEFAULT_REGISTER_FETCH_TIMEOUT: Duration = Duration::from_secs(10);
+
 impl Client {
     /// Instantiate a new client.
     pub async fn new(signer: SecretKey, peers: Option<Vec<(PeerId, Multiaddr)>>) -> Result<Self> {
@@ -42,6 +56,7 @@ impl Client {
             network: network.clone(),
             events_channel,
             signer,
+            register_fetch_timeout: DEFAULT_REGISTER_FETCH_TIMEOUT,
         };
 
         let mut must_dial_network = true;
@@ -104,6 +119,14 @@ impl Client {
             NetworkEvent::RequestReceived { .. } => {}
             // We do not listen on sockets.
             NetworkEvent::NewListenAddr(_) => {}
+            // We do not hold Registers.
+            NetworkEvent::RegisterUpdated { .. } => {}
+            // The routing table is asked for the peers it holds whenever needed.
+            NetworkEvent::PeersRemoved(_) | NetworkEvent::PeerEvicted(_) => {}
+            // We do not hold any data to replicate.
+            NetworkEvent::ReplicationCandidate { .. } => {}
+            // We do not serve DHT requests.
+            NetworkEvent::InboundDhtRequest { .. } => {}
             NetworkEvent::PeerAdded(peer_id) => {
                 debug!("PeerAdded: {peer_id}");
                 self.events_channel
@@ -114,6 +137,19 @@ impl Client {
         Ok(())
     }
 
+    /// Waits until the client's routing table holds at least `min_peers` peers,
+    /// erroring out if that doesn't happen within the provided `timeout`.
+    pub async fn wait_until_ready(&self, min_peers: usize, timeout: Duration) -> Result<()> {
+        let peers_in_routing_table = self.network.wait_for_peers(min_peers, timeout).await?;
+        if peers_in_routing_table < min_peers {
+            return Err(Error::NotEnoughPeers {
+                peers_in_routing_table,
+                min_peers,
+            });
+        }
+        Ok(())
+    }
+
     /// Get the client events channel.
     pub fn events_channel(&self) -> ClientEventsReceiver {
         self.events_channel.subscribe()
@@ -129,12 +165,54 @@ impl Client {
         self.signer.public_key()
     }
 
+    /// Sets the time to wait for the closest peers, all asked at once, to answer with a Register
+    /// when retrieving it, before giving up on those which haven't answered.
+    pub fn set_register_fetch_timeout(&mut self, timeout: Duration) {
+        self.register_fetch_timeout = timeout;
+    }
+
+    /// Sets whether the responses from peers are rejected unless signed by them, so the data
+    /// they answer with can't be fabricated by a peer which simply doesn't sign its response.
+    pub async fn set_require_signed_responses(&self, require_signed_responses: bool) -> Result<()> {
+        self.network
+            .set_require_signed_responses(require_signed_responses)
+            .await?;
+        Ok(())
+    }
+
     /// Retrieve a Register from the network.
     pub async fn get_register(&self, xorname: XorName, tag: u64) -> Result<Register> {
         info!("Retrieving a Register replica with name {xorname} and tag {tag}");
         Register::retrieve(self.clone(), xorname, tag).await
     }
 
+    /// Read the current entries of a Register from the network, without retrieving the whole
+    /// Register. When `compressed` is set, they are sent compressed, and transparently
+    /// decompressed, for less data to be transferred, e.g. over slow links.
+    pub async fn read_register(
+        &self,
+        address: RegisterAddress,
+        compressed: bool,
+    ) -> Result<BTreeSet<(EntryHash, Entry)>> {
+        info!("Reading the entries of Register {address:?}, compressed: {compressed}");
+        let query = if compressed {
+            RegisterQuery::ReadCompressed(address)
+        } else {
+            RegisterQuery::Read(address)
+        };
+        let request = Request::Query(Query::Register(query));
+        match self.send_and_wait_till_first_rsp(request).await? {
+            Response::Query(QueryResponse::ReadRegister(result)) => Ok(result?),
+            Response::Query(QueryResponse::ReadRegisterCompressed(result)) => {
+                Ok(decompress_register_entries(&result?, DEFAULT_MAX_MSG_SIZE)?)
+            }
+            other => {
+                warn!("On reading Register {address:?} received unexpected response {other:?}");
+                Err(Error::UnexpectedResponses)
+            }
+        }
+    }
+
     /// Create a new Register.
     pub async fn create_register(&self, xorname: XorName, tag: u64) -> Result<Register> {
         info!("Instantiating a new Register replica with name {xorname} and tag {tag}");
@@ -171,23 +249,65 @@ impl Client {
     pub(super) async fn get_chunk(&self, address: ChunkAddress) -> Result<Chunk> {
         info!("Getting chunk: {address:?}");
         let xorname = address.name();
-        match self
+        let response = self
             .network
             .get_provided_data(RecordKey::new(xorname))
-            .await?
-        {
-            Ok(QueryResponse::GetChunk(result)) => Ok(result?),
-            Ok(other) => {
-                warn!("On querying chunk {xorname:?} received unexpected response {other:?}",);
+            .await?;
+        chunk_from_response(address, response)
+    }
+
+    /// Retrieve a `Chunk` directly from the given peer, rather than looking it up across the
+    /// network, e.g. to check whether that peer actually holds it.
+    /// Errs with `ChunkNotFound` if the peer doesn't hold the chunk.
+    pub async fn get_chunk_from(&self, address: ChunkAddress, peer: PeerId) -> Result<Chunk> {
+        info!("Getting chunk {address:?} from peer {peer:?}");
+        let request = Request::Query(Query::GetStoredChunk(address));
+        match self.network.send_request(request, peer).await? {
+            Response::Query(response) => chunk_from_response(address, Ok(response)),
+            other => {
+                warn!("On querying chunk {address:?} from {peer:?} received unexpected response {other:?}");
                 Err(Error::UnexpectedResponses)
             }
-            Err(err) => {
-                warn!("Local internal error when trying to query chunk {xorname:?}: {err:?}",);
-                Err(err.into())
-            }
         }
     }
 
+    /// Probe the close group of the given content address, returning how many of those peers
+    /// hold the content, i.e. how many of them successfully respond to a query for it.
+    pub async fn probe_content_holders(&self, address: &NetworkAddress) -> Result<usize> {
+        let query = match address {
+            NetworkAddress::ChunkAddress(addr) => Query::GetChunk(*addr),
+            NetworkAddress::RegisterAddress(addr) => Query::Register(RegisterQuery::Get(*addr)),
+            NetworkAddress::DbcAddress(addr) => Query::Spend(SpendQuery::GetDbcSpend(*addr)),
+            NetworkAddress::PeerId(_) => return Err(Error::NotContentAddress(address.clone())),
+        };
+
+        trace!("Probing the closest peers to {address:?} with {query:?}.");
+        let closest_peers = self.network.client_get_closest_peers(address).await?;
+
+        let list_of_futures = closest_peers.into_iter().map(|peer| {
+            let request = Request::Query(query.clone());
+            Box::pin(self.network.send_request(request, peer))
+        });
+
+        let holders = join_all(list_of_futures)
+            .await
+            .into_iter()
+            .filter(|resp| {
+                matches!(
+                    resp,
+                    Ok(Response::Query(
+                        QueryResponse::GetChunk(Ok(_))
+                            | QueryResponse::GetRegister(Ok(_))
+                            | QueryResponse::GetDbcSpend(Ok(_))
+                    ))
+                )
+            })
+            .count();
+
+        trace!("Content at {address:?} is held by {holders} of its closest peers.");
+        Ok(holders)
+    }
+
     pub(crate) async fn send_to_closest(&self, request: Request) -> Result<Vec<Result<Response>>> {
         let responses = self
             .network
@@ -199,6 +319,68 @@ impl Client {
         Ok(responses)
     }
 
+    /// Returns the `n` closest peers to the given address, sorted by their distance to it,
+    /// e.g. to inspect which nodes are responsible for it, or to pre-warm connections to them.
+    /// No more than the close group of the address is ever returned.
+    pub async fn closest_peers(&self, addr: NetworkAddress, n: usize) -> Result<Vec<PeerId>> {
+        trace!("Getting the {n} closest peers to {addr:?}.");
+        let mut closest_peers = self.network.client_get_closest_peers(&addr).await?;
+        closest_peers.truncate(n);
+        Ok(closest_peers)
+    }
+
+    /// Whether the dbc with the given id has been spent, i.e. whether a majority of the close
+    /// group of its spend hold a spend of it, e.g. for a wallet to detect its dbcs spent by
+    /// another device sharing its key. It's not spent if a majority of them hold no spend of it,
+    /// while an error is returned if not enough of them agree either way.
+    pub async fn is_spent(&self, dbc_id: &DbcId) -> Result<bool> {
+        let address = DbcAddress::from_dbc_id(dbc_id);
+        let network_address = NetworkAddress::from_dbc_address(address);
+        trace!("Getting the closest peers to {dbc_id:?} / {network_address:?}.");
+        let closest_peers = self
+            .network
+            .client_get_closest_peers(&network_address)
+            .await?;
+
+        let query = Query::Spend(SpendQuery::GetDbcSpend(address));
+        let list_of_futures = closest_peers.into_iter().map(|peer| {
+            self.network
+                .send_request(Request::Query(query.clone()), peer)
+        });
+
+        let mut spent = 0;
+        let mut unspent = 0;
+        for response in join_all(list_of_futures).await {
+            match response {
+                Ok(Response::Query(QueryResponse::GetDbcSpend(Ok(spend))))
+                    if spend.dbc_id() == dbc_id =>
+                {
+                    spent += 1
+                }
+                Ok(Response::Query(QueryResponse::GetDbcSpend(Err(ProtocolError::Storage(
+                    StorageError::SpendNotFound(_),
+                ))))) => unspent += 1,
+                other => trace!("Unexpected response to the spend query of {dbc_id:?}: {other:?}"),
+            }
+        }
+
+        if spent >= close_group_majority() {
+            Ok(true)
+        } else if unspent >= close_group_majority() {
+            Ok(false)
+        } else {
+            Err(Error::CouldNotVerifyTransfer(format!(
+                "Not enough close group nodes agreed on whether {dbc_id:?} is spent. \
+                Spent: {spent}, unspent: {unspent}, required: {}.",
+                close_group_majority()
+            )))
+        }
+    }
+
+    pub(crate) async fn send_to_peer(&self, request: Request, peer: PeerId) -> Result<Response> {
+        Ok(self.network.send_request(request, peer).await?)
+    }
+
     pub(crate) async fn send_and_wait_till_first_rsp(&self, request: Request) -> Result<Response> {
         let mut responses = self
             .network
@@ -346,3 +528,123 @@ impl Client {
         )))
     }
 }
+
+// Returns the chunk out of the response to querying the given address, telling a chunk which
+// couldn't be found apart from one whose content doesn't match the address it was queried at.
+fn chunk_from_response(
+    address: ChunkAddress,
+    response: std::result::Result<QueryResponse, NetworkError>,
+) -> Result<Chunk> {
+    let xorname = address.name();
+    match response {
+        Ok(QueryResponse::GetChunk(Ok(chunk))) => {
+            if chunk.address() == &address {
+                Ok(chunk)
+            } else {
+                warn!(
+                    "Chunk queried at {xorname:?} has content of {:?}",
+                    chunk.name()
+                );
+                Err(Error::ChunkCorrupted(address))
+            }
+        }
+        Ok(QueryResponse::GetChunk(Err(ProtocolError::Storage(StorageError::ChunkNotFound(
+            _,
+        )))))
+        | Err(NetworkError::RecordNotFound) => Err(Error::ChunkNotFound(address)),
+        Ok(QueryResponse::GetChunk(Err(err))) => Err(err.into()),
+        Ok(other) => {
+            warn!("On querying chunk {xorname:?} received unexpected response {other:?}",);
+            Err(Error::UnexpectedResponses)
+        }
+        Err(err) => {
+            warn!("Local internal error when trying to query chunk {xorname:?}: {err:?}",);
+            Err(err.into())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::network::CLOSE_GROUP_SIZE;
+
+    use bytes::Bytes;
+    use eyre::{eyre, Result};
+    use std::path::Path;
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn closest_peers_are_the_seeded_ones_closest_to_the_key() -> Result<()> {
+        let (network, _network_event_receiver, swarm_driver) = SwarmDriver::new_client()?;
+        let _driver_handle = spawn(swarm_driver.run());
+        let client = Client {
+            network: network.clone(),
+            events_channel: ClientEventsChannel::default(),
+            signer: SecretKey::random(),
+            register_fetch_timeout: DEFAULT_REGISTER_FETCH_TIMEOUT,
+        };
+
+        // Seed the client's routing table with nodes listening locally.
+        let mut seeded_peers = vec![];
+        for _ in 0..CLOSE_GROUP_SIZE + 2 {
+            let (node, mut node_events, driver) =
+                SwarmDriver::new("127.0.0.1:0".parse()?, Path::new(""))?;
+            let _driver_handle = spawn(driver.run());
+            let listen_addr = tokio::time::timeout(Duration::from_secs(10), async {
+                while let Some(event) = node_events.recv().await {
+                    if let NetworkEvent::NewListenAddr(addr) = event {
+                        return Some(addr);
+                    }
+                }
+                None
+            })
+            .await?
+            .ok_or_else(|| eyre!("The node stopped before listening"))?;
+            let _events_handle = spawn(async move { while node_events.recv().await.is_some() {} });
+
+            network
+                .add_to_routing_table(node.peer_id, listen_addr)
+                .await?;
+            seeded_peers.push(node.peer_id);
+        }
+
+        let key = NetworkAddress::from_chunk_address(ChunkAddress::new(XorName::random(
+            &mut rand::thread_rng(),
+        )));
+        seeded_peers.sort_by_key(|peer| key.distance(&NetworkAddress::from_peer(*peer)));
+
+        let n = 3;
+        let closest_peers = client.closest_peers(key, n).await?;
+        assert_eq!(closest_peers, seeded_peers[..n].to_vec());
+
+        Ok(())
+    }
+
+    #[test]
+    fn chunk_misses_and_corruptions_are_told_apart() -> Result<()> {
+        let chunk = Chunk::new(Bytes::from_static(b"chunk content"));
+        let address = *chunk.address();
+        assert_eq!(
+            chunk_from_response(address, Ok(QueryResponse::GetChunk(Ok(chunk.clone()))))?,
+            chunk
+        );
+
+        let other_chunk = Chunk::new(Bytes::from_static(b"other content"));
+        match chunk_from_response(address, Ok(QueryResponse::GetChunk(Ok(other_chunk)))) {
+            Err(Error::ChunkCorrupted(addr)) if addr == address => {}
+            other => return Err(eyre!("Unexpected result: {other:?}")),
+        }
+
+        match chunk_from_response(address, Err(NetworkError::RecordNotFound)) {
+            Err(Error::ChunkNotFound(addr)) if addr == address => {}
+            other => return Err(eyre!("Unexpected result: {other:?}")),
+        }
+
+        let not_found = StorageError::ChunkNotFound(address).into();
+        match chunk_from_response(address, Ok(QueryResponse::GetChunk(Err(not_found)))) {
+            Err(Error::ChunkNotFound(addr)) if addr == address => Ok(()),
+            other => Err(eyre!("Unexpected result: {other:?}")),
+        }
+    }
+}
@@ -63,6 +63,14 @@ pub enum Error {
         retrieved: usize,
     },
 
+    #[error("Invalid chunk size {size}, it must be between 1 and {maximum} bytes.")]
+    InvalidChunkSize {
+        /// The chunk size requested
+        size: usize,
+        /// Maximum chunk size
+        maximum: usize,
+    },
+
     #[error("Not all data was chunked, expected {expected}, but we have {chunked}.)")]
     NotAllDataWasChunked {
         /// Number of Chunks expected to be generated
@@ -10,7 +10,7 @@ use super::{Error, Result};
 
 use crate::protocol::storage::Chunk;
 
-use self_encryption::{DataMap, EncryptedChunk, MAX_CHUNK_SIZE};
+use self_encryption::{DataMap, EncryptedChunk, MAX_CHUNK_SIZE, MIN_ENCRYPTABLE_BYTES};
 
 use bincode::serialize;
 use bytes::Bytes;
@@ -27,8 +27,18 @@ pub(crate) enum DataMapLevel {
     // resulting from chunking up a previous level data map.
     // This happens when that previous level data map was too big to fit in a chunk itself.
     Additional(DataMap),
+    // Holds the data maps to each of the segments the source data was split into, in order,
+    // each of them self-encrypted on its own so it's stored as chunks of the requested size.
+    Segmented {
+        chunk_size: usize,
+        segments: Vec<DataMap>,
+    },
 }
 
+// Number of chunks self-encryption splits data into, when it's not larger
+// than three times `MAX_CHUNK_SIZE`, which a segment never is.
+const CHUNKS_PER_SEGMENT: usize = 3;
+
 #[allow(unused)]
 pub(crate) fn encrypt_from_path(path: &Path) -> Result<(XorName, Vec<Chunk>)> {
     let (data_map, encrypted_chunks) = encrypt_file(path)?;
@@ -40,6 +50,60 @@ pub(crate) fn encrypt_large(data: Bytes) -> Result<(XorName, Vec<Chunk>)> {
     pack(data_map, encrypted_chunks)
 }
 
+/// Same as `encrypt_large`, but the data is stored as chunks of about `chunk_size` bytes rather
+/// than those self-encryption would produce, by splitting it into segments which are each
+/// self-encrypted on their own. The segments' data maps, along with the chunk size, are
+/// recorded in the top-most data map, for the data to be reassembled.
+pub(crate) fn encrypt_large_in_segments(
+    data: Bytes,
+    chunk_size: usize,
+) -> Result<(XorName, Vec<Chunk>)> {
+    if chunk_size == 0 || chunk_size > MAX_CHUNK_SIZE {
+        return Err(Error::InvalidChunkSize {
+            size: chunk_size,
+            maximum: MAX_CHUNK_SIZE,
+        });
+    }
+
+    let segment_size = (chunk_size * CHUNKS_PER_SEGMENT).max(MIN_ENCRYPTABLE_BYTES);
+    let mut segment_starts: Vec<usize> = (0..data.len()).step_by(segment_size).collect();
+    // A trailing segment too small to be self-encrypted is merged into the previous one.
+    let trailing_too_small = matches!(
+        segment_starts.as_slice(),
+        [.., _, last_start] if data.len() - last_start < MIN_ENCRYPTABLE_BYTES
+    );
+    if trailing_too_small {
+        let _ = segment_starts.pop();
+    }
+    let segments: Vec<Bytes> = segment_starts
+        .iter()
+        .enumerate()
+        .map(|(index, start)| {
+            let end = segment_starts.get(index + 1).copied().unwrap_or(data.len());
+            data.slice(*start..end)
+        })
+        .collect();
+
+    let encrypted_segments = segments
+        .into_par_iter()
+        .map(encrypt_data)
+        .collect::<Result<Vec<_>>>()?;
+    let mut data_maps = vec![];
+    let mut encrypted_chunks = vec![];
+    for (data_map, segment_chunks) in encrypted_segments {
+        data_maps.push(data_map);
+        encrypted_chunks.extend(segment_chunks);
+    }
+
+    pack_level(
+        DataMapLevel::Segmented {
+            chunk_size,
+            segments: data_maps,
+        },
+        encrypted_chunks,
+    )
+}
+
 /// Returns the top-most chunk address through which the entire
 /// data tree can be accessed, and all the other encrypted chunks.
 /// If encryption is provided, the additional `DataMapLevel` chunks are encrypted with it.
@@ -47,6 +111,14 @@ pub(crate) fn encrypt_large(data: Bytes) -> Result<(XorName, Vec<Chunk>)> {
 pub(crate) fn pack(
     data_map: DataMap,
     encrypted_chunks: Vec<EncryptedChunk>,
+) -> Result<(XorName, Vec<Chunk>)> {
+    pack_level(DataMapLevel::First(data_map), encrypted_chunks)
+}
+
+// Same as `pack`, but for any first `DataMapLevel`.
+fn pack_level(
+    first_level: DataMapLevel,
+    encrypted_chunks: Vec<EncryptedChunk>,
 ) -> Result<(XorName, Vec<Chunk>)> {
     // Produces a chunk out of the first `DataMap`, which is validated for its size.
     // If the chunk is too big, it is self-encrypted and the resulting (additional level) `DataMap` is put into a chunk.
@@ -55,7 +127,7 @@ pub(crate) fn pack(
     // self encrypted into additional chunks, and now we have a new `DataMap`
     // which points to all of those additional chunks.. and so on.
     let mut chunks = vec![];
-    let mut chunk_content = pack_data_map(DataMapLevel::First(data_map))?;
+    let mut chunk_content = pack_data_map(first_level)?;
 
     let (address, additional_chunks) = loop {
         let chunk = to_chunk(chunk_content);
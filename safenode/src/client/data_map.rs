@@ -0,0 +1,105 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::error::{Error, Result};
+
+use crate::protocol::storage::ChunkAddress;
+
+use serde::{Deserialize, Serialize};
+use xor_name::XorName;
+
+/// A compact and shareable reference to a file stored on the network.
+///
+/// It lists the addresses of all the chunks the file was stored as, in order, the last one
+/// being the chunk the file is read through, along with the file size and a root hash over
+/// those addresses, which identifies the whole file.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DataMap {
+    chunks: Vec<ChunkAddress>,
+    size: usize,
+    root_hash: XorName,
+}
+
+impl DataMap {
+    /// Creates a data map out of the addresses of all the chunks a file was stored as,
+    /// the last of them being the one the file is read through.
+    pub(super) fn new(chunks: Vec<ChunkAddress>, size: usize) -> Self {
+        let root_hash = root_hash(&chunks);
+        Self {
+            chunks,
+            size,
+            root_hash,
+        }
+    }
+
+    /// The address of the chunk the file is read through.
+    pub fn address(&self) -> Option<&ChunkAddress> {
+        self.chunks.last()
+    }
+
+    /// The addresses of all the chunks the file was stored as, in order.
+    pub fn chunks(&self) -> &[ChunkAddress] {
+        &self.chunks
+    }
+
+    /// The size of the file in bytes.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// The hash over all the chunks' addresses, identifying the whole file.
+    pub fn root_hash(&self) -> XorName {
+        self.root_hash
+    }
+
+    /// Checks the root hash matches the chunks' addresses, returning the address of the
+    /// chunk the file is read through.
+    pub(super) fn verify(&self) -> Result<ChunkAddress> {
+        if root_hash(&self.chunks) != self.root_hash {
+            return Err(Error::InvalidDataMap(
+                "the root hash doesn't match the chunks' addresses".to_string(),
+            ));
+        }
+        self.address()
+            .copied()
+            .ok_or_else(|| Error::InvalidDataMap("no chunk addresses".to_string()))
+    }
+}
+
+fn root_hash(chunks: &[ChunkAddress]) -> XorName {
+    let names: Vec<u8> = chunks.iter().flat_map(|addr| addr.name().0).collect();
+    XorName::from_content(&names)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use eyre::{bail, Result};
+    use rand::thread_rng;
+
+    #[test]
+    fn data_map_with_tampered_chunks_is_invalid() -> Result<()> {
+        let chunks: Vec<_> = (0..4)
+            .map(|_| ChunkAddress::new(XorName::random(&mut thread_rng())))
+            .collect();
+        let data_map = DataMap::new(chunks.clone(), 1024);
+        assert_eq!(data_map.verify()?, chunks[3]);
+
+        let mut reordered = chunks;
+        reordered.swap(0, 1);
+        let tampered = DataMap {
+            chunks: reordered,
+            ..data_map
+        };
+        match tampered.verify() {
+            Err(Error::InvalidDataMap(_)) => Ok(()),
+            other => bail!("Tampered data map should be invalid: {other:?}"),
+        }
+    }
+}
@@ -8,7 +8,13 @@
 
 pub(super) type Result<T, E = Error> = std::result::Result<T, E>;
 
-use crate::protocol::storage::registers::{Entry, EntryHash};
+use crate::protocol::{
+    storage::{
+        registers::{Entry, EntryHash},
+        ChunkAddress,
+    },
+    NetworkAddress,
+};
 
 use std::collections::BTreeSet;
 use thiserror::Error;
@@ -37,6 +43,40 @@ pub enum Error {
     #[error("Failed to verify transfer validity in the network {0}")]
     CouldNotVerifyTransfer(String),
 
+    #[error(
+        "Client not ready, only {peers_in_routing_table} peers in its routing table \
+        out of the {min_peers} required"
+    )]
+    NotEnoughPeers {
+        peers_in_routing_table: usize,
+        min_peers: usize,
+    },
+
+    #[error("The provided address doesn't point to any content: {0:?}")]
+    NotContentAddress(NetworkAddress),
+
+    #[error("Operation cancelled, after storing chunks: {stored_chunks:?}")]
+    Cancelled { stored_chunks: Vec<ChunkAddress> },
+
+    #[error("Chunk not found: {0:?}")]
+    ChunkNotFound(ChunkAddress),
+
+    #[error("Chunk could not be stored: {0:?}")]
+    ChunkNotStored(ChunkAddress),
+
+    #[error("Chunk content doesn't match its address: {0:?}")]
+    ChunkCorrupted(ChunkAddress),
+
+    #[error("Invalid data map: {0}")]
+    InvalidDataMap(String),
+
+    #[error("Byte range {start}..{end} is out of the {size} bytes of the file")]
+    RangeOutOfBounds {
+        start: usize,
+        end: usize,
+        size: usize,
+    },
+
     #[error("Chunks error {0}.")]
     Chunks(#[from] super::chunks::Error),
 
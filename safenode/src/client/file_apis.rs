@@ -7,35 +7,67 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 use super::{
-    chunks::{to_chunk, DataMapLevel, Error, LargeFile, SmallFile},
-    error::Result,
+    chunks::{encrypt_large_in_segments, to_chunk, DataMapLevel, Error, LargeFile, SmallFile},
+    data_map::DataMap,
+    error::{Error as ClientError, Result},
     Client,
 };
 
 use crate::protocol::storage::{Chunk, ChunkAddress};
 
-use self_encryption::{self, ChunkInfo, DataMap, EncryptedChunk};
+use self_encryption::{self, ChunkInfo, EncryptedChunk};
 
 use bincode::deserialize;
-use bytes::Bytes;
-use futures::future::join_all;
+use bytes::{Bytes, BytesMut};
+use futures::future::{join_all, BoxFuture};
 use itertools::Itertools;
-use tokio::task;
+use std::{collections::VecDeque, future::Future, time::Duration};
+use tokio::{task, time::Instant};
+use tokio_util::sync::CancellationToken;
 use tracing::trace;
 use xor_name::XorName;
 
 // Maximum number of concurrent chunks to be uploaded/retrieved for a file
 const CHUNKS_BATCH_MAX_SIZE: usize = 5;
+// Maximum number of concurrent chunks to be uploaded by `upload_many`, while the network copes.
+const UPLOAD_MAX_CONCURRENCY: usize = 4 * CHUNKS_BATCH_MAX_SIZE;
+// Number of times `upload_many` tries to store a chunk before failing the upload.
+const CHUNK_STORE_MAX_ATTEMPTS: usize = 3;
+// Factor over the lowest latency observed storing chunks, beyond which the network is
+// deemed congested by the chunks being stored concurrently.
+const CONGESTION_LATENCY_FACTOR: u32 = 2;
 
 /// File APIs.
 pub struct Files {
     client: Client,
+    // Size of the chunks large files are stored as, if other than self-encryption's.
+    chunk_size: Option<usize>,
 }
 
 impl Files {
     /// Create file apis instance.
     pub fn new(client: Client) -> Self {
-        Self { client }
+        Self {
+            client,
+            chunk_size: None,
+        }
+    }
+
+    /// Create file apis instance storing large files as chunks of about `chunk_size` bytes,
+    /// e.g. smaller ones for many small files, or larger ones for large media.
+    /// The chunk size is recorded along with the file, so it's read back the same way
+    /// regardless of the chunk size the reader uses.
+    pub fn with_chunk_size(client: Client, chunk_size: usize) -> Result<Self> {
+        if chunk_size == 0 || chunk_size > self_encryption::MAX_CHUNK_SIZE {
+            return Err(Error::InvalidChunkSize {
+                size: chunk_size,
+                maximum: self_encryption::MAX_CHUNK_SIZE,
+            })?;
+        }
+        Ok(Self {
+            client,
+            chunk_size: Some(chunk_size),
+        })
     }
 
     #[instrument(skip(self), level = "debug")]
@@ -44,8 +76,8 @@ impl Files {
         let chunk = self.client.get_chunk(address).await?;
 
         // first try to deserialize a LargeFile, if it works, we go and seek it
-        if let Ok(data_map) = self.unpack_chunk(chunk.clone()).await {
-            self.read_all(data_map).await
+        if let Ok(data_maps) = self.unpack_chunk(chunk.clone()).await {
+            self.read_segments(data_maps).await
         } else {
             // if an error occurs, we assume it's a SmallFile
             Ok(chunk.value().clone())
@@ -75,8 +107,8 @@ impl Files {
 
         // First try to deserialize a LargeFile, if it works, we go and seek it.
         // If an error occurs, we consider it to be a SmallFile.
-        if let Ok(data_map) = self.unpack_chunk(chunk.clone()).await {
-            return self.seek(data_map, position, length).await;
+        if let Ok(data_maps) = self.unpack_chunk(chunk.clone()).await {
+            return self.seek_segments(data_maps, position, length).await;
         }
 
         // The error above is ignored to avoid leaking the storage format detail of SmallFiles and LargeFiles.
@@ -90,11 +122,68 @@ impl Files {
         Ok(bytes)
     }
 
+    /// Reads the file referenced by the given [`DataMap`] from the network,
+    /// checking it's consistent with the data map.
+    #[instrument(skip(self), level = "debug")]
+    pub async fn read_from_datamap(&self, data_map: &DataMap) -> Result<Bytes> {
+        let address = data_map.verify()?;
+        let bytes = self.read_bytes(address).await?;
+        if bytes.len() != data_map.size() {
+            return Err(ClientError::InvalidDataMap(format!(
+                "read {} bytes instead of {}",
+                bytes.len(),
+                data_map.size()
+            )));
+        }
+        Ok(bytes)
+    }
+
+    /// Reads `len` bytes, starting at `start`, of the file referenced by the given [`DataMap`],
+    /// getting only the chunks which hold that range, e.g. to resume a download or to seek
+    /// within a media file. The chunk boundaries are those the file was stored with.
+    #[instrument(skip(self), level = "debug")]
+    pub async fn read_range_from_datamap(
+        &self,
+        data_map: &DataMap,
+        start: usize,
+        len: usize,
+    ) -> Result<Bytes> {
+        let size = data_map.size();
+        let end = start.saturating_add(len);
+        if end > size {
+            return Err(ClientError::RangeOutOfBounds { start, end, size });
+        }
+
+        let address = data_map.verify()?;
+        let bytes = self.read_from(address, start, len).await?;
+        if bytes.len() != len {
+            return Err(ClientError::InvalidDataMap(format!(
+                "read {} bytes of the range instead of {len}",
+                bytes.len()
+            )));
+        }
+        Ok(bytes)
+    }
+
     /// Directly writes [`Bytes`] to the network in the
     /// form of immutable chunks, without any batching.
+    /// Returns the [`DataMap`] through which the file can be read.
     #[instrument(skip(self, bytes), level = "debug")]
-    pub async fn upload(&self, bytes: Bytes) -> Result<ChunkAddress> {
-        self.upload_bytes(bytes, false).await
+    pub async fn upload(&self, bytes: Bytes) -> Result<DataMap> {
+        self.upload_bytes(bytes, false, &CancellationToken::new())
+            .await
+    }
+
+    /// Same as `upload`, but it stops storing chunks once the `cancellation_token` is cancelled.
+    /// The chunks already being stored are waited for, and their addresses are returned
+    /// within an `Error::Cancelled`, so they can be cleaned up or the upload resumed.
+    #[instrument(skip(self, bytes), level = "debug")]
+    pub async fn upload_with_cancellation(
+        &self,
+        bytes: Bytes,
+        cancellation_token: &CancellationToken,
+    ) -> Result<DataMap> {
+        self.upload_bytes(bytes, false, cancellation_token).await
     }
 
     /// Directly writes [`Bytes`] to the network in the
@@ -102,15 +191,77 @@ impl Files {
     /// It also attempts to verify that all the data was uploaded to the network before returning.
     /// It does this via running `read_bytes` with each chunk with `query_timeout` set.
     #[instrument(skip_all, level = "trace")]
-    pub async fn upload_and_verify(&self, bytes: Bytes) -> Result<ChunkAddress> {
-        self.upload_bytes(bytes, true).await
+    pub async fn upload_and_verify(&self, bytes: Bytes) -> Result<DataMap> {
+        self.upload_bytes(bytes, true, &CancellationToken::new())
+            .await
+    }
+
+    /// Same as `upload_and_verify`, but it stops storing chunks once the `cancellation_token`
+    /// is cancelled, as done by `upload_with_cancellation`.
+    #[instrument(skip_all, level = "trace")]
+    pub async fn upload_and_verify_with_cancellation(
+        &self,
+        bytes: Bytes,
+        cancellation_token: &CancellationToken,
+    ) -> Result<DataMap> {
+        self.upload_bytes(bytes, true, cancellation_token).await
+    }
+
+    /// Writes each of the files to the network in the form of immutable chunks, storing the
+    /// chunks of all of them concurrently. The concurrency adapts to how the network copes
+    /// with it: it's increased by one after each batch of chunks stored in good time, and
+    /// halved after a batch which took much longer than usual or in which any chunk failed,
+    /// so the upload backs off a stressed network, e.g. during churn, and ramps up on a healthy
+    /// one. Failed chunks are retried in later batches, up to a few times each.
+    /// Returns the [`DataMap`] through which each of the files can be read, in the same order.
+    #[instrument(skip_all, level = "debug")]
+    pub async fn upload_many(&self, files: Vec<Bytes>) -> Result<Vec<DataMap>> {
+        self.upload_many_with_cancellation(files, &CancellationToken::new())
+            .await
+    }
+
+    /// Same as `upload_many`, but it stops storing chunks once the `cancellation_token` is
+    /// cancelled, as done by `upload_with_cancellation`.
+    #[instrument(skip_all, level = "debug")]
+    pub async fn upload_many_with_cancellation(
+        &self,
+        files: Vec<Bytes>,
+        cancellation_token: &CancellationToken,
+    ) -> Result<Vec<DataMap>> {
+        let mut data_maps = vec![];
+        let mut all_chunks = vec![];
+        for bytes in files {
+            let (data_map, chunks) = self.chunk_file(bytes)?;
+            data_maps.push(data_map);
+            all_chunks.extend(chunks);
+        }
+
+        let batch_sizes = store_chunks_adaptively(
+            all_chunks,
+            UPLOAD_MAX_CONCURRENCY,
+            cancellation_token,
+            self.chunk_storer(false),
+        )
+        .await?;
+        debug!(
+            "Uploaded {} file/s in {} batch/es of chunks, of sizes: {batch_sizes:?}",
+            data_maps.len(),
+            batch_sizes.len()
+        );
+
+        Ok(data_maps)
     }
 
     /// Calculates a LargeFile's/SmallFile's address from self encrypted chunks,
     /// without storing them onto the network.
     #[instrument(skip_all, level = "debug")]
     pub fn calculate_address(&self, bytes: Bytes) -> Result<XorName> {
-        chunk_bytes(bytes).map(|(name, _)| name)
+        match self.chunk_size {
+            Some(chunk_size) if LargeFile::new(bytes.clone()).is_ok() => {
+                Ok(encrypt_large_in_segments(bytes, chunk_size)?.0)
+            }
+            _ => chunk_bytes(bytes).map(|(name, _)| name),
+        }
     }
 
     // --------------------------------------------
@@ -118,91 +269,117 @@ impl Files {
     // --------------------------------------------
 
     #[instrument(skip(self, bytes), level = "trace")]
-    async fn upload_bytes(&self, bytes: Bytes, verify: bool) -> Result<ChunkAddress> {
-        match LargeFile::new(bytes.clone()) {
-            Ok(file) => self.upload_large(file, verify).await,
-            Err(Error::TooSmallForSelfEncryption { .. }) => {
-                let file = SmallFile::new(bytes)?;
-                self.upload_small(file, verify).await
-            }
-            Err(error) => Err(error)?,
-        }
+    async fn upload_bytes(
+        &self,
+        bytes: Bytes,
+        verify: bool,
+        cancellation_token: &CancellationToken,
+    ) -> Result<DataMap> {
+        let (data_map, chunks) = self.chunk_file(bytes)?;
+        store_chunks_in_batches(chunks, cancellation_token, self.chunk_storer(verify)).await?;
+        Ok(data_map)
     }
 
-    /// Directly writes a [`SmallFile`] to the network in the
-    /// form of a single chunk, without any batching.
-    #[instrument(skip_all, level = "trace")]
-    async fn upload_small(&self, small: SmallFile, verify: bool) -> Result<ChunkAddress> {
-        let chunk = package_small(small)?;
-        let address = *chunk.address();
-
-        self.client.store_chunk(chunk).await?;
-
-        if verify {
-            self.verify_chunk_is_stored(address).await?;
+    // Returns the fn chunks are stored with, which also reads each of them back
+    // once stored if `verify` is set.
+    fn chunk_storer(&self, verify: bool) -> impl Fn(Chunk) -> BoxFuture<'static, Result<()>> {
+        let client = self.client.clone();
+        move |chunk| {
+            let client = client.clone();
+            Box::pin(async move {
+                let chunk_addr = *chunk.address();
+                client.store_chunk(chunk).await?;
+                if verify {
+                    let _ = client.get_chunk(chunk_addr).await?;
+                }
+                Ok(())
+            })
         }
-
-        Ok(address)
     }
 
-    /// Directly writes a [`LargeFile`] to the network in the
-    /// form of immutable self encrypted chunks, without any batching.
-    #[instrument(skip_all, level = "trace")]
-    async fn upload_large(&self, large: LargeFile, verify: bool) -> Result<ChunkAddress> {
-        let (head_address, all_chunks) = encrypt_large(large)?;
-        for next_batch in all_chunks.chunks(CHUNKS_BATCH_MAX_SIZE) {
-            let tasks = next_batch.iter().cloned().map(|chunk| {
-                let client = self.client.clone();
-
-                task::spawn(async move {
-                    let chunk_addr = *chunk.address();
-                    client.store_chunk(chunk).await?;
-                    if verify {
-                        let _ = client.get_chunk(chunk_addr).await?;
-                    }
-                    Ok::<(), super::error::Error>(())
-                })
-            });
-
-            let respones = join_all(tasks)
-                .await
-                .into_iter()
-                .flatten() // swallows errors
-                .collect_vec();
-
-            for res in respones {
-                // fail with any issue here
-                res?;
+    // Splits the file into the chunks it's to be stored as, without storing them,
+    // along with the data map it can be read through once they are.
+    fn chunk_file(&self, bytes: Bytes) -> Result<(DataMap, Vec<Chunk>)> {
+        let size = bytes.len();
+        let chunks = match LargeFile::new(bytes.clone()) {
+            Ok(large) => match self.chunk_size {
+                Some(chunk_size) => encrypt_large_in_segments(large.bytes(), chunk_size)?.1,
+                None => encrypt_large(large)?.1,
+            },
+            Err(Error::TooSmallForSelfEncryption { .. }) => {
+                vec![package_small(SmallFile::new(bytes)?)?]
             }
-        }
-
-        Ok(ChunkAddress::new(head_address))
-    }
-
-    // Verify a chunk is stored at provided address
-    async fn verify_chunk_is_stored(&self, address: ChunkAddress) -> Result<()> {
-        let _ = self.client.get_chunk(address).await?;
-        Ok(())
+            Err(error) => Err(error)?,
+        };
+        let data_map = DataMap::new(chunks.iter().map(|chunk| *chunk.address()).collect(), size);
+        Ok((data_map, chunks))
     }
 
     // Gets and decrypts chunks from the network using nothing else but the data map,
     // then returns the raw data.
-    async fn read_all(&self, data_map: DataMap) -> Result<Bytes> {
+    async fn read_all(&self, data_map: self_encryption::DataMap) -> Result<Bytes> {
         let encrypted_chunks = self.try_get_chunks(data_map.infos()).await?;
         let bytes = self_encryption::decrypt_full_set(&data_map, &encrypted_chunks)
             .map_err(Error::SelfEncryption)?;
         Ok(bytes)
     }
 
-    /// Extracts a file DataMapLevel from a chunk.
+    // Reads the segments the file was split into, in order, concatenating their raw data.
+    async fn read_segments(&self, data_maps: Vec<self_encryption::DataMap>) -> Result<Bytes> {
+        let mut bytes = BytesMut::new();
+        for data_map in data_maps {
+            bytes.extend_from_slice(&self.read_all(data_map).await?);
+        }
+        Ok(bytes.freeze())
+    }
+
+    // Reads `len` bytes of the file starting at `pos`, out of the segments it was split into,
+    // getting only the chunks which hold that range.
+    async fn seek_segments(
+        &self,
+        data_maps: Vec<self_encryption::DataMap>,
+        pos: usize,
+        len: usize,
+    ) -> Result<Bytes> {
+        if let [data_map] = &data_maps[..] {
+            return self.seek(data_map.clone(), pos, len).await;
+        }
+
+        let end = pos.saturating_add(len);
+        let mut bytes = BytesMut::new();
+        let mut segment_start = 0;
+        for data_map in data_maps {
+            let segment_end = segment_start + data_map.file_size();
+            if segment_end > pos && segment_start < end {
+                let relative_pos = pos.saturating_sub(segment_start);
+                let relative_len = end.min(segment_end) - segment_start - relative_pos;
+                bytes.extend_from_slice(&self.seek(data_map, relative_pos, relative_len).await?);
+            }
+            segment_start = segment_end;
+        }
+        Ok(bytes.freeze())
+    }
+
+    /// Extracts the data maps of a file from a chunk, i.e. that of the whole file,
+    /// or those of each of the segments it was split into if stored with a chunk size.
     /// If the DataMapLevel is not the first level mapping directly to the user's contents,
     /// the process repeats itself until it obtains the first level DataMapLevel.
     #[instrument(skip_all, level = "trace")]
-    async fn unpack_chunk(&self, mut chunk: Chunk) -> Result<DataMap> {
+    async fn unpack_chunk(&self, mut chunk: Chunk) -> Result<Vec<self_encryption::DataMap>> {
         loop {
             match deserialize(chunk.value()).map_err(Error::Serialisation)? {
                 DataMapLevel::First(data_map) => {
-                    return Ok(data_map);
+                    return Ok(vec![data_map]);
+                }
+                DataMapLevel::Segmented {
+                    chunk_size,
+                    segments,
+                } => {
+                    trace!(
+                        "Reading file stored in {} segment/s, as chunks of {chunk_size} bytes",
+                        segments.len()
+                    );
+                    return Ok(segments);
                 }
                 DataMapLevel::Additional(data_map) => {
                     let serialized_chunk = self.read_all(data_map).await?;
@@ -214,7 +391,12 @@ impl Files {
     // Gets a subset of chunks from the network, decrypts and
     // reads `len` bytes of the data starting at given `pos` of original file.
     #[instrument(skip_all, level = "trace")]
-    async fn seek(&self, data_map: DataMap, pos: usize, len: usize) -> Result<Bytes> {
+    async fn seek(
+        &self,
+        data_map: self_encryption::DataMap,
+        pos: usize,
+        len: usize,
+    ) -> Result<Bytes> {
         let info = self_encryption::seek_info(data_map.file_size(), pos, len);
         let range = &info.index_range;
         let all_infos = data_map.infos();
@@ -278,6 +460,171 @@ impl Files {
     }
 }
 
+/// Stores the chunks in batches with the provided `store_chunk` fn. The `cancellation_token` is
+/// checked before each batch, once cancelled no new chunk is stored, and the addresses of the
+/// chunks stored so far, including those of the last batch in-flight, are returned in the error.
+async fn store_chunks_in_batches<F, Fut>(
+    chunks: Vec<Chunk>,
+    cancellation_token: &CancellationToken,
+    store_chunk: F,
+) -> Result<()>
+where
+    F: Fn(Chunk) -> Fut,
+    Fut: Future<Output = Result<()>> + Send + 'static,
+{
+    let mut stored_chunks = vec![];
+    for next_batch in chunks.chunks(CHUNKS_BATCH_MAX_SIZE) {
+        if cancellation_token.is_cancelled() {
+            return Err(ClientError::Cancelled { stored_chunks });
+        }
+
+        let tasks = next_batch.iter().cloned().map(|chunk| {
+            let chunk_addr = *chunk.address();
+            let store = store_chunk(chunk);
+            task::spawn(async move { store.await.map(|()| chunk_addr) })
+        });
+
+        let respones = join_all(tasks)
+            .await
+            .into_iter()
+            .flatten() // swallows errors
+            .collect_vec();
+
+        let mut batch_result = Ok(());
+        for res in respones {
+            match res {
+                Ok(chunk_addr) => stored_chunks.push(chunk_addr),
+                // fail with any issue here, once all the batch has settled
+                Err(err) => batch_result = Err(err),
+            }
+        }
+        batch_result?;
+    }
+
+    Ok(())
+}
+
+/// Concurrency chunks are stored with, adapted by additive-increase/multiplicative-decrease
+/// to how the network copes with it, as told by the latency of storing them and their failures.
+#[derive(Debug)]
+struct AdaptiveConcurrency {
+    current: usize,
+    max: usize,
+    // Lowest mean latency of storing the chunks of a batch observed so far, taken as
+    // the latency of the network when it's not congested.
+    base_latency: Option<Duration>,
+}
+
+impl AdaptiveConcurrency {
+    fn new(max: usize) -> Self {
+        Self {
+            current: 1,
+            max: max.max(1),
+            base_latency: None,
+        }
+    }
+
+    // Adapts the concurrency upon storing a batch of chunks, given the mean latency
+    // of storing each of them and whether any of them failed.
+    fn on_batch_stored(&mut self, mean_latency: Duration, any_failed: bool) {
+        let base_latency = self
+            .base_latency
+            .map_or(mean_latency, |base_latency| base_latency.min(mean_latency));
+        self.base_latency = Some(base_latency);
+
+        let congested = any_failed || mean_latency > base_latency * CONGESTION_LATENCY_FACTOR;
+        self.current = if congested {
+            (self.current / 2).max(1)
+        } else {
+            (self.current + 1).min(self.max)
+        };
+    }
+}
+
+/// Stores the chunks with the provided `store_chunk` fn, in batches whose size, i.e. the number
+/// of chunks stored concurrently, adapts to the latency and failures observed storing them, up
+/// to `max_concurrency`. A failed chunk is retried in a later batch, and its last error returned
+/// once it's failed `CHUNK_STORE_MAX_ATTEMPTS` times. Returns the size of each batch stored.
+/// The `cancellation_token` is checked before each batch, as done by `store_chunks_in_batches`.
+async fn store_chunks_adaptively<F, Fut>(
+    chunks: Vec<Chunk>,
+    max_concurrency: usize,
+    cancellation_token: &CancellationToken,
+    store_chunk: F,
+) -> Result<Vec<usize>>
+where
+    F: Fn(Chunk) -> Fut,
+    Fut: Future<Output = Result<()>> + Send + 'static,
+{
+    let mut concurrency = AdaptiveConcurrency::new(max_concurrency);
+    let mut pending: VecDeque<_> = chunks.into_iter().map(|chunk| (chunk, 0)).collect();
+    let mut stored_chunks = vec![];
+    let mut batch_sizes = vec![];
+    while !pending.is_empty() {
+        if cancellation_token.is_cancelled() {
+            return Err(ClientError::Cancelled { stored_chunks });
+        }
+
+        let batch_size = concurrency.current.min(pending.len());
+        batch_sizes.push(batch_size);
+
+        let (batch, tasks): (Vec<_>, Vec<_>) = pending
+            .drain(..batch_size)
+            .map(|(chunk, attempts)| {
+                let store = store_chunk(chunk.clone());
+                let task = task::spawn(async move {
+                    let started = Instant::now();
+                    let result = store.await;
+                    (started.elapsed(), result)
+                });
+                ((chunk, attempts + 1), task)
+            })
+            .unzip();
+
+        let mut total_latency = Duration::ZERO;
+        let mut any_failed = false;
+        for ((chunk, attempts), outcome) in batch.into_iter().zip(join_all(tasks).await) {
+            let result = match outcome {
+                Ok((latency, result)) => {
+                    total_latency += latency;
+                    result
+                }
+                Err(err) => {
+                    warn!(
+                        "Storing chunk {:?} didn't complete: {err:?}",
+                        chunk.address()
+                    );
+                    Err(ClientError::ChunkNotStored(*chunk.address()))
+                }
+            };
+            match result {
+                Ok(()) => stored_chunks.push(*chunk.address()),
+                Err(err) => {
+                    any_failed = true;
+                    if attempts >= CHUNK_STORE_MAX_ATTEMPTS {
+                        return Err(err);
+                    }
+                    warn!(
+                        "Failed to store chunk {:?}, attempt #{attempts}: {err:?}",
+                        chunk.address()
+                    );
+                    pending.push_back((chunk, attempts));
+                }
+            }
+        }
+
+        let mean_latency = total_latency / batch_size as u32;
+        concurrency.on_batch_stored(mean_latency, any_failed);
+        trace!(
+            "Stored batch of {batch_size} chunk/s, with a mean latency of {mean_latency:?}, \
+            next batches to store {} chunk/s concurrently",
+            concurrency.current
+        );
+    }
+
+    Ok(batch_sizes)
+}
+
 /// Tries to chunk the bytes, returning an address and chunks, without storing anything to network.
 #[instrument(skip_all, level = "trace")]
 pub fn chunk_bytes(bytes: Bytes) -> Result<(XorName, Vec<Chunk>)> {
@@ -308,3 +655,178 @@ fn package_small(file: SmallFile) -> Result<Chunk> {
     }
     Ok(chunk)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use eyre::{bail, Result};
+    use std::{
+        collections::BTreeSet,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+    };
+
+    #[tokio::test]
+    async fn cancelled_upload_returns_stored_chunks() -> Result<()> {
+        let chunks = (0..3 * CHUNKS_BATCH_MAX_SIZE)
+            .map(|i| Chunk::new(Bytes::from(format!("chunk #{i}"))))
+            .collect_vec();
+
+        // Cancel the upload once a couple of chunks have been stored.
+        let cancellation_token = CancellationToken::new();
+        let num_of_stores = Arc::new(AtomicUsize::new(0));
+        let result = store_chunks_in_batches(chunks.clone(), &cancellation_token, |_chunk| {
+            let num_of_stores = num_of_stores.clone();
+            let cancellation_token = cancellation_token.clone();
+            async move {
+                if num_of_stores.fetch_add(1, Ordering::SeqCst) + 1 == 2 {
+                    cancellation_token.cancel();
+                }
+                Ok(())
+            }
+        })
+        .await;
+
+        // The in-flight batch settles, but no further chunk is stored.
+        let expected: BTreeSet<_> = chunks[..CHUNKS_BATCH_MAX_SIZE]
+            .iter()
+            .map(|chunk| *chunk.address())
+            .collect();
+        match result {
+            Err(ClientError::Cancelled { stored_chunks }) => {
+                assert_eq!(stored_chunks.into_iter().collect::<BTreeSet<_>>(), expected);
+            }
+            other => bail!("Upload should have been cancelled: {other:?}"),
+        }
+        assert_eq!(num_of_stores.load(Ordering::SeqCst), CHUNKS_BATCH_MAX_SIZE);
+
+        Ok(())
+    }
+
+    // The clock is paused, hence the latencies below are simulated rather than waited for,
+    // and the same on every run.
+    #[tokio::test(start_paused = true)]
+    async fn upload_backs_off_when_latency_increases_under_load() -> Result<()> {
+        // Enough chunks for the concurrency to ramp up, by one per batch, to its maximum.
+        let max_concurrency = 10;
+        let chunks = (0..80)
+            .map(|i| Chunk::new(Bytes::from(format!("chunk #{i}"))))
+            .collect_vec();
+
+        // On a healthy network, the latency of storing a chunk doesn't depend on the load.
+        let healthy = store_chunks_adaptively(
+            chunks.clone(),
+            max_concurrency,
+            &CancellationToken::new(),
+            |_chunk| async {
+                tokio::time::sleep(Duration::from_millis(20)).await;
+                Ok(())
+            },
+        )
+        .await?;
+        assert_eq!(healthy.iter().sum::<usize>(), chunks.len());
+        assert_eq!(
+            healthy[..max_concurrency],
+            (1..=max_concurrency).collect_vec()
+        );
+        assert_eq!(healthy.iter().max(), Some(&max_concurrency));
+
+        // On a stressed network, it increases with the number of chunks stored concurrently.
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let stressed = store_chunks_adaptively(
+            chunks.clone(),
+            max_concurrency,
+            &CancellationToken::new(),
+            |_chunk| {
+                let in_flight = in_flight.clone();
+                async move {
+                    let load = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                    tokio::time::sleep(Duration::from_millis(20 * load as u64)).await;
+                    let _ = in_flight.fetch_sub(1, Ordering::SeqCst);
+                    Ok(())
+                }
+            },
+        )
+        .await?;
+        assert_eq!(stressed.iter().sum::<usize>(), chunks.len());
+        let peak = stressed.iter().max().copied().unwrap_or_default();
+        assert!(peak < max_concurrency);
+        // The concurrency is cut down once the latency rises, rather than kept at its peak.
+        let backed_off = stressed
+            .windows(2)
+            .any(|sizes| sizes[0] == peak && sizes[1] < peak);
+        assert!(backed_off, "Concurrency never backed off: {stressed:?}");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn cancelled_adaptive_upload_returns_stored_chunks() -> Result<()> {
+        let chunks = (0..10)
+            .map(|i| Chunk::new(Bytes::from(format!("chunk #{i}"))))
+            .collect_vec();
+
+        // Cancel the upload once the first batch, of a single chunk, has been stored.
+        let cancellation_token = CancellationToken::new();
+        let result = store_chunks_adaptively(chunks.clone(), 10, &cancellation_token, |_chunk| {
+            let cancellation_token = cancellation_token.clone();
+            async move {
+                cancellation_token.cancel();
+                Ok(())
+            }
+        })
+        .await;
+
+        match result {
+            Err(ClientError::Cancelled { stored_chunks }) => {
+                assert_eq!(stored_chunks, vec![*chunks[0].address()]);
+            }
+            other => bail!("Upload should have been cancelled: {other:?}"),
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn failed_chunks_are_retried_with_less_concurrency() -> Result<()> {
+        let chunks = (0..10)
+            .map(|i| Chunk::new(Bytes::from(format!("chunk #{i}"))))
+            .collect_vec();
+        let failing = *chunks[5].address();
+
+        // The chunk fails the first time it's stored only.
+        let failures = Arc::new(AtomicUsize::new(0));
+        let batch_sizes =
+            store_chunks_adaptively(chunks.clone(), 10, &CancellationToken::new(), |chunk| {
+                let failures = failures.clone();
+                async move {
+                    if *chunk.address() == failing && failures.fetch_add(1, Ordering::SeqCst) == 0 {
+                        return Err(ClientError::ChunkNotStored(failing));
+                    }
+                    Ok(())
+                }
+            })
+            .await?;
+        assert_eq!(batch_sizes.iter().sum::<usize>(), chunks.len() + 1);
+        assert_eq!(failures.load(Ordering::SeqCst), 2);
+
+        // A chunk failing every time fails the upload.
+        let result =
+            store_chunks_adaptively(chunks, 10, &CancellationToken::new(), |chunk| async move {
+                if *chunk.address() == failing {
+                    return Err(ClientError::ChunkNotStored(failing));
+                }
+                Ok(())
+            })
+            .await;
+        match result {
+            Err(ClientError::ChunkNotStored(address)) => assert_eq!(address, failing),
+            other => bail!("Upload should have failed: {other:?}"),
+        }
+
+        Ok(())
+    }
+}
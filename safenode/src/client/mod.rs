@@ -8,6 +8,7 @@
 
 mod api;
 mod chunks;
+mod data_map;
 mod error;
 mod event;
 mod file_apis;
@@ -15,21 +16,26 @@ mod register;
 mod wallet;
 
 pub use self::{
+    data_map::DataMap,
     error::Error,
     event::{ClientEvent, ClientEventsReceiver},
     file_apis::Files,
     register::{Register, RegisterOffline},
-    wallet::WalletClient,
+    wallet::{SendConfirmation, SendReceipt, WalletClient},
 };
 
 use self::event::ClientEventsChannel;
 
 use crate::network::Network;
 
+use std::time::Duration;
+
 /// Client API implementation to store and get data.
 #[derive(Clone)]
 pub struct Client {
     network: Network,
     events_channel: ClientEventsChannel,
     signer: bls::SecretKey,
+    // Time to wait for the peers to answer with a Register, before giving up on them.
+    register_fetch_timeout: Duration,
 }
@@ -10,6 +10,7 @@ use super::{Client, Error, Register, Result};
 
 use crate::{
     domain::storage::RegisterReplica,
+    network::CLOSE_GROUP_SIZE,
     protocol::{
         error::Error as ProtocolError,
         messages::{
@@ -20,13 +21,18 @@ use crate::{
             registers::{Action, DataAuthority, Entry, EntryHash, Permissions, Policy, User},
             RegisterAddress,
         },
+        NetworkAddress,
     },
 };
 
 use bincode::serialize;
+use futures::{stream::FuturesUnordered, StreamExt};
+use libp2p::PeerId;
 use std::{
     collections::{BTreeSet, LinkedList},
     convert::From,
+    future::Future,
+    time::Duration,
 };
 use xor_name::XorName;
 
@@ -148,6 +154,7 @@ impl RegisterOffline {
         let op = EditRegister {
             address: *self.register.address(),
             edit,
+            expected_size: None,
         };
         let auth = DataAuthority {
             public_key,
@@ -181,7 +188,9 @@ impl RegisterOffline {
             while let Some(cmd) = self.ops.pop_back() {
                 let result = match cmd {
                     RegisterCmd::Create { .. } => self.publish_register_create(cmd.clone()).await,
-                    RegisterCmd::Edit { .. } => self.publish_register_edit(cmd.clone()).await,
+                    RegisterCmd::Edit { .. } | RegisterCmd::SetExpiry { .. } => {
+                        self.publish_register_edit(cmd.clone()).await
+                    }
                 };
 
                 if let Err(err) = result {
@@ -215,6 +224,7 @@ impl RegisterOffline {
             name,
             tag,
             policy: policy.clone(),
+            initial_entries: vec![],
         };
         let auth = DataAuthority {
             public_key,
@@ -292,34 +302,142 @@ impl RegisterOffline {
         Err(Error::UnexpectedResponses)
     }
 
-    // Retrieve a `Register` from the closest peers.
+    // Retrieve a `Register` from the closest peers, asking one at a time, closest first.
     async fn get_register(client: &Client, name: XorName, tag: u64) -> Result<RegisterReplica> {
         let address = RegisterAddress { name, tag };
         debug!("Retrieving Register from: {address:?}");
         let request = Request::Query(Query::Register(RegisterQuery::Get(address)));
-        let responses = client.send_to_closest(request).await?;
+        let peers = client
+            .closest_peers(
+                NetworkAddress::from_register_address(address),
+                CLOSE_GROUP_SIZE,
+            )
+            .await?;
+
+        first_register_within(peers, client.register_fetch_timeout, |peer| {
+            client.send_to_peer(request.clone(), peer)
+        })
+        .await
+    }
+}
 
-        // We will return the first register we get.
-        for resp in responses.iter().flatten() {
-            if let Response::Query(QueryResponse::GetRegister(Ok(register))) = resp {
-                return Ok(register.clone().into());
-            };
+// Returns the first `Register` any of the peers, all asked at once, answers with. The peers
+// which haven't answered within the `timeout` are given up on, with the errors answered by the
+// others, if any, returned instead of the timeout.
+async fn first_register_within<F, Fut>(
+    peers: Vec<PeerId>,
+    timeout: Duration,
+    send_request: F,
+) -> Result<RegisterReplica>
+where
+    F: FnMut(PeerId) -> Fut,
+    Fut: Future<Output = Result<Response>>,
+{
+    let mut pending: FuturesUnordered<_> = peers.into_iter().map(send_request).collect();
+    let mut responses = vec![];
+    let first_register = async {
+        while let Some(response) = pending.next().await {
+            match response {
+                Ok(Response::Query(QueryResponse::GetRegister(Ok(register)))) => {
+                    return Some(register);
+                }
+                response => responses.push(response),
+            }
+        }
+        None
+    };
+    let outcome = tokio::time::timeout(timeout, first_register).await;
+    match outcome {
+        Ok(Some(register)) => return Ok(register.into()),
+        Ok(None) => {}
+        Err(elapsed) => {
+            debug!("Not all peers answered with the Register within {timeout:?}");
+            responses.push(Err(Error::ResponseTimeout(elapsed)));
         }
+    }
 
-        // If no register was gotten, we will return the first error sent to us.
-        for resp in responses.iter().flatten() {
-            if let Response::Query(QueryResponse::GetRegister(result)) = resp {
-                let _ = result.clone()?;
+    // If no register was gotten, we will return the first error sent to us.
+    for resp in responses.iter().flatten() {
+        if let Response::Query(QueryResponse::GetRegister(result)) = resp {
+            let _ = result.clone()?;
+        };
+    }
+
+    // If there were no success or fail to the expected query,
+    // we check if there were any send errors.
+    for resp in responses {
+        let _ = resp?;
+    }
+
+    // If there was none of the above, then we had unexpected responses.
+    Err(Error::UnexpectedResponses)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use eyre::Result;
+    use tokio::time::Instant;
+
+    #[tokio::test]
+    async fn register_fetch_is_not_held_up_by_slow_peers() -> Result<()> {
+        let owner = User::Key(bls::SecretKey::random().public_key());
+        let policy = Policy {
+            owner,
+            permissions: Default::default(),
+        };
+        let slow_register = RegisterReplica::new(
+            owner,
+            XorName::random(&mut rand::thread_rng()),
+            0,
+            policy.clone(),
+        );
+        let fast_register =
+            RegisterReplica::new(owner, XorName::random(&mut rand::thread_rng()), 0, policy);
+
+        let slow_peer = PeerId::random();
+        let fast_peer = PeerId::random();
+        let timeout = Duration::from_millis(200);
+
+        let started = Instant::now();
+        let register = first_register_within(vec![slow_peer, fast_peer], timeout, |peer| {
+            let (register, delay) = if peer == slow_peer {
+                (slow_register.clone(), Duration::from_secs(30))
+            } else {
+                (fast_register.clone(), Duration::ZERO)
             };
-        }
+            async move {
+                tokio::time::sleep(delay).await;
+                Ok(Response::Query(QueryResponse::GetRegister(Ok(
+                    register.into()
+                ))))
+            }
+        })
+        .await?;
 
-        // If there were no success or fail to the expected query,
-        // we check if there were any send errors.
-        for resp in responses {
-            let _ = resp?;
-        }
+        assert_eq!(register, fast_register);
+        assert!(started.elapsed() < timeout);
 
-        // If there was none of the above, then we had unexpected responses.
-        Err(Error::UnexpectedResponses)
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn register_fetch_gives_up_on_all_peers_at_once() -> Result<()> {
+        let peers: Vec<_> = (0..CLOSE_GROUP_SIZE).map(|_| PeerId::random()).collect();
+        let timeout = Duration::from_millis(200);
+
+        let started = Instant::now();
+        let result = first_register_within(peers, timeout, |_| async {
+            tokio::time::sleep(Duration::from_secs(30)).await;
+            Err(Error::UnexpectedResponses)
+        })
+        .await;
+
+        assert!(matches!(result, Err(Error::ResponseTimeout(_))));
+        // The peers are given up on after a single timeout, rather than one per peer.
+        assert!(started.elapsed() < timeout * 2);
+
+        Ok(())
     }
 }
@@ -11,36 +11,182 @@ pub(crate) mod verifying_client;
 
 use super::Client;
 
-use crate::domain::wallet::{Error, Result, SendWallet};
+use crate::domain::wallet::{
+    Error, LocalWallet, Result, SendWallet, TxRecord, VerifyingClient, Wallet,
+};
 
-use sn_dbc::{Dbc, PublicAddress, Token};
+use sn_dbc::{Dbc, DbcId, MainKey, PublicAddress, Token};
+use std::{path::Path, time::Duration};
+use tokio::time::{sleep, Instant};
+
+// Default maximum number of dbcs a send is deemed reasonable to take as inputs.
+const DEFAULT_MAX_SEND_INPUTS: usize = 10;
+// Interval the spentbook is polled at, when awaiting a send to be confirmed.
+const SPEND_CONFIRMATION_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// The result of sending tokens, exposing the addresses
+/// each of the outputs was reissued to, for auditing purposes.
+#[derive(Debug, Clone)]
+pub struct SendReceipt {
+    /// The dbc created for the recipient.
+    pub dbc: Dbc,
+    /// The address the payment was reissued to, i.e. the recipient's.
+    pub payment_address: PublicAddress,
+    /// The address the change was reissued to, if there was any change.
+    pub change_address: Option<PublicAddress>,
+}
+
+/// The result of sending tokens and awaiting the spend to be recorded in the spentbook.
+#[derive(Debug, Clone)]
+pub struct SendConfirmation {
+    /// The dbc created for the recipient.
+    pub dbc: Dbc,
+    /// Whether the spends of the dbc were found in the spentbook before the timeout.
+    pub confirmed: bool,
+}
 
 /// A wallet client can be used to send and
 /// receive tokens to/from other wallets.
 pub struct WalletClient<W: SendWallet> {
     client: Client,
     wallet: W,
+    // Maximum number of dbcs a send is deemed reasonable to take as inputs.
+    max_send_inputs: usize,
 }
 
 impl<W: SendWallet> WalletClient<W> {
     /// Create a new wallet client.
     pub fn new(client: Client, wallet: W) -> Self {
-        Self { client, wallet }
+        Self {
+            client,
+            wallet,
+            max_send_inputs: DEFAULT_MAX_SEND_INPUTS,
+        }
+    }
+
+    /// Set the maximum number of dbcs a send is deemed reasonable to take as inputs,
+    /// beyond which the wallet is advised to consolidate its dust first.
+    pub fn set_max_send_inputs(&mut self, max_send_inputs: usize) {
+        self.max_send_inputs = max_send_inputs;
+    }
+
+    /// Whether sending the given amount would take more dbcs as inputs than the configured
+    /// maximum, and so more fees, in which case the dust had better be consolidated first.
+    pub fn send_requires_consolidation(&self, amount: Token) -> bool {
+        self.wallet
+            .requires_consolidation(amount, self.max_send_inputs)
     }
 
     /// Send tokens to another wallet.
-    pub async fn send(&mut self, amount: Token, to: PublicAddress) -> Result<Dbc> {
-        let dbcs = self.wallet.send(vec![(amount, to)], &self.client).await?;
-        match &dbcs[..] {
-            [info, ..] => Ok(info.dbc.clone()),
+    /// Returns the dbc created for the recipient, along with the addresses
+    /// the payment and the change, if any, were reissued to.
+    pub async fn send(&mut self, amount: Token, to: PublicAddress) -> Result<SendReceipt> {
+        let outputs = self.wallet.send(vec![(amount, to)], &self.client).await?;
+        match &outputs.created_dbcs[..] {
+            [info, ..] => Ok(SendReceipt {
+                dbc: info.dbc.clone(),
+                payment_address: *info.dbc.public_address(),
+                change_address: outputs
+                    .change_dbc
+                    .as_ref()
+                    .map(|change_dbc| *change_dbc.public_address()),
+            }),
             [] => Err(Error::CouldNotSendTokens(
                 "No DBCs were returned from the wallet.".into(),
             )),
         }
     }
 
+    /// Send tokens to another wallet, and then await the spends of the created dbc
+    /// to be recorded in the spentbook, polling it until the given timeout.
+    /// The tokens are sent regardless of the spends being confirmed before the timeout,
+    /// which only tells whether the recipient can already verify the dbc.
+    pub async fn send_and_confirm(
+        &mut self,
+        amount: Token,
+        to: PublicAddress,
+        timeout: Duration,
+    ) -> Result<SendConfirmation> {
+        let dbc = self.send(amount, to).await?.dbc;
+        let confirmed = self.await_spends_recorded(&dbc, timeout).await;
+        Ok(SendConfirmation { dbc, confirmed })
+    }
+
+    // Polls the spentbook until it holds all the spends of the dbc, or the timeout elapses.
+    async fn await_spends_recorded(&self, dbc: &Dbc, timeout: Duration) -> bool {
+        let deadline = Instant::now() + timeout;
+        loop {
+            match self.client.verify(dbc).await {
+                Ok(()) => return true,
+                Err(err) => trace!("Spends of dbc {:?} not confirmed yet: {err:?}", dbc.id()),
+            }
+            if Instant::now() + SPEND_CONFIRMATION_POLL_INTERVAL > deadline {
+                warn!(
+                    "Spends of dbc {:?} not confirmed within {timeout:?}",
+                    dbc.id()
+                );
+                return false;
+            }
+            sleep(SPEND_CONFIRMATION_POLL_INTERVAL).await;
+        }
+    }
+
+    /// Split the tokens held by the wallet into dbcs of the given denominations,
+    /// e.g. to make many small payments without the churn of a change dbc for each.
+    /// Returns the dbcs created, which are held by the wallet along with any change.
+    pub async fn split(&mut self, denominations: Vec<Token>) -> Result<Vec<Dbc>> {
+        let outputs = self.wallet.split(denominations, &self.client).await?;
+        Ok(outputs
+            .created_dbcs
+            .into_iter()
+            .map(|created| created.dbc)
+            .collect())
+    }
+
+    /// List the dbcs held by the wallet which are available to spend, along with their value.
+    pub fn available_dbcs(&self) -> Vec<(DbcId, Token)> {
+        self.wallet.available_dbcs()
+    }
+
+    /// List the dbcs held by the wallet which are available to spend and worth at least `min`,
+    /// along with their value, so dust dbcs can be left out of a send.
+    pub fn dbcs_above(&self, min: Token) -> Vec<(DbcId, Token)> {
+        self.wallet.dbcs_above(min)
+    }
+
     /// Return the wallet.
     pub fn into_wallet(self) -> W {
         self.wallet
     }
 }
+
+impl WalletClient<LocalWallet> {
+    /// Create a new wallet client, with the wallet stored at the given path
+    /// decrypted with the given passphrase.
+    pub async fn load_encrypted(client: Client, root_dir: &Path, passphrase: &str) -> Result<Self> {
+        let wallet = LocalWallet::load_encrypted_from(root_dir, passphrase).await?;
+        Ok(Self::new(client, wallet))
+    }
+
+    /// Reconstruct the wallet of the given key at the given path, e.g. when its local state
+    /// has been lost, out of the dbcs received by it which the network reports as unspent.
+    /// Returns the balance recovered. See [`LocalWallet::recover`] for which dbcs can be recovered.
+    pub async fn recover_from_network(
+        client: Client,
+        root_dir: &Path,
+        key: MainKey,
+    ) -> Result<Token> {
+        let wallet = LocalWallet::recover(root_dir, key, &client).await?;
+        Ok(wallet.balance())
+    }
+
+    /// The record of each of the sends made from the wallet, along with the fee paid for each.
+    pub fn tx_history(&self) -> &[TxRecord] {
+        self.wallet.tx_history()
+    }
+
+    /// Store the wallet to disk, encrypted with the given passphrase.
+    pub async fn save_encrypted(&mut self, passphrase: &str) -> Result<()> {
+        self.wallet.store_encrypted(passphrase).await
+    }
+}
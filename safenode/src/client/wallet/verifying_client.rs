@@ -11,7 +11,7 @@ use super::Client;
 use crate::domain::wallet::{Error, Result, VerifyingClient};
 
 use futures::future::join_all;
-use sn_dbc::Dbc;
+use sn_dbc::{Dbc, DbcId};
 
 #[async_trait::async_trait]
 impl VerifyingClient for Client {
@@ -40,4 +40,10 @@ impl VerifyingClient for Client {
             "The spends in network were not the same as the ones in the DBC.".into(),
         ))
     }
+
+    async fn is_spent(&self, dbc_id: &DbcId) -> Result<bool> {
+        Client::is_spent(self, dbc_id)
+            .await
+            .map_err(|err| Error::CouldNotVerifyTransfer(err.to_string()))
+    }
 }
@@ -88,7 +88,8 @@ pub(crate) async fn send(
     let new_dbc = wallet_client
         .send(amount, to)
         .await
-        .expect("Tokens shall be successfully sent.");
+        .expect("Tokens shall be successfully sent.")
+        .dbc;
 
     let mut wallet = wallet_client.into_wallet();
     wallet
@@ -6,7 +6,7 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use crate::network::CLOSE_GROUP_SIZE;
+use crate::{network::CLOSE_GROUP_SIZE, protocol::storage::ChunkAddress};
 
 use libp2p::{
     identity::PeerId,
@@ -17,7 +17,18 @@ use libp2p::{
     },
 };
 use std::time::{Duration, Instant};
-use std::{borrow::Cow, collections::HashSet, fs, path::PathBuf, vec};
+use std::{
+    borrow::Cow,
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
+    vec,
+};
+
+// Name of the file, within the storage dir, where the keys of the pinned records are persisted.
+const PINNED_RECORDS_FILENAME: &str = "pinned_records";
+// Name of the file the keys of the pinned records are written to before replacing the persisted ones.
+const PINNED_RECORDS_TMP_FILENAME: &str = "pinned_records.tmp";
 
 // Control the random replication factor, which means `one in x` copies got replicated each time.
 const RANDOM_REPLICATION_FACTOR: usize = CLOSE_GROUP_SIZE / 2;
@@ -33,6 +44,8 @@ pub(crate) struct DiskBackedRecordStore {
     config: DiskBackedRecordStoreConfig,
     /// A set of keys, each corresponding to a data `Record` stored on disk.
     records: HashSet<Key>,
+    /// Keys of the records which are never to be removed, even if out of our range.
+    pinned: HashSet<Key>,
     /// Records for the next replication.
     replication_records: Vec<Key>,
     /// Time that replication triggered.
@@ -69,16 +82,18 @@ impl DiskBackedRecordStore {
 
     /// Creates a new `DiskBackedStore` with the given configuration.
     pub(crate) fn with_config(local_id: PeerId, config: DiskBackedRecordStoreConfig) -> Self {
+        let pinned = Self::read_pinned(&config.storage_dir);
         DiskBackedRecordStore {
             local_key: KBucketKey::from(local_id),
             config,
             records: Default::default(),
+            pinned,
             replication_records: Default::default(),
             replication_start: Instant::now(),
         }
     }
 
-    /// Retains the records satisfying a predicate.
+    /// Retains the records satisfying a predicate, as well as the pinned ones.
     #[allow(dead_code)]
     pub(crate) fn retain<F>(&mut self, predicate: F)
     where
@@ -94,6 +109,62 @@ impl DiskBackedRecordStore {
         to_be_removed.iter().for_each(|key| self.remove(key));
     }
 
+    /// Whether the record of the given key is held by the store, without reading it from disk.
+    pub(crate) fn contains(&self, key: &Key) -> bool {
+        self.records.contains(key)
+    }
+
+    /// Pins the chunk at the given address, so it's never removed from the store
+    /// until it's unpinned.
+    pub(crate) fn pin(&mut self, address: &ChunkAddress) {
+        if self.pinned.insert(Key::new(address.name())) {
+            self.write_pinned();
+        }
+    }
+
+    /// Unpins the chunk at the given address, so it can be removed from the store again.
+    pub(crate) fn unpin(&mut self, address: &ChunkAddress) {
+        if self.pinned.remove(&Key::new(address.name())) {
+            self.write_pinned();
+        }
+    }
+
+    // Reads the keys of the pinned records persisted in the given storage dir, if any.
+    fn read_pinned(storage_dir: &Path) -> HashSet<Key> {
+        let file_path = storage_dir.join(PINNED_RECORDS_FILENAME);
+        if !file_path.exists() {
+            return HashSet::new();
+        }
+
+        let keys: Vec<Vec<u8>> = match fs::read(&file_path)
+            .map_err(|err| err.to_string())
+            .and_then(|bytes| bincode::deserialize(&bytes).map_err(|err| err.to_string()))
+        {
+            Ok(keys) => keys,
+            Err(err) => {
+                error!("Error while reading pinned records from {file_path:?}: {err}");
+                return HashSet::new();
+            }
+        };
+        keys.into_iter().map(Key::from).collect()
+    }
+
+    // Persists the keys of the pinned records into the storage dir. They're written to a tmp
+    // file first, then moved over the previous ones, so they're never left partially written.
+    fn write_pinned(&self) {
+        let file_path = self.config.storage_dir.join(PINNED_RECORDS_FILENAME);
+        let tmp_path = self.config.storage_dir.join(PINNED_RECORDS_TMP_FILENAME);
+        let keys: Vec<Vec<u8>> = self.pinned.iter().map(|key| key.to_vec()).collect();
+        let result = bincode::serialize(&keys)
+            .map_err(|err| err.to_string())
+            .and_then(|bytes| fs::write(&tmp_path, bytes).map_err(|err| err.to_string()))
+            .and_then(|()| fs::rename(&tmp_path, &file_path).map_err(|err| err.to_string()));
+        match result {
+            Ok(()) => trace!("Wrote {} pinned record/s to disk", keys.len()),
+            Err(err) => error!("Error while writing pinned records to {file_path:?}: {err}"),
+        }
+    }
+
     /// Trigger a future replication
     pub(crate) fn trigger_replication(&mut self) {
         self.replication_start = Instant::now();
@@ -221,6 +292,11 @@ impl RecordStore for DiskBackedRecordStore {
     }
 
     fn remove(&mut self, k: &Key) {
+        if self.pinned.contains(k) {
+            debug!("Record with key {k:?} is pinned, not removing it.");
+            return;
+        }
+
         let _ = self.records.remove(k);
 
         let filename = Self::key_to_hex(k);
@@ -245,27 +321,30 @@ impl RecordStore for DiskBackedRecordStore {
         records.into_iter()
     }
 
-    fn add_provider(&mut self, _record: ProviderRecord) -> Result<()> {
-        todo!("ProviderRecords are not used currently.")
+    // ProviderRecords are not used currently, hence they're not stored,
+    // so any provider query results in no providers rather than failing.
+    fn add_provider(&mut self, record: ProviderRecord) -> Result<()> {
+        trace!("Not storing ProviderRecord for key {:?}", record.key);
+        Ok(())
     }
 
     fn providers(&self, _key: &Key) -> Vec<ProviderRecord> {
-        todo!("ProviderRecords are not used currently.")
+        vec![]
     }
 
     fn provided(&self) -> Self::ProvidedIter<'_> {
-        todo!("ProviderRecords are not used currently.")
+        vec![].into_iter()
     }
 
-    fn remove_provider(&mut self, _key: &Key, _provider: &PeerId) {
-        todo!("ProviderRecords are not used currently.")
-    }
+    fn remove_provider(&mut self, _key: &Key, _provider: &PeerId) {}
 }
 
 #[allow(trivial_casts)]
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::protocol::storage::Chunk;
+    use bytes::Bytes;
     use libp2p::{core::multihash::Multihash, kad::kbucket::Key as KBucketKey};
     use quickcheck::*;
 
@@ -344,4 +423,46 @@ mod tests {
         }
         quickcheck(prop as fn(_))
     }
+
+    #[test]
+    fn provider_records_are_ignored() {
+        fn prop(r: ArbitraryProviderRecord) {
+            let r = r.0;
+            let mut store = DiskBackedRecordStore::new(PeerId::random());
+            assert!(store.add_provider(r.clone()).is_ok());
+            assert!(store.providers(&r.key).is_empty());
+            assert_eq!(store.provided().count(), 0);
+            store.remove_provider(&r.key, &r.provider);
+        }
+        quickcheck(prop as fn(_))
+    }
+
+    #[test]
+    fn pinned_chunk_survives_removal_until_unpinned() -> eyre::Result<()> {
+        let storage_dir = assert_fs::TempDir::new()?;
+        let config = DiskBackedRecordStoreConfig {
+            storage_dir: storage_dir.path().to_path_buf(),
+            ..Default::default()
+        };
+        let mut store = DiskBackedRecordStore::with_config(PeerId::random(), config.clone());
+
+        let chunk = Chunk::new(Bytes::from_static(b"pinned chunk"));
+        let key = Key::new(chunk.name());
+        store.put(Record::new(key.clone(), chunk.value().to_vec()))?;
+        store.pin(chunk.address());
+
+        // a removal of everything out of our range, which our range is now empty of
+        store.retain(|_| false);
+        assert!(store.get(&key).is_some());
+
+        // the pin is persisted alongside the records
+        let reopened = DiskBackedRecordStore::with_config(PeerId::random(), config);
+        assert!(reopened.pinned.contains(&key));
+
+        store.unpin(chunk.address());
+        store.retain(|_| false);
+        assert!(store.get(&key).is_none());
+
+        Ok(())
+    }
 }
@@ -14,7 +14,7 @@ pub(crate) use self::{
     disk_backed_record_store::{
         DiskBackedRecordStore, DiskBackedRecordStoreConfig, REPLICATION_INTERVAL,
     },
-    registers::{RegisterReplica, RegisterStorage},
+    registers::{register_op_id, RegisterReplica, RegisterStorage},
     spends::SpendStorage,
 };
 
@@ -0,0 +1,179 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::RegisterReplica;
+
+use crate::protocol::storage::RegisterAddress;
+
+use std::collections::BTreeMap;
+
+/// In-memory cache of Registers, optionally capped by the estimated size, in bytes, of all the
+/// Registers it holds, rather than by their number, so a few huge Registers cannot blow the
+/// memory up. The least recently used Registers are evicted until it's back under the cap.
+#[derive(Debug, Default)]
+pub(super) struct RegisterCache {
+    // Maximum estimated size of all the Registers held, unbounded if not set.
+    max_bytes: Option<usize>,
+    // The Registers held, along with their estimated size and the tick they were last used at.
+    registers: BTreeMap<RegisterAddress, (RegisterReplica, usize, u64)>,
+    // The addresses of the Registers held, by the tick they were last used at, oldest first.
+    recently_used: BTreeMap<u64, RegisterAddress>,
+    total_bytes: usize,
+    tick: u64,
+}
+
+impl RegisterCache {
+    pub(super) fn with_max_bytes(max_bytes: usize) -> Self {
+        Self {
+            max_bytes: Some(max_bytes),
+            ..Default::default()
+        }
+    }
+
+    pub(super) fn contains_key(&self, address: &RegisterAddress) -> bool {
+        self.registers.contains_key(address)
+    }
+
+    /// Returns a copy of the Register, marking it as the most recently used.
+    pub(super) fn get(&mut self, address: &RegisterAddress) -> Option<RegisterReplica> {
+        let tick = self.next_tick();
+        let (register, _, last_used) = self.registers.get_mut(address)?;
+        let _ = self.recently_used.remove(last_used);
+        let _ = self.recently_used.insert(tick, *address);
+        *last_used = tick;
+        Some(register.clone())
+    }
+
+    /// Inserts the Register, evicting the least recently used ones if needed to stay under
+    /// the cap. A Register bigger than the cap by itself is not held at all.
+    pub(super) fn insert(&mut self, address: RegisterAddress, register: RegisterReplica) {
+        let size = estimated_size(&register);
+        let _ = self.remove(&address);
+        if self.max_bytes.is_some_and(|max_bytes| size > max_bytes) {
+            trace!("Register {address:?} of {size} bytes is too big to be cached");
+            return;
+        }
+
+        let tick = self.next_tick();
+        let _ = self.registers.insert(address, (register, size, tick));
+        let _ = self.recently_used.insert(tick, address);
+        self.total_bytes = self.total_bytes.saturating_add(size);
+
+        while self
+            .max_bytes
+            .is_some_and(|max_bytes| self.total_bytes > max_bytes)
+        {
+            let oldest = match self.recently_used.values().next() {
+                Some(oldest) => *oldest,
+                None => break,
+            };
+            trace!("Evicting Register {oldest:?} from the cache");
+            let _ = self.remove(&oldest);
+        }
+    }
+
+    pub(super) fn remove(&mut self, address: &RegisterAddress) -> Option<RegisterReplica> {
+        let (register, size, last_used) = self.registers.remove(address)?;
+        let _ = self.recently_used.remove(&last_used);
+        self.total_bytes = self.total_bytes.saturating_sub(size);
+        Some(register)
+    }
+
+    /// Estimated size, in bytes, of all the Registers held.
+    #[cfg(test)]
+    pub(super) fn total_bytes(&self) -> usize {
+        self.total_bytes
+    }
+
+    fn next_tick(&mut self) -> u64 {
+        self.tick += 1;
+        self.tick
+    }
+}
+
+// Estimates the size a Register takes up in memory by its serialised size. One which
+// can't be serialised is deemed too big to be cached under any cap.
+fn estimated_size(register: &RegisterReplica) -> usize {
+    bincode::serialized_size(register)
+        .ok()
+        .and_then(|size| usize::try_from(size).ok())
+        .unwrap_or(usize::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{estimated_size, RegisterCache};
+
+    use crate::{
+        domain::storage::registers::RegisterReplica,
+        protocol::storage::{registers::User, RegisterAddress},
+    };
+
+    use bls::SecretKey;
+    use eyre::Result;
+    use std::collections::BTreeSet;
+
+    #[test]
+    fn registers_are_evicted_to_stay_under_the_byte_cap() -> Result<()> {
+        // Registers of increasing sizes, each holding as many entries of 512 bytes.
+        let registers = (1..=4)
+            .map(register_with_entries)
+            .collect::<Result<Vec<_>>>()?;
+        let sizes: Vec<_> = registers.iter().map(estimated_size).collect();
+        let address = |i: usize| *registers[i].address();
+
+        // Enough room for the two biggest Registers, but not for all of them.
+        let max_bytes = sizes[2] + sizes[3];
+        let mut cache = RegisterCache::with_max_bytes(max_bytes);
+        for register in &registers[..3] {
+            cache.insert(*register.address(), register.clone());
+            assert!(cache.total_bytes() <= max_bytes);
+        }
+        assert_eq!(cache.total_bytes(), sizes[0] + sizes[1] + sizes[2]);
+
+        // The first one is used again, so the second one is the least recently used.
+        assert_eq!(cache.get(&address(0)).as_ref(), Some(&registers[0]));
+        cache.insert(address(3), registers[3].clone());
+        assert!(cache.total_bytes() <= max_bytes);
+        assert!(!cache.contains_key(&address(1)));
+        assert!(cache.contains_key(&address(3)));
+        assert_eq!(
+            cache.total_bytes(),
+            [0, 2, 3]
+                .into_iter()
+                .filter(|i| cache.contains_key(&address(*i)))
+                .map(|i| sizes[i])
+                .sum::<usize>()
+        );
+
+        // A Register bigger than the cap by itself is not cached at all.
+        let mut small_cache = RegisterCache::with_max_bytes(sizes[0]);
+        small_cache.insert(address(3), registers[3].clone());
+        assert!(!small_cache.contains_key(&address(3)));
+        assert_eq!(small_cache.total_bytes(), 0);
+
+        // Without a cap, every Register is held.
+        let mut unbounded = RegisterCache::default();
+        for register in &registers {
+            unbounded.insert(*register.address(), register.clone());
+        }
+        assert_eq!(unbounded.total_bytes(), sizes.iter().sum::<usize>());
+
+        Ok(())
+    }
+
+    fn register_with_entries(num_entries: usize) -> Result<RegisterReplica> {
+        let owner = User::Key(SecretKey::random().public_key());
+        let address = RegisterAddress::new(rand::random(), 0);
+        let mut register = RegisterReplica::new_owned(owner, *address.name(), address.tag());
+        for i in 0..num_entries {
+            let _ = register.write(vec![i as u8; 512], BTreeSet::new())?;
+        }
+        Ok(register)
+    }
+}
@@ -0,0 +1,98 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::protocol::messages::RegisterCmd;
+
+use std::time::SystemTime;
+use thiserror::Error;
+
+// The high bit flags the first byte of a cmd file as its format tag. Files written before
+// cmd files were tagged start with the bincode variant index of the cmd, i.e. 0 or 1,
+// so they can never be mistaken for a tagged file.
+const FORMAT_TAG_FLAG: u8 = 0x80;
+
+/// Format Register cmd files are serialised with, tagged by its version
+/// in the first byte of each file, so files of different formats can
+/// coexist in a store and be migrated one at a time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(super) enum CmdFileFormat {
+    /// The cmd serialised with bincode.
+    Bincode,
+    /// The cmd serialised with bincode, along with the time the node stored it at.
+    #[default]
+    BincodeWithStoredAt,
+}
+
+impl CmdFileFormat {
+    fn version(self) -> u8 {
+        match self {
+            Self::Bincode => 1,
+            Self::BincodeWithStoredAt => 2,
+        }
+    }
+
+    fn from_version(version: u8) -> Option<Self> {
+        match version {
+            1 => Some(Self::Bincode),
+            2 => Some(Self::BincodeWithStoredAt),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+pub(super) enum CmdFileError {
+    #[error("Unknown Register cmd file format version: {0}")]
+    UnknownVersion(u8),
+    #[error("Failed to serialise/deserialise the Register cmd: {0}")]
+    Bincode(#[from] bincode::Error),
+}
+
+/// Serialises the cmd in the given format, prefixed with the format tag. The time the cmd
+/// is stored at is kept alongside it, outside of its signed op, if the format holds it.
+pub(super) fn encode_cmd(
+    cmd: &RegisterCmd,
+    stored_at: SystemTime,
+    format: CmdFileFormat,
+) -> Result<Vec<u8>, CmdFileError> {
+    let mut data = vec![FORMAT_TAG_FLAG | format.version()];
+    match format {
+        CmdFileFormat::Bincode => bincode::serialize_into(&mut data, cmd)?,
+        CmdFileFormat::BincodeWithStoredAt => {
+            bincode::serialize_into(&mut data, &(stored_at, cmd))?
+        }
+    }
+    Ok(data)
+}
+
+/// Deserialises the cmd out of the contents of a cmd file, in the format it's tagged with,
+/// or out of the whole contents as bincode if it's untagged, i.e. written before the tag.
+pub(super) fn decode_cmd(data: &[u8]) -> Result<RegisterCmd, CmdFileError> {
+    decode_stored_cmd(data).map(|(cmd, _)| cmd)
+}
+
+/// Same as `decode_cmd`, but also returns the time the cmd was stored at,
+/// if the format of the file holds it.
+pub(super) fn decode_stored_cmd(
+    data: &[u8],
+) -> Result<(RegisterCmd, Option<SystemTime>), CmdFileError> {
+    match data.split_first() {
+        Some((tag, serialized_cmd)) if tag & FORMAT_TAG_FLAG != 0 => {
+            let version = tag & !FORMAT_TAG_FLAG;
+            match CmdFileFormat::from_version(version) {
+                Some(CmdFileFormat::Bincode) => Ok((bincode::deserialize(serialized_cmd)?, None)),
+                Some(CmdFileFormat::BincodeWithStoredAt) => {
+                    let (stored_at, cmd) = bincode::deserialize(serialized_cmd)?;
+                    Ok((cmd, Some(stored_at)))
+                }
+                None => Err(CmdFileError::UnknownVersion(version)),
+            }
+        }
+        _ => Ok((bincode::deserialize(data)?, None)),
+    }
+}
@@ -6,30 +6,53 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
+mod cache;
+mod cmd_format;
 mod reg_crdt;
 mod reg_replica;
 
 pub(crate) use reg_replica::RegisterReplica;
 
+use cache::RegisterCache;
+use cmd_format::{decode_cmd, decode_stored_cmd, encode_cmd, CmdFileFormat};
+use reg_replica::{MAX_REG_ENTRY_SIZE, MAX_REG_NUM_ENTRIES};
+
 use super::{prefix_tree_path, Result};
 
-use crate::protocol::{
-    error::{Error as ProtocolError, StorageError as Error},
-    messages::{
-        EditRegister, QueryResponse, RegisterCmd, RegisterQuery, ReplicatedRegisterLog,
-        SignedRegisterCreate, SignedRegisterEdit,
-    },
-    storage::{
-        registers::{Action, EntryHash, User},
-        DataAuthority, RegisterAddress,
+use crate::{
+    network::Network,
+    protocol::{
+        error::{Error as ProtocolError, StorageError as Error},
+        messages::{
+            compress_register_entries, EditRegister, QueryResponse, RegisterCmd, RegisterQuery,
+            ReplicatedRegisterLog, SignedRegisterCreate, SignedRegisterEdit,
+            SignedRegisterSetExpiry,
+        },
+        storage::{
+            registers::{Action, Entry, EntryHash, User},
+            DataAuthority, RegisterAddress,
+        },
     },
 };
 
 use bincode::serialize;
-use std::path::{Path, PathBuf};
+use bls::SecretKey;
+use futures::{stream, Stream, StreamExt};
+use itertools::Itertools;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    io,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::SystemTime,
+};
 use tokio::{
-    fs::{create_dir_all, read, remove_file, File},
+    fs::{create_dir_all, read, remove_file, rename, File},
     io::AsyncWriteExt,
+    spawn,
+    sync::{oneshot, Mutex, RwLock},
 };
 use tracing::trace;
 use walkdir::WalkDir;
@@ -37,29 +60,211 @@ use walkdir::WalkDir;
 pub(super) type RegisterLog = Vec<RegisterCmd>;
 
 const REGISTERS_STORE_DIR_NAME: &str = "registers";
+// Default number of cmd files a Register's log can hold before it's compacted.
+const DEFAULT_COMPACT_AFTER: usize = 256;
+// Name of the file a Register's log is compacted into.
+const REGISTER_SNAPSHOT_FILE_NAME: &str = "snapshot";
+// Name of the file a snapshot is written to before replacing the current one.
+const REGISTER_SNAPSHOT_TMP_FILE_NAME: &str = "snapshot.tmp";
+// Name of the file briefly written at the root of the store to check it's writable.
+const WRITABLE_PROBE_FILE_NAME: &str = ".writable_probe";
+
+// Prefix of the name of the file a batch of cmds is journaled to, in the Register's log dir,
+// before each of them is written to its own file. Each batch gets its own journal, named
+// after a random suffix, so batches written concurrently don't overwrite each other's.
+const REGISTER_JOURNAL_FILE_PREFIX: &str = "journal-";
+
+// Hook used to durably persist a dir's entries to disk, replaceable to observe the syncs issued.
+type SyncDirHook = Arc<dyn Fn(&Path) -> io::Result<()> + Send + Sync>;
+
+// Clock telling the time cmds are recorded as stored at upon writing them, replaceable in tests.
+type Clock = Arc<dyn Fn() -> SystemTime + Send + Sync>;
+
+// Predicate telling whether the node is responsible for the Register at the given address.
+type ResponsibilityCheck = Arc<dyn Fn(&RegisterAddress) -> bool + Send + Sync>;
 
 #[derive(Clone, Debug)]
 struct StoredRegister {
     state: Option<RegisterReplica>,
     op_log: RegisterLog,
     op_log_path: PathBuf,
+    // Number of cmds, at the start of the op_log, already applied to the state
+    // loaded from a snapshot, hence not to be replayed when reconstructing the Register.
+    replay_from: usize,
+    // Time each of the cmds of the op_log, by its id, was stored at on this node, if known.
+    stored_at: BTreeMap<String, SystemTime>,
+}
+
+// A Register's log compacted into a single file, along with the Register reconstructed from it.
+#[derive(Debug, Serialize, Deserialize)]
+struct RegisterSnapshot {
+    state: Option<RegisterReplica>,
+    op_log: RegisterLog,
+    // Time each of the compacted cmds, by its id, was stored at on this node, if known.
+    stored_at: BTreeMap<String, SystemTime>,
+}
+
+// A snapshot written before the time the compacted cmds were stored at was kept in it.
+#[derive(Deserialize)]
+struct LegacyRegisterSnapshot {
+    state: Option<RegisterReplica>,
+    op_log: RegisterLog,
+}
+
+/// What compacting the log of a Register reclaimed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) struct CompactionStats {
+    /// Number of cmd files in the log before compacting it.
+    pub(crate) cmds_before: usize,
+    /// Number of cmd files left in the log after compacting it.
+    pub(crate) cmds_after: usize,
+    /// Disk space freed up by compacting the log, in bytes.
+    pub(crate) bytes_reclaimed: u64,
+}
+
+/// Estimated cost of reconstructing a Register out of its log, told without reading the log.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) struct ReconCost {
+    /// Number of cmd files in the log, i.e. of cmds to be replayed, but those compacted
+    /// into the snapshot, which only count towards `total_bytes`.
+    pub(crate) op_log_len: usize,
+    /// Number of bytes to be read from disk, i.e. the size of all the cmd files and snapshot.
+    pub(crate) total_bytes: u64,
+}
+
+/// Outcome of verifying the signatures of all the cmds in a Register's log,
+/// and whether the entries they write reference any entry missing from it.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct LogVerificationReport {
+    /// Number of cmds in the log which were verified.
+    pub(crate) cmds_verified: usize,
+    /// Ids of the cmds whose signature is invalid, sorted.
+    pub(crate) invalid_cmds: Vec<String>,
+    /// Hashes of the entries referenced as parents by entries in the log, but not written
+    /// by any of its cmds, sorted. These are the ancestors to be fetched from other replicas.
+    pub(crate) gaps: Vec<EntryHash>,
 }
 
 /// Operations over the RegisterReplica data type and its storage.
 #[derive(Clone)]
 pub(crate) struct RegisterStorage {
     file_store_path: PathBuf,
+    sync_dir: SyncDirHook,
+    clock: Clock,
+    // Tells whether a cmd is for a Register the node is responsible for, rejecting it otherwise.
+    is_responsible: ResponsibilityCheck,
+    // Whether the cmds which can't be applied upon reconstructing a Register are skipped,
+    // rather than failing to load the whole Register.
+    lenient_load: bool,
+    // The Registers as created, i.e. with their policy and initial entries but without edits,
+    // used to validate edits without reconstructing the whole Register from its log.
+    created_registers: Arc<RwLock<RegisterCache>>,
+    // Number of cmd files a Register's log can hold before it's automatically compacted.
+    compact_after: usize,
+    // The Registers whose log is currently being compacted.
+    compacting: Arc<Mutex<BTreeSet<RegisterAddress>>>,
+    // The network to notify of the edits applied to a Register upon a replication, if any.
+    network: Option<Network>,
 }
 
 impl RegisterStorage {
     pub(crate) fn new(path: &Path) -> Self {
+        Self::with_compact_after(path, DEFAULT_COMPACT_AFTER)
+    }
+
+    /// Same as `new` API but compacting the log of a Register once
+    /// it holds more than the provided `compact_after` cmd files.
+    pub(crate) fn with_compact_after(path: &Path, compact_after: usize) -> Self {
         Self {
             file_store_path: path.join(REGISTERS_STORE_DIR_NAME),
+            sync_dir: Arc::new(sync_dir),
+            clock: Arc::new(SystemTime::now),
+            is_responsible: Arc::new(|_| true),
+            lenient_load: false,
+            created_registers: Default::default(),
+            compact_after,
+            compacting: Default::default(),
+            network: None,
+        }
+    }
+
+    /// Sets the network to emit a `NetworkEvent::RegisterUpdated` through,
+    /// whenever new edits are applied to a Register upon a replication.
+    pub(crate) fn set_network(&mut self, network: Network) {
+        self.network = Some(network);
+    }
+
+    /// Sets the predicate telling whether the node is responsible for a Register, given its
+    /// address, e.g. whether it falls within the XOR range of addresses the node stores.
+    /// Cmds for Registers the node isn't responsible for are then rejected with an
+    /// `Error::NotResponsible`, rather than stored, while all of them are accepted by default.
+    pub(crate) fn set_responsibility<F>(&mut self, is_responsible: F)
+    where
+        F: Fn(&RegisterAddress) -> bool + Send + Sync + 'static,
+    {
+        self.is_responsible = Arc::new(is_responsible);
+    }
+
+    /// Sets whether Registers are loaded leniently, i.e. skipping, and logging, any cmd of their
+    /// log which can't be applied upon reconstructing them, e.g. a single bad edit, so it doesn't
+    /// make the whole Register unreadable. Otherwise, as by default, loading them fails.
+    pub(crate) fn set_lenient_load(&mut self, lenient_load: bool) {
+        self.lenient_load = lenient_load;
+    }
+
+    /// Caps the total estimated size, in bytes, of the created Registers held in memory,
+    /// evicting the least recently used ones once over it, which are then reconstructed
+    /// from their log when needed.
+    pub(crate) fn set_cache_max_bytes(&mut self, max_bytes: usize) {
+        self.created_registers = Arc::new(RwLock::new(RegisterCache::with_max_bytes(max_bytes)));
+    }
+
+    /// Checks the store's directory can be written to, creating it if missing, by writing
+    /// and removing a probe file in it, so an unwritable store is detected up front.
+    pub(crate) async fn check_writable(&self) -> Result<()> {
+        let not_writable = |err| {
+            warn!(
+                "Registers store at {} is not writable: {err:?}",
+                self.file_store_path.display()
+            );
+            Error::StoreNotWritable {
+                path: self.file_store_path.clone(),
+            }
+        };
+        create_dir_all(&self.file_store_path)
+            .await
+            .map_err(not_writable)?;
+        let probe_path = self.file_store_path.join(WRITABLE_PROBE_FILE_NAME);
+        let _ = File::create(&probe_path).await.map_err(not_writable)?;
+        remove_file(&probe_path).await.map_err(not_writable)
+    }
+
+    // Rejects cmds for a Register the node is not responsible for, as they were misrouted.
+    fn check_responsible(&self, address: &RegisterAddress) -> Result<()> {
+        if (self.is_responsible)(address) {
+            Ok(())
+        } else {
+            warn!("Rejecting cmd for Register {address:?}, which we are not responsible for");
+            Err(Error::NotResponsible { address: *address })
+        }
+    }
+
+    // Error to return upon failing to write a cmd of the Register to disk, telling apart
+    // whether the whole store has become unwritable.
+    async fn not_stored_error(&self, addr: RegisterAddress) -> Error {
+        match self.check_writable().await {
+            Err(err) => err,
+            Ok(()) => Error::RegisterCmdNotStored(addr),
         }
     }
 
     /// Read from the RegisterReplica's log based on provided RegisterQuery.
-    pub(crate) async fn read(&self, read: &RegisterQuery, requester: User) -> QueryResponse {
+    /// A `None` requester is anonymous, only allowed to read the Registers readable by anyone.
+    pub(crate) async fn read(
+        &self,
+        read: &RegisterQuery,
+        requester: Option<User>,
+    ) -> QueryResponse {
         trace!("Reading register: {:?}", read.dst());
         use RegisterQuery::*;
         match read {
@@ -70,34 +275,367 @@ impl RegisterStorage {
                     .map_err(ProtocolError::Storage),
             ),
             Read(address) => self.read_register(*address, requester).await,
+            ReadCompressed(address) => self.read_register_compressed(*address, requester).await,
+            ReadWithTips(address) => self.read_register_with_tips(*address, requester).await,
+            ReadWithLog(address) => self.read_register_with_log(*address, requester).await,
+            ReadBranch { address, tip } => {
+                self.read_register_branch(*address, *tip, requester).await
+            }
+            ChangedSince {
+                address,
+                known_tip_hash,
+            } => {
+                self.changed_since(*address, *known_tip_hash, requester)
+                    .await
+            }
+            ReadByAuthor { address, author } => {
+                self.read_register_by_author(*address, *author, requester)
+                    .await
+            }
+            ReadSince { address, since } => {
+                self.read_register_since(*address, *since, requester).await
+            }
+            GetTips { address } => self.get_tips(*address, requester).await,
             GetOwner(address) => self.get_owner(*address, requester).await,
+            GetOwners { addresses } => self.get_owners(addresses, requester).await,
             GetEntry { address, hash } => self.get_entry(*address, *hash, requester).await,
             GetPolicy(address) => self.get_policy(*address, requester).await,
             GetUserPermissions { address, user } => {
                 self.get_user_permissions(*address, *user, requester).await
             }
+            CanPerform {
+                address,
+                user,
+                action,
+            } => self.can_perform(*address, *user, *action, requester).await,
+        }
+    }
+
+    /// Read all entries of the RegisterReplica along with the hashes of their parents,
+    /// i.e. the entries each of them directly supersedes, so its whole history can be rebuilt.
+    #[allow(dead_code)]
+    pub(crate) async fn read_with_parents(
+        &self,
+        address: &RegisterAddress,
+        requester: Option<User>,
+    ) -> Result<Vec<(EntryHash, Entry, BTreeSet<EntryHash>)>> {
+        let register = self.get_register(address, Action::Read, requester).await?;
+        Ok(register.read_with_parents())
+    }
+
+    /// Reconstruct the RegisterReplica as it was right after applying the cmds of its log up to,
+    /// and including, the one with the provided `up_to_op_id`, replaying them in the order
+    /// they were stored in, on top of the Register created by its 'Register create' cmd.
+    #[allow(dead_code)]
+    pub(crate) async fn read_as_of(
+        &self,
+        address: &RegisterAddress,
+        up_to_op_id: &str,
+    ) -> Result<RegisterReplica> {
+        let stored_reg = self.open_reg_log_from_disk(address).await?;
+
+        let mut prefix_len = None;
+        for (index, cmd) in stored_reg.op_log.iter().enumerate() {
+            if register_op_id(cmd)? == up_to_op_id {
+                prefix_len = Some(index + 1);
+                break;
+            }
+        }
+        let prefix_len = prefix_len.ok_or_else(|| Error::RegisterOpNotFound {
+            address: *address,
+            op_id: up_to_op_id.to_string(),
+        })?;
+
+        let mut register = None;
+        for cmd in &stored_reg.op_log {
+            if let RegisterCmd::Create(SignedRegisterCreate { op, .. }) = cmd {
+                register = Some(RegisterReplica::new(
+                    op.policy.owner,
+                    op.name,
+                    op.tag,
+                    op.policy.clone(),
+                ));
+                break;
+            }
+        }
+        let mut register = register.ok_or(Error::RegisterNotFound(*address))?;
+
+        for cmd in stored_reg.op_log.iter().take(prefix_len) {
+            match cmd {
+                RegisterCmd::Create(SignedRegisterCreate { op, .. }) => {
+                    for entry_op in &op.initial_entries {
+                        register.apply_op(entry_op.clone())?;
+                    }
+                }
+                RegisterCmd::Edit(SignedRegisterEdit { op, .. }) => {
+                    register.apply_op(op.edit.clone())?;
+                }
+                RegisterCmd::SetExpiry(_) => {}
+            }
         }
+
+        Ok(register)
     }
 
     /// Write a RegisterCmd to the RegisterReplica's log.
     pub(crate) async fn write(&self, cmd: &RegisterCmd) -> Result<()> {
         info!("Writing register cmd: {cmd:?}");
+        self.check_responsible(&cmd.dst())?;
+        check_entries_size(cmd)?;
+        match cmd {
+            RegisterCmd::Edit(edit_cmd) => self.append_edit(edit_cmd).await,
+            RegisterCmd::Create(_) | RegisterCmd::SetExpiry(_) => self.apply_and_write(cmd).await,
+        }
+    }
+
+    /// Overwrites the whole content of the Register with the given value, writing it as an
+    /// edit whose parents are all the current tips of the Register, i.e. merging all its
+    /// branches, so the value causally supersedes every prior entry and is the only one read.
+    /// The edit is signed with the given key, which has to be allowed to write to the Register.
+    /// Returns the hash of the entry written.
+    #[allow(dead_code)]
+    pub(crate) async fn overwrite(
+        &self,
+        address: &RegisterAddress,
+        value: Entry,
+        auth_key: &SecretKey,
+    ) -> Result<EntryHash> {
+        let public_key = auth_key.public_key();
+        let mut register = self
+            .get_register(address, Action::Write, Some(User::Key(public_key)))
+            .await?;
+        let tips = register.tips();
+        debug!(
+            "Overwriting Register {address:?}, merging its {} tip/s",
+            tips.len()
+        );
+        let (hash, edit) = register.write(value, tips)?;
+        let op = EditRegister {
+            address: *address,
+            edit,
+            expected_size: None,
+        };
+        let serialized_op = serialize(&op).map_err(|err| {
+            warn!("We couldn't serialise the Register overwrite op to sign it: {err:?}");
+            Error::RegisterCmdNotStored(*address)
+        })?;
+        let cmd = RegisterCmd::Edit(SignedRegisterEdit {
+            op,
+            auth: DataAuthority {
+                public_key,
+                signature: auth_key.sign(serialized_op),
+            },
+        });
+        self.write(&cmd).await?;
+        Ok(hash)
+    }
+
+    /// Validates a `RegisterCmd` the same way as when writing it, i.e. its signature, the
+    /// permissions of its author and whether it can be applied to the Register, without
+    /// storing it. The cmd is applied to a copy of the Register loaded from disk, which
+    /// is left untouched.
+    pub(crate) async fn validate(&self, cmd: &RegisterCmd) -> Result<()> {
         let addr = cmd.dst();
-        // First try to load and reconstruct the replica of the register
-        // we have in local storage, to then try to apply the new cmd to it.
+        trace!("Validating register cmd: {cmd:?}");
+        check_entries_size(cmd)?;
         let mut stored_reg = self.try_load_stored_register(&addr).await?;
+        self.try_to_apply_cmd_against_register_state(cmd, &mut stored_reg)
+    }
 
-        self.try_to_apply_cmd_against_register_state(cmd, &mut stored_reg)?;
+    /// Write a 'Register create' cmd along with edits to the Register it creates, as a single
+    /// batch. The create cmd is verified and the edits applied to the Register before anything
+    /// is written, so either all of them are stored or none is, and the whole batch is then
+    /// synced to disk at once, so a crash can't leave the Register created but without edits.
+    #[allow(dead_code)]
+    pub(crate) async fn write_create_with_edits(
+        &self,
+        create: &SignedRegisterCreate,
+        edits: &[SignedRegisterEdit],
+    ) -> Result<()> {
+        let addr = create.dst();
+        info!(
+            "Writing register create cmd along with {} edit/s: {addr:?}",
+            edits.len()
+        );
+        let mut stored_reg = self.try_load_stored_register(&addr).await?;
+        let num_of_stored_cmds = stored_reg.op_log.len();
 
-        // Everything went fine, write the new cmd to disk.
-        self.write_log_to_disk(&vec![cmd.clone()], &stored_reg.op_log_path, addr)
+        let create_cmd = RegisterCmd::Create(create.clone());
+        self.try_to_apply_cmd_against_register_state(&create_cmd, &mut stored_reg)?;
+        for edit in edits {
+            let edit_cmd = RegisterCmd::Edit(edit.clone());
+            self.try_to_apply_cmd_against_register_state(&edit_cmd, &mut stored_reg)?;
+        }
+
+        let new_cmds = stored_reg.op_log[num_of_stored_cmds..].to_vec();
+        self.write_log_to_disk(&new_cmds, &stored_reg.op_log_path, addr)
+            .await?;
+        self.cache_created_register(&stored_reg).await?;
+        self.compact_if_too_long(addr, &stored_reg.op_log_path)
+            .await;
+        Ok(())
+    }
+
+    /// Append an edit cmd to the RegisterReplica's log. Since applying edits is commutative,
+    /// if we already hold the 'Register create' cmd the edit is only verified against the
+    /// Register's policy, and written to disk without reconstructing the Register from its log.
+    /// Otherwise, or if the edit depends on the Register's current state, it falls back to
+    /// reconstructing the Register to apply the edit to it.
+    pub(crate) async fn append_edit(&self, edit_cmd: &SignedRegisterEdit) -> Result<()> {
+        let cmd = RegisterCmd::Edit(edit_cmd.clone());
+        let addr = cmd.dst();
+
+        let created_register = self.created_registers.write().await.get(&addr);
+        let mut register = match created_register {
+            Some(register) if edit_cmd.op.expected_size.is_none() => register,
+            _ => return self.apply_and_write(&cmd).await,
+        };
+
+        // The number of entries can only be checked against the full Register, so fall back
+        // to it if the log could be reaching the maximum number of entries, or if the log
+        // was compacted, in which case the Register is cheaply loaded from the snapshot anyway.
+        let op_log_path = self.address_to_filepath(&addr)?;
+        let num_of_cmds = list_files_in(&op_log_path).len() as u64;
+        if op_log_path.join(REGISTER_SNAPSHOT_FILE_NAME).exists()
+            || register.size() + num_of_cmds >= MAX_REG_NUM_ENTRIES.into()
+        {
+            return self.apply_and_write(&cmd).await;
+        }
+
+        trace!("Appending edit cmd to the log of Register {addr:?}");
+        apply_cmd(&cmd, &mut register)?;
+        self.write_log_to_disk(&vec![cmd], &op_log_path, addr)
+            .await?;
+        self.compact_if_too_long(addr, &op_log_path).await;
+        Ok(())
+    }
+
+    /// Compact the log of the RegisterReplica into a single snapshot file, holding all its cmds
+    /// along with the reconstructed RegisterReplica, so it can be loaded without replaying them.
+    /// Returns what the compaction reclaimed.
+    pub(crate) async fn compact(&self, address: &RegisterAddress) -> Result<CompactionStats> {
+        let (cmds_before, bytes_before) = log_dir_usage(&self.address_to_filepath(address)?);
+        let stored_reg = self.try_load_stored_register(address).await?;
+        self.compact_stored_register(address, stored_reg, cmds_before, bytes_before)
+            .await
+    }
+
+    // Compacts the given reconstructed log of the Register, as `compact` does,
+    // with the number of cmd files and bytes its log dir held before it was loaded.
+    async fn compact_stored_register(
+        &self,
+        address: &RegisterAddress,
+        stored_reg: StoredRegister,
+        cmds_before: usize,
+        bytes_before: u64,
+    ) -> Result<CompactionStats> {
+        if stored_reg.op_log.is_empty() {
+            return Ok(CompactionStats {
+                cmds_before,
+                cmds_after: cmds_before,
+                bytes_reclaimed: 0,
+            });
+        }
+        debug!(
+            "Compacting log of {} cmd/s of Register {address:?}",
+            stored_reg.op_log.len()
+        );
+
+        let path = stored_reg.op_log_path;
+        let snapshot = RegisterSnapshot {
+            state: stored_reg.state,
+            op_log: stored_reg.op_log,
+            stored_at: stored_reg.stored_at,
+        };
+        let serialized_snapshot = serialize(&snapshot).map_err(|err| {
+            warn!("We couldn't serialise the Register snapshot to write it to disk: {err:?}");
+            Error::RegisterCmdNotStored(*address)
+        })?;
+
+        // Write it to a tmp file first, so a partially written snapshot is never read.
+        let tmp_path = path.join(REGISTER_SNAPSHOT_TMP_FILE_NAME);
+        let mut file = File::create(&tmp_path).await.map_err(|err| {
+            warn!("We couldn't create file to write Register snapshot to disk: {err:?}");
+            Error::RegisterCmdNotStored(*address)
+        })?;
+        file.write_all(&serialized_snapshot).await.map_err(|err| {
+            warn!("We couldn't write the serialised Register snapshot to disk: {err:?}");
+            Error::RegisterCmdNotStored(*address)
+        })?;
+        file.sync_all().await.map_err(|err| {
+            warn!("We couldn't sync the Register snapshot to disk: {err:?}");
+            Error::RegisterCmdNotStored(*address)
+        })?;
+        rename(&tmp_path, path.join(REGISTER_SNAPSHOT_FILE_NAME))
             .await
+            .map_err(|err| {
+                warn!("We couldn't replace the Register snapshot on disk: {err:?}");
+                Error::RegisterCmdNotStored(*address)
+            })?;
+        if let Err(err) = (self.sync_dir)(&path) {
+            warn!("We couldn't sync Register log dir to disk: {err:?}");
+        }
+
+        // Only remove the files of the cmds now held by the snapshot,
+        // others may have been written since we loaded the log.
+        for cmd in &snapshot.op_log {
+            let cmd_path = path.join(register_op_id(cmd)?);
+            if cmd_path.exists() {
+                if let Err(err) = remove_file(&cmd_path).await {
+                    warn!("We couldn't remove compacted Register cmd from disk: {err:?}");
+                }
+            }
+        }
+        if let Err(err) = (self.sync_dir)(&path) {
+            warn!("We couldn't sync Register log dir to disk: {err:?}");
+        }
+
+        let (cmds_after, bytes_after) = log_dir_usage(&path);
+        let stats = CompactionStats {
+            cmds_before,
+            cmds_after,
+            bytes_reclaimed: bytes_before.saturating_sub(bytes_after),
+        };
+        debug!("Compacted log of Register {address:?}: {stats:?}");
+
+        Ok(stats)
+    }
+
+    /// Returns the disk space taken by the log of the Register, i.e. by all its cmd files
+    /// along with its snapshot, so disk usage can be attributed to each Register.
+    #[allow(dead_code)]
+    pub(crate) fn register_size_on_disk(&self, address: &RegisterAddress) -> Result<u64> {
+        let (_, bytes) = log_dir_usage(&self.address_to_filepath(address)?);
+        Ok(bytes)
+    }
+
+    /// Estimates the cost of reconstructing the Register by listing the files of its log,
+    /// without deserialising them, e.g. for a node under load to decide whether to serve it.
+    #[allow(dead_code)]
+    pub(crate) fn reconstruction_cost(&self, address: &RegisterAddress) -> Result<ReconCost> {
+        let mut cost = ReconCost::default();
+        for filepath in list_files_in(&self.address_to_filepath(address)?) {
+            let file_name = filepath.file_name().and_then(|name| name.to_str());
+            match file_name {
+                Some(REGISTER_SNAPSHOT_TMP_FILE_NAME) => continue,
+                Some(name) if is_journal_file_name(name) => continue,
+                Some(REGISTER_SNAPSHOT_FILE_NAME) => {}
+                _ => cost.op_log_len += 1,
+            }
+            match filepath.metadata() {
+                Ok(metadata) => cost.total_bytes += metadata.len(),
+                Err(err) => warn!("Store: failed to read the metadata of {filepath:?}: {err}"),
+            }
+        }
+        trace!("Cost of reconstructing Register {address:?}: {cost:?}");
+        Ok(cost)
     }
 
     /// This is to be used when a node is shrinking the address range it is responsible for.
     #[allow(dead_code)]
     pub(super) async fn remove(&self, address: &RegisterAddress) -> Result<()> {
         trace!("Removing Register: {address:?}");
+        let _ = self.created_registers.write().await.remove(address);
         let filepath = self.address_to_filepath(address)?;
         if let Err(err) = remove_file(filepath).await {
             warn!("We couldn't remove Register from disk: {err:?}");
@@ -105,12 +643,102 @@ impl RegisterStorage {
         Ok(())
     }
 
-    /// Update our RegisterReplica's replica on receiving data from other nodes.
+    /// Reconstructs the Register from its log leniently, skipping any cmd which can't be applied
+    /// to it, regardless of the store being set to load Registers leniently.
+    /// Returns the Register as partially reconstructed, along with the ids of the cmds skipped.
     #[allow(dead_code)]
-    pub(super) async fn update(&self, data: &ReplicatedRegisterLog) -> Result<()> {
+    pub(crate) async fn load_leniently(
+        &self,
+        address: &RegisterAddress,
+    ) -> Result<(RegisterReplica, Vec<String>)> {
+        let (stored_reg, skipped) = self.reconstruct_stored_register(address, true).await?;
+        let register = stored_reg.state.ok_or(Error::RegisterNotFound(*address))?;
+        Ok((register, skipped))
+    }
+
+    /// Streams the raw cmds of the Register's log, as stored, e.g. for backup tools to archive
+    /// them, yielding each of them as its file is read, without reconstructing the Register nor
+    /// validating them, so they are yielded even if invalid or out of order. The cmds compacted
+    /// into the snapshot are yielded too, hence a cmd re-written after the compaction may be
+    /// yielded twice. A file which can't be read yields an error, after which the rest follow.
+    #[allow(dead_code)]
+    pub(crate) fn raw_op_log_stream(
+        &self,
+        address: &RegisterAddress,
+    ) -> impl Stream<Item = Result<RegisterCmd>> {
+        let address = *address;
+        let (files, path_error) = match self.address_to_filepath(&address) {
+            Ok(path) => (list_files_in_written_order(&path), None),
+            Err(err) => (vec![], Some(Err(err))),
+        };
+
+        stream::iter(path_error).chain(
+            stream::iter(files)
+                .filter(|filepath| {
+                    let file_name = filepath.file_name().and_then(|name| name.to_str());
+                    let is_cmd = match file_name {
+                        Some(REGISTER_SNAPSHOT_TMP_FILE_NAME) => false,
+                        Some(name) => !is_journal_file_name(name),
+                        None => true,
+                    };
+                    async move { is_cmd }
+                })
+                .then(move |filepath| read_raw_cmds(address, filepath))
+                .flat_map(stream::iter),
+        )
+    }
+
+    /// Returns the ids of all the cmds held in the RegisterReplica's log.
+    #[cfg(test)]
+    async fn op_ids(&self, address: &RegisterAddress) -> Result<BTreeSet<String>> {
+        let stored_reg = self.open_reg_log_from_disk(address).await?;
+        stored_reg.op_log.iter().map(register_op_id).collect()
+    }
+
+    /// Returns the cmds of the RegisterReplica's log which are not among the provided ids
+    /// of those the requester already holds, in the order they were stored in.
+    pub(crate) async fn log_delta(
+        &self,
+        address: &RegisterAddress,
+        have: &BTreeSet<String>,
+    ) -> Result<ReplicatedRegisterLog> {
+        let stored_reg = self.open_reg_log_from_disk(address).await?;
+        if stored_reg.op_log.is_empty() {
+            return Err(Error::RegisterNotFound(*address));
+        }
+
+        let mut op_log = vec![];
+        for cmd in stored_reg.op_log {
+            if !have.contains(&register_op_id(&cmd)?) {
+                op_log.push(cmd);
+            }
+        }
+        trace!(
+            "Sending {} cmd/s of Register {address:?} missing from the requester",
+            op_log.len()
+        );
+
+        Ok(ReplicatedRegisterLog {
+            address: *address,
+            op_log,
+        })
+    }
+
+    /// Update our RegisterReplica's replica on receiving data from other nodes.
+    pub(crate) async fn update(&self, data: &ReplicatedRegisterLog) -> Result<()> {
         let addr = data.address;
         debug!("Updating Register store: {addr:?}");
+        self.check_responsible(&addr)?;
         let mut stored_reg = self.try_load_stored_register(&addr).await?;
+        let known_entries: BTreeSet<EntryHash> = match &stored_reg.state {
+            Some(register) => data
+                .op_log
+                .iter()
+                .filter_map(edit_entry_hash)
+                .filter(|hash| register.get(*hash).is_ok())
+                .collect(),
+            None => BTreeSet::new(),
+        };
 
         let mut log_to_write = Vec::new();
         for replicated_cmd in &data.op_log {
@@ -123,15 +751,149 @@ impl RegisterStorage {
             }
         }
 
-        // Write the new cmds all to disk
-        self.write_log_to_disk(&log_to_write, &stored_reg.op_log_path, addr)
-            .await
+        // Write the new cmds all to disk, journaling them first so the
+        // batch can be completed upon restarting if it's interrupted.
+        self.write_journaled_log_to_disk(&log_to_write, &stored_reg.op_log_path, addr)
+            .await?;
+        self.cache_created_register(&stored_reg).await?;
+        self.compact_if_too_long(addr, &stored_reg.op_log_path)
+            .await;
+
+        if let Some(network) = &self.network {
+            let new_entries = new_entries(&stored_reg, &log_to_write, known_entries);
+            if !new_entries.is_empty() {
+                if let Err(err) = network.notify_register_updated(addr, new_entries).await {
+                    warn!("Failed to notify of the edits applied to Register {addr:?}: {err:?}");
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Verifies the signature of every cmd in the Register's log. Since verifying signatures
+    /// is CPU-bound, the cmds are verified in parallel on rayon's bounded thread pool,
+    /// off the async runtime, reporting the ids of the invalid ones in a stable order.
+    /// Any gap in the log, i.e. a parent of an entry which the log doesn't hold, is reported too.
+    #[allow(dead_code)]
+    pub(crate) async fn verify(&self, address: &RegisterAddress) -> Result<LogVerificationReport> {
+        let op_log = self.open_reg_log_from_disk(address).await?.op_log;
+        let (report, _) = verify_log(*address, op_log).await?;
+        Ok(report)
+    }
+
+    /// Verifies the Register's log as `verify` does, and only if it's fully valid, i.e. no cmd
+    /// is invalid nor any entry is missing from it, and the Register can be reconstructed from it,
+    /// compacts it as `compact` does. Otherwise the log is left untouched, and the stats returned
+    /// report nothing was reclaimed. The log is loaded only once, so the cmds compacted are
+    /// exactly those verified.
+    #[allow(dead_code)]
+    pub(crate) async fn compact_and_verify(
+        &self,
+        address: &RegisterAddress,
+    ) -> Result<(LogVerificationReport, CompactionStats)> {
+        let (cmds_before, bytes_before) = log_dir_usage(&self.address_to_filepath(address)?);
+        let mut stored_reg = self.open_reg_log_from_disk(address).await?;
+        let (report, op_log) = verify_log(*address, std::mem::take(&mut stored_reg.op_log)).await?;
+        stored_reg.op_log = op_log;
+
+        let verified_reg = if report.invalid_cmds.is_empty() && report.gaps.is_empty() {
+            replay_stored_register(address, stored_reg, self.lenient_load)
+                .ok()
+                .map(|(stored_reg, _)| stored_reg)
+                .filter(|stored_reg| stored_reg.state.is_some())
+        } else {
+            None
+        };
+        let stats = match verified_reg {
+            Some(stored_reg) => {
+                self.compact_stored_register(address, stored_reg, cmds_before, bytes_before)
+                    .await?
+            }
+            None => {
+                warn!("Skipping compaction of Register {address:?}, its log failed verification: {report:?}");
+                CompactionStats {
+                    cmds_before,
+                    cmds_after: cmds_before,
+                    bytes_reclaimed: 0,
+                }
+            }
+        };
+        Ok((report, stats))
     }
 
     /// ---------------------------------------------------
     /// ----------------- Private fns ---------------------
     /// ---------------------------------------------------
 
+    // Reconstructs the Register from its log, to then try to apply the new cmd to it,
+    // and writes the cmd to disk only if it could be applied.
+    async fn apply_and_write(&self, cmd: &RegisterCmd) -> Result<()> {
+        let addr = cmd.dst();
+        let mut stored_reg = self.try_load_stored_register(&addr).await?;
+
+        self.try_to_apply_cmd_against_register_state(cmd, &mut stored_reg)?;
+
+        // Everything went fine, write the new cmd to disk.
+        self.write_log_to_disk(&vec![cmd.clone()], &stored_reg.op_log_path, addr)
+            .await?;
+        self.cache_created_register(&stored_reg).await?;
+        self.compact_if_too_long(addr, &stored_reg.op_log_path)
+            .await;
+        Ok(())
+    }
+
+    // Compacts the log of the Register if it holds more cmd files than the configured threshold.
+    // It's done on a separate task so the write isn't held up by it, and only if the Register
+    // is not already being compacted.
+    async fn compact_if_too_long(&self, addr: RegisterAddress, op_log_path: &Path) {
+        if list_files_in(op_log_path).len() <= self.compact_after {
+            return;
+        }
+        if !self.compacting.lock().await.insert(addr) {
+            trace!("Register {addr:?} log is already being compacted");
+            return;
+        }
+
+        let store = self.clone();
+        let _handle = spawn(async move {
+            match store.compact(&addr).await {
+                Ok(stats) => info!(
+                    "Compacted the log of Register {addr:?} from {} to {} cmd/s, reclaiming {} bytes",
+                    stats.cmds_before, stats.cmds_after, stats.bytes_reclaimed
+                ),
+                Err(err) => warn!("Failed to compact the log of Register {addr:?}: {err:?}"),
+            }
+            let _ = store.compacting.lock().await.remove(&addr);
+        });
+    }
+
+    // Keeps the Register as created by the 'Register create' cmd found in the log, if any,
+    // so later edits can be verified against it.
+    async fn cache_created_register(&self, stored_reg: &StoredRegister) -> Result<()> {
+        let create_op = stored_reg.op_log.iter().find_map(|cmd| match cmd {
+            RegisterCmd::Create(SignedRegisterCreate { op, .. }) => Some(op),
+            RegisterCmd::Edit(_) | RegisterCmd::SetExpiry(_) => None,
+        });
+        let op = match create_op {
+            Some(op) if stored_reg.state.is_some() => op,
+            _ => return Ok(()),
+        };
+        if self.created_registers.read().await.contains_key(&op.dst()) {
+            return Ok(());
+        }
+
+        let mut register =
+            RegisterReplica::new(op.policy.owner, op.name, op.tag, op.policy.clone());
+        for entry_op in &op.initial_entries {
+            register.apply_op(entry_op.clone())?;
+        }
+        self.created_registers
+            .write()
+            .await
+            .insert(op.dst(), register);
+        Ok(())
+    }
+
     /// Persists a RegisterLog to disk
     async fn write_log_to_disk(
         &self,
@@ -148,10 +910,10 @@ impl RegisterStorage {
             return Ok(());
         }
 
-        create_dir_all(path).await.map_err(|err| {
+        if let Err(err) = create_dir_all(path).await {
             warn!("We couldn't create dir structure to write Register cmd to disk: {err:?}");
-            Error::RegisterCmdNotStored(addr)
-        })?;
+            return Err(self.not_stored_error(addr).await);
+        }
 
         let mut last_err = None;
 
@@ -162,6 +924,12 @@ impl RegisterStorage {
             }
         }
 
+        // Sync the whole batch to disk at once, rather than each cmd file separately,
+        // to reduce the chances of concurrent reading failing by reading an incomplete log.
+        if let Err(err) = (self.sync_dir)(path) {
+            warn!("We couldn't sync Register log dir to disk: {err:?}");
+        }
+
         if let Some(err) = last_err {
             Err(err)
         } else {
@@ -174,16 +942,123 @@ impl RegisterStorage {
         }
     }
 
-    /// Persists a RegisterCmd to disk.
-    async fn write_register_cmd(&self, cmd: &RegisterCmd, path: &Path) -> Result<()> {
-        let addr = cmd.dst();
-        let reg_cmd_id = register_op_id(cmd)?;
-        let path = path.join(&reg_cmd_id);
-
-        trace!(
-            "Writing cmd register log for {addr:?} at {}",
-            path.display()
-        );
+    /// Persists a RegisterLog to disk through a write-ahead journal: the whole batch is first
+    /// written and synced to a journal file of its own, and only then is each cmd written to its
+    /// own file. The journal is removed once all of them were written, otherwise it's left for
+    /// `replay_journals` to complete the batch with, so disk doesn't fall behind a partial batch.
+    async fn write_journaled_log_to_disk(
+        &self,
+        log: &RegisterLog,
+        path: &Path,
+        addr: RegisterAddress,
+    ) -> Result<()> {
+        if log.is_empty() {
+            return Ok(());
+        }
+        let journal_path = self.write_journal(log, path, addr).await?;
+        self.write_log_to_disk(log, path, addr).await?;
+
+        if let Err(err) = remove_file(&journal_path).await {
+            warn!("We couldn't remove the journal of Register {addr:?} from disk: {err:?}");
+        }
+        Ok(())
+    }
+
+    /// Writes the batch of cmds to a new journal file in the Register's log dir, synced to disk.
+    /// Returns the path of the journal.
+    async fn write_journal(
+        &self,
+        log: &RegisterLog,
+        path: &Path,
+        addr: RegisterAddress,
+    ) -> Result<PathBuf> {
+        if let Err(err) = create_dir_all(path).await {
+            warn!("We couldn't create dir structure to write Register journal to disk: {err:?}");
+            return Err(self.not_stored_error(addr).await);
+        }
+
+        let serialized_log = serialize(log).map_err(|err| {
+            warn!("We couldn't serialise the Register journal to write it to disk: {err:?}");
+            Error::RegisterCmdNotStored(addr)
+        })?;
+        let journal_path = path.join(format!(
+            "{REGISTER_JOURNAL_FILE_PREFIX}{}",
+            hex::encode(rand::random::<[u8; 16]>())
+        ));
+        let mut file = match File::create(&journal_path).await {
+            Ok(file) => file,
+            Err(err) => {
+                warn!("We couldn't create file to write Register journal to disk: {err:?}");
+                return Err(self.not_stored_error(addr).await);
+            }
+        };
+        file.write_all(&serialized_log).await.map_err(|err| {
+            warn!("We couldn't write the serialised Register journal to disk: {err:?}");
+            Error::RegisterCmdNotStored(addr)
+        })?;
+        file.sync_all().await.map_err(|err| {
+            warn!("We couldn't sync the Register journal to disk: {err:?}");
+            Error::RegisterCmdNotStored(addr)
+        })?;
+        Ok(journal_path)
+    }
+
+    /// Completes the batches of cmds whose writing was interrupted, e.g. by a crash, by writing
+    /// the cmds of every journal left in the store which are still missing. A journal which
+    /// can't be read was never fully written, thus neither was any cmd of its batch, and is
+    /// discarded. To be run upon starting up. Returns the number of batches completed.
+    pub(crate) async fn replay_journals(&self) -> Result<usize> {
+        let mut replayed = 0;
+        for log_dir in register_log_dirs(&self.file_store_path) {
+            let journal_paths = list_files_in(&log_dir).into_iter().filter(|filepath| {
+                filepath
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(is_journal_file_name)
+            });
+            for journal_path in journal_paths {
+                match read(&journal_path)
+                    .await
+                    .map(|serialized_log| bincode::deserialize::<RegisterLog>(&serialized_log))
+                {
+                    Ok(Ok(log)) => {
+                        if let Some(addr) = log.first().map(|cmd| cmd.dst()) {
+                            debug!(
+                                "Replaying journal of {} cmd/s of Register {addr:?}",
+                                log.len()
+                            );
+                            self.write_log_to_disk(&log, &log_dir, addr).await?;
+                            replayed += 1;
+                        }
+                    }
+                    other => warn!(
+                        "Discarding unreadable Register journal found at {}: {other:?}",
+                        journal_path.display()
+                    ),
+                }
+
+                if let Err(err) = remove_file(&journal_path).await {
+                    warn!("We couldn't remove the Register journal from disk: {err:?}");
+                }
+            }
+        }
+
+        if replayed > 0 {
+            info!("Replayed {replayed} Register journal/s");
+        }
+        Ok(replayed)
+    }
+
+    /// Persists a RegisterCmd to disk.
+    async fn write_register_cmd(&self, cmd: &RegisterCmd, path: &Path) -> Result<()> {
+        let addr = cmd.dst();
+        let reg_cmd_id = register_op_id(cmd)?;
+        let path = path.join(&reg_cmd_id);
+
+        trace!(
+            "Writing cmd register log for {addr:?} at {}",
+            path.display()
+        );
 
         let entry_hash = if let RegisterCmd::Edit(edit_cmd) = cmd {
             let entry_hash = EntryHash(edit_cmd.op.edit.crdt_op.hash());
@@ -206,26 +1081,26 @@ impl RegisterStorage {
             return Ok(());
         }
 
-        let mut file = File::create(&path).await.map_err(|err| {
-            warn!("We couldn't create file to write Register cmd to disk: {err:?}");
-            Error::RegisterCmdNotStored(addr)
-        })?;
+        let mut file = match File::create(&path).await {
+            Ok(file) => file,
+            Err(err) => {
+                warn!("We couldn't create file to write Register cmd to disk: {err:?}");
+                return Err(self.not_stored_error(addr).await);
+            }
+        };
 
-        let serialized_data = serialize(cmd).map_err(|err| {
-            warn!("We couldn't serialise the Register cmd to write it to disk: {err:?}");
-            Error::RegisterCmdNotStored(addr)
-        })?;
+        // The time the cmd is stored at is recorded along with it, as told by our own clock,
+        // which its entries are deemed written at.
+        let serialized_data =
+            encode_cmd(cmd, (self.clock)(), CmdFileFormat::default()).map_err(|err| {
+                warn!("We couldn't serialise the Register cmd to write it to disk: {err:?}");
+                Error::RegisterCmdNotStored(addr)
+            })?;
         file.write_all(&serialized_data).await.map_err(|err| {
             warn!("We couldn't write the serialised Register cmd to disk: {err:?}");
             Error::RegisterCmdNotStored(addr)
         })?;
 
-        // Sync OS data to disk to reduce the chances of
-        // concurrent reading failing by reading an empty/incomplete file.
-        if let Err(err) = file.sync_data().await {
-            warn!("We couldn't sync Register file to disk: {err:?}");
-        }
-
         trace!(
             "RegisterCmd writing successful for {addr:?}, id {reg_cmd_id}, at {}, entry hash: {entry_hash:?}",
             path.display()
@@ -239,11 +1114,20 @@ impl RegisterStorage {
         &self,
         address: &RegisterAddress,
         action: Action,
-        requester: User,
+        requester: Option<User>,
     ) -> Result<RegisterReplica> {
+        // Registers we don't hold are reported right away, without going through their loading.
+        if !self.holds_register(address) {
+            trace!("Register {address:?} is not held by this node");
+            return Err(Error::RegisterNotFound(*address));
+        }
+
         let stored_reg = self.try_load_stored_register(address).await?;
         if let Some(register) = stored_reg.state {
-            register.check_permissions(action, Some(requester))?;
+            register.check_permissions(action, requester)?;
+            if action == Action::Read {
+                self.check_not_expired(address, &stored_reg.op_log)?;
+            }
 
             Ok(register)
         } else {
@@ -251,7 +1135,30 @@ impl RegisterStorage {
         }
     }
 
-    async fn read_register(&self, address: RegisterAddress, requester: User) -> QueryResponse {
+    // Fails if the expiry set on the Register by the cmds of its log, if any, has been reached,
+    // as told by our clock. The earliest of the expiries set is the one the Register expires at.
+    fn check_not_expired(&self, address: &RegisterAddress, op_log: &RegisterLog) -> Result<()> {
+        let expires_at = op_log
+            .iter()
+            .filter_map(|cmd| match cmd {
+                RegisterCmd::SetExpiry(SignedRegisterSetExpiry { op, .. }) => Some(op.expires_at),
+                _ => None,
+            })
+            .min();
+        match expires_at {
+            Some(expires_at) if (self.clock)() >= expires_at => {
+                trace!("Register {address:?} expired at {expires_at:?}");
+                Err(Error::RegisterExpired(*address))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    async fn read_register(
+        &self,
+        address: RegisterAddress,
+        requester: Option<User>,
+    ) -> QueryResponse {
         let result = match self.get_register(&address, Action::Read, requester).await {
             Ok(register) => Ok(register.read()),
             Err(error) => Err(error),
@@ -261,7 +1168,193 @@ impl RegisterStorage {
         QueryResponse::ReadRegister(result)
     }
 
-    async fn get_owner(&self, address: RegisterAddress, requester: User) -> QueryResponse {
+    async fn read_register_compressed(
+        &self,
+        address: RegisterAddress,
+        requester: Option<User>,
+    ) -> QueryResponse {
+        let result = match self.get_register(&address, Action::Read, requester).await {
+            Ok(register) => compress_register_entries(&register.read()),
+            Err(error) => Err(ProtocolError::Storage(error)),
+        };
+
+        QueryResponse::ReadRegisterCompressed(result)
+    }
+
+    async fn read_register_with_tips(
+        &self,
+        address: RegisterAddress,
+        requester: Option<User>,
+    ) -> QueryResponse {
+        // Both are taken from the same replica, so the tips are exactly those of the entries.
+        let result = self
+            .get_register(&address, Action::Read, requester)
+            .await
+            .map(|register| (register.read(), register.tips()))
+            .map_err(ProtocolError::Storage);
+
+        QueryResponse::ReadRegisterWithTips(result)
+    }
+
+    async fn read_register_with_log(
+        &self,
+        address: RegisterAddress,
+        requester: Option<User>,
+    ) -> QueryResponse {
+        let result = self
+            .entries_with_log(&address, requester)
+            .await
+            .map_err(ProtocolError::Storage);
+
+        QueryResponse::ReadRegisterWithLog(result)
+    }
+
+    // Returns the entries of the Register along with the log of cmds it was reconstructed from.
+    async fn entries_with_log(
+        &self,
+        address: &RegisterAddress,
+        requester: Option<User>,
+    ) -> Result<(BTreeSet<(EntryHash, Entry)>, ReplicatedRegisterLog)> {
+        if !self.holds_register(address) {
+            return Err(Error::RegisterNotFound(*address));
+        }
+        let stored_reg = self.try_load_stored_register(address).await?;
+        let register = stored_reg.state.ok_or(Error::RegisterNotFound(*address))?;
+        register.check_permissions(Action::Read, requester)?;
+        self.check_not_expired(address, &stored_reg.op_log)?;
+
+        let log = ReplicatedRegisterLog {
+            address: *address,
+            op_log: stored_reg.op_log,
+        };
+        Ok((register.read(), log))
+    }
+
+    async fn read_register_branch(
+        &self,
+        address: RegisterAddress,
+        tip: EntryHash,
+        requester: Option<User>,
+    ) -> QueryResponse {
+        let result = self
+            .get_register(&address, Action::Read, requester)
+            .await
+            .and_then(|register| register.read_branch(tip))
+            .map_err(ProtocolError::Storage);
+
+        QueryResponse::ReadRegisterBranch(result)
+    }
+
+    async fn get_tips(&self, address: RegisterAddress, requester: Option<User>) -> QueryResponse {
+        let result = self
+            .get_register(&address, Action::Read, requester)
+            .await
+            .map(|register| register.tips())
+            .map_err(ProtocolError::Storage);
+
+        QueryResponse::GetRegisterTips(result)
+    }
+
+    async fn changed_since(
+        &self,
+        address: RegisterAddress,
+        known_tip_hash: EntryHash,
+        requester: Option<User>,
+    ) -> QueryResponse {
+        // Only the hashes of the current entries are looked at, the known tip being
+        // current as long as no other entry supersedes it or was written concurrently.
+        let result = self
+            .get_register(&address, Action::Read, requester)
+            .await
+            .map(|register| {
+                register
+                    .read()
+                    .into_iter()
+                    .map(|(hash, _)| hash)
+                    .find(|hash| *hash != known_tip_hash)
+            })
+            .map_err(ProtocolError::Storage);
+
+        QueryResponse::RegisterChangedSince(result)
+    }
+
+    async fn read_register_by_author(
+        &self,
+        address: RegisterAddress,
+        author: User,
+        requester: Option<User>,
+    ) -> QueryResponse {
+        let result = self
+            .entries_by_author(&address, author, requester)
+            .await
+            .map_err(ProtocolError::Storage);
+
+        QueryResponse::ReadRegisterByAuthor(result)
+    }
+
+    // Returns all the entries of the Register written by the given author, which is
+    // told by the key each of the cmds in its log, writing them, was signed with.
+    async fn entries_by_author(
+        &self,
+        address: &RegisterAddress,
+        author: User,
+        requester: Option<User>,
+    ) -> Result<BTreeSet<(EntryHash, Entry)>> {
+        let stored_reg = self.try_load_stored_register(address).await?;
+        let register = stored_reg.state.ok_or(Error::RegisterNotFound(*address))?;
+        register.check_permissions(Action::Read, requester)?;
+        self.check_not_expired(address, &stored_reg.op_log)?;
+
+        let authors = entry_authors(&stored_reg.op_log);
+        Ok(register
+            .read_with_parents()
+            .into_iter()
+            .filter(|(hash, _, _)| authors.get(hash) == Some(&author))
+            .map(|(hash, entry, _)| (hash, entry))
+            .collect())
+    }
+
+    async fn read_register_since(
+        &self,
+        address: RegisterAddress,
+        since: SystemTime,
+        requester: Option<User>,
+    ) -> QueryResponse {
+        let result = self
+            .entries_since(&address, since, requester)
+            .await
+            .map_err(ProtocolError::Storage);
+
+        QueryResponse::ReadRegisterSince(result)
+    }
+
+    // Returns all the entries of the Register written on this node at or after `since`,
+    // as told by the time the cmd writing each of them was stored at.
+    async fn entries_since(
+        &self,
+        address: &RegisterAddress,
+        since: SystemTime,
+        requester: Option<User>,
+    ) -> Result<BTreeSet<(EntryHash, Entry)>> {
+        let stored_reg = self.try_load_stored_register(address).await?;
+        let write_times = entry_write_times(&stored_reg);
+        let register = stored_reg.state.ok_or(Error::RegisterNotFound(*address))?;
+        register.check_permissions(Action::Read, requester)?;
+        self.check_not_expired(address, &stored_reg.op_log)?;
+
+        Ok(register
+            .read_with_parents()
+            .into_iter()
+            .filter(|(hash, _, _)| {
+                write_times
+                    .get(hash)
+                    .is_some_and(|written_at| *written_at >= since)
+            })
+            .map(|(hash, entry, _)| (hash, entry))
+            .collect())
+    }
+
+    async fn get_owner(&self, address: RegisterAddress, requester: Option<User>) -> QueryResponse {
         let result = match self.get_register(&address, Action::Read, requester).await {
             Ok(res) => Ok(res.owner()),
             Err(error) => Err(error),
@@ -271,11 +1364,29 @@ impl RegisterStorage {
         QueryResponse::GetRegisterOwner(result)
     }
 
+    async fn get_owners(
+        &self,
+        addresses: &[RegisterAddress],
+        requester: Option<User>,
+    ) -> QueryResponse {
+        let mut owners = Vec::with_capacity(addresses.len());
+        for address in addresses {
+            let result = self
+                .get_register(address, Action::Read, requester)
+                .await
+                .map(|register| register.owner())
+                .map_err(ProtocolError::Storage);
+            owners.push((*address, result));
+        }
+
+        QueryResponse::GetRegisterOwners(owners)
+    }
+
     async fn get_entry(
         &self,
         address: RegisterAddress,
         hash: EntryHash,
-        requester: User,
+        requester: Option<User>,
     ) -> QueryResponse {
         let result = self
             .get_register(&address, Action::Read, requester)
@@ -290,7 +1401,7 @@ impl RegisterStorage {
         &self,
         address: RegisterAddress,
         user: User,
-        requester: User,
+        requester: Option<User>,
     ) -> QueryResponse {
         let result = self
             .get_register(&address, Action::Read, requester)
@@ -301,7 +1412,28 @@ impl RegisterStorage {
         QueryResponse::GetRegisterUserPermissions(result)
     }
 
-    async fn get_policy(&self, address: RegisterAddress, requester_pk: User) -> QueryResponse {
+    async fn can_perform(
+        &self,
+        address: RegisterAddress,
+        user: User,
+        action: Action,
+        requester: Option<User>,
+    ) -> QueryResponse {
+        // Only the permissions are checked, nothing is performed on the Register.
+        let result = self
+            .get_register(&address, Action::Read, requester)
+            .await
+            .map(|register| register.check_permissions(action, Some(user)).is_ok())
+            .map_err(ProtocolError::Storage);
+
+        QueryResponse::RegisterCanPerform(result)
+    }
+
+    async fn get_policy(
+        &self,
+        address: RegisterAddress,
+        requester_pk: Option<User>,
+    ) -> QueryResponse {
         let result = self
             .get_register(&address, Action::Read, requester_pk)
             .await
@@ -311,6 +1443,13 @@ impl RegisterStorage {
         QueryResponse::GetRegisterPolicy(result)
     }
 
+    // Whether the log of the Register is held in the store, checking only its dir exists.
+    fn holds_register(&self, address: &RegisterAddress) -> bool {
+        self.address_to_filepath(address)
+            .map(|path| path.exists())
+            .unwrap_or(false)
+    }
+
     fn address_to_filepath(&self, address: &RegisterAddress) -> Result<PathBuf> {
         // This is a unique identifier of the Register,
         // since it encodes both the xorname and tag.
@@ -338,7 +1477,10 @@ impl RegisterStorage {
         // verified until we have the `Register create` cmd.
         match (stored_reg.state.as_mut(), cmd) {
             (Some(_), RegisterCmd::Create { .. }) => return Ok(()), // no op, since already created
-            (Some(ref mut register), RegisterCmd::Edit(_)) => self.apply(cmd, register)?,
+            (Some(ref mut register), RegisterCmd::Edit(edit_cmd)) => {
+                check_expected_size(&edit_cmd.op, register)?;
+                apply_cmd(cmd, register)?
+            }
             (None, RegisterCmd::Create(cmd)) => {
                 // the target Register is not in our store or we don't have the 'Register create',
                 // let's verify the create cmd we received is valid and try to apply stored cmds we may have.
@@ -355,12 +1497,20 @@ impl RegisterStorage {
                 let mut register =
                     RegisterReplica::new(op.policy.owner, op.name, op.tag, op.policy.clone());
 
+                // the initial entries are accepted only if they can all be applied
+                let public_key = auth.public_key;
+                for entry_op in &op.initial_entries {
+                    register.check_permissions(Action::Write, Some(User::Key(public_key)))?;
+                    register.apply_op(entry_op.clone())?;
+                }
+
                 for cmd in &stored_reg.op_log {
-                    self.apply(cmd, &mut register)?;
+                    apply_cmd(cmd, &mut register)?;
                 }
 
                 stored_reg.state = Some(register);
             }
+            (Some(ref mut register), RegisterCmd::SetExpiry(_)) => apply_cmd(cmd, register)?,
             (None, _edit_cmd) => { /* we cannot validate it right now, but we'll store it */ }
         }
 
@@ -368,70 +1518,40 @@ impl RegisterStorage {
         Ok(())
     }
 
-    // Try to apply the provided cmd to the register state, performing all op validations
-    fn apply(&self, cmd: &RegisterCmd, register: &mut RegisterReplica) -> Result<()> {
-        let dst_addr = cmd.dst();
-        if &dst_addr != register.address() {
-            return Err(Error::RegisterAddrMismatch {
-                dst_addr,
-                reg_addr: *register.address(),
-            });
-        }
-
-        match cmd {
-            RegisterCmd::Create { .. } => Ok(()),
-            RegisterCmd::Edit(SignedRegisterEdit { op, auth }) => {
-                let serialised_op = serialize(op).map_err(|err| {
-                    warn!("We couldn't serialise the Register cmd to write it to disk: {err:?}");
-                    Error::RegisterCmdNotStored(dst_addr)
-                })?;
-                verify_authority(auth, serialised_op)?;
-
-                info!("Editing Register: {dst_addr:?}");
-                let public_key = auth.public_key;
-                register.check_permissions(Action::Write, Some(User::Key(public_key)))?;
-                let result = register.apply_op(op.edit.clone());
-
-                match result {
-                    Ok(()) => {
-                        trace!("Editing Register success: {dst_addr:?}");
-                        Ok(())
-                    }
-                    Err(err) => {
-                        trace!("Editing Register failed {dst_addr:?}: {err:?}");
-                        Err(err)
-                    }
-                }
-            }
-        }
-    }
-
     // Gets stored register log from disk, trying to reconstruct the Register
     // Note this doesn't perform any cmd sig/perms validation, it's only used when the log
     // is read from disk which has already been validated before storing it.
     async fn try_load_stored_register(&self, addr: &RegisterAddress) -> Result<StoredRegister> {
-        let mut stored_reg = self.open_reg_log_from_disk(addr).await?;
-        // if we have the Register creation cmd, apply all ops to reconstruct the Register
-        if let Some(register) = &mut stored_reg.state {
-            for cmd in &stored_reg.op_log {
-                if let RegisterCmd::Edit(SignedRegisterEdit { op, .. }) = cmd {
-                    let EditRegister { edit, .. } = op;
-                    register.apply_op(edit.clone())?;
-                }
-            }
-        }
-
+        let (stored_reg, _) = self
+            .reconstruct_stored_register(addr, self.lenient_load)
+            .await?;
         Ok(stored_reg)
     }
 
-    /// Opens the log of RegisterCmds for a given register address.
+    // Same as `try_load_stored_register`, but if `lenient`, the cmds which can't be applied
+    // are skipped rather than failing, returning the ids of those skipped.
+    async fn reconstruct_stored_register(
+        &self,
+        addr: &RegisterAddress,
+        lenient: bool,
+    ) -> Result<(StoredRegister, Vec<String>)> {
+        let stored_reg = self.open_reg_log_from_disk(addr).await?;
+        replay_stored_register(addr, stored_reg, lenient)
+    }
+
+    /// Opens the log of RegisterCmds for a given register address, in the order they were stored.
     /// Creates a new log if no data is found.
+    /// If the log was compacted, the Register is loaded from the snapshot unless its
+    /// 'Register create' cmd conflicts with it, e.g. if it was received after the
+    /// compaction, in which case the Register is rebuilt from all the cmds.
     async fn open_reg_log_from_disk(&self, addr: &RegisterAddress) -> Result<StoredRegister> {
         let path = self.address_to_filepath(addr)?;
         let mut stored_reg = StoredRegister {
             state: None,
             op_log: RegisterLog::new(),
             op_log_path: path.clone(),
+            replay_from: 0,
+            stored_at: BTreeMap::new(),
         };
 
         if !path.exists() {
@@ -443,30 +1563,60 @@ impl RegisterStorage {
         }
 
         trace!("Register log path for {addr:?} exists: {}", path.display());
-        for filepath in list_files_in(&path) {
+        let snapshot = self.read_snapshot(addr, &path).await;
+        let (snapshot_state, snapshot_len) = match snapshot {
+            Some(RegisterSnapshot {
+                state,
+                op_log,
+                stored_at,
+            }) => {
+                let snapshot_len = op_log.len();
+                stored_reg.op_log = op_log;
+                stored_reg.stored_at = stored_at;
+                (state, snapshot_len)
+            }
+            None => (None, 0),
+        };
+        let snapshot_cmd_ids = stored_reg
+            .op_log
+            .iter()
+            .map(register_op_id)
+            .collect::<Result<BTreeSet<_>>>()?;
+        let mut loaded_cmd_ids = snapshot_cmd_ids.clone();
+        let mut duplicates = 0;
+
+        for filepath in list_files_in_written_order(&path) {
+            let file_name = filepath.file_name().and_then(|name| name.to_str());
+            match file_name {
+                Some(REGISTER_SNAPSHOT_FILE_NAME | REGISTER_SNAPSHOT_TMP_FILE_NAME) => continue,
+                Some(name) if is_journal_file_name(name) => continue,
+                // a cmd re-written after the compaction, which the snapshot already holds
+                Some(name) if snapshot_cmd_ids.contains(name) => continue,
+                _ => {}
+            }
             match read(&filepath)
                 .await
-                .map(|serialized_data| bincode::deserialize::<RegisterCmd>(&serialized_data))
+                .map(|serialized_data| decode_stored_cmd(&serialized_data))
             {
-                Ok(Ok(reg_cmd)) => {
-                    stored_reg.op_log.push(reg_cmd.clone());
-
-                    if let RegisterCmd::Create(cmd) = reg_cmd {
-                        let SignedRegisterCreate { op, .. } = cmd;
-                        let register =
-                            RegisterReplica::new(op.policy.owner, op.name, op.tag, op.policy);
-                        match &stored_reg.state {
-                            Some(s) => {
-                                if s != &register {
-                                    warn!("Unexpectedly found multiple different RegisterCmd::Create for {addr:?}: {s:?} and {register:?}");
-                                } else {
-                                    warn!("Unexpectedly found multiple identical RegisterCmd::Create for {addr:?}: {s:?}");
-                                }
-                            }
-                            None => {
-                                stored_reg.state = Some(register);
-                            }
+                Ok(Ok((reg_cmd, stored_at))) => {
+                    // the same cmd may have been stored under more than one file name,
+                    // e.g. after a botched copy of the log, so we keep it only once
+                    let reg_cmd_id = register_op_id(&reg_cmd)?;
+                    if loaded_cmd_ids.insert(reg_cmd_id.clone()) {
+                        // cmd files written before their time was recorded along with
+                        // them are deemed stored at the time they were last modified
+                        let stored_at = stored_at.or_else(|| {
+                            filepath
+                                .metadata()
+                                .and_then(|metadata| metadata.modified())
+                                .ok()
+                        });
+                        if let Some(stored_at) = stored_at {
+                            let _ = stored_reg.stored_at.insert(reg_cmd_id, stored_at);
                         }
+                        stored_reg.op_log.push(reg_cmd);
+                    } else {
+                        duplicates += 1;
                     }
                 }
                 other => {
@@ -477,15 +1627,73 @@ impl RegisterStorage {
                 }
             }
         }
+        if duplicates > 0 {
+            warn!("Skipped {duplicates} duplicated Register cmd/s found in storage for {addr:?}");
+        }
+
+        for reg_cmd in &stored_reg.op_log {
+            if let RegisterCmd::Create(cmd) = reg_cmd {
+                let SignedRegisterCreate { op, .. } = cmd;
+                let register =
+                    RegisterReplica::new(op.policy.owner, op.name, op.tag, op.policy.clone());
+                match &stored_reg.state {
+                    Some(s) => {
+                        if s != &register {
+                            warn!("Unexpectedly found multiple different RegisterCmd::Create for {addr:?}: {s:?} and {register:?}");
+                        } else {
+                            warn!("Unexpectedly found multiple identical RegisterCmd::Create for {addr:?}: {s:?}");
+                        }
+                    }
+                    None => {
+                        stored_reg.state = Some(register);
+                    }
+                }
+            }
+        }
+
+        // Only trust the snapshot's state if it was built upon the same 'Register create' cmd,
+        // otherwise the Register is rebuilt by replaying all the cmds on top of the created one.
+        match (snapshot_state, &stored_reg.state) {
+            (Some(snapshot_state), Some(created)) if is_same_register(&snapshot_state, created) => {
+                stored_reg.state = Some(snapshot_state);
+                stored_reg.replay_from = snapshot_len;
+            }
+            (Some(_), _) => {
+                warn!("Register snapshot conflicts with its RegisterCmd::Create for {addr:?}, rebuilding it from its cmds");
+            }
+            (None, Some(_)) if snapshot_len > 0 => {
+                debug!("Register snapshot was compacted before its RegisterCmd::Create was received for {addr:?}, rebuilding it from its cmds");
+            }
+            (None, _) => {}
+        }
 
         Ok(stored_reg)
     }
 
+    // Reads the snapshot the log of the Register was compacted into, if any.
+    async fn read_snapshot(&self, addr: &RegisterAddress, path: &Path) -> Option<RegisterSnapshot> {
+        let snapshot_path = path.join(REGISTER_SNAPSHOT_FILE_NAME);
+        if !snapshot_path.exists() {
+            return None;
+        }
+
+        match read(&snapshot_path)
+            .await
+            .map(|serialized_data| decode_snapshot(&serialized_data))
+        {
+            Ok(Ok(snapshot)) => Some(snapshot),
+            other => {
+                warn!(
+                    "Ignoring corrupted Register snapshot from storage, for {addr:?}, found at {}: {other:?}",
+                    snapshot_path.display()
+                );
+                None
+            }
+        }
+    }
+
     /// Used for replication of data to new nodes.
-    /// Currently only used by the tests.
-    /// TODO: to be used by replication logic.
-    #[cfg(test)]
-    async fn get_register_replica(
+    pub(crate) async fn get_register_replica(
         &self,
         address: &RegisterAddress,
     ) -> Result<ReplicatedRegisterLog> {
@@ -500,31 +1708,147 @@ impl RegisterStorage {
 
     #[cfg(test)]
     async fn stored_addrs(&self) -> Vec<RegisterAddress> {
-        use bincode::deserialize;
-        use std::collections::{btree_map::Entry, BTreeMap};
+        self.addrs_page(0, usize::MAX).await
+    }
 
-        trace!("Listing all register addrs");
+    /// Returns the number of Registers held in the store, without reading any of them.
+    #[allow(dead_code)]
+    pub(crate) fn count_addrs(&self) -> usize {
+        register_log_dirs(&self.file_store_path).count()
+    }
 
-        let iter = list_files_in(&self.file_store_path)
-            .into_iter()
-            .filter_map(|e| e.parent().map(|parent| (parent.to_path_buf(), e.clone())));
+    /// Returns the addresses of, at most, `limit` of the Registers held in the store,
+    /// skipping the first `offset` of them. Registers are always listed in the same order,
+    /// so the whole store can be enumerated page by page, holding one page in memory at a time.
+    pub(crate) async fn addrs_page(&self, offset: usize, limit: usize) -> Vec<RegisterAddress> {
+        trace!("Listing register addrs, offset: {offset}, limit: {limit}");
+
+        let mut addrs = Vec::new();
+        for log_dir in register_log_dirs(&self.file_store_path)
+            .skip(offset)
+            .take(limit)
+        {
+            match read_log_dir_addr(&log_dir).await {
+                Some(addr) => addrs.push(addr),
+                None => warn!("Couldn't read the address of the Register logged at {log_dir:?}"),
+            }
+        }
 
-        let mut addrs = BTreeMap::new();
-        for (parent, op_file) in iter {
-            if let Entry::Vacant(vacant) = addrs.entry(parent) {
-                if let Ok(Ok(cmd)) = read(op_file)
-                    .await
-                    .map(|serialized_data| deserialize::<RegisterCmd>(&serialized_data))
-                {
-                    let _existing = vacant.insert(cmd.dst());
+        trace!("Listing register addrs done, {} found.", addrs.len());
+        addrs
+    }
+
+    /// Exports the log of every Register held in the store into a single archive, e.g. for
+    /// backups or migrations, instead of copying each of the files of the logs. Each Register
+    /// is framed by the length of its serialised log, followed by the log itself.
+    /// Returns the number of Registers exported.
+    #[allow(dead_code)]
+    pub(crate) async fn export_archive(&self, mut writer: impl io::Write) -> Result<usize> {
+        let mut exported = 0;
+        for log_dir in register_log_dirs(&self.file_store_path) {
+            let addr = match read_log_dir_addr(&log_dir).await {
+                Some(addr) => addr,
+                None => {
+                    warn!("Couldn't read the address of the Register logged at {log_dir:?}");
+                    continue;
                 }
+            };
+            let replica = self.get_register_replica(&addr).await?;
+            let serialized_replica = serialize(&replica).map_err(|err| {
+                Error::RegisterArchive(format!("Failed to serialise Register {addr:?}: {err}"))
+            })?;
+            writer
+                .write_all(&(serialized_replica.len() as u64).to_le_bytes())
+                .and_then(|()| writer.write_all(&serialized_replica))
+                .map_err(|err| {
+                    Error::RegisterArchive(format!("Failed to write Register {addr:?}: {err}"))
+                })?;
+            exported += 1;
+        }
+        writer
+            .flush()
+            .map_err(|err| Error::RegisterArchive(format!("Failed to flush the archive: {err}")))?;
+
+        trace!("Exported {exported} Register/s to the archive");
+        Ok(exported)
+    }
+
+    /// Imports the Registers of an archive created by `export_archive`, replaying
+    /// each of their logs through `update`, so the cmds are validated as upon a replication.
+    /// Returns the number of Registers imported.
+    #[allow(dead_code)]
+    pub(crate) async fn import_archive(&self, mut reader: impl io::Read) -> Result<usize> {
+        let mut imported = 0;
+        loop {
+            let mut len_bytes = [0; 8];
+            match reader.read_exact(&mut len_bytes) {
+                Ok(()) => {}
+                Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => break,
+                Err(err) => {
+                    return Err(Error::RegisterArchive(format!(
+                        "Failed to read the archive: {err}"
+                    )))
+                }
+            }
+            // The buffer only grows as the Register is actually read, so a corrupted
+            // length can't make us allocate more than what the archive holds.
+            let len = u64::from_le_bytes(len_bytes);
+            let mut serialized_replica = vec![];
+            let read_len = io::Read::read_to_end(
+                &mut io::Read::take(&mut reader, len),
+                &mut serialized_replica,
+            )
+            .map_err(|err| Error::RegisterArchive(format!("Failed to read the archive: {err}")))?;
+            if (read_len as u64) < len {
+                return Err(Error::RegisterArchive(format!(
+                    "The archive is truncated: {read_len} out of {len} bytes of a Register read"
+                )));
             }
+            let replica: ReplicatedRegisterLog = bincode::deserialize(&serialized_replica)
+                .map_err(|err| {
+                    Error::RegisterArchive(format!("Failed to deserialise a Register: {err}"))
+                })?;
+            self.update(&replica).await?;
+            imported += 1;
         }
 
-        trace!("Listing all register addrs done.");
+        trace!("Imported {imported} Register/s from the archive");
+        Ok(imported)
+    }
+}
 
-        addrs.into_values().collect()
+// Reads the address of the Register out of the first of the files in its log dir it can be read from.
+async fn read_log_dir_addr(log_dir: &Path) -> Option<RegisterAddress> {
+    for filepath in walk_files_in(log_dir) {
+        let serialized_data = match read(&filepath).await {
+            Ok(serialized_data) => serialized_data,
+            Err(_) => continue,
+        };
+        let file_name = filepath.file_name().and_then(|name| name.to_str());
+        if file_name.is_some_and(is_journal_file_name) {
+            continue;
+        } else if file_name == Some(REGISTER_SNAPSHOT_FILE_NAME) {
+            if let Ok(snapshot) = decode_snapshot(&serialized_data) {
+                let addr = match &snapshot.state {
+                    Some(register) => Some(*register.address()),
+                    None => snapshot.op_log.first().map(|cmd| cmd.dst()),
+                };
+                if addr.is_some() {
+                    return addr;
+                }
+            }
+        } else if let Ok(cmd) = decode_cmd(&serialized_data) {
+            return Some(cmd.dst());
+        }
     }
+    None
+}
+
+// Whether both replicas are of the same Register, i.e. same address, authority and policy.
+fn is_same_register(a: &RegisterReplica, b: &RegisterReplica) -> bool {
+    a.address() == b.address()
+        && a.replica_authority() == b.replica_authority()
+        && a.policy() == b.policy()
 }
 
 /// Verify the authority over the provided `payload`.
@@ -536,9 +1860,116 @@ fn verify_authority(auth: &DataAuthority, payload: impl AsRef<[u8]>) -> Result<(
     }
 }
 
+/// Reconstructs a Register out of its log of cmds, as sent along with its entries by a
+/// [`RegisterQuery::ReadWithLog`], verifying the signature of every cmd and the permissions of
+/// its author, so the entries read can be checked without trusting the node serving the read.
+#[allow(dead_code)]
+pub(crate) fn reconstruct_verified(log: &ReplicatedRegisterLog) -> Result<RegisterReplica> {
+    let create = log.op_log.iter().find_map(|cmd| match cmd {
+        RegisterCmd::Create(create) if create.dst() == log.address => Some(create),
+        _ => None,
+    });
+    let SignedRegisterCreate { op, auth } = create.ok_or(Error::RegisterNotFound(log.address))?;
+    let serialised_op = serialize(op).map_err(|_| Error::RegisterCmdNotStored(log.address))?;
+    verify_authority(auth, serialised_op)?;
+
+    let mut register = RegisterReplica::new(op.policy.owner, op.name, op.tag, op.policy.clone());
+    for entry_op in &op.initial_entries {
+        register.check_permissions(Action::Write, Some(User::Key(auth.public_key)))?;
+        register.apply_op(entry_op.clone())?;
+    }
+    for cmd in &log.op_log {
+        apply_cmd(cmd, &mut register)?;
+    }
+    Ok(register)
+}
+
+// Try to apply the provided cmd to the register state, performing all op validations
+fn apply_cmd(cmd: &RegisterCmd, register: &mut RegisterReplica) -> Result<()> {
+    let dst_addr = cmd.dst();
+    if &dst_addr != register.address() {
+        return Err(Error::RegisterAddrMismatch {
+            dst_addr,
+            reg_addr: *register.address(),
+        });
+    }
+
+    match cmd {
+        RegisterCmd::Create { .. } => Ok(()),
+        RegisterCmd::Edit(SignedRegisterEdit { op, auth }) => {
+            let serialised_op = serialize(op).map_err(|err| {
+                warn!("We couldn't serialise the Register cmd to write it to disk: {err:?}");
+                Error::RegisterCmdNotStored(dst_addr)
+            })?;
+            verify_authority(auth, serialised_op)?;
+
+            info!("Editing Register: {dst_addr:?}");
+            let public_key = auth.public_key;
+            register.check_permissions(Action::Write, Some(User::Key(public_key)))?;
+            let result = register.apply_op(op.edit.clone());
+
+            match result {
+                Ok(()) => {
+                    trace!("Editing Register success: {dst_addr:?}");
+                    Ok(())
+                }
+                Err(err) => {
+                    trace!("Editing Register failed {dst_addr:?}: {err:?}");
+                    Err(err)
+                }
+            }
+        }
+        RegisterCmd::SetExpiry(SignedRegisterSetExpiry { op, auth }) => {
+            let serialised_op = serialize(op).map_err(|err| {
+                warn!("We couldn't serialise the Register cmd to write it to disk: {err:?}");
+                Error::RegisterCmdNotStored(dst_addr)
+            })?;
+            verify_authority(auth, serialised_op)?;
+
+            // The expiry doesn't change the Register itself, it's read from its log.
+            let user = User::Key(auth.public_key);
+            if user != register.owner() {
+                return Err(Error::NotRegisterOwner {
+                    address: dst_addr,
+                    user,
+                });
+            }
+            info!(
+                "Setting expiry of Register {dst_addr:?} at {:?}",
+                op.expires_at
+            );
+            Ok(())
+        }
+    }
+}
+
+// Verify the authority over the op of the provided cmd.
+fn verify_cmd_authority(cmd: &RegisterCmd) -> Result<()> {
+    let (serialised_op, auth) = match cmd {
+        RegisterCmd::Create(SignedRegisterCreate { op, auth }) => (serialize(op), auth),
+        RegisterCmd::Edit(SignedRegisterEdit { op, auth }) => (serialize(op), auth),
+        RegisterCmd::SetExpiry(SignedRegisterSetExpiry { op, auth }) => (serialize(op), auth),
+    };
+    let serialised_op = serialised_op.map_err(|_| Error::RegisterCmdNotStored(cmd.dst()))?;
+    verify_authority(auth, serialised_op)
+}
+
+// Check the Register size matches the one expected by a conditional edit. An edit which was
+// already applied is not checked, so duplicated conditional edits remain idempotent.
+fn check_expected_size(op: &EditRegister, register: &RegisterReplica) -> Result<()> {
+    if let Some(expected) = op.expected_size {
+        let already_applied = register.get(EntryHash(op.edit.crdt_op.hash())).is_ok();
+        let actual = register.size();
+        if !already_applied && expected != actual {
+            return Err(Error::PreconditionFailed { expected, actual });
+        }
+    }
+    Ok(())
+}
+
 // Gets an operation id, deterministic for a RegisterCmd, it takes
 // the full Cmd and all signers into consideration
-fn register_op_id(cmd: &RegisterCmd) -> Result<String> {
+pub(crate) fn register_op_id(cmd: &RegisterCmd) -> Result<String> {
     use tiny_keccak::Hasher;
     let mut hasher = tiny_keccak::Sha3::v256();
     let bytes = serialize(cmd).map_err(|err| {
@@ -553,42 +1984,387 @@ fn register_op_id(cmd: &RegisterCmd) -> Result<String> {
 }
 
 fn list_files_in(path: &Path) -> Vec<PathBuf> {
-    if !path.exists() {
-        return vec![];
-    }
+    walk_files_in(path).collect()
+}
 
+// Lazily walks the files under the given path, each dir's entries sorted by their name,
+// so they are always walked in the same order.
+fn walk_files_in(path: &Path) -> impl Iterator<Item = PathBuf> {
     WalkDir::new(path)
+        .sort_by_file_name()
         .into_iter()
         .filter_map(|e| match e {
             Ok(direntry) => Some(direntry),
             Err(err) => {
-                warn!("Store: failed to process filesystem entry: {}", err);
+                // it's fine if there is no store dir just yet
+                if err.io_error().map(|err| err.kind()) != Some(io::ErrorKind::NotFound) {
+                    warn!("Store: failed to process filesystem entry: {}", err);
+                }
                 None
             }
         })
         .filter(|entry| entry.file_type().is_file())
         .map(|entry| entry.path().to_path_buf())
+}
+
+// Lazily walks the dirs under the given path holding a Register's log, i.e. those holding files.
+// Since log dirs hold no other dirs, all the files of a log dir are walked one after the other.
+fn register_log_dirs(path: &Path) -> impl Iterator<Item = PathBuf> {
+    walk_files_in(path)
+        .filter(|filepath| {
+            filepath.file_name().and_then(|name| name.to_str()) != Some(WRITABLE_PROBE_FILE_NAME)
+        })
+        .filter_map(|filepath| filepath.parent().map(Path::to_path_buf))
+        .dedup()
+}
+
+// Returns the number of cmd files in a Register's log dir, along with the
+// disk space taken by all its files, snapshot included.
+fn log_dir_usage(path: &Path) -> (usize, u64) {
+    let mut cmds = 0;
+    let mut bytes = 0;
+    for filepath in list_files_in(path) {
+        let file_name = filepath.file_name().and_then(|name| name.to_str());
+        let is_cmd = match file_name {
+            Some(REGISTER_SNAPSHOT_FILE_NAME | REGISTER_SNAPSHOT_TMP_FILE_NAME) => false,
+            Some(name) => !is_journal_file_name(name),
+            None => true,
+        };
+        if is_cmd {
+            cmds += 1;
+        }
+        match filepath.metadata() {
+            Ok(metadata) => bytes += disk_usage(&metadata),
+            Err(err) => warn!("Store: failed to read the metadata of {filepath:?}: {err}"),
+        }
+    }
+    (cmds, bytes)
+}
+
+// The space allocated on disk for a file, which for small files, as cmd files are,
+// is usually well above their length.
+fn disk_usage(metadata: &std::fs::Metadata) -> u64 {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        metadata.blocks() * 512
+    }
+    #[cfg(not(unix))]
+    {
+        metadata.len()
+    }
+}
+
+// Whether the file of a Register's log dir is the journal of a batch of cmds.
+fn is_journal_file_name(file_name: &str) -> bool {
+    file_name.starts_with(REGISTER_JOURNAL_FILE_PREFIX)
+}
+
+// Lists the files in the given dir ordered by the time they were last modified,
+// i.e. for the cmd files, which are never modified once written, in the order they were stored.
+fn list_files_in_written_order(path: &Path) -> Vec<PathBuf> {
+    let mut files = list_files_in(path);
+    files.sort_by_cached_key(|filepath| {
+        let modified = filepath.metadata().and_then(|metadata| metadata.modified());
+        (modified.ok(), filepath.clone())
+    });
+    files
+}
+
+// Reads the cmds stored in the given file of the Register's log, i.e. either a cmd file or
+// the snapshot, holding all the cmds compacted into it, without validating them.
+async fn read_raw_cmds(address: RegisterAddress, filepath: PathBuf) -> Vec<Result<RegisterCmd>> {
+    let is_snapshot =
+        filepath.file_name().and_then(|name| name.to_str()) == Some(REGISTER_SNAPSHOT_FILE_NAME);
+    let unreadable = |err| {
+        warn!(
+            "Couldn't read raw Register cmd/s of {address:?} from {}: {err:?}",
+            filepath.display()
+        );
+        Error::RegisterCmdUnreadable {
+            address,
+            path: filepath.clone(),
+        }
+    };
+    let serialized_data = match read(&filepath).await {
+        Ok(serialized_data) => serialized_data,
+        Err(err) => return vec![Err(unreadable(err.to_string()))],
+    };
+
+    if is_snapshot {
+        match decode_snapshot(&serialized_data) {
+            Ok(snapshot) => snapshot.op_log.into_iter().map(Ok).collect(),
+            Err(err) => vec![Err(unreadable(err.to_string()))],
+        }
+    } else {
+        vec![decode_cmd(&serialized_data).map_err(|err| unreadable(err.to_string()))]
+    }
+}
+
+// Syncs the dir at the given path to disk, persisting its entries.
+fn sync_dir(path: &Path) -> io::Result<()> {
+    std::fs::File::open(path)?.sync_all()
+}
+
+// Checks the size of the entries written by the cmd, so an oversized cmd is rejected
+// before the Register it targets is even loaded from disk.
+fn check_entries_size(cmd: &RegisterCmd) -> Result<()> {
+    let oversized = match cmd {
+        RegisterCmd::Edit(SignedRegisterEdit { op, .. }) => {
+            Some(op.edit.crdt_op.value.len()).filter(|size| *size > MAX_REG_ENTRY_SIZE)
+        }
+        RegisterCmd::Create(SignedRegisterCreate { op, .. }) => op
+            .initial_entries
+            .iter()
+            .map(|entry_op| entry_op.crdt_op.value.len())
+            .find(|size| *size > MAX_REG_ENTRY_SIZE),
+        RegisterCmd::SetExpiry(_) => None,
+    };
+    match oversized {
+        Some(size) => Err(Error::EntryTooBig {
+            size,
+            max: MAX_REG_ENTRY_SIZE,
+        }),
+        None => Ok(()),
+    }
+}
+
+// Returns the hash of the entry the cmd writes, if it's an edit cmd.
+fn edit_entry_hash(cmd: &RegisterCmd) -> Option<EntryHash> {
+    match cmd {
+        RegisterCmd::Edit(SignedRegisterEdit { op, .. }) => Some(EntryHash(op.edit.crdt_op.hash())),
+        RegisterCmd::Create(_) | RegisterCmd::SetExpiry(_) => None,
+    }
+}
+
+// Maps the hash of each entry written by the cmds of the log, including the initial entries
+// of the 'Register create' cmd, to the user whose key the cmd writing it was signed with.
+fn entry_authors(op_log: &RegisterLog) -> BTreeMap<EntryHash, User> {
+    let mut authors = BTreeMap::new();
+    for cmd in op_log {
+        match cmd {
+            RegisterCmd::Create(SignedRegisterCreate { op, auth }) => {
+                for entry_op in &op.initial_entries {
+                    let _ = authors.insert(
+                        EntryHash(entry_op.crdt_op.hash()),
+                        User::Key(auth.public_key),
+                    );
+                }
+            }
+            RegisterCmd::Edit(SignedRegisterEdit { op, auth }) => {
+                let _ = authors.insert(
+                    EntryHash(op.edit.crdt_op.hash()),
+                    User::Key(auth.public_key),
+                );
+            }
+            RegisterCmd::SetExpiry(_) => {}
+        }
+    }
+    authors
+}
+
+// Maps the hash of each entry written by the cmds of the Register's log, including the initial
+// entries of the 'Register create' cmd, to the time the cmd writing it was stored at on this node.
+// The entries of the cmds whose store time isn't known are left out.
+fn entry_write_times(stored_reg: &StoredRegister) -> BTreeMap<EntryHash, SystemTime> {
+    let mut write_times = BTreeMap::new();
+    for cmd in &stored_reg.op_log {
+        let stored_at = register_op_id(cmd)
+            .ok()
+            .and_then(|cmd_id| stored_reg.stored_at.get(&cmd_id));
+        let written_at = match stored_at {
+            Some(stored_at) => *stored_at,
+            None => continue,
+        };
+        match cmd {
+            RegisterCmd::Create(SignedRegisterCreate { op, .. }) => {
+                for entry_op in &op.initial_entries {
+                    let _ = write_times.insert(EntryHash(entry_op.crdt_op.hash()), written_at);
+                }
+            }
+            RegisterCmd::Edit(SignedRegisterEdit { op, .. }) => {
+                let _ = write_times.insert(EntryHash(op.edit.crdt_op.hash()), written_at);
+            }
+            RegisterCmd::SetExpiry(_) => {}
+        }
+    }
+    write_times
+}
+
+// Deserialises a Register snapshot, including those written before the time
+// the compacted cmds were stored at was kept in it.
+fn decode_snapshot(data: &[u8]) -> bincode::Result<RegisterSnapshot> {
+    bincode::deserialize::<RegisterSnapshot>(data).or_else(|err| {
+        match bincode::deserialize::<LegacyRegisterSnapshot>(data) {
+            Ok(LegacyRegisterSnapshot { state, op_log }) => Ok(RegisterSnapshot {
+                state,
+                op_log,
+                stored_at: BTreeMap::new(),
+            }),
+            Err(_) => Err(err),
+        }
+    })
+}
+
+// Reconstructs the Register by applying the cmds of its loaded log, but those already applied
+// to the state loaded from a snapshot. If `lenient`, the cmds which can't be applied are
+// skipped rather than failing, returning the ids of those skipped.
+fn replay_stored_register(
+    addr: &RegisterAddress,
+    mut stored_reg: StoredRegister,
+    lenient: bool,
+) -> Result<(StoredRegister, Vec<String>)> {
+    let mut skipped = vec![];
+    // if we have the Register creation cmd, apply all ops to reconstruct the Register,
+    // but those already applied to the state loaded from a snapshot.
+    if let Some(register) = &mut stored_reg.state {
+        for cmd in stored_reg.op_log.iter().skip(stored_reg.replay_from) {
+            let applied = match cmd {
+                RegisterCmd::Create(SignedRegisterCreate { op, .. }) => op
+                    .initial_entries
+                    .iter()
+                    .try_for_each(|entry_op| register.apply_op(entry_op.clone())),
+                RegisterCmd::Edit(SignedRegisterEdit { op, .. }) => {
+                    let EditRegister { edit, .. } = op;
+                    register.apply_op(edit.clone())
+                }
+                RegisterCmd::SetExpiry(_) => Ok(()),
+            };
+            if let Err(err) = applied {
+                if !lenient {
+                    return Err(err);
+                }
+                let op_id = register_op_id(cmd)?;
+                warn!("Skipping cmd {op_id} of Register {addr:?}, which can't be applied: {err:?}");
+                skipped.push(op_id);
+            }
+        }
+    }
+
+    Ok((stored_reg, skipped))
+}
+
+// Verifies the Register's log, as `RegisterStorage::verify` does, handing the log back.
+async fn verify_log(
+    address: RegisterAddress,
+    op_log: RegisterLog,
+) -> Result<(LogVerificationReport, RegisterLog)> {
+    let gaps = find_gaps(&op_log);
+    if !gaps.is_empty() {
+        warn!(
+            "Register {address:?} log is missing {} entry/ies referenced as parents",
+            gaps.len()
+        );
+    }
+
+    let (sender, receiver) = oneshot::channel();
+    rayon::spawn(move || {
+        let results: Result<Vec<_>> = op_log
+            .par_iter()
+            .map(|cmd| Ok((register_op_id(cmd)?, verify_cmd_authority(cmd).is_ok())))
+            .collect();
+        let _ = sender.send((results, op_log));
+    });
+    let (results, op_log) = receiver
+        .await
+        .map_err(|_| Error::RegisterLogNotVerified(address))?;
+    let results = results?;
+
+    let cmds_verified = results.len();
+    let mut invalid_cmds: Vec<String> = results
+        .into_iter()
+        .filter(|(_, is_valid)| !is_valid)
+        .map(|(op_id, _)| op_id)
+        .collect();
+    invalid_cmds.sort();
+
+    let report = LogVerificationReport {
+        cmds_verified,
+        invalid_cmds,
+        gaps,
+    };
+    Ok((report, op_log))
+}
+
+// Returns the hashes of the parents of the entries written by the cmds of the log
+// which none of its cmds writes, i.e. the gaps in the log's causal history, sorted.
+fn find_gaps(op_log: &RegisterLog) -> Vec<EntryHash> {
+    let written = entry_authors(op_log);
+    let mut gaps = BTreeSet::new();
+    for cmd in op_log {
+        let entry_ops: Vec<_> = match cmd {
+            RegisterCmd::Create(SignedRegisterCreate { op, .. }) => {
+                op.initial_entries.iter().collect()
+            }
+            RegisterCmd::Edit(SignedRegisterEdit { op, .. }) => vec![&op.edit],
+            RegisterCmd::SetExpiry(_) => vec![],
+        };
+        for entry_op in entry_ops {
+            gaps.extend(
+                entry_op
+                    .crdt_op
+                    .children
+                    .iter()
+                    .map(|parent| EntryHash(*parent))
+                    .filter(|parent| !written.contains_key(parent)),
+            );
+        }
+    }
+    gaps.into_iter().collect()
+}
+
+// Returns the hashes of the entries written by the applied cmds which the Register now holds,
+// leaving out those it held already, in the order they were applied.
+fn new_entries(
+    stored_reg: &StoredRegister,
+    applied_cmds: &[RegisterCmd],
+    mut known_entries: BTreeSet<EntryHash>,
+) -> Vec<EntryHash> {
+    let register = match &stored_reg.state {
+        Some(register) => register,
+        None => return vec![],
+    };
+    applied_cmds
+        .iter()
+        .filter_map(edit_entry_hash)
+        .filter(|hash| register.get(*hash).is_ok() && known_entries.insert(*hash))
         .collect()
 }
 
 #[cfg(test)]
 mod test {
-    use super::{Error, RegisterReplica, RegisterStorage};
+    use super::{
+        decode_cmd, is_journal_file_name, list_files_in, reconstruct_verified, register_op_id,
+        Error, RegisterReplica, RegisterStorage, REGISTER_SNAPSHOT_FILE_NAME,
+    };
 
+    use crate::network::{NetworkEvent, SwarmDriver, DEFAULT_MAX_MSG_SIZE};
     use crate::protocol::{
         error::Error as ProtocolError,
         messages::{
-            CreateRegister, EditRegister, QueryResponse, RegisterCmd, RegisterQuery,
-            SignedRegisterCreate, SignedRegisterEdit,
+            decompress_register_entries, CreateRegister, EditRegister, QueryResponse, RegisterCmd,
+            RegisterQuery, ReplicatedRegisterLog, SetRegisterExpiry, SignedRegisterCreate,
+            SignedRegisterEdit, SignedRegisterSetExpiry,
+        },
+        storage::{
+            registers::{Action, DataAuthority, EntryHash, Permissions, Policy, User},
+            RegisterAddress,
         },
-        storage::registers::{DataAuthority, EntryHash, Policy, User},
     };
 
     use bincode::serialize;
     use bls::SecretKey;
     use eyre::{bail, Result};
+    use futures::StreamExt;
     use rand::{distributions::Alphanumeric, Rng};
-    use std::collections::BTreeSet;
+    use std::{
+        collections::{BTreeMap, BTreeSet},
+        sync::{
+            atomic::{AtomicU64, AtomicUsize, Ordering},
+            Arc,
+        },
+        time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+    };
     use xor_name::XorName;
 
     #[tokio::test]
@@ -635,6 +2411,37 @@ mod test {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_register_try_load_stored_skips_duplicated_cmds() -> Result<()> {
+        let store = new_store();
+
+        let (cmd_create, _, sk, name, policy) = create_register()?;
+        let addr = cmd_create.dst();
+        let log_path = store.address_to_filepath(&addr)?;
+        let mut register = RegisterReplica::new(policy.owner, name, 0, policy);
+        store.write(&cmd_create).await?;
+        let cmd_edit = edit_register(&mut register, &sk)?;
+        store.write(&cmd_edit).await?;
+
+        // Copy the edit cmd file under another name, as a botched copy of the log could do.
+        let cmd_edit_path = log_path.join(register_op_id(&cmd_edit)?);
+        let _ = std::fs::copy(&cmd_edit_path, log_path.join("copy-of-edit"))?;
+
+        let stored_reg = store.try_load_stored_register(&addr).await?;
+        assert_eq!(stored_reg.op_log.len(), 2);
+        assert_eq!(
+            stored_reg
+                .op_log
+                .iter()
+                .filter(|op| *op == &cmd_edit)
+                .count(),
+            1
+        );
+        assert_eq!(stored_reg.state.as_ref(), Some(&register));
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_register_try_load_stored_inverted_cmds_order() -> Result<()> {
         let store = new_store();
@@ -800,7 +2607,7 @@ mod test {
         store.write(&cmd).await?;
 
         let addr = cmd.dst();
-        match store.read(&RegisterQuery::Get(addr), authority).await {
+        match store.read(&RegisterQuery::Get(addr), Some(authority)).await {
             QueryResponse::GetRegister(Ok(reg)) => {
                 assert_eq!(reg.crdt.address, addr, "Should have same address!");
                 assert_eq!(reg.policy.owner, authority, "Should have same owner!");
@@ -818,6 +2625,49 @@ mod test {
         }
     }
 
+    #[tokio::test]
+    async fn test_register_addrs_paging() -> Result<()> {
+        let store = new_store();
+        assert_eq!(store.count_addrs(), 0);
+        assert!(store.addrs_page(0, 10).await.is_empty());
+
+        let mut addrs = BTreeSet::new();
+        for _ in 0..7 {
+            let (cmd_create, _, sk, name, policy) = create_register()?;
+            let address = cmd_create.dst();
+            store.write(&cmd_create).await?;
+            let mut register = RegisterReplica::new(policy.owner, name, 0, policy);
+            store.write(&edit_register(&mut register, &sk)?).await?;
+            let _ = addrs.insert(address);
+        }
+        // a compacted Register is listed as well
+        let compacted = addrs.iter().next().copied();
+        if let Some(address) = compacted {
+            let _ = store.compact(&address).await?;
+        }
+
+        let all_addrs = store.stored_addrs().await;
+        assert_eq!(store.count_addrs(), all_addrs.len());
+        assert_eq!(all_addrs.len(), addrs.len());
+
+        let mut paged = Vec::new();
+        let mut offset = 0;
+        loop {
+            let page = store.addrs_page(offset, 3).await;
+            if page.is_empty() {
+                break;
+            }
+            assert!(page.len() <= 3);
+            offset += page.len();
+            paged.extend(page);
+        }
+        // every address is listed exactly once
+        assert_eq!(paged, all_addrs);
+        assert_eq!(paged.iter().copied().collect::<BTreeSet<_>>(), addrs);
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_register_export() -> Result<()> {
         let store = new_store();
@@ -863,7 +2713,9 @@ mod test {
         }
 
         // Should be able to read the same value from this new store as well.
-        let res = new_store.read(&RegisterQuery::Get(addr), authority).await;
+        let res = new_store
+            .read(&RegisterQuery::Get(addr), Some(authority))
+            .await;
 
         match res {
             QueryResponse::GetRegister(Ok(reg)) => {
@@ -876,6 +2728,129 @@ mod test {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_register_validate_leaves_store_untouched() -> Result<()> {
+        let store = new_store();
+
+        let (cmd_create, _, sk, name, policy) = create_register()?;
+        let address = cmd_create.dst();
+        store.write(&cmd_create).await?;
+        let path = store.address_to_filepath(&address)?;
+        let files_before: BTreeSet<_> = list_files_in(&path).into_iter().collect();
+
+        let mut register = RegisterReplica::new(policy.owner, name, 0, policy);
+        let cmd_edit = edit_register(&mut register, &sk)?;
+        store.validate(&cmd_edit).await?;
+
+        // An edit signed with a key other than the one it claims is rejected.
+        let mut forged_edit = as_edit_cmd(&edit_register(&mut register, &sk)?)?.clone();
+        forged_edit.auth.signature = SecretKey::random().sign(serialize(&forged_edit.op)?);
+        match store.validate(&RegisterCmd::Edit(forged_edit)).await {
+            Err(Error::InvalidSignature(_)) => {}
+            other => bail!("Validating a wrongly signed edit should fail: {other:?}"),
+        }
+
+        // Neither validation stored anything.
+        let files_after: BTreeSet<_> = list_files_in(&path).into_iter().collect();
+        assert_eq!(files_after, files_before);
+        let stored_reg = store.try_load_stored_register(&address).await?;
+        assert_eq!(stored_reg.op_log.len(), 1);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_register_export_archive() -> Result<()> {
+        let store = new_store();
+
+        let mut addrs = vec![];
+        for _ in 0..5 {
+            let (cmd_create, _, sk, name, policy) = create_register()?;
+            let mut register = RegisterReplica::new(policy.owner, name, 0, policy);
+            store.write(&cmd_create).await?;
+            for _ in 0..3 {
+                let cmd_edit = edit_register(&mut register, &sk)?;
+                store.write(&cmd_edit).await?;
+            }
+            addrs.push(cmd_create.dst());
+        }
+
+        let mut archive = vec![];
+        assert_eq!(store.export_archive(&mut archive).await?, addrs.len());
+
+        let new_store = new_store();
+        assert_eq!(
+            new_store.import_archive(archive.as_slice()).await?,
+            addrs.len()
+        );
+
+        for addr in addrs {
+            let original = store.try_load_stored_register(&addr).await?.state;
+            let imported = new_store.try_load_stored_register(&addr).await?.state;
+            assert!(original.is_some());
+            assert_eq!(original, imported);
+            assert_eq!(store.op_ids(&addr).await?, new_store.op_ids(&addr).await?);
+        }
+
+        // A truncated archive is reported rather than partially imported as if whole.
+        let mut archive = vec![];
+        let _ = store.export_archive(&mut archive).await?;
+        archive.truncate(archive.len() - 1);
+        match new_store.import_archive(archive.as_slice()).await {
+            Err(Error::RegisterArchive(_)) => {}
+            other => bail!("Importing a truncated archive should fail: {other:?}"),
+        }
+
+        // As is one whose length of a Register is corrupted, without allocating that length.
+        let mut archive = u64::MAX.to_le_bytes().to_vec();
+        archive.extend_from_slice(b"not a Register");
+        match new_store.import_archive(archive.as_slice()).await {
+            Err(Error::RegisterArchive(_)) => {}
+            other => bail!("Importing a corrupted archive should fail: {other:?}"),
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_register_anonymous_read() -> Result<()> {
+        let store = new_store();
+        let (owner, sk) = random_user();
+
+        // a Register whose policy grants anyone to read it
+        let public_policy = Policy {
+            owner,
+            permissions: [(User::Anyone, Permissions::new(None))]
+                .into_iter()
+                .collect(),
+        };
+        let public_cmd = create_reg_w_policy(xor_name::rand::random(), 0, public_policy, &sk)?;
+        let public_addr = public_cmd.dst();
+        store.write(&public_cmd).await?;
+
+        match store.read(&RegisterQuery::Get(public_addr), None).await {
+            QueryResponse::GetRegister(Ok(_)) => {}
+            other => bail!("Anonymous read of a public Register should succeed: {other:?}"),
+        }
+
+        // whereas a Register readable only by its owner is denied to an anonymous requester
+        let (private_cmd, ..) = create_register()?;
+        let private_addr = private_cmd.dst();
+        store.write(&private_cmd).await?;
+
+        match store.read(&RegisterQuery::Get(private_addr), None).await {
+            QueryResponse::GetRegister(Err(ProtocolError::Storage(Error::AccessDenied {
+                address,
+                user: User::Anyone,
+                action: Action::Read,
+                granted: None,
+            }))) => assert_eq!(address, private_addr),
+            other => bail!("Anonymous read of a private Register should be denied: {other:?}"),
+        }
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_register_non_existing_entry() -> Result<()> {
         let store = new_store();
@@ -888,7 +2863,7 @@ mod test {
         // Try get permissions of random user.
         let address = cmd_create.dst();
         let res = store
-            .read(&RegisterQuery::GetEntry { address, hash }, authority)
+            .read(&RegisterQuery::GetEntry { address, hash }, Some(authority))
             .await;
         match res {
             QueryResponse::GetRegisterEntry(Err(e)) => {
@@ -904,72 +2879,1948 @@ mod test {
     }
 
     #[tokio::test]
-    async fn test_register_non_existing_permissions() -> Result<()> {
+    async fn test_register_get_owners() -> Result<()> {
         let store = new_store();
 
-        let (cmd_create, authority, _, _, _) = create_register()?;
-        store.write(&cmd_create).await?;
-
-        let (user, _) = random_user();
+        let (cmd_create_1, authority, sk, _, policy) = create_register()?;
+        store.write(&cmd_create_1).await?;
+        let cmd_create_2 = create_reg_w_policy(xor_name::rand::random(), 0, policy, &sk)?;
+        store.write(&cmd_create_2).await?;
 
-        // Try get permissions of random user.
-        let address = cmd_create.dst();
+        let missing = RegisterAddress::new(xor_name::rand::random(), 0);
+        let addresses = vec![cmd_create_1.dst(), missing, cmd_create_2.dst()];
         let res = store
             .read(
-                &RegisterQuery::GetUserPermissions { address, user },
-                authority,
+                &RegisterQuery::GetOwners {
+                    addresses: addresses.clone(),
+                },
+                Some(authority),
             )
             .await;
         match res {
-            QueryResponse::GetRegisterUserPermissions(Err(e)) => {
-                assert_eq!(e, ProtocolError::Storage(Error::NoSuchUser(user)))
-            }
-            QueryResponse::GetRegisterUserPermissions(Ok(perms)) => {
-                panic!("Should not exist any permissions for random user! {perms:?}",)
+            QueryResponse::GetRegisterOwners(owners) => {
+                assert_eq!(
+                    owners,
+                    vec![
+                        (addresses[0], Ok(authority)),
+                        (
+                            missing,
+                            Err(ProtocolError::Storage(Error::RegisterNotFound(missing)))
+                        ),
+                        (addresses[2], Ok(authority)),
+                    ]
+                );
             }
-            e => panic!("Could not read! {e:?}"),
+            e => panic!("Could not read owners! {e:?}"),
         }
 
         Ok(())
     }
 
-    fn random_user() -> (User, SecretKey) {
-        let sk = SecretKey::random();
-        let authority = User::Key(sk.public_key());
-        (authority, sk)
-    }
+    #[tokio::test]
+    async fn test_register_can_perform() -> Result<()> {
+        let store = new_store();
 
-    fn create_register() -> Result<(RegisterCmd, User, SecretKey, XorName, Policy)> {
         let (authority, sk) = random_user();
+        let (writer, _) = random_user();
+        let (other_user, _) = random_user();
         let policy = Policy {
             owner: authority,
-            permissions: Default::default(),
+            permissions: [(writer, Permissions::new(true))].into(),
         };
-        let xorname = xor_name::rand::random();
-        let cmd = create_reg_w_policy(xorname, 0, policy.clone(), &sk)?;
+        let cmd_create = create_reg_w_policy(xor_name::rand::random(), 0, policy, &sk)?;
+        let address = cmd_create.dst();
+        store.write(&cmd_create).await?;
 
-        Ok((cmd, authority, sk, xorname, policy))
-    }
+        let cases = [
+            (writer, Action::Write, true),
+            (writer, Action::Admin, false),
+            (authority, Action::Admin, true),
+            (other_user, Action::Write, false),
+        ];
+        for (user, action, expected) in cases {
+            let query = RegisterQuery::CanPerform {
+                address,
+                user,
+                action,
+            };
+            match store.read(&query, Some(authority)).await {
+                QueryResponse::RegisterCanPerform(Ok(allowed)) => assert_eq!(
+                    allowed, expected,
+                    "Unexpected permission of {user:?} to {action:?}"
+                ),
+                other => bail!("Could not check the permissions: {other:?}"),
+            }
+        }
 
-    fn edit_register(register: &mut RegisterReplica, sk: &SecretKey) -> Result<RegisterCmd> {
-        let data = rand::thread_rng()
+        // Nothing was performed on the Register.
+        match store
+            .read(&RegisterQuery::Read(address), Some(authority))
+            .await
+        {
+            QueryResponse::ReadRegister(Ok(entries)) => assert!(entries.is_empty()),
+            other => bail!("Could not read the Register: {other:?}"),
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_register_not_held_is_reported_right_away() -> Result<()> {
+        let store = new_store();
+
+        let (cmd_create, authority, _, _, _) = create_register()?;
+        store.write(&cmd_create).await?;
+
+        let missing = RegisterAddress::new(xor_name::rand::random(), 0);
+        let start = Instant::now();
+        for _ in 0..1000 {
+            match store
+                .read(&RegisterQuery::Get(missing), Some(authority))
+                .await
+            {
+                QueryResponse::GetRegister(Err(ProtocolError::Storage(
+                    Error::RegisterNotFound(address),
+                ))) => assert_eq!(address, missing),
+                other => bail!("Unexpected response for a Register not held: {other:?}"),
+            }
+        }
+        // Generous, as it's only meant to catch the Register being loaded for each query.
+        assert!(start.elapsed() < Duration::from_secs(5));
+        assert!(!store.holds_register(&missing));
+        assert_eq!(store.stored_addrs().await, vec![cmd_create.dst()]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_register_non_existing_permissions() -> Result<()> {
+        let store = new_store();
+
+        let (cmd_create, authority, _, _, _) = create_register()?;
+        store.write(&cmd_create).await?;
+
+        let (user, _) = random_user();
+
+        // Try get permissions of random user.
+        let address = cmd_create.dst();
+        let res = store
+            .read(
+                &RegisterQuery::GetUserPermissions { address, user },
+                Some(authority),
+            )
+            .await;
+        match res {
+            QueryResponse::GetRegisterUserPermissions(Err(e)) => {
+                assert_eq!(e, ProtocolError::Storage(Error::NoSuchUser(user)))
+            }
+            QueryResponse::GetRegisterUserPermissions(Ok(perms)) => {
+                panic!("Should not exist any permissions for random user! {perms:?}",)
+            }
+            e => panic!("Could not read! {e:?}"),
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_register_access_denied() -> Result<()> {
+        let store = new_store();
+
+        let (cmd_create, _, _, name, policy) = create_register()?;
+        let address = cmd_create.dst();
+        store.write(&cmd_create).await?;
+
+        // Registers are public data, hence reading it as an unpermitted user is allowed.
+        let (user, user_sk) = random_user();
+        match store.read(&RegisterQuery::Get(address), Some(user)).await {
+            QueryResponse::GetRegister(Ok(_)) => {}
+            other => bail!("Reading a Register should be allowed to anyone: {other:?}"),
+        }
+
+        // But editing it is denied, with the details of what was denied to whom.
+        let mut register = RegisterReplica::new(policy.owner, name, 0, policy);
+        let cmd_edit = edit_register(&mut register, &user_sk)?;
+        match store.write(&cmd_edit).await {
+            Err(Error::AccessDenied {
+                address: denied_address,
+                user: denied_user,
+                action,
+                granted,
+            }) => {
+                assert_eq!(denied_address, address);
+                assert_eq!(denied_user, user);
+                assert_eq!(action, Action::Write);
+                assert_eq!(granted, None);
+            }
+            other => bail!("Editing as an unpermitted user should be denied: {other:?}"),
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_register_access_denied_names_granted_permissions() -> Result<()> {
+        let store = new_store();
+
+        // a Register the user is explicitly granted to read, but not to write
+        let (owner, owner_sk) = random_user();
+        let (user, user_sk) = random_user();
+        let read_only = Permissions::new(false);
+        let policy = Policy {
+            owner,
+            permissions: [(user, read_only)].into_iter().collect(),
+        };
+        let name = xor_name::rand::random();
+        let cmd_create = create_reg_w_policy(name, 0, policy.clone(), &owner_sk)?;
+        let address = cmd_create.dst();
+        store.write(&cmd_create).await?;
+
+        let mut register = RegisterReplica::new(owner, name, 0, policy);
+        let cmd_edit = edit_register(&mut register, &user_sk)?;
+        match store.write(&cmd_edit).await {
+            Err(err @ Error::AccessDenied { .. }) => {
+                assert_eq!(
+                    err,
+                    Error::AccessDenied {
+                        address,
+                        user,
+                        action: Action::Write,
+                        granted: Some(read_only),
+                    }
+                );
+                assert!(err.to_string().contains("Write"));
+            }
+            other => bail!("Editing as a read-only user should be denied: {other:?}"),
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_register_create_with_initial_entries() -> Result<()> {
+        let store = new_store();
+
+        let (authority, sk) = random_user();
+        let policy = Policy {
+            owner: authority,
+            permissions: Default::default(),
+        };
+        let name = xor_name::rand::random();
+        let mut register = RegisterReplica::new(policy.owner, name, 0, policy.clone());
+
+        // Build three chained seed entries on a local replica.
+        let mut initial_entries = vec![];
+        for _ in 0..3 {
+            let children = register.read().into_iter().map(|(hash, _)| hash).collect();
+            let (_, edit) = register.write(random_entry(), children)?;
+            initial_entries.push(edit);
+        }
+
+        let op = CreateRegister {
+            name,
+            tag: 0,
+            policy,
+            initial_entries,
+        };
+        let auth = DataAuthority {
+            public_key: sk.public_key(),
+            signature: sk.sign(serialize(&op)?),
+        };
+        let cmd_create = RegisterCmd::Create(SignedRegisterCreate { op, auth });
+
+        // A single create write shall be enough to have all seed entries stored.
+        store.write(&cmd_create).await?;
+
+        let stored_reg = store.try_load_stored_register(&cmd_create.dst()).await?;
+        assert_eq!(stored_reg.op_log, vec![cmd_create]);
+        assert_eq!(stored_reg.state.as_ref(), Some(&register));
+        assert_eq!(stored_reg.state.map(|reg| reg.size()), Some(3));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_register_create_with_unauthorised_initial_entries() -> Result<()> {
+        let store = new_store();
+
+        let (owner, _) = random_user();
+        let policy = Policy {
+            owner,
+            permissions: Default::default(),
+        };
+        let name = xor_name::rand::random();
+        let mut register = RegisterReplica::new(owner, name, 0, policy.clone());
+        let (_, edit) = register.write(random_entry(), BTreeSet::default())?;
+
+        // The create cmd is signed by a user with no write permissions.
+        let (_, other_sk) = random_user();
+        let op = CreateRegister {
+            name,
+            tag: 0,
+            policy,
+            initial_entries: vec![edit],
+        };
+        let auth = DataAuthority {
+            public_key: other_sk.public_key(),
+            signature: other_sk.sign(serialize(&op)?),
+        };
+        let cmd_create = RegisterCmd::Create(SignedRegisterCreate { op, auth });
+
+        match store.write(&cmd_create).await {
+            Err(Error::AccessDenied { user, .. }) => {
+                assert_eq!(user, User::Key(other_sk.public_key()))
+            }
+            other => bail!("Create with unauthorised initial entries should fail: {other:?}"),
+        }
+
+        let stored_reg = store.try_load_stored_register(&cmd_create.dst()).await?;
+        assert!(stored_reg.state.is_none());
+        assert!(stored_reg.op_log.is_empty());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_register_conditional_edits_race() -> Result<()> {
+        let store = new_store();
+
+        let (cmd_create, _, sk, name, policy) = create_register()?;
+        let addr = cmd_create.dst();
+        store.write(&cmd_create).await?;
+
+        // Two writers, each with their own replica, race to edit the empty register.
+        let mut replica_1 = RegisterReplica::new(policy.owner, name, 0, policy.clone());
+        let mut replica_2 = RegisterReplica::new(policy.owner, name, 0, policy);
+        let cmd_edit_1 = edit_register_if_size(&mut replica_1, &sk, Some(0))?;
+        let cmd_edit_2 = edit_register_if_size(&mut replica_2, &sk, Some(0))?;
+
+        let results = [
+            store.write(&cmd_edit_1).await,
+            store.write(&cmd_edit_2).await,
+        ];
+        assert_eq!(results.iter().filter(|res| res.is_ok()).count(), 1);
+        assert!(results.contains(&Err(Error::PreconditionFailed {
+            expected: 0,
+            actual: 1
+        })));
+
+        // Only the edit which won the race was stored.
+        let stored_reg = store.try_load_stored_register(&addr).await?;
+        assert_eq!(stored_reg.state.as_ref().map(|reg| reg.size()), Some(1));
+        assert_eq!(stored_reg.op_log.len(), 2);
+
+        // Retrying the winning edit is still idempotent.
+        store.write(&cmd_edit_1).await?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_register_log_batch_is_synced_once() -> Result<()> {
+        let mut store = new_store();
+        let num_of_syncs = Arc::new(AtomicUsize::new(0));
+        let syncs = num_of_syncs.clone();
+        store.sync_dir = Arc::new(move |_| {
+            let _ = syncs.fetch_add(1, Ordering::Relaxed);
+            Ok(())
+        });
+
+        let (cmd_create, _, sk, name, policy) = create_register()?;
+        let address = cmd_create.dst();
+        store.write(&cmd_create).await?;
+        assert_eq!(num_of_syncs.swap(0, Ordering::Relaxed), 1);
+
+        let mut register = RegisterReplica::new(policy.owner, name, 0, policy);
+        for num_of_cmds in [1, 5, 20] {
+            let mut op_log = vec![];
+            for _ in 0..num_of_cmds {
+                op_log.push(edit_register(&mut register, &sk)?);
+            }
+            store
+                .update(&ReplicatedRegisterLog { address, op_log })
+                .await?;
+
+            // A single sync is issued for the whole batch, regardless of its size.
+            assert_eq!(num_of_syncs.swap(0, Ordering::Relaxed), 1);
+        }
+
+        let stored_reg = store.try_load_stored_register(&address).await?;
+        assert_eq!(stored_reg.state.as_ref(), Some(&register));
+        assert_eq!(stored_reg.op_log.len(), 27);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_register_journal_replay_completes_interrupted_batch() -> Result<()> {
+        let store = new_store();
+
+        let (cmd_create, _, sk, name, policy) = create_register()?;
+        let address = cmd_create.dst();
+        store.write(&cmd_create).await?;
+
+        let mut register = RegisterReplica::new(policy.owner, name, 0, policy);
+        let mut op_log = vec![];
+        for _ in 0..5 {
+            op_log.push(edit_register(&mut register, &sk)?);
+        }
+
+        // Simulate a crash amid two concurrent batches: both were journaled,
+        // but only the first couple of cmds of the first one made it to their own files.
+        let path = store.address_to_filepath(&address)?;
+        let _ = store
+            .write_journal(&op_log[..3].to_vec(), &path, address)
+            .await?;
+        let _ = store
+            .write_journal(&op_log[3..].to_vec(), &path, address)
+            .await?;
+        store
+            .write_log_to_disk(&op_log[..2].to_vec(), &path, address)
+            .await?;
+        let stored_reg = store.try_load_stored_register(&address).await?;
+        assert_eq!(stored_reg.op_log.len(), 3);
+
+        assert_eq!(store.replay_journals().await?, 2);
+        assert_eq!(journals_in(&path), 0);
+
+        let stored_reg = store.try_load_stored_register(&address).await?;
+        assert_eq!(stored_reg.op_log.len(), 6);
+        assert_eq!(stored_reg.state.as_ref(), Some(&register));
+
+        // Nothing's left to replay, and complete batches, even concurrent ones,
+        // leave no journal behind.
+        assert_eq!(store.replay_journals().await?, 0);
+        let first = ReplicatedRegisterLog {
+            address,
+            op_log: vec![edit_register(&mut register, &sk)?],
+        };
+        let second = ReplicatedRegisterLog {
+            address,
+            op_log: vec![edit_register(&mut register, &sk)?],
+        };
+        let (first_result, second_result) =
+            tokio::join!(store.update(&first), store.update(&second));
+        first_result?;
+        second_result?;
+        assert_eq!(journals_in(&path), 0);
+        assert_eq!(store.op_ids(&address).await?.len(), 8);
+
+        Ok(())
+    }
+
+    fn journals_in(path: &std::path::Path) -> usize {
+        list_files_in(path)
+            .iter()
+            .filter_map(|filepath| filepath.file_name().and_then(|name| name.to_str()))
+            .filter(|name| is_journal_file_name(name))
+            .count()
+    }
+
+    #[tokio::test]
+    async fn test_register_write_create_with_edits() -> Result<()> {
+        let mut store = new_store();
+        let num_of_syncs = Arc::new(AtomicUsize::new(0));
+        let syncs = num_of_syncs.clone();
+        store.sync_dir = Arc::new(move |_| {
+            let _ = syncs.fetch_add(1, Ordering::Relaxed);
+            Ok(())
+        });
+
+        let (cmd_create, authority, sk, name, policy) = create_register()?;
+        let address = cmd_create.dst();
+        let create = match cmd_create {
+            RegisterCmd::Create(create) => create,
+            other => bail!("Expected a create cmd, got {other:?}"),
+        };
+        let mut register = RegisterReplica::new(policy.owner, name, 0, policy);
+        let mut edits = vec![];
+        let mut expected_entries = BTreeSet::new();
+        for _ in 0..3 {
+            let (hash, cmd) =
+                edit_register_with_children(&mut register, &sk, BTreeSet::default(), None)?;
+            edits.push(as_edit_cmd(&cmd)?.clone());
+            let _ = expected_entries.insert(hash);
+        }
+
+        // Had we crashed before the call, nothing would be stored.
+        let log_path = store.address_to_filepath(&address)?;
+        assert!(list_files_in(&log_path).is_empty());
+
+        // A single invalid edit, signed by a user not allowed to write, has nothing stored.
+        let (_, intruder_sk) = random_user();
+        let mut intruder_register = register.clone();
+        let intruder_edit = edit_register(&mut intruder_register, &intruder_sk)?;
+        let mut invalid_edits = edits.clone();
+        invalid_edits.push(as_edit_cmd(&intruder_edit)?.clone());
+        assert!(store
+            .write_create_with_edits(&create, &invalid_edits)
+            .await
+            .is_err());
+        assert!(list_files_in(&log_path).is_empty());
+        assert_eq!(num_of_syncs.load(Ordering::Relaxed), 0);
+
+        store.write_create_with_edits(&create, &edits).await?;
+        // The whole log is synced to disk at once.
+        assert_eq!(num_of_syncs.load(Ordering::Relaxed), 1);
+        assert_eq!(list_files_in(&log_path).len(), 4);
+
+        match store
+            .read(&RegisterQuery::Read(address), Some(authority))
+            .await
+        {
+            QueryResponse::ReadRegister(Ok(entries)) => {
+                let hashes: BTreeSet<_> = entries.into_iter().map(|(hash, _)| hash).collect();
+                assert_eq!(hashes, expected_entries);
+            }
+            other => bail!("Could not read the register! {other:?}"),
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_register_update_notifies_new_entries() -> Result<()> {
+        let (network, mut network_event_receiver, swarm_driver) = SwarmDriver::new_client()?;
+        let _handle = tokio::spawn(swarm_driver.run());
+        let mut store = new_store();
+        store.set_network(network);
+
+        let (cmd_create, _, sk, name, policy) = create_register()?;
+        let address = cmd_create.dst();
+        store.write(&cmd_create).await?;
+
+        let mut register = RegisterReplica::new(policy.owner, name, 0, policy);
+        let cmd_edit_1 = edit_register(&mut register, &sk)?;
+        store.write(&cmd_edit_1).await?;
+        let (new_hash, cmd_edit_2) =
+            edit_register_with_children(&mut register, &sk, BTreeSet::default(), None)?;
+
+        // Only the edit the store didn't hold yet is notified.
+        store
+            .update(&ReplicatedRegisterLog {
+                address,
+                op_log: vec![cmd_edit_1, cmd_edit_2],
+            })
+            .await?;
+
+        let event =
+            tokio::time::timeout(Duration::from_secs(5), network_event_receiver.recv()).await?;
+        match event {
+            Some(NetworkEvent::RegisterUpdated {
+                address: updated,
+                new_entries,
+            }) => {
+                assert_eq!(updated, address);
+                assert_eq!(new_entries, vec![new_hash]);
+            }
+            other => bail!("Unexpected network event: {other:?}"),
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_register_sync_sends_only_missing_cmds() -> Result<()> {
+        let sender = new_store();
+        let receiver = new_store();
+
+        let (cmd_create, authority, sk, name, policy) = create_register()?;
+        let address = cmd_create.dst();
+        sender.write(&cmd_create).await?;
+        receiver.write(&cmd_create).await?;
+
+        // The receiver only holds the first half of the edits.
+        let mut register = RegisterReplica::new(policy.owner, name, 0, policy);
+        let mut missing_ids = BTreeSet::new();
+        for i in 0..10 {
+            let cmd = edit_register(&mut register, &sk)?;
+            sender.write(&cmd).await?;
+            if i < 5 {
+                receiver.write(&cmd).await?;
+            } else {
+                let _ = missing_ids.insert(register_op_id(&cmd)?);
+            }
+        }
+
+        let have = receiver.op_ids(&address).await?;
+        assert_eq!(have.len(), 6);
+        let delta = sender.log_delta(&address, &have).await?;
+        let sent_ids = delta
+            .op_log
+            .iter()
+            .map(register_op_id)
+            .collect::<std::result::Result<BTreeSet<_>, _>>()?;
+        assert_eq!(sent_ids, missing_ids);
+
+        // Applying the delta brings the receiver up to date with the sender.
+        receiver.update(&delta).await?;
+        assert_eq!(
+            receiver.op_ids(&address).await?,
+            sender.op_ids(&address).await?
+        );
+        match receiver
+            .read(&RegisterQuery::Read(address), Some(authority))
+            .await
+        {
+            // None of the edits supersedes any other, so they're all current entries.
+            QueryResponse::ReadRegister(Ok(entries)) => assert_eq!(entries.len(), 10),
+            other => bail!("Could not read the synced register! {other:?}"),
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_register_read_is_independent_of_edits_order() -> Result<()> {
+        let store = new_store();
+        let other_store = new_store();
+
+        let (cmd_create, authority, sk, name, policy) = create_register()?;
+        let address = cmd_create.dst();
+        store.write(&cmd_create).await?;
+        other_store.write(&cmd_create).await?;
+
+        // Concurrent edits, so they are all current entries, applied in opposite orders.
+        let mut register = RegisterReplica::new(policy.owner, name, 0, policy);
+        let mut edits = vec![];
+        for _ in 0..8 {
+            edits.push(edit_register(&mut register, &sk)?);
+        }
+        for cmd in &edits {
+            store.write(cmd).await?;
+        }
+        for cmd in edits.iter().rev() {
+            other_store.write(cmd).await?;
+        }
+
+        let query = RegisterQuery::Read(address);
+        let response = store.read(&query, Some(authority)).await;
+        let other_response = other_store.read(&query, Some(authority)).await;
+        let entries = match &response {
+            QueryResponse::ReadRegister(Ok(entries)) => entries.iter().cloned().collect::<Vec<_>>(),
+            other => bail!("Could not read register! {other:?}"),
+        };
+        assert_eq!(entries.len(), 8);
+        assert!(entries.windows(2).all(|pair| pair[0].0 < pair[1].0));
+
+        // Both replicas answer with exactly the same bytes.
+        assert_eq!(response, other_response);
+        assert_eq!(serialize(&response)?, serialize(&other_response)?);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_register_read_compressed() -> Result<()> {
+        let store = new_store();
+
+        let (cmd_create, authority, sk, name, policy) = create_register()?;
+        let address = cmd_create.dst();
+        store.write(&cmd_create).await?;
+
+        // A large Register, with as many concurrent entries of textual content.
+        let mut register = RegisterReplica::new(policy.owner, name, 0, policy);
+        for i in 0..200 {
+            let entry = format!("line {i} of a document held in a Register, ")
+                .repeat(10)
+                .into_bytes();
+            let (_, edit) = register.write(entry, BTreeSet::new())?;
+            let op = EditRegister {
+                address,
+                edit,
+                expected_size: None,
+            };
+            let auth = DataAuthority {
+                public_key: sk.public_key(),
+                signature: sk.sign(serialize(&op)?),
+            };
+            store
+                .write(&RegisterCmd::Edit(SignedRegisterEdit { op, auth }))
+                .await?;
+        }
+
+        let response = store
+            .read(&RegisterQuery::Read(address), Some(authority))
+            .await;
+        let entries = match &response {
+            QueryResponse::ReadRegister(Ok(entries)) => entries.clone(),
+            other => bail!("Could not read register! {other:?}"),
+        };
+        assert_eq!(entries.len(), 200);
+
+        let compressed_response = store
+            .read(&RegisterQuery::ReadCompressed(address), Some(authority))
+            .await;
+        match &compressed_response {
+            QueryResponse::ReadRegisterCompressed(Ok(compressed)) => {
+                assert_eq!(
+                    decompress_register_entries(compressed, DEFAULT_MAX_MSG_SIZE)?,
+                    entries
+                );
+
+                // Decompressing beyond the maximum size is aborted, rather than
+                // exhausting the memory with a decompression bomb.
+                let serialized_len = serialize(&entries)?.len();
+                match decompress_register_entries(compressed, serialized_len - 1) {
+                    Err(ProtocolError::Storage(Error::RegisterCompression(_))) => {}
+                    other => bail!("Decompression beyond the maximum size: {other:?}"),
+                }
+            }
+            other => bail!("Could not read register compressed! {other:?}"),
+        }
+        assert!(serialize(&compressed_response)?.len() < serialize(&response)?.len() / 2);
+
+        // A failed read is reported as such, rather than compressed.
+        let missing = RegisterAddress::new(xor_name::rand::random(), 0);
+        match store
+            .read(&RegisterQuery::ReadCompressed(missing), Some(authority))
+            .await
+        {
+            QueryResponse::ReadRegisterCompressed(Err(ProtocolError::Storage(
+                Error::RegisterNotFound(addr),
+            ))) => assert_eq!(addr, missing),
+            other => bail!("Unexpected response to a failed read: {other:?}"),
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_register_changed_since() -> Result<()> {
+        let store = new_store();
+
+        let (cmd_create, authority, sk, name, policy) = create_register()?;
+        let address = cmd_create.dst();
+        store.write(&cmd_create).await?;
+
+        let mut register = RegisterReplica::new(policy.owner, name, 0, policy);
+        let (first, cmd_first) =
+            edit_register_with_children(&mut register, &sk, BTreeSet::default(), None)?;
+        store.write(&cmd_first).await?;
+        assert_eq!(
+            changed_since(&store, address, first, authority).await?,
+            None
+        );
+
+        // An edit superseding the known tip is reported as a change.
+        let (second, cmd_second) =
+            edit_register_with_children(&mut register, &sk, [first].into(), None)?;
+        store.write(&cmd_second).await?;
+        assert_eq!(
+            changed_since(&store, address, first, authority).await?,
+            Some(second)
+        );
+
+        // No change once the known tip is the current one.
+        assert_eq!(
+            changed_since(&store, address, second, authority).await?,
+            None
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_register_read_branch() -> Result<()> {
+        let store = new_store();
+
+        let (cmd_create, authority, sk, name, policy) = create_register()?;
+        let address = cmd_create.dst();
+        store.write(&cmd_create).await?;
+
+        // Build a forked history: `root` <- `left` <- `left_tip`, and `root` <- `right_tip`.
+        let mut register = RegisterReplica::new(policy.owner, name, 0, policy);
+        let (root, cmd_root) = edit_register_with_children(&mut register, &sk, [].into(), None)?;
+        let (left, cmd_left) =
+            edit_register_with_children(&mut register, &sk, [root].into(), None)?;
+        let (left_tip, cmd_left_tip) =
+            edit_register_with_children(&mut register, &sk, [left].into(), None)?;
+        let (right_tip, cmd_right_tip) =
+            edit_register_with_children(&mut register, &sk, [root].into(), None)?;
+        for cmd in [&cmd_root, &cmd_left, &cmd_left_tip, &cmd_right_tip] {
+            store.write(cmd).await?;
+        }
+
+        let left_branch = read_branch_hashes(&store, address, left_tip, authority).await?;
+        let right_branch = read_branch_hashes(&store, address, right_tip, authority).await?;
+        assert_eq!(left_branch, [root, left, left_tip].into());
+        assert_eq!(right_branch, [root, right_tip].into());
+
+        // Besides their common ancestor, the branches' entries are disjoint.
+        let common: BTreeSet<_> = left_branch.intersection(&right_branch).copied().collect();
+        assert_eq!(common, [root].into());
+
+        // Reading a branch from a tip which is not in the register fails.
+        let tip = EntryHash(rand::thread_rng().gen::<[u8; 32]>());
+        match store
+            .read(&RegisterQuery::ReadBranch { address, tip }, Some(authority))
+            .await
+        {
+            QueryResponse::ReadRegisterBranch(Err(e)) => {
+                assert_eq!(e, ProtocolError::Storage(Error::NoSuchEntry(tip)))
+            }
+            other => bail!("Reading a branch from a random tip should fail: {other:?}"),
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_register_get_tips() -> Result<()> {
+        let store = new_store();
+
+        let (cmd_create, authority, sk, name, policy) = create_register()?;
+        let address = cmd_create.dst();
+        store.write(&cmd_create).await?;
+
+        // A linear chain `root` <- `middle` <- `tip` has a single tip.
+        let mut register = RegisterReplica::new(policy.owner, name, 0, policy);
+        let (root, cmd_root) = edit_register_with_children(&mut register, &sk, [].into(), None)?;
+        let (middle, cmd_middle) =
+            edit_register_with_children(&mut register, &sk, [root].into(), None)?;
+        let (tip, cmd_tip) =
+            edit_register_with_children(&mut register, &sk, [middle].into(), None)?;
+        for cmd in [&cmd_root, &cmd_middle, &cmd_tip] {
+            store.write(cmd).await?;
+        }
+        match store
+            .read(&RegisterQuery::GetTips { address }, Some(authority))
+            .await
+        {
+            QueryResponse::GetRegisterTips(Ok(tips)) => assert_eq!(tips, [tip].into()),
+            other => bail!("Could not get the tips of a linear Register: {other:?}"),
+        }
+
+        // Forking the history from `middle` leaves two tips.
+        let (fork_tip, cmd_fork_tip) =
+            edit_register_with_children(&mut register, &sk, [middle].into(), None)?;
+        store.write(&cmd_fork_tip).await?;
+        match store
+            .read(&RegisterQuery::GetTips { address }, Some(authority))
+            .await
+        {
+            QueryResponse::GetRegisterTips(Ok(tips)) => assert_eq!(tips, [tip, fork_tip].into()),
+            other => bail!("Could not get the tips of a forked Register: {other:?}"),
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_register_read_with_tips() -> Result<()> {
+        let store = new_store();
+
+        let (cmd_create, authority, sk, name, policy) = create_register()?;
+        let address = cmd_create.dst();
+        store.write(&cmd_create).await?;
+
+        // A forked history `root` <- `tip`, `root` <- `fork_tip`, so there are two tips.
+        let mut register = RegisterReplica::new(policy.owner, name, 0, policy);
+        let (root, cmd_root) = edit_register_with_children(&mut register, &sk, [].into(), None)?;
+        let (_, cmd_tip) = edit_register_with_children(&mut register, &sk, [root].into(), None)?;
+        let (_, cmd_fork_tip) =
+            edit_register_with_children(&mut register, &sk, [root].into(), None)?;
+        for cmd in [&cmd_root, &cmd_tip, &cmd_fork_tip] {
+            store.write(cmd).await?;
+        }
+
+        let (entries, tips) = match store
+            .read(&RegisterQuery::ReadWithTips(address), Some(authority))
+            .await
+        {
+            QueryResponse::ReadRegisterWithTips(Ok(entries_and_tips)) => entries_and_tips,
+            other => bail!("Could not read register with its tips! {other:?}"),
+        };
+        assert_eq!(entries, register.read());
+        match store
+            .read(&RegisterQuery::GetTips { address }, Some(authority))
+            .await
+        {
+            QueryResponse::GetRegisterTips(Ok(expected_tips)) => {
+                assert_eq!(tips, expected_tips);
+                assert_eq!(tips.len(), 2);
+            }
+            other => bail!("Could not get the tips of the Register: {other:?}"),
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_register_read_with_log_is_verifiable() -> Result<()> {
+        let store = new_store();
+
+        let (cmd_create, authority, sk, name, policy) = create_register()?;
+        let address = cmd_create.dst();
+        store.write(&cmd_create).await?;
+        let mut register = RegisterReplica::new(authority, name, 0, policy);
+        for _ in 0..3 {
+            store.write(&edit_register(&mut register, &sk)?).await?;
+        }
+
+        let (entries, log) = match store
+            .read(&RegisterQuery::ReadWithLog(address), Some(authority))
+            .await
+        {
+            QueryResponse::ReadRegisterWithLog(Ok(entries_and_log)) => entries_and_log,
+            other => bail!("Could not read register with its log! {other:?}"),
+        };
+        assert_eq!(log.op_log.len(), 4);
+
+        // The requester reconstructs the Register itself, verifying every cmd of the log.
+        let reconstructed = reconstruct_verified(&log)?;
+        assert_eq!(reconstructed.read(), entries);
+        assert_eq!(entries, register.read());
+
+        // A log with a cmd whose signature doesn't match it can't be reconstructed.
+        let mut tampered_log = log;
+        if let Some(RegisterCmd::Edit(edit)) = tampered_log.op_log.last_mut() {
+            edit.auth.signature = SecretKey::random().sign(serialize(&edit.op)?);
+        }
+        match reconstruct_verified(&tampered_log) {
+            Err(Error::InvalidSignature(_)) => {}
+            other => bail!("Tampered log should not have been verified: {other:?}"),
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_register_overwrite_merges_all_tips() -> Result<()> {
+        let store = new_store();
+
+        let (cmd_create, authority, sk, name, policy) = create_register()?;
+        let address = cmd_create.dst();
+        store.write(&cmd_create).await?;
+
+        // several concurrent entries, i.e. as many tips
+        let mut register = RegisterReplica::new(policy.owner, name, 0, policy);
+        for _ in 0..3 {
+            let cmd = edit_register(&mut register, &sk)?;
+            store.write(&cmd).await?;
+        }
+        match store
+            .read(&RegisterQuery::GetTips { address }, Some(authority))
+            .await
+        {
+            QueryResponse::GetRegisterTips(Ok(tips)) => assert_eq!(tips.len(), 3),
+            other => bail!("Could not get the tips of the Register: {other:?}"),
+        }
+
+        let value = random_entry();
+        let hash = store.overwrite(&address, value.clone(), &sk).await?;
+        match store
+            .read(&RegisterQuery::Read(address), Some(authority))
+            .await
+        {
+            QueryResponse::ReadRegister(Ok(entries)) => {
+                assert_eq!(entries, [(hash, value)].into())
+            }
+            other => bail!("Could not read the overwritten Register: {other:?}"),
+        }
+
+        // a user not allowed to write can't overwrite it
+        let (_, other_sk) = random_user();
+        match store.overwrite(&address, random_entry(), &other_sk).await {
+            Err(Error::AccessDenied { .. }) => {}
+            other => bail!("Overwriting as an unpermitted user should be denied: {other:?}"),
+        }
+
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_register_store_not_writable() -> Result<()> {
+        use std::{fs, os::unix::fs::PermissionsExt};
+
+        let tmp_dir = assert_fs::TempDir::new()?;
+        let store = RegisterStorage::new(tmp_dir.path());
+        fs::set_permissions(tmp_dir.path(), fs::Permissions::from_mode(0o555))?;
+        // Permissions are bypassed when running with elevated privileges.
+        if fs::File::create(tmp_dir.path().join("probe")).is_ok() {
+            return Ok(());
+        }
+
+        let expected = Error::StoreNotWritable {
+            path: store.file_store_path.clone(),
+        };
+        assert_eq!(store.check_writable().await, Err(expected.clone()));
+
+        let (cmd_create, _, _, _, _) = create_register()?;
+        assert_eq!(store.write(&cmd_create).await, Err(expected));
+
+        // Let the temp dir be cleaned up.
+        fs::set_permissions(tmp_dir.path(), fs::Permissions::from_mode(0o755))?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_register_read_by_author() -> Result<()> {
+        let store = new_store();
+
+        let (owner, owner_sk) = random_user();
+        let (writer, writer_sk) = random_user();
+        let policy = Policy {
+            owner,
+            permissions: [(writer, Permissions::new(true))].into(),
+        };
+        let name = xor_name::rand::random();
+        let cmd_create = create_reg_w_policy(name, 0, policy.clone(), &owner_sk)?;
+        let address = cmd_create.dst();
+        store.write(&cmd_create).await?;
+
+        // Edits are interleaved between the owner and another user allowed to write.
+        let mut register = RegisterReplica::new(owner, name, 0, policy);
+        let mut owner_entries = BTreeSet::new();
+        let mut writer_entries = BTreeSet::new();
+        for i in 0..6 {
+            let (sk, entries) = if i % 2 == 0 {
+                (&owner_sk, &mut owner_entries)
+            } else {
+                (&writer_sk, &mut writer_entries)
+            };
+            let (hash, cmd) = edit_register_with_children(&mut register, sk, [].into(), None)?;
+            store.write(&cmd).await?;
+            let _ = entries.insert(hash);
+        }
+
+        for (author, expected) in [(owner, owner_entries), (writer, writer_entries)] {
+            match store
+                .read(
+                    &RegisterQuery::ReadByAuthor { address, author },
+                    Some(owner),
+                )
+                .await
+            {
+                QueryResponse::ReadRegisterByAuthor(Ok(entries)) => {
+                    let hashes: BTreeSet<_> = entries.into_iter().map(|(hash, _)| hash).collect();
+                    assert_eq!(hashes, expected);
+                }
+                other => bail!("Could not read entries by author! {other:?}"),
+            }
+        }
+
+        // No entries are returned for a user who hasn't written any.
+        let (stranger, _) = random_user();
+        match store
+            .read(
+                &RegisterQuery::ReadByAuthor {
+                    address,
+                    author: stranger,
+                },
+                Some(owner),
+            )
+            .await
+        {
+            QueryResponse::ReadRegisterByAuthor(Ok(entries)) => assert!(entries.is_empty()),
+            other => bail!("Could not read entries by author! {other:?}"),
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_register_writes_outside_responsibility_are_rejected() -> Result<()> {
+        let mut store = new_store();
+        // Only responsible for the Registers whose name starts with a zero byte.
+        store.set_responsibility(|address| address.name().0[0] == 0);
+
+        let (owner, sk) = random_user();
+        let policy = Policy {
+            owner,
+            permissions: BTreeMap::new(),
+        };
+        let mut in_range = xor_name::rand::random::<XorName>();
+        in_range.0[0] = 0;
+        let mut out_of_range = xor_name::rand::random::<XorName>();
+        out_of_range.0[0] = 0xff;
+
+        let cmd_create = create_reg_w_policy(in_range, 0, policy.clone(), &sk)?;
+        store.write(&cmd_create).await?;
+        let mut register = RegisterReplica::new(owner, in_range, 0, policy.clone());
+        store.write(&edit_register(&mut register, &sk)?).await?;
+        assert_eq!(store.stored_addrs().await, vec![cmd_create.dst()]);
+
+        let cmd_create = create_reg_w_policy(out_of_range, 0, policy.clone(), &sk)?;
+        let address = cmd_create.dst();
+        match store.write(&cmd_create).await {
+            Err(Error::NotResponsible { address: rejected }) => assert_eq!(rejected, address),
+            other => bail!("Write should have been rejected: {other:?}"),
+        }
+        let mut register = RegisterReplica::new(owner, out_of_range, 0, policy);
+        let op_log = vec![cmd_create, edit_register(&mut register, &sk)?];
+        match store
+            .update(&ReplicatedRegisterLog { address, op_log })
+            .await
+        {
+            Err(Error::NotResponsible { address: rejected }) => assert_eq!(rejected, address),
+            other => bail!("Replication should have been rejected: {other:?}"),
+        }
+        assert!(!store.holds_register(&address));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_register_lenient_load_skips_unapplicable_edit() -> Result<()> {
+        let mut store = new_store();
+
+        let (cmd_create, authority, sk, name, policy) = create_register()?;
+        let address = cmd_create.dst();
+        store.write(&cmd_create).await?;
+        let mut register = RegisterReplica::new(authority, name, 0, policy.clone());
+        for _ in 0..3 {
+            store.write(&edit_register(&mut register, &sk)?).await?;
+        }
+
+        // An edit of another Register, bypassing the validation upon storing it.
+        let mut other_register =
+            RegisterReplica::new(authority, xor_name::rand::random(), 0, policy);
+        let bad_edit = edit_register(&mut other_register, &sk)?;
+        let log_path = store.address_to_filepath(&address)?;
+        store
+            .write_log_to_disk(&vec![bad_edit.clone()], &log_path, address)
+            .await?;
+        store.write(&edit_register(&mut register, &sk)?).await?;
+
+        // The whole Register is unreadable when not loading it leniently.
+        match store
+            .read(&RegisterQuery::Read(address), Some(authority))
+            .await
+        {
+            QueryResponse::ReadRegister(Err(_)) => {}
+            other => bail!("Register should not have been readable: {other:?}"),
+        }
+
+        let (loaded, skipped) = store.load_leniently(&address).await?;
+        assert_eq!(loaded.read(), register.read());
+        assert_eq!(skipped, vec![register_op_id(&bad_edit)?]);
+
+        store.set_lenient_load(true);
+        match store
+            .read(&RegisterQuery::Read(address), Some(authority))
+            .await
+        {
+            QueryResponse::ReadRegister(Ok(entries)) => assert_eq!(entries, register.read()),
+            other => bail!("Could not read register leniently! {other:?}"),
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_register_legacy_untagged_cmd_files_are_loaded() -> Result<()> {
+        let store = new_store();
+
+        let (cmd_create, authority, sk, name, policy) = create_register()?;
+        let address = cmd_create.dst();
+        store.write(&cmd_create).await?;
+        let mut register = RegisterReplica::new(authority, name, 0, policy);
+        let tagged_edit = edit_register(&mut register, &sk)?;
+        store.write(&tagged_edit).await?;
+
+        // The cmd files written by the store are tagged with their format.
+        let log_path = store.address_to_filepath(&address)?;
+        let tagged_data = std::fs::read(log_path.join(register_op_id(&tagged_edit)?))?;
+        assert_ne!(tagged_data, serialize(&tagged_edit)?);
+        assert_eq!(decode_cmd(&tagged_data)?, tagged_edit);
+
+        // A cmd file written before they were tagged, i.e. just the bincode serialised cmd.
+        let legacy_edit = edit_register(&mut register, &sk)?;
+        std::fs::write(
+            log_path.join(register_op_id(&legacy_edit)?),
+            serialize(&legacy_edit)?,
+        )?;
+
+        match store
+            .read(&RegisterQuery::Read(address), Some(authority))
+            .await
+        {
+            QueryResponse::ReadRegister(Ok(entries)) => assert_eq!(entries, register.read()),
+            other => bail!("Could not read register! {other:?}"),
+        }
+        let stored_reg = store.try_load_stored_register(&address).await?;
+        assert!(stored_reg.op_log.contains(&tagged_edit));
+        assert!(stored_reg.op_log.contains(&legacy_edit));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_register_raw_op_log_stream() -> Result<()> {
+        let store = new_store();
+
+        let (cmd_create, _, sk, name, policy) = create_register()?;
+        let address = cmd_create.dst();
+        let mut register = RegisterReplica::new(policy.owner, name, 0, policy);
+        store.write(&cmd_create).await?;
+        let mut expected = vec![register_op_id(&cmd_create)?];
+        for _ in 0..3 {
+            let cmd = edit_register(&mut register, &sk)?;
+            store.write(&cmd).await?;
+            expected.push(register_op_id(&cmd)?);
+        }
+        let _ = store.compact(&address).await?;
+
+        // Edits stored out of order, i.e. children before their parents,
+        // bypassing the validation upon storing them.
+        let (parent, cmd_parent) =
+            edit_register_with_children(&mut register, &sk, [].into(), None)?;
+        let (_, cmd_child) =
+            edit_register_with_children(&mut register, &sk, [parent].into(), None)?;
+        let log_path = store.address_to_filepath(&address)?;
+        for cmd in [cmd_child, cmd_parent] {
+            store
+                .write_log_to_disk(&vec![cmd.clone()], &log_path, address)
+                .await?;
+            expected.push(register_op_id(&cmd)?);
+        }
+
+        let mut streamed = vec![];
+        let mut cmds = Box::pin(store.raw_op_log_stream(&address));
+        while let Some(cmd) = cmds.next().await {
+            streamed.push(register_op_id(&cmd?)?);
+        }
+        expected.sort();
+        streamed.sort();
+        assert_eq!(streamed, expected);
+
+        // A corrupted cmd file yields an error, without holding back the other cmds.
+        std::fs::write(log_path.join("corrupted"), b"not a cmd")?;
+        let results: Vec<_> = store.raw_op_log_stream(&address).collect().await;
+        assert_eq!(results.len(), expected.len() + 1);
+        assert_eq!(results.iter().filter(|result| result.is_err()).count(), 1);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_register_read_since() -> Result<()> {
+        let mut store = new_store();
+        // The store's clock is told to be at the given number of secs since the epoch.
+        let clock_secs = Arc::new(AtomicU64::new(1_000));
+        let secs = clock_secs.clone();
+        store.clock =
+            Arc::new(move || UNIX_EPOCH + Duration::from_secs(secs.load(Ordering::Relaxed)));
+        let time_at = |secs| UNIX_EPOCH + Duration::from_secs(secs);
+
+        let (cmd_create, authority, sk, name, policy) = create_register()?;
+        let address = cmd_create.dst();
+        store.write(&cmd_create).await?;
+
+        let mut register = RegisterReplica::new(authority, name, 0, policy);
+        let mut old_entries = BTreeSet::new();
+        for _ in 0..3 {
+            let (hash, cmd) = edit_register_with_children(&mut register, &sk, [].into(), None)?;
+            store.write(&cmd).await?;
+            let _ = old_entries.insert(hash);
+        }
+
+        // An hour later, more entries are written.
+        clock_secs.store(1_000 + 3_600, Ordering::Relaxed);
+        let mut recent_entries = BTreeSet::new();
+        for _ in 0..2 {
+            let (hash, cmd) = edit_register_with_children(&mut register, &sk, [].into(), None)?;
+            store.write(&cmd).await?;
+            let _ = recent_entries.insert(hash);
+        }
+
+        // The times the cmds were stored at are kept once the log is compacted.
+        let all_entries: BTreeSet<_> = old_entries.union(&recent_entries).copied().collect();
+        for compacted in [false, true] {
+            if compacted {
+                let _ = store.compact(&address).await?;
+            }
+            for (since, expected) in [
+                (time_at(1_000), all_entries.clone()),
+                (time_at(1_000 + 1_800), recent_entries.clone()),
+                (time_at(1_000 + 3_600), recent_entries.clone()),
+                (time_at(1_000 + 3_601), BTreeSet::new()),
+            ] {
+                match store
+                    .read(
+                        &RegisterQuery::ReadSince { address, since },
+                        Some(authority),
+                    )
+                    .await
+                {
+                    QueryResponse::ReadRegisterSince(Ok(entries)) => {
+                        let hashes: BTreeSet<_> =
+                            entries.into_iter().map(|(hash, _)| hash).collect();
+                        assert_eq!(hashes, expected, "compacted: {compacted}");
+                    }
+                    other => bail!("Could not read entries since {since:?}! {other:?}"),
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_register_reads_fail_once_expired() -> Result<()> {
+        let mut store = new_store();
+        let clock_secs = Arc::new(AtomicU64::new(1_000));
+        let secs = clock_secs.clone();
+        store.clock =
+            Arc::new(move || UNIX_EPOCH + Duration::from_secs(secs.load(Ordering::Relaxed)));
+
+        let (cmd_create, authority, sk, name, policy) = create_register()?;
+        let address = cmd_create.dst();
+        store.write(&cmd_create).await?;
+        let mut register = RegisterReplica::new(authority, name, 0, policy);
+        store.write(&edit_register(&mut register, &sk)?).await?;
+
+        // Only the owner can set the expiry.
+        let expires_at = UNIX_EPOCH + Duration::from_secs(2_000);
+        let (other_user, other_sk) = random_user();
+        match store
+            .write(&set_expiry_cmd(address, expires_at, &other_sk)?)
+            .await
+        {
+            Err(Error::NotRegisterOwner { user, .. }) => assert_eq!(user, other_user),
+            other => bail!("Expiry should have been rejected for a non-owner: {other:?}"),
+        }
+        store
+            .write(&set_expiry_cmd(address, expires_at, &sk)?)
+            .await?;
+
+        // Before the expiry the Register is read as usual.
+        match store
+            .read(&RegisterQuery::Read(address), Some(authority))
+            .await
+        {
+            QueryResponse::ReadRegister(Ok(entries)) => assert_eq!(entries, register.read()),
+            other => bail!("Could not read register before its expiry! {other:?}"),
+        }
+
+        // Once the clock reaches the expiry, the reads fail, though its data is still stored.
+        clock_secs.store(2_000, Ordering::Relaxed);
+        match store
+            .read(&RegisterQuery::Read(address), Some(authority))
+            .await
+        {
+            QueryResponse::ReadRegister(Err(ProtocolError::Storage(Error::RegisterExpired(
+                expired,
+            )))) => assert_eq!(expired, address),
+            other => bail!("Register should have expired: {other:?}"),
+        }
+        assert_eq!(
+            store.try_load_stored_register(&address).await?.op_log.len(),
+            3
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_register_read_with_parents() -> Result<()> {
+        let store = new_store();
+
+        let (cmd_create, authority, sk, name, policy) = create_register()?;
+        let address = cmd_create.dst();
+        store.write(&cmd_create).await?;
+
+        // Build a branched history which is then merged: `root` <- `left` <- `merge`,
+        // and `root` <- `right` <- `merge`.
+        let mut register = RegisterReplica::new(policy.owner, name, 0, policy);
+        let (root, cmd_root) = edit_register_with_children(&mut register, &sk, [].into(), None)?;
+        let (left, cmd_left) =
+            edit_register_with_children(&mut register, &sk, [root].into(), None)?;
+        let (right, cmd_right) =
+            edit_register_with_children(&mut register, &sk, [root].into(), None)?;
+        let (merge, cmd_merge) =
+            edit_register_with_children(&mut register, &sk, [left, right].into(), None)?;
+        for cmd in [&cmd_root, &cmd_left, &cmd_right, &cmd_merge] {
+            store.write(cmd).await?;
+        }
+
+        let dag: BTreeMap<_, _> = store
+            .read_with_parents(&address, Some(authority))
+            .await?
+            .into_iter()
+            .map(|(hash, _, parents)| (hash, parents))
+            .collect();
+
+        let expected: BTreeMap<_, BTreeSet<_>> = [
+            (root, [].into()),
+            (left, [root].into()),
+            (right, [root].into()),
+            (merge, [left, right].into()),
+        ]
+        .into();
+        assert_eq!(dag, expected);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_register_append_edit_matches_full_reconstruction() -> Result<()> {
+        let fast_path_store = new_store();
+        let full_path_store = new_store();
+        // edits appended before the create cmd is held fall back to the full path
+        let fallback_store = new_store();
+
+        let (create_cmd, authority, sk, name, policy) = create_register()?;
+        let addr = create_cmd.dst();
+        fast_path_store.write(&create_cmd).await?;
+        full_path_store.apply_and_write(&create_cmd).await?;
+
+        let mut replica = RegisterReplica::new(authority, name, 0, policy);
+        let mut edit_cmds = vec![];
+        for _ in 0..5 {
+            let cmd = edit_register(&mut replica, &sk)?;
+            let edit_cmd = as_edit_cmd(&cmd)?;
+            fast_path_store.append_edit(edit_cmd).await?;
+            full_path_store.apply_and_write(&cmd).await?;
+            fallback_store.append_edit(edit_cmd).await?;
+            edit_cmds.push(cmd);
+        }
+        fallback_store.write(&create_cmd).await?;
+
+        let expected = full_path_store
+            .read(&RegisterQuery::Read(addr), Some(authority))
+            .await;
+        assert_eq!(
+            fast_path_store
+                .read(&RegisterQuery::Read(addr), Some(authority))
+                .await,
+            expected
+        );
+        assert_eq!(
+            fallback_store
+                .read(&RegisterQuery::Read(addr), Some(authority))
+                .await,
+            expected
+        );
+        match expected {
+            QueryResponse::ReadRegister(Ok(entries)) => {
+                assert_eq!(entries, replica.read())
+            }
+            other => bail!("Could not read register! {other:?}"),
+        }
+
+        // an edit by someone without write permissions is rejected by the fast path too
+        let (_, other_sk) = random_user();
+        let cmd = edit_register(&mut replica, &other_sk)?;
+        let edit_cmd = as_edit_cmd(&cmd)?;
+        match fast_path_store.append_edit(edit_cmd).await {
+            Err(Error::AccessDenied { .. }) => Ok(()),
+            other => bail!("Should have been access denied, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_register_create_received_after_compaction() -> Result<()> {
+        let store = new_store();
+
+        let (create_cmd, authority, sk, name, policy) = create_register()?;
+        let addr = create_cmd.dst();
+        let log_path = store.address_to_filepath(&addr)?;
+        let mut replica = RegisterReplica::new(authority, name, 0, policy);
+
+        // the edits received before the create cmd are compacted without it
+        for _ in 0..3 {
+            let cmd = edit_register(&mut replica, &sk)?;
+            store.write(&cmd).await?;
+        }
+        let _ = store.compact(&addr).await?;
+        assert_eq!(list_files_in(&log_path).len(), 1);
+
+        store.write(&create_cmd).await?;
+        let cmd = edit_register(&mut replica, &sk)?;
+        store.write(&cmd).await?;
+
+        match store
+            .read(&RegisterQuery::Read(addr), Some(authority))
+            .await
+        {
+            QueryResponse::ReadRegister(Ok(entries)) => assert_eq!(entries, replica.read()),
+            other => bail!("Could not read register! {other:?}"),
+        }
+
+        // once compacted again, now along with the create cmd, it's loaded from the snapshot
+        let _ = store.compact(&addr).await?;
+        assert_eq!(list_files_in(&log_path).len(), 1);
+        match store
+            .read(&RegisterQuery::Read(addr), Some(authority))
+            .await
+        {
+            QueryResponse::ReadRegister(Ok(entries)) => assert_eq!(entries, replica.read()),
+            other => bail!("Could not read register! {other:?}"),
+        }
+
+        let replicated_log = store.get_register_replica(&addr).await?;
+        assert_eq!(replicated_log.op_log.len(), 5);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_register_compaction_stats() -> Result<()> {
+        let store = new_store();
+
+        let (create_cmd, authority, sk, name, policy) = create_register()?;
+        let addr = create_cmd.dst();
+        let mut replica = RegisterReplica::new(authority, name, 0, policy);
+
+        store.write(&create_cmd).await?;
+        let num_of_edits = 20;
+        for _ in 0..num_of_edits {
+            let cmd = edit_register(&mut replica, &sk)?;
+            store.write(&cmd).await?;
+        }
+
+        let stats = store.compact(&addr).await?;
+        assert_eq!(stats.cmds_before, num_of_edits + 1);
+        assert_eq!(stats.cmds_after, 0);
+        assert!(stats.bytes_reclaimed > 0);
+
+        // nothing left to reclaim on an already compacted log
+        let stats = store.compact(&addr).await?;
+        assert_eq!(stats.cmds_before, 0);
+        assert_eq!(stats.cmds_after, 0);
+
+        match store
+            .read(&RegisterQuery::Read(addr), Some(authority))
+            .await
+        {
+            QueryResponse::ReadRegister(Ok(entries)) => assert_eq!(entries, replica.read()),
+            other => bail!("Could not read register! {other:?}"),
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_register_size_on_disk() -> Result<()> {
+        let store = new_store();
+
+        let (create_cmd, authority, sk, name, policy) = create_register()?;
+        let addr = create_cmd.dst();
+        let mut replica = RegisterReplica::new(authority, name, 0, policy);
+        assert_eq!(store.register_size_on_disk(&addr)?, 0);
+
+        store.write(&create_cmd).await?;
+        let mut size = store.register_size_on_disk(&addr)?;
+        assert!(size > 0);
+
+        for _ in 0..5 {
+            let cmd = edit_register(&mut replica, &sk)?;
+            store.write(&cmd).await?;
+            let new_size = store.register_size_on_disk(&addr)?;
+            assert!(
+                new_size > size,
+                "{new_size} bytes after an edit, from {size}"
+            );
+            size = new_size;
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_register_reconstruction_cost() -> Result<()> {
+        let store = new_store();
+
+        let (create_cmd, authority, sk, name, policy) = create_register()?;
+        let addr = create_cmd.dst();
+        let mut replica = RegisterReplica::new(authority, name, 0, policy);
+        assert_eq!(store.reconstruction_cost(&addr)?.op_log_len, 0);
+
+        store.write(&create_cmd).await?;
+        for _ in 0..5 {
+            store.write(&edit_register(&mut replica, &sk)?).await?;
+        }
+
+        let log_path = store.address_to_filepath(&addr)?;
+        let cmd_files = list_files_in(&log_path);
+        let cost = store.reconstruction_cost(&addr)?;
+        assert_eq!(cost.op_log_len, cmd_files.len());
+        assert_eq!(cost.op_log_len, 6);
+        let files_len: u64 = cmd_files
+            .iter()
+            .map(|filepath| filepath.metadata().map(|metadata| metadata.len()))
+            .sum::<std::io::Result<_>>()?;
+        assert_eq!(cost.total_bytes, files_len);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_register_verify_large_log() -> Result<()> {
+        let store = new_store();
+
+        let (cmd_create, _, sk, name, policy) = create_register()?;
+        let address = cmd_create.dst();
+        store.write(&cmd_create).await?;
+
+        let mut register = RegisterReplica::new(policy.owner, name, 0, policy);
+        let mut op_log = vec![];
+        // kept below the number of cmds which triggers compacting the log
+        for _ in 0..200 {
+            op_log.push(edit_register(&mut register, &sk)?);
+        }
+        store
+            .update(&ReplicatedRegisterLog { address, op_log })
+            .await?;
+
+        let report = store.verify(&address).await?;
+        assert_eq!(report.cmds_verified, 201);
+        assert!(report.invalid_cmds.is_empty());
+        assert!(report.gaps.is_empty());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_register_verify_flags_invalid_cmds() -> Result<()> {
+        let store = new_store();
+
+        let (cmd_create, _, sk, name, policy) = create_register()?;
+        let address = cmd_create.dst();
+        store.write(&cmd_create).await?;
+
+        let mut register = RegisterReplica::new(policy.owner, name, 0, policy);
+        let mut op_log = vec![];
+        let mut expected_invalid = vec![];
+        for i in 0..50 {
+            let mut cmd = edit_register(&mut register, &sk)?;
+            // every third edit is signed by someone else than the key it claims
+            if i % 3 == 0 {
+                if let RegisterCmd::Edit(SignedRegisterEdit { op, auth }) = &mut cmd {
+                    auth.signature = SecretKey::random().sign(serialize(op)?);
+                }
+                expected_invalid.push(register_op_id(&cmd)?);
+            }
+            op_log.push(cmd);
+        }
+        // bypass the validation upon storing the cmds, as if the log was tampered with on disk
+        let log_path = store.address_to_filepath(&address)?;
+        store.write_log_to_disk(&op_log, &log_path, address).await?;
+
+        expected_invalid.sort();
+        let report = store.verify(&address).await?;
+        assert_eq!(report.cmds_verified, 51);
+        assert_eq!(report.invalid_cmds, expected_invalid);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_register_verify_reports_gaps() -> Result<()> {
+        let store = new_store();
+
+        let (cmd_create, _, sk, name, policy) = create_register()?;
+        let address = cmd_create.dst();
+        store.write(&cmd_create).await?;
+
+        // `root` <- `middle` <- `tip`, and `root` <- `fork`, but `middle` is never received.
+        let mut register = RegisterReplica::new(policy.owner, name, 0, policy);
+        let (root, cmd_root) = edit_register_with_children(&mut register, &sk, [].into(), None)?;
+        let (middle, _) = edit_register_with_children(&mut register, &sk, [root].into(), None)?;
+        let (_, cmd_tip) = edit_register_with_children(&mut register, &sk, [middle].into(), None)?;
+        let (_, cmd_fork) = edit_register_with_children(&mut register, &sk, [root].into(), None)?;
+        let log_path = store.address_to_filepath(&address)?;
+        store
+            .write_log_to_disk(&vec![cmd_root, cmd_fork], &log_path, address)
+            .await?;
+
+        // no gap as long as every parent is held
+        let report = store.verify(&address).await?;
+        assert!(report.gaps.is_empty());
+
+        store
+            .write_log_to_disk(&vec![cmd_tip], &log_path, address)
+            .await?;
+        let report = store.verify(&address).await?;
+        assert_eq!(report.cmds_verified, 4);
+        assert!(report.invalid_cmds.is_empty());
+        assert_eq!(report.gaps, vec![middle]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_register_compact_and_verify() -> Result<()> {
+        let store = new_store();
+
+        let (cmd_create, authority, sk, name, policy) = create_register()?;
+        let address = cmd_create.dst();
+        store.write(&cmd_create).await?;
+        let mut register = RegisterReplica::new(authority, name, 0, policy);
+        for _ in 0..10 {
+            let cmd = edit_register(&mut register, &sk)?;
+            store.write(&cmd).await?;
+        }
+
+        let (report, stats) = store.compact_and_verify(&address).await?;
+        assert_eq!(report.cmds_verified, 11);
+        assert!(report.invalid_cmds.is_empty());
+        assert!(report.gaps.is_empty());
+        assert_eq!(stats.cmds_before, 11);
+        assert_eq!(stats.cmds_after, 0);
+        assert!(stats.bytes_reclaimed > 0);
+
+        match store
+            .read(&RegisterQuery::Read(address), Some(authority))
+            .await
+        {
+            QueryResponse::ReadRegister(Ok(entries)) => assert_eq!(entries, register.read()),
+            other => bail!("Could not read register! {other:?}"),
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_register_compact_and_verify_skips_tampered_log() -> Result<()> {
+        let store = new_store();
+
+        let (cmd_create, _, sk, name, policy) = create_register()?;
+        let address = cmd_create.dst();
+        store.write(&cmd_create).await?;
+
+        let mut register = RegisterReplica::new(policy.owner, name, 0, policy);
+        let mut op_log = vec![];
+        for _ in 0..5 {
+            op_log.push(edit_register(&mut register, &sk)?);
+        }
+        // one of the edits is signed by someone else than the key it claims
+        let mut tampered = edit_register(&mut register, &sk)?;
+        if let RegisterCmd::Edit(SignedRegisterEdit { op, auth }) = &mut tampered {
+            auth.signature = SecretKey::random().sign(serialize(op)?);
+        }
+        op_log.push(tampered.clone());
+        // bypass the validation upon storing the cmds, as if the log was tampered with on disk
+        let log_path = store.address_to_filepath(&address)?;
+        store.write_log_to_disk(&op_log, &log_path, address).await?;
+        let ids_before = store.op_ids(&address).await?;
+
+        let (report, stats) = store.compact_and_verify(&address).await?;
+        assert_eq!(report.cmds_verified, 7);
+        assert_eq!(report.invalid_cmds, vec![register_op_id(&tampered)?]);
+        assert_eq!(stats.cmds_before, 7);
+        assert_eq!(stats.cmds_after, 7);
+        assert_eq!(stats.bytes_reclaimed, 0);
+
+        // no cmd was lost, nor was the log compacted into a snapshot
+        assert_eq!(store.op_ids(&address).await?, ids_before);
+        assert!(!log_path.join(REGISTER_SNAPSHOT_FILE_NAME).exists());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_register_read_as_of_op_id() -> Result<()> {
+        let store = new_store();
+
+        let (create_cmd, authority, sk, name, policy) = create_register()?;
+        let addr = create_cmd.dst();
+        let mut replica = RegisterReplica::new(authority, name, 0, policy);
+        store.write(&create_cmd).await?;
+
+        // keep the state of the Register, and the id of the cmd, after each edit
+        let mut history = Vec::new();
+        for _ in 0..5 {
+            let cmd = edit_register(&mut replica, &sk)?;
+            store.write(&cmd).await?;
+            history.push((register_op_id(&cmd)?, replica.read()));
+        }
+
+        for (op_id, expected) in &history {
+            let reg_as_of = store.read_as_of(&addr, op_id).await?;
+            assert_eq!(&reg_as_of.read(), expected);
+        }
+
+        // as of the create cmd the Register is empty
+        let reg_as_of = store
+            .read_as_of(&addr, &register_op_id(&create_cmd)?)
+            .await?;
+        assert!(reg_as_of.read().is_empty());
+
+        match store.read_as_of(&addr, "unknown").await {
+            Err(Error::RegisterOpNotFound { address, op_id }) => {
+                assert_eq!(address, addr);
+                assert_eq!(op_id, "unknown");
+                Ok(())
+            }
+            other => bail!("Should have been an unknown op id error, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_register_log_compacted_after_threshold() -> Result<()> {
+        let compact_after = 3;
+        let tmp_dir = assert_fs::TempDir::new()?;
+        let store = RegisterStorage::with_compact_after(tmp_dir.path(), compact_after);
+
+        let (create_cmd, authority, sk, name, policy) = create_register()?;
+        let addr = create_cmd.dst();
+        let log_path = store.address_to_filepath(&addr)?;
+        let mut replica = RegisterReplica::new(authority, name, 0, policy);
+
+        store.write(&create_cmd).await?;
+        for _ in 1..compact_after {
+            let cmd = edit_register(&mut replica, &sk)?;
+            store.write(&cmd).await?;
+        }
+        assert_eq!(list_files_in(&log_path).len(), compact_after);
+
+        // this write takes the log over the threshold
+        let cmd = edit_register(&mut replica, &sk)?;
+        store.write(&cmd).await?;
+
+        let compaction = async {
+            while list_files_in(&log_path).len() > 1 {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+            }
+        };
+        if tokio::time::timeout(Duration::from_secs(5), compaction)
+            .await
+            .is_err()
+        {
+            bail!("Register log was not compacted after going over the threshold");
+        }
+
+        match store
+            .read(&RegisterQuery::Read(addr), Some(authority))
+            .await
+        {
+            QueryResponse::ReadRegister(Ok(entries)) => assert_eq!(entries, replica.read()),
+            other => bail!("Could not read register! {other:?}"),
+        }
+
+        Ok(())
+    }
+
+    async fn read_branch_hashes(
+        store: &RegisterStorage,
+        address: RegisterAddress,
+        tip: EntryHash,
+        requester: User,
+    ) -> Result<BTreeSet<EntryHash>> {
+        match store
+            .read(&RegisterQuery::ReadBranch { address, tip }, Some(requester))
+            .await
+        {
+            QueryResponse::ReadRegisterBranch(Ok(entries)) => {
+                Ok(entries.into_iter().map(|(hash, _)| hash).collect())
+            }
+            other => bail!("Could not read branch! {other:?}"),
+        }
+    }
+
+    async fn changed_since(
+        store: &RegisterStorage,
+        address: RegisterAddress,
+        known_tip_hash: EntryHash,
+        requester: User,
+    ) -> Result<Option<EntryHash>> {
+        match store
+            .read(
+                &RegisterQuery::ChangedSince {
+                    address,
+                    known_tip_hash,
+                },
+                Some(requester),
+            )
+            .await
+        {
+            QueryResponse::RegisterChangedSince(Ok(changed)) => Ok(changed),
+            other => bail!("Could not check whether the register changed! {other:?}"),
+        }
+    }
+
+    fn random_user() -> (User, SecretKey) {
+        let sk = SecretKey::random();
+        let authority = User::Key(sk.public_key());
+        (authority, sk)
+    }
+
+    fn create_register() -> Result<(RegisterCmd, User, SecretKey, XorName, Policy)> {
+        let (authority, sk) = random_user();
+        let policy = Policy {
+            owner: authority,
+            permissions: Default::default(),
+        };
+        let xorname = xor_name::rand::random();
+        let cmd = create_reg_w_policy(xorname, 0, policy.clone(), &sk)?;
+
+        Ok((cmd, authority, sk, xorname, policy))
+    }
+
+    fn set_expiry_cmd(
+        address: RegisterAddress,
+        expires_at: SystemTime,
+        sk: &SecretKey,
+    ) -> Result<RegisterCmd> {
+        let op = SetRegisterExpiry {
+            address,
+            expires_at,
+        };
+        let signature = sk.sign(serialize(&op)?);
+        Ok(RegisterCmd::SetExpiry(SignedRegisterSetExpiry {
+            op,
+            auth: DataAuthority {
+                public_key: sk.public_key(),
+                signature,
+            },
+        }))
+    }
+
+    fn random_entry() -> Vec<u8> {
+        rand::thread_rng()
             .sample_iter(&Alphanumeric)
             .take(15)
-            .collect();
-        let (_, edit) = register.write(data, BTreeSet::default())?;
+            .collect()
+    }
+
+    fn edit_register(register: &mut RegisterReplica, sk: &SecretKey) -> Result<RegisterCmd> {
+        edit_register_if_size(register, sk, None)
+    }
+
+    fn edit_register_if_size(
+        register: &mut RegisterReplica,
+        sk: &SecretKey,
+        expected_size: Option<u64>,
+    ) -> Result<RegisterCmd> {
+        let (_, cmd) =
+            edit_register_with_children(register, sk, BTreeSet::default(), expected_size)?;
+        Ok(cmd)
+    }
+
+    fn edit_register_with_children(
+        register: &mut RegisterReplica,
+        sk: &SecretKey,
+        children: BTreeSet<EntryHash>,
+        expected_size: Option<u64>,
+    ) -> Result<(EntryHash, RegisterCmd)> {
+        let (hash, edit) = register.write(random_entry(), children)?;
         let op = EditRegister {
             address: *register.address(),
             edit,
+            expected_size,
         };
         let signature = sk.sign(serialize(&op)?);
 
-        Ok(RegisterCmd::Edit(SignedRegisterEdit {
+        let cmd = RegisterCmd::Edit(SignedRegisterEdit {
             op,
             auth: DataAuthority {
                 public_key: sk.public_key(),
                 signature,
             },
-        }))
+        });
+        Ok((hash, cmd))
+    }
+
+    fn as_edit_cmd(cmd: &RegisterCmd) -> Result<&SignedRegisterEdit> {
+        match cmd {
+            RegisterCmd::Edit(edit_cmd) => Ok(edit_cmd),
+            other => bail!("Expected an edit cmd, got {other:?}"),
+        }
     }
 
     fn new_store() -> RegisterStorage {
@@ -986,7 +4837,12 @@ mod test {
         policy: Policy,
         sk: &SecretKey,
     ) -> Result<RegisterCmd> {
-        let op = CreateRegister { name, tag, policy };
+        let op = CreateRegister {
+            name,
+            tag,
+            policy,
+            initial_entries: vec![],
+        };
         let signature = sk.sign(serialize(&op)?);
 
         let auth = DataAuthority {
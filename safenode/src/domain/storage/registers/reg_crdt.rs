@@ -16,10 +16,13 @@ use crate::protocol::{
 
 use super::Result;
 
-use crdts::{merkle_reg::MerkleReg, CmRDT, CvRDT};
+use crdts::{
+    merkle_reg::{Hash as NodeHash, MerkleReg, Node},
+    CmRDT, CvRDT,
+};
 use serde::{Deserialize, Serialize};
 use std::{
-    collections::BTreeSet,
+    collections::{BTreeMap, BTreeSet},
     fmt::{self, Debug, Display, Formatter},
     hash::Hash,
 };
@@ -140,7 +143,34 @@ impl RegisterCrdtImpl {
         self.data.node(hash.0).map(|node| &node.value)
     }
 
-    /// Read current entries (multiple entries occur on concurrent writes).
+    /// Read the entry corresponding to the provided `tip` hash along with all its ancestors,
+    /// i.e. all the entries it transitively supersedes, if the `tip` exists.
+    pub(crate) fn read_branch(&self, tip: EntryHash) -> Option<BTreeSet<(EntryHash, Entry)>> {
+        let _ = self.data.node(tip.0)?;
+        let branch = self
+            .ancestors(vec![tip.0])
+            .into_iter()
+            .map(|(hash, node)| (EntryHash(hash), node.value.clone()))
+            .collect();
+
+        Some(branch)
+    }
+
+    /// Read all entries along with the hashes of their parents,
+    /// i.e. the entries each of them directly supersedes.
+    pub(crate) fn read_with_parents(&self) -> Vec<(EntryHash, Entry, BTreeSet<EntryHash>)> {
+        let roots = self.data.read().hashes().into_iter().collect();
+        self.ancestors(roots)
+            .into_iter()
+            .map(|(hash, node)| {
+                let parents = node.children.iter().copied().map(EntryHash).collect();
+                (EntryHash(hash), node.value.clone(), parents)
+            })
+            .collect()
+    }
+
+    /// Read current entries (multiple entries occur on concurrent writes),
+    /// sorted by their hash, regardless of the order the ops were applied in.
     pub(crate) fn read(&self) -> BTreeSet<(EntryHash, Entry)> {
         self.data
             .read()
@@ -148,6 +178,22 @@ impl RegisterCrdtImpl {
             .map(|(hash, node)| (EntryHash(hash), node.value.clone()))
             .collect()
     }
+
+    // Collects the nodes with the provided hashes along with all their ancestors.
+    fn ancestors(&self, mut to_visit: Vec<NodeHash>) -> BTreeMap<NodeHash, &Node<Entry>> {
+        let mut ancestors = BTreeMap::new();
+        while let Some(hash) = to_visit.pop() {
+            if ancestors.contains_key(&hash) {
+                continue;
+            }
+            // Ancestors not yet received are orphans' missing children, just skip them.
+            if let Some(node) = self.data.node(hash) {
+                let _ = ancestors.insert(hash, node);
+                to_visit.extend(node.children.iter().copied());
+            }
+        }
+        ancestors
+    }
 }
 
 #[cfg(test)]
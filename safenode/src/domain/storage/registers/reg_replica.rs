@@ -23,10 +23,10 @@ use std::{collections::BTreeSet, hash::Hash};
 use xor_name::XorName;
 
 /// Arbitrary maximum size of a register entry.
-const MAX_REG_ENTRY_SIZE: usize = MIN_ENCRYPTABLE_BYTES / 3; // 1024 bytes
+pub(super) const MAX_REG_ENTRY_SIZE: usize = MIN_ENCRYPTABLE_BYTES / 3; // 1024 bytes
 
 /// Maximum number of entries of a register.
-const MAX_REG_NUM_ENTRIES: u16 = 1024;
+pub(super) const MAX_REG_NUM_ENTRIES: u16 = 1024;
 
 /// Object storing the data of a Register, implementing the functions for CRDT operations
 #[derive(Clone, Eq, PartialEq, PartialOrd, Hash, Serialize, Deserialize, Debug)]
@@ -103,7 +103,6 @@ impl RegisterReplica {
     }
 
     /// Return the PK which the messages are expected to be signed with by this replica.
-    #[cfg(test)]
     pub(crate) fn replica_authority(&self) -> User {
         self.authority
     }
@@ -123,6 +122,21 @@ impl RegisterReplica {
         self.crdt.read()
     }
 
+    /// Return the hashes of the current tips, i.e. the entries which are no other entry's parent.
+    pub(crate) fn tips(&self) -> BTreeSet<EntryHash> {
+        self.crdt.read().into_iter().map(|(hash, _)| hash).collect()
+    }
+
+    /// Read the entry corresponding to the provided `tip` hash along with all its ancestors.
+    pub(crate) fn read_branch(&self, tip: EntryHash) -> Result<BTreeSet<(EntryHash, Entry)>> {
+        self.crdt.read_branch(tip).ok_or(Error::NoSuchEntry(tip))
+    }
+
+    /// Read all entries along with the hashes of their parents.
+    pub(crate) fn read_with_parents(&self) -> Vec<(EntryHash, Entry, BTreeSet<EntryHash>)> {
+        self.crdt.read_with_parents()
+    }
+
     /// Return user permissions, if applicable.
     pub(crate) fn permissions(&self, user: User) -> Result<Permissions> {
         if user == self.policy.owner {
@@ -169,12 +183,16 @@ impl RegisterReplica {
 
     /// Helper to check permissions for given `action`
     /// for the given requester's public key.
+    /// A `None` requester is anonymous, hence only allowed what the policy grants to anyone.
     ///
     /// Returns:
     /// `Ok(())` if the permissions are valid,
     /// `Err::AccessDenied` if the action is not allowed.
     pub(crate) fn check_permissions(&self, action: Action, requester: Option<User>) -> Result<()> {
-        let requester = requester.unwrap_or(self.authority);
+        let requester = match requester {
+            Some(requester) => requester,
+            None => return self.check_anyone_permissions(action),
+        };
         // First checks if the requester is the owner.
         if action == Action::Read || requester == self.policy.owner {
             Ok(())
@@ -184,12 +202,43 @@ impl RegisterReplica {
                 .or_else(|| self.is_action_allowed_by_user(&User::Anyone, action))
             {
                 Some(true) => Ok(()),
-                Some(false) => Err(Error::AccessDenied(requester)),
-                None => Err(Error::AccessDenied(requester)),
+                Some(false) | None => Err(Error::AccessDenied {
+                    address: *self.address(),
+                    user: requester,
+                    action,
+                    granted: self.granted_permissions(&requester),
+                }),
             }
         }
     }
 
+    // Private helper to check the given `action` is allowed to anyone, i.e. to anonymous requesters,
+    // which is the case if the Register is owned by anyone or its policy grants it to anyone.
+    fn check_anyone_permissions(&self, action: Action) -> Result<()> {
+        if self.policy.owner == User::Anyone
+            || self.is_action_allowed_by_user(&User::Anyone, action) == Some(true)
+        {
+            Ok(())
+        } else {
+            Err(Error::AccessDenied {
+                address: *self.address(),
+                user: User::Anyone,
+                action,
+                granted: self.granted_permissions(&User::Anyone),
+            })
+        }
+    }
+
+    // Private helper to get the permissions the policy grants to the given user,
+    // i.e. those set for the user, or else those set for anyone.
+    fn granted_permissions(&self, user: &User) -> Option<Permissions> {
+        self.policy
+            .permissions
+            .get(user)
+            .or_else(|| self.policy.permissions.get(&User::Anyone))
+            .copied()
+    }
+
     // Private helper to check the given Entry's size is within define limit,
     // as well as check the Register hasn't already reached the maximum number of entries.
     fn check_entry_and_reg_sizes(&self, entry: &Entry) -> Result<()> {
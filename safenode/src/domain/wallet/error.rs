@@ -32,6 +32,21 @@ pub enum Error {
     /// Failed to serialize a main key to hex.
     #[error("Could not serialize main key to hex: {0}")]
     FailedToHexEncodeKey(String),
+    /// A wallet was found where a new one was to be stored, which would have been overwritten.
+    #[error("A wallet already exists at {0:?}")]
+    WalletAlreadyExists(std::path::PathBuf),
+    /// A wallet stored encrypted was to be loaded without its passphrase.
+    #[error("The wallet at {0:?} is encrypted, its passphrase is required to load it")]
+    WalletEncrypted(std::path::PathBuf),
+    /// Failed to decrypt a wallet, most likely due to a wrong passphrase.
+    #[error("Failed to decrypt the wallet")]
+    DecryptionFailed,
+    /// Failed to derive the key to encrypt/decrypt a wallet with from a passphrase.
+    #[error("Failed to derive the wallet encryption key: {0}")]
+    KeyDerivation(String),
+    /// Failed to encrypt a wallet.
+    #[error("Failed to encrypt the wallet")]
+    EncryptionFailed,
     /// Dbc error.
     #[error("Dbc error: {0}")]
     Dbc(#[from] sn_dbc::Error),
@@ -39,6 +39,14 @@ pub(super) async fn store_new_keypair(wallet_dir: &Path, main_key: &MainKey) ->
     Ok(())
 }
 
+/// Removes the main key stored in plaintext, if any, keeping the public address.
+pub(super) async fn remove_main_key(wallet_dir: &Path) -> Result<()> {
+    match fs::remove_file(wallet_dir.join(MAIN_KEY_FILENAME)).await {
+        Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(err.into()),
+        _ => Ok(()),
+    }
+}
+
 /// Returns Some(sn_dbc::MainKey) or None if file doesn't exist. It assumes it's hex-encoded.
 pub(super) async fn get_main_key(wallet_dir: &Path) -> Result<Option<MainKey>> {
     let path = wallet_dir.join(MAIN_KEY_FILENAME);
@@ -7,16 +7,19 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 use super::{
-    keys::{get_main_key, store_new_keypair},
+    keys::{get_main_key, remove_main_key, store_new_keypair},
     wallet_file::{
-        create_received_dbcs_dir, get_wallet, load_received_dbcs, store_created_dbcs, store_wallet,
+        create_received_dbcs_dir, encrypted_wallet_exists, get_encrypted_wallet, get_wallet,
+        load_received_dbcs, remove_wallet, store_created_dbcs, store_encrypted_wallet,
+        store_wallet,
     },
-    DepositWallet, KeyLessWallet, Result, SendClient, SendWallet, SigningWallet, Wallet,
+    DepositWallet, Error, KeyLessWallet, Result, SendClient, SendOutputs, SendWallet,
+    SigningWallet, TxRecord, VerifyingClient, Wallet,
 };
 
-use crate::domain::client_transfers::{create_transfer, CreatedDbc, Outputs as TransferDetails};
+use crate::domain::client_transfers::{create_transfer, Outputs as TransferDetails};
 
-use sn_dbc::{Dbc, DbcIdSource, MainKey, PublicAddress, Token};
+use sn_dbc::{Dbc, DbcId, DbcIdSource, MainKey, PublicAddress, Token};
 
 use async_trait::async_trait;
 use std::{
@@ -35,12 +38,30 @@ pub struct LocalWallet {
     wallet: KeyLessWallet,
     /// The dir of the wallet file, main key, public address, and new dbcs.
     wallet_dir: PathBuf,
+    /// The passphrase the wallet is stored encrypted with, if it is.
+    passphrase: Option<String>,
 }
 
 impl LocalWallet {
-    /// Stores the wallet to disk.
+    /// Stores the wallet to disk, encrypted with its passphrase if it's stored encrypted.
     pub async fn store(&self) -> Result<()> {
-        store_wallet(&self.wallet_dir, &self.wallet).await
+        match &self.passphrase {
+            Some(passphrase) => {
+                store_encrypted_wallet(&self.wallet_dir, &self.key, &self.wallet, passphrase).await
+            }
+            None => store_wallet(&self.wallet_dir, &self.wallet).await,
+        }
+    }
+
+    /// Stores the wallet to disk, along with its main key, encrypted with a key derived from
+    /// the given passphrase, removing the wallet and main key stored in plaintext, if any.
+    /// The wallet is stored encrypted with it from then on.
+    pub async fn store_encrypted(&mut self, passphrase: &str) -> Result<()> {
+        store_encrypted_wallet(&self.wallet_dir, &self.key, &self.wallet, passphrase).await?;
+        remove_wallet(&self.wallet_dir).await?;
+        remove_main_key(&self.wallet_dir).await?;
+        self.passphrase = Some(passphrase.to_owned());
+        Ok(())
     }
 
     /// Stores the given dbc to the `created dbcs dir` in the wallet dir.
@@ -57,9 +78,19 @@ impl LocalWallet {
         Ok(())
     }
 
-    /// Loads a serialized wallet from a path.
+    /// The record of each of the sends made from this wallet, in the order they were made.
+    pub fn tx_history(&self) -> &[TxRecord] {
+        &self.wallet.tx_history
+    }
+
+    /// Loads a serialized wallet from a path. Errors with `Error::WalletEncrypted` if the wallet
+    /// is stored encrypted, rather than creating a new one, which is to be loaded with
+    /// `load_encrypted_from` instead.
     pub async fn load_from(root_dir: &Path) -> Result<Self> {
         let wallet_dir = root_dir.join(WALLET_DIR_NAME);
+        if encrypted_wallet_exists(&wallet_dir) {
+            return Err(Error::WalletEncrypted(wallet_dir));
+        }
         // This creates the received_dbcs dir if it doesn't exist.
         tokio::fs::create_dir_all(&wallet_dir).await?;
         let (key, wallet) = load_from_path(&wallet_dir).await?;
@@ -67,8 +98,78 @@ impl LocalWallet {
             key,
             wallet,
             wallet_dir: wallet_dir.to_path_buf(),
+            passphrase: None,
+        })
+    }
+
+    /// Loads a wallet, previously stored encrypted with the given passphrase, from a path.
+    pub async fn load_encrypted_from(root_dir: &Path, passphrase: &str) -> Result<Self> {
+        let wallet_dir = root_dir.join(WALLET_DIR_NAME);
+        let (key, wallet) = get_encrypted_wallet(&wallet_dir, passphrase).await?;
+        create_received_dbcs_dir(&wallet_dir).await?;
+        Ok(Self {
+            key,
+            wallet,
+            wallet_dir,
+            passphrase: Some(passphrase.to_owned()),
         })
     }
+
+    /// Reconstructs the wallet of the given key, e.g. when its wallet file has been lost,
+    /// storing it under the given path along with the key. Only the dbcs found in the
+    /// `received dbcs dir` in the wallet dir are recovered, and out of those, only the ones
+    /// the network reports as unspent are deposited. The network only holds the spends of dbcs,
+    /// so the dbcs owned by a key cannot be discovered through it, hence any dbc never received
+    /// as a file, e.g. the change of a send, which was only held in the lost wallet file,
+    /// cannot be recovered. Hence it errors with `Error::WalletAlreadyExists` if a wallet file,
+    /// or a key other than the given one, is found under the path, rather than overwriting it.
+    pub async fn recover<C: VerifyingClient>(
+        root_dir: &Path,
+        key: MainKey,
+        client: &C,
+    ) -> Result<Self> {
+        let wallet_dir = root_dir.join(WALLET_DIR_NAME);
+        let other_key_stored = get_main_key(&wallet_dir)
+            .await?
+            .is_some_and(|stored_key| stored_key.to_bytes() != key.to_bytes());
+        if other_key_stored
+            || get_wallet(&wallet_dir).await?.is_some()
+            || encrypted_wallet_exists(&wallet_dir)
+        {
+            return Err(Error::WalletAlreadyExists(wallet_dir));
+        }
+        tokio::fs::create_dir_all(&wallet_dir).await?;
+        create_received_dbcs_dir(&wallet_dir).await?;
+        store_new_keypair(&wallet_dir, &key).await?;
+
+        let mut unspent = vec![];
+        for dbc in load_received_dbcs(&wallet_dir).await? {
+            if client.is_spent(&dbc.id()).await? {
+                println!("Dbc {:?} has been spent already, skipping it.", dbc.id());
+            } else {
+                unspent.push(dbc);
+            }
+        }
+
+        let mut wallet = KeyLessWallet::new();
+        wallet.deposit(unspent, &key);
+        store_wallet(&wallet_dir, &wallet).await?;
+
+        Ok(Self {
+            key,
+            wallet,
+            wallet_dir,
+            passphrase: None,
+        })
+    }
+
+    // The value of a dbc owned by this wallet, zero if it cannot be revealed with our key.
+    fn dbc_value(&self, dbc: &Dbc) -> u64 {
+        dbc.derived_key(&self.key)
+            .and_then(|derived_key| dbc.revealed_input(&derived_key))
+            .map(|input| input.revealed_amount().value())
+            .unwrap_or_default()
+    }
 }
 
 /// Loads a serialized wallet from a path.
@@ -102,6 +203,7 @@ impl KeyLessWallet {
             available_dbcs: BTreeMap::new(),
             dbcs_created_for_others: vec![],
             unconfirmed_txs: vec![],
+            tx_history: vec![],
         }
     }
 
@@ -109,6 +211,17 @@ impl KeyLessWallet {
         self.balance
     }
 
+    fn available_dbcs(&self, key: &MainKey) -> Vec<(DbcId, Token)> {
+        self.available_dbcs
+            .iter()
+            .flat_map(|(id, dbc)| {
+                dbc.derived_key(key)
+                    .and_then(|derived_key| dbc.revealed_input(&derived_key))
+                    .map(|input| (*id, Token::from_nano(input.revealed_amount().value())))
+            })
+            .collect()
+    }
+
     fn deposit(&mut self, dbcs: Vec<Dbc>, key: &MainKey) {
         if dbcs.is_empty() {
             return;
@@ -144,6 +257,10 @@ impl Wallet for LocalWallet {
     fn balance(&self) -> Token {
         self.wallet.balance()
     }
+
+    fn available_dbcs(&self) -> Vec<(DbcId, Token)> {
+        self.wallet.available_dbcs(&self.key)
+    }
 }
 
 impl SigningWallet for LocalWallet {
@@ -168,7 +285,7 @@ impl SendWallet for LocalWallet {
         &mut self,
         to: Vec<(Token, PublicAddress)>,
         client: &C,
-    ) -> Result<Vec<CreatedDbc>> {
+    ) -> Result<SendOutputs> {
         // First resend any pending txs. This is not guaranteed to succeed.
         // If the spend was invalid to start with then it will always fail here.
         // It can disrupt the use of the wallet, if we got change from that invalid
@@ -190,55 +307,118 @@ impl SendWallet for LocalWallet {
             })
             .collect();
         if to.is_empty() {
-            return Ok(vec![]);
+            return Ok(SendOutputs::default());
         }
 
-        let mut available_dbcs = vec![];
-        for dbc in self.wallet.available_dbcs.values() {
-            if let Ok(derived_key) = dbc.derived_key(&self.key) {
-                available_dbcs.push((dbc.clone(), derived_key));
-            } else {
-                println!(
-                    "Skipping DBC {:?} because we don't have the key to spend it",
-                    dbc.id()
-                );
-            }
-        }
-
-        let transfer = create_transfer(available_dbcs, to, self.address())?;
-
-        let TransferDetails {
-            change_dbc,
-            created_dbcs,
-            ..
-        } = transfer.clone();
+        let outputs = reissue(self, to, client).await?;
+        self.wallet
+            .dbcs_created_for_others
+            .extend(outputs.created_dbcs.clone());
+        Ok(outputs)
+    }
 
-        // First of all, update client local state.
-        let spent_dbc_ids: BTreeSet<_> = created_dbcs
-            .iter()
-            .flat_map(|created| &created.dbc.signed_spends)
-            .map(|spend| spend.dbc_id())
-            .collect();
+    async fn split<C: SendClient>(
+        &mut self,
+        denominations: Vec<Token>,
+        client: &C,
+    ) -> Result<SendOutputs> {
+        resend_pending_txs(self, client).await;
 
-        let mut spent_dbcs = spent_dbc_ids
+        let address = self.address();
+        let to: Vec<_> = denominations
             .into_iter()
-            .filter_map(|id| self.wallet.available_dbcs.remove(id).map(|dbc| (*id, dbc)))
+            .map(|amount| (amount, address.random_dbc_id_src(&mut rand::thread_rng())))
             .collect();
+        if to.is_empty() {
+            return Ok(SendOutputs::default());
+        }
 
-        self.deposit(change_dbc.into_iter().collect());
-        self.wallet.spent_dbcs.append(&mut spent_dbcs);
-        self.wallet
-            .dbcs_created_for_others
-            .extend(created_dbcs.clone());
+        // The dbcs created are owned by ourselves, same as the change.
+        let outputs = reissue(self, to, client).await?;
+        self.deposit(
+            outputs
+                .created_dbcs
+                .iter()
+                .map(|created| created.dbc.clone())
+                .collect(),
+        );
+        Ok(outputs)
+    }
+}
 
-        // Last of all, register the spend in the network.
-        if let Err(error) = client.send(transfer.clone()).await {
-            println!("The transfer was not successfully registered in the network: {error:?}. It will be retried later.");
-            let _ = self.wallet.unconfirmed_txs.push(transfer);
+// Reissues the available dbcs into new ones of the given amounts, the change going back to
+// the wallet. The dbcs spent are moved out of the available ones, but the dbcs created are
+// left for the caller to keep track of.
+async fn reissue<C: SendClient>(
+    local: &mut LocalWallet,
+    to: Vec<(Token, DbcIdSource)>,
+    client: &C,
+) -> Result<SendOutputs> {
+    let mut available_dbcs = vec![];
+    for dbc in local.wallet.available_dbcs.values() {
+        if let Ok(derived_key) = dbc.derived_key(&local.key) {
+            available_dbcs.push((dbc.clone(), derived_key));
+        } else {
+            println!(
+                "Skipping DBC {:?} because we don't have the key to spend it",
+                dbc.id()
+            );
         }
+    }
 
-        Ok(created_dbcs)
+    let transfer = create_transfer(available_dbcs, to, local.address())?;
+
+    let TransferDetails {
+        tx_hash,
+        change_dbc,
+        created_dbcs,
+        ..
+    } = transfer.clone();
+
+    // First of all, update client local state.
+    let spent_dbc_ids: BTreeSet<_> = created_dbcs
+        .iter()
+        .flat_map(|created| &created.dbc.signed_spends)
+        .map(|spend| spend.dbc_id())
+        .collect();
+
+    let mut spent_dbcs: BTreeMap<_, _> = spent_dbc_ids
+        .into_iter()
+        .filter_map(|id| local.wallet.available_dbcs.remove(id).map(|dbc| (*id, dbc)))
+        .collect();
+
+    // The fee is whatever the spent dbcs are worth beyond the dbcs created.
+    let inputs_total: u64 = spent_dbcs.values().map(|dbc| local.dbc_value(dbc)).sum();
+    let sent: u64 = created_dbcs
+        .iter()
+        .map(|created| created.amount.value())
+        .sum();
+    let change = change_dbc
+        .as_ref()
+        .map(|dbc| local.dbc_value(dbc))
+        .unwrap_or_default();
+    let outputs_total = sent + change;
+    local.wallet.tx_history.push(TxRecord {
+        tx_hash,
+        sent: Token::from_nano(sent),
+        inputs_total: Token::from_nano(inputs_total),
+        outputs_total: Token::from_nano(outputs_total),
+        fee: Token::from_nano(inputs_total.saturating_sub(outputs_total)),
+    });
+
+    local.deposit(change_dbc.clone().into_iter().collect());
+    local.wallet.spent_dbcs.append(&mut spent_dbcs);
+
+    // Last of all, register the spend in the network.
+    if let Err(error) = client.send(transfer.clone()).await {
+        println!("The transfer was not successfully registered in the network: {error:?}. It will be retried later.");
+        local.wallet.unconfirmed_txs.push(transfer);
     }
+
+    Ok(SendOutputs {
+        created_dbcs,
+        change_dbc,
+    })
 }
 
 async fn resend_pending_txs<C: SendClient>(local: &mut LocalWallet, client: &C) {
@@ -264,13 +444,15 @@ mod tests {
             client_transfers::Outputs as TransferDetails,
             dbc_genesis::{create_first_dbc_from_key, GENESIS_DBC_AMOUNT},
             wallet::{
-                local_store::WALLET_DIR_NAME, public_address_name, KeyLessWallet, SendClient,
+                local_store::WALLET_DIR_NAME, public_address_name, Error, KeyLessWallet,
+                SendClient, VerifyingClient,
             },
         },
         protocol::storage::DbcAddress,
     };
 
-    use sn_dbc::{MainKey, Token};
+    use sn_dbc::{Dbc, DbcId, MainKey, Token};
+    use std::collections::BTreeSet;
 
     use assert_fs::TempDir;
     use eyre::Result;
@@ -311,6 +493,7 @@ mod tests {
             key,
             wallet: KeyLessWallet::new(),
             wallet_dir: dir.path().to_path_buf(),
+            passphrase: None,
         };
 
         assert_eq!(public_address, deposit_only.address());
@@ -342,6 +525,7 @@ mod tests {
             key: MainKey::random(),
             wallet: KeyLessWallet::new(),
             wallet_dir: dir.path().to_path_buf(),
+            passphrase: None,
         };
 
         deposit_only.deposit(vec![]);
@@ -368,6 +552,7 @@ mod tests {
             key,
             wallet: KeyLessWallet::new(),
             wallet_dir: dir.path().to_path_buf(),
+            passphrase: None,
         };
 
         deposit_only.deposit(vec![genesis]);
@@ -390,6 +575,7 @@ mod tests {
             key: MainKey::random(),
             wallet: KeyLessWallet::new(),
             wallet_dir: dir.path().to_path_buf(),
+            passphrase: None,
         };
 
         local_wallet.deposit(vec![genesis]);
@@ -413,6 +599,7 @@ mod tests {
             key,
             wallet: KeyLessWallet::new(),
             wallet_dir: dir.path().to_path_buf(),
+            passphrase: None,
         };
 
         deposit_only.deposit(vec![genesis_0.clone()]);
@@ -472,6 +659,58 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn encrypted_wallet_to_and_from_file() -> Result<()> {
+        // Bring in the necessary traits.
+        use super::{DepositWallet, Wallet};
+
+        let dir = create_temp_dir();
+        let root_dir = dir.path().to_path_buf();
+
+        let mut depositor = LocalWallet::load_from(&root_dir).await?;
+        let genesis =
+            create_first_dbc_from_key(&depositor.key).expect("Genesis creation to succeed.");
+        depositor.deposit(vec![genesis]);
+        depositor
+            .store_encrypted("correct horse battery staple")
+            .await?;
+
+        let decrypted =
+            LocalWallet::load_encrypted_from(&root_dir, "correct horse battery staple").await?;
+        assert_eq!(depositor.address(), decrypted.address());
+        assert_eq!(GENESIS_DBC_AMOUNT, decrypted.balance().as_nano());
+        assert_eq!(1, decrypted.wallet.available_dbcs.len());
+
+        // Nothing is left in plaintext, and the wallet keeps being stored encrypted.
+        assert!(!depositor.wallet_dir.join("wallet").exists());
+        assert!(!depositor.wallet_dir.join("main_key").exists());
+        assert!(matches!(
+            LocalWallet::load_from(&root_dir).await,
+            Err(Error::WalletEncrypted(_))
+        ));
+        decrypted.store().await?;
+        assert!(!depositor.wallet_dir.join("wallet").exists());
+        let reloaded =
+            LocalWallet::load_encrypted_from(&root_dir, "correct horse battery staple").await?;
+        assert_eq!(GENESIS_DBC_AMOUNT, reloaded.balance().as_nano());
+
+        let wrong_passphrase =
+            LocalWallet::load_encrypted_from(&root_dir, "incorrect horse battery staple").await;
+        assert!(matches!(wrong_passphrase, Err(Error::DecryptionFailed)));
+
+        // A wallet file which has been tampered with is rejected too.
+        let wallet_file = depositor.wallet_dir.join("encrypted_wallet");
+        let mut bytes = tokio::fs::read(&wallet_file).await?;
+        let last = bytes.len() - 1;
+        bytes[last] ^= 1;
+        tokio::fs::write(&wallet_file, bytes).await?;
+        let tampered =
+            LocalWallet::load_encrypted_from(&root_dir, "correct horse battery staple").await;
+        assert!(matches!(tampered, Err(Error::DecryptionFailed)));
+
+        Ok(())
+    }
+
     /// --------------------------------
     /// <-------> SendWallet <--------->
     /// --------------------------------
@@ -496,7 +735,7 @@ mod tests {
         let recipient_key = MainKey::random();
         let recipient_public_address = recipient_key.public_address();
         let to = vec![(Token::from_nano(send_amount), recipient_public_address)];
-        let created_dbcs = sender.send(to, &MockSendClient).await?;
+        let created_dbcs = sender.send(to, &MockSendClient).await?.created_dbcs;
 
         assert_eq!(1, created_dbcs.len());
         assert_eq!(GENESIS_DBC_AMOUNT - send_amount, sender.balance().as_nano());
@@ -511,6 +750,271 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn sending_records_the_fee_paid() -> Result<()> {
+        // Bring in the necessary traits.
+        use super::{DepositWallet, SendWallet, Wallet};
+
+        let dir = create_temp_dir();
+        let root_dir = dir.path().to_path_buf();
+
+        let mut sender = LocalWallet::load_from(&root_dir).await?;
+        let sender_dbc =
+            create_first_dbc_from_key(&sender.key).expect("Genesis creation to succeed.");
+        sender.deposit(vec![sender_dbc]);
+        let balance_before = sender.balance().as_nano();
+
+        let send_amount = 100;
+        let to = vec![(
+            Token::from_nano(send_amount),
+            MainKey::random().public_address(),
+        )];
+        let _ = sender.send(to, &MockSendClient).await?;
+
+        let history = sender.tx_history();
+        assert_eq!(1, history.len());
+        let record = &history[0];
+        assert_eq!(send_amount, record.sent.as_nano());
+        assert_eq!(GENESIS_DBC_AMOUNT, record.inputs_total.as_nano());
+        assert_eq!(
+            record.fee.as_nano(),
+            record.inputs_total.as_nano() - record.outputs_total.as_nano()
+        );
+
+        // The balance drops by exactly what was sent plus the fee.
+        let balance_delta = balance_before - sender.balance().as_nano();
+        assert_eq!(balance_delta, record.sent.as_nano() + record.fee.as_nano());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn send_outputs_are_owned_by_recipient_and_change_by_self() -> Result<()> {
+        // Bring in the necessary traits.
+        use super::{DepositWallet, SendWallet, Wallet};
+
+        let dir = create_temp_dir();
+        let root_dir = dir.path().to_path_buf();
+
+        let mut sender = LocalWallet::load_from(&root_dir).await?;
+        let sender_dbc =
+            create_first_dbc_from_key(&sender.key).expect("Genesis creation to succeed.");
+        sender.deposit(vec![sender_dbc]);
+
+        let send_amount = 100;
+        let recipient_public_address = MainKey::random().public_address();
+        let to = vec![(Token::from_nano(send_amount), recipient_public_address)];
+        let outputs = sender.send(to, &MockSendClient).await?;
+
+        assert_eq!(1, outputs.created_dbcs.len());
+        assert_eq!(
+            &recipient_public_address,
+            outputs.created_dbcs[0].dbc.public_address()
+        );
+
+        let change_dbc = outputs.change_dbc.expect("There to be a change DBC.");
+        assert_eq!(&sender.address(), change_dbc.public_address());
+        assert!(sender.wallet.available_dbcs.contains_key(&change_dbc.id()));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn splitting_keeps_the_denominations_and_change_in_the_wallet() -> Result<()> {
+        // Bring in the necessary traits.
+        use super::{DepositWallet, SendWallet, Wallet};
+
+        let dir = create_temp_dir();
+        let mut wallet = LocalWallet::load_from(dir.path()).await?;
+        let dbc = create_first_dbc_from_key(&wallet.key).expect("Genesis creation to succeed.");
+        let genesis_id = dbc.id();
+        wallet.deposit(vec![dbc]);
+
+        let denominations = vec![100, 200, 300];
+        let outputs = wallet
+            .split(
+                denominations.iter().map(|d| Token::from_nano(*d)).collect(),
+                &MockSendClient,
+            )
+            .await?;
+
+        assert_eq!(denominations.len(), outputs.created_dbcs.len());
+        let change_dbc = outputs.change_dbc.expect("There to be a change DBC.");
+
+        // The split dbc was spent, and the wallet holds the denominations plus the change.
+        let mut available: Vec<_> = wallet
+            .available_dbcs()
+            .into_iter()
+            .map(|(_, value)| value.as_nano())
+            .collect();
+        available.sort();
+        let change = GENESIS_DBC_AMOUNT - denominations.iter().sum::<u64>();
+        let mut expected = denominations.clone();
+        expected.push(change);
+        expected.sort();
+        assert_eq!(expected, available);
+
+        assert!(!wallet.wallet.available_dbcs.contains_key(&genesis_id));
+        assert!(wallet.wallet.available_dbcs.contains_key(&change_dbc.id()));
+        for created in &outputs.created_dbcs {
+            assert_eq!(&wallet.address(), created.dbc.public_address());
+            assert!(wallet.wallet.available_dbcs.contains_key(&created.dbc.id()));
+        }
+        assert_eq!(GENESIS_DBC_AMOUNT, wallet.balance().as_nano());
+        assert!(wallet.wallet.dbcs_created_for_others.is_empty());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn splitting_beyond_the_balance_fails() -> Result<()> {
+        // Bring in the necessary traits.
+        use super::{DepositWallet, SendWallet, Wallet};
+
+        let dir = create_temp_dir();
+        let mut wallet = LocalWallet::load_from(dir.path()).await?;
+        let dbc = create_first_dbc_from_key(&wallet.key).expect("Genesis creation to succeed.");
+        wallet.deposit(vec![dbc]);
+
+        let denominations = vec![Token::from_nano(GENESIS_DBC_AMOUNT), Token::from_nano(1)];
+        assert!(wallet.split(denominations, &MockSendClient).await.is_err());
+        assert_eq!(GENESIS_DBC_AMOUNT, wallet.balance().as_nano());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn dbcs_above_leave_out_the_dust() -> Result<()> {
+        // Bring in the necessary traits.
+        use super::{DepositWallet, SendWallet, Wallet};
+
+        let dir = create_temp_dir();
+        let mut wallet = LocalWallet::load_from(dir.path()).await?;
+        let dbc = create_first_dbc_from_key(&wallet.key).expect("Genesis creation to succeed.");
+        wallet.deposit(vec![dbc]);
+
+        // Two dust dbcs and two large ones, besides the change.
+        let denominations = [1, 2, 1_000, 2_000];
+        let _ = wallet
+            .split(
+                denominations.iter().map(|d| Token::from_nano(*d)).collect(),
+                &MockSendClient,
+            )
+            .await?;
+        let change = GENESIS_DBC_AMOUNT - denominations.iter().sum::<u64>();
+
+        let mut above: Vec<_> = wallet
+            .dbcs_above(Token::from_nano(1_000))
+            .into_iter()
+            .map(|(_, value)| value.as_nano())
+            .collect();
+        above.sort();
+        let mut expected = vec![1_000, 2_000, change];
+        expected.sort();
+        assert_eq!(expected, above);
+
+        assert_eq!(
+            wallet.dbcs_above(Token::from_nano(0)).len(),
+            wallet.available_dbcs().len()
+        );
+        assert!(wallet
+            .dbcs_above(Token::from_nano(GENESIS_DBC_AMOUNT))
+            .is_empty());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn dust_wallet_requires_consolidation_before_a_large_send() -> Result<()> {
+        // Bring in the necessary traits.
+        use super::{DepositWallet, SendWallet, Wallet};
+
+        let sender_dir = create_temp_dir();
+        let mut sender = LocalWallet::load_from(sender_dir.path()).await?;
+        let dbc = create_first_dbc_from_key(&sender.key).expect("Genesis creation to succeed.");
+        sender.deposit(vec![dbc]);
+
+        // The recipient is sent twenty dust dbcs.
+        let dust_dir = create_temp_dir();
+        let mut dust_wallet = LocalWallet::load_from(dust_dir.path()).await?;
+        let to = vec![(Token::from_nano(10), dust_wallet.address()); 20];
+        let created_dbcs = sender.send(to, &MockSendClient).await?.created_dbcs;
+        dust_wallet.deposit(created_dbcs.into_iter().map(|info| info.dbc).collect());
+        assert_eq!(20, dust_wallet.available_dbcs().len());
+
+        let max_inputs = 10;
+        assert!(dust_wallet.requires_consolidation(Token::from_nano(150), max_inputs));
+        assert!(!dust_wallet.requires_consolidation(Token::from_nano(100), max_inputs));
+        // Consolidating wouldn't make an amount beyond the balance sendable.
+        assert!(!dust_wallet.requires_consolidation(Token::from_nano(201), max_inputs));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn wallet_with_a_large_dbc_does_not_require_consolidation() -> Result<()> {
+        // Bring in the necessary traits.
+        use super::{DepositWallet, Wallet};
+
+        let dir = create_temp_dir();
+        let mut wallet = LocalWallet::load_from(dir.path()).await?;
+        let dbc = create_first_dbc_from_key(&wallet.key).expect("Genesis creation to succeed.");
+        wallet.deposit(vec![dbc]);
+
+        assert!(!wallet.requires_consolidation(Token::from_nano(GENESIS_DBC_AMOUNT), 1));
+        assert!(!wallet.requires_consolidation(Token::from_nano(150), 10));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn available_dbcs_lists_deposited_dbcs() -> Result<()> {
+        // Bring in the necessary traits.
+        use super::{DepositWallet, SendWallet, Wallet};
+
+        let sender_dir = create_temp_dir();
+        let mut sender = LocalWallet::load_from(sender_dir.path()).await?;
+        let sender_dbc =
+            create_first_dbc_from_key(&sender.key).expect("Genesis creation to succeed.");
+        sender.deposit(vec![sender_dbc]);
+
+        let recipient_dir = create_temp_dir();
+        let mut recipient = LocalWallet::load_from(recipient_dir.path()).await?;
+        assert!(recipient.available_dbcs().is_empty());
+
+        let amounts = [100, 200, 300];
+        let to = amounts
+            .iter()
+            .map(|amount| (Token::from_nano(*amount), recipient.address()))
+            .collect();
+        let created_dbcs = sender.send(to, &MockSendClient).await?.created_dbcs;
+        recipient.deposit(
+            created_dbcs
+                .iter()
+                .map(|created| created.dbc.clone())
+                .collect(),
+        );
+
+        let mut expected: Vec<_> = created_dbcs
+            .iter()
+            .map(|created| (created.dbc.id(), created.amount.value()))
+            .collect();
+        expected.sort();
+        let mut available: Vec<_> = recipient
+            .available_dbcs()
+            .into_iter()
+            .map(|(id, amount)| (id, amount.as_nano()))
+            .collect();
+        available.sort();
+        assert_eq!(available, expected);
+
+        let mut deposited_amounts: Vec<_> = available.iter().map(|(_, amount)| *amount).collect();
+        deposited_amounts.sort();
+        assert_eq!(deposited_amounts, amounts);
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn send_wallet_to_and_from_file() -> Result<()> {
         // Bring in the necessary traits.
@@ -615,7 +1119,7 @@ mod tests {
         let recipient_public_address = recipient.key.public_address();
 
         let to = vec![(Token::from_nano(send_amount), recipient_public_address)];
-        let created_dbcs = sender.send(to, &MockSendClient).await?;
+        let created_dbcs = sender.send(to, &MockSendClient).await?.created_dbcs;
         let dbc = created_dbcs[0].dbc.clone();
         let dbc_id = dbc.id();
         sender.store_created_dbc(dbc).await?;
@@ -660,6 +1164,98 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn recover_deposits_the_received_dbcs_still_unspent() -> Result<()> {
+        // Bring in the necessary traits.
+        use super::{DepositWallet, SendWallet, Wallet};
+
+        let sender_root_dir = create_temp_dir();
+        let mut sender = LocalWallet::load_from(sender_root_dir.path()).await?;
+        let sender_dbc =
+            create_first_dbc_from_key(&sender.key).expect("Genesis creation to succeed.");
+        sender.deposit(vec![sender_dbc]);
+
+        // The recipient received two dbcs, and another one not owned by it, as files,
+        // but its wallet file has been lost.
+        let recipient_key = MainKey::random();
+        let recipient_public_address = recipient_key.public_address();
+        let to = vec![
+            (Token::from_nano(100), recipient_public_address),
+            (Token::from_nano(200), recipient_public_address),
+            (Token::from_nano(300), MainKey::random().public_address()),
+        ];
+        let created_dbcs = sender.send(to, &MockSendClient).await?.created_dbcs;
+        let spent_dbc = created_dbcs
+            .iter()
+            .find(|created| created.amount.value() == 100)
+            .map(|created| created.dbc.id())
+            .ok_or_else(|| eyre::eyre!("There to be a dbc of 100 nanos."))?;
+
+        let recipient_root_dir = create_temp_dir();
+        let received_dbcs_dir = recipient_root_dir
+            .path()
+            .join(WALLET_DIR_NAME)
+            .join("received_dbcs");
+        tokio::fs::create_dir_all(&received_dbcs_dir).await?;
+        for (i, created) in created_dbcs.iter().enumerate() {
+            let dbc_file = received_dbcs_dir.join(format!("{i}.dbc"));
+            tokio::fs::write(dbc_file, created.dbc.to_hex()?).await?;
+        }
+
+        // The dbc of 100 nanos has been spent since.
+        let client = MockVerifyingClient {
+            spent: BTreeSet::from([spent_dbc]),
+        };
+        let recovered =
+            LocalWallet::recover(recipient_root_dir.path(), recipient_key, &client).await?;
+        assert_eq!(200, recovered.balance().as_nano());
+        assert_eq!(recipient_public_address, recovered.address());
+
+        // The recovered wallet has been stored, along with its key.
+        let loaded = LocalWallet::load_from(recipient_root_dir.path()).await?;
+        assert_eq!(200, loaded.balance().as_nano());
+        assert_eq!(recipient_public_address, loaded.address());
+
+        // Recovering again refuses to overwrite the wallet now stored.
+        let key_bytes: [u8; bls::SK_SIZE] = loaded
+            .key
+            .to_bytes()
+            .try_into()
+            .map_err(|_| Error::FailedToParseBlsKey)?;
+        let same_key = MainKey::new(bls::SecretKey::from_bytes(key_bytes)?);
+        let recovered_again =
+            LocalWallet::recover(recipient_root_dir.path(), same_key, &client).await;
+        assert!(matches!(
+            recovered_again,
+            Err(Error::WalletAlreadyExists(dir)) if dir == recipient_root_dir.path().join(WALLET_DIR_NAME)
+        ));
+        assert_eq!(
+            200,
+            LocalWallet::load_from(recipient_root_dir.path())
+                .await?
+                .balance()
+                .as_nano()
+        );
+
+        Ok(())
+    }
+
+    #[derive(Clone)]
+    struct MockVerifyingClient {
+        spent: BTreeSet<DbcId>,
+    }
+
+    #[async_trait::async_trait]
+    impl VerifyingClient for MockVerifyingClient {
+        async fn verify(&self, _dbc: &Dbc) -> super::Result<()> {
+            Ok(())
+        }
+
+        async fn is_spent(&self, dbc_id: &DbcId) -> super::Result<bool> {
+            Ok(self.spent.contains(dbc_id))
+        }
+    }
+
     #[derive(Clone)]
     struct MockSendClient;
 
@@ -101,6 +101,8 @@ pub trait SendClient: Send + Sync + Clone {
 pub trait VerifyingClient: Send + Sync + Clone {
     ///
     async fn verify(&self, dbc: &Dbc) -> Result<()>;
+    /// Whether the dbc with the given id has been spent, i.e. a spend of it is held by the network.
+    async fn is_spent(&self, dbc_id: &DbcId) -> Result<bool>;
 }
 
 /// A wallet has an address and a balance.
@@ -109,6 +111,30 @@ pub trait Wallet {
     fn address(&self) -> PublicAddress;
     /// The current balance of the wallet.
     fn balance(&self) -> Token;
+    /// The dbcs held by the wallet which are available to spend, along with their value.
+    fn available_dbcs(&self) -> Vec<(DbcId, Token)>;
+    /// The dbcs available to spend which are worth at least `min`, along with their value,
+    /// e.g. to leave dust dbcs out when selecting those to spend.
+    fn dbcs_above(&self, min: Token) -> Vec<(DbcId, Token)> {
+        self.available_dbcs()
+            .into_iter()
+            .filter(|(_, value)| value.as_nano() >= min.as_nano())
+            .collect()
+    }
+    /// Whether sending `amount` would take more than `max_inputs` of the dbcs available to spend,
+    /// even picking the most valuable ones first, so the dust had better be consolidated first.
+    /// An amount beyond the balance doesn't, as consolidating wouldn't make it sendable anyway.
+    fn requires_consolidation(&self, amount: Token, max_inputs: usize) -> bool {
+        let mut values: Vec<_> = self
+            .available_dbcs()
+            .into_iter()
+            .map(|(_, value)| value.as_nano())
+            .collect();
+        values.sort_unstable_by(|a, b| b.cmp(a));
+        let total = |values: &[u64]| values.iter().fold(0u64, |sum, v| sum.saturating_add(*v));
+        let (largest, _) = values.split_at(max_inputs.min(values.len()));
+        total(largest) < amount.as_nano() && total(&values) >= amount.as_nano()
+    }
 }
 
 /// A wallet that can sign msgs.
@@ -122,14 +148,47 @@ pub trait SigningWallet {
 #[async_trait]
 pub trait SendWallet: DepositWallet {
     /// Sends the given tokens to the given addresses.
-    /// Returns the new dbcs that were created.
+    /// Returns the new dbcs that were created, along with the change dbc if any.
     /// Depending on the implementation of the send client, this may
     /// also register the transaction with the network.
     async fn send<C: SendClient>(
         &mut self,
         to: Vec<(Token, PublicAddress)>,
         client: &C,
-    ) -> Result<Vec<CreatedDbc>>;
+    ) -> Result<SendOutputs>;
+    /// Reissues the held dbcs into new dbcs of the given denominations, owned by the wallet
+    /// itself, failing if their total exceeds the balance. Returns the new dbcs that were
+    /// created, along with the change dbc if any.
+    async fn split<C: SendClient>(
+        &mut self,
+        denominations: Vec<Token>,
+        client: &C,
+    ) -> Result<SendOutputs>;
+}
+
+/// The dbcs resulting from sending tokens to other addresses.
+#[derive(Debug, Clone, Default)]
+pub struct SendOutputs {
+    /// The dbcs that were created containing
+    /// the tokens sent to respective recipient.
+    pub created_dbcs: Vec<CreatedDbc>,
+    /// The dbc holding the surplus tokens returned to the sender, if any.
+    pub change_dbc: Option<Dbc>,
+}
+
+/// The record of a past send of tokens to other addresses.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct TxRecord {
+    /// The hash of the transaction the tokens were sent with.
+    pub tx_hash: sn_dbc::Hash,
+    /// The tokens sent to the recipients, change excluded.
+    pub sent: Token,
+    /// The total value of the dbcs spent by the transaction.
+    pub inputs_total: Token,
+    /// The total value of the dbcs created by the transaction, change included.
+    pub outputs_total: Token,
+    /// The fee paid for the send, i.e. what the inputs total exceeds the outputs total by.
+    pub fee: Token,
 }
 
 /// A deposit wallet is a wallet that can receive tokens from other wallets.
@@ -162,6 +221,9 @@ pub(super) struct KeyLessWallet {
     /// keep them here so we can track our
     /// transfer history.
     dbcs_created_for_others: Vec<CreatedDbc>,
+    /// The record of each of the sends made, in the order they were made.
+    #[serde(default)]
+    tx_history: Vec<TxRecord>,
 }
 
 /// Return the name of a PublicAddress.
@@ -6,16 +6,87 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use super::{error::Result, public_address_name, KeyLessWallet};
+use super::{
+    error::{Error, Result},
+    public_address_name, KeyLessWallet,
+};
 
 use crate::protocol::storage::DbcAddress;
 
-use sn_dbc::Dbc;
-use std::path::Path;
-use tokio::fs;
+use argon2::{Algorithm, Argon2, Params, Version};
+use chacha20poly1305::{
+    aead::{Aead, KeyInit},
+    Key, XChaCha20Poly1305, XNonce,
+};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use sn_dbc::{Dbc, MainKey};
+use std::{io::ErrorKind, path::Path};
+use tokio::{fs, io::AsyncWriteExt};
 
 // Filename for storing a wallet.
 const WALLET_FILE_NAME: &str = "wallet";
+// Filename for storing a wallet along with its main key, encrypted with a passphrase.
+const ENCRYPTED_WALLET_FILE_NAME: &str = "encrypted_wallet";
+// Filename an encrypted wallet is written to before replacing the current one.
+const ENCRYPTED_WALLET_TMP_FILE_NAME: &str = "encrypted_wallet.tmp";
+// Argon2id memory cost in KiB, number of iterations and degree of parallelism the key
+// to encrypt a new wallet with is derived with, as recommended by OWASP.
+const KDF_MEMORY_COST: u32 = 19 * 1024;
+const KDF_ITERATIONS: u32 = 2;
+const KDF_PARALLELISM: u32 = 1;
+// Upper bounds of the Argon2id parameters read from a wallet file, so a crafted file can't
+// make deriving its key take an unbounded amount of memory or time.
+const MAX_KDF_MEMORY_COST: u32 = 256 * 1024;
+const MAX_KDF_ITERATIONS: u32 = 16;
+const MAX_KDF_PARALLELISM: u32 = 16;
+
+// The on-disk format of an encrypted wallet.
+#[derive(Serialize, Deserialize)]
+struct EncryptedWallet {
+    // Parameters the encryption key is derived from the passphrase with,
+    // stored so they can be raised for new wallets without breaking existing ones.
+    kdf_params: KdfParams,
+    // Random salt the encryption key is derived with, along with the passphrase.
+    salt: [u8; 32],
+    // Random nonce the wallet is encrypted with.
+    nonce: [u8; 24],
+    // The serialised wallet, encrypted and authenticated with XChaCha20-Poly1305.
+    ciphertext: Vec<u8>,
+}
+
+// Argon2id parameters the encryption key of a wallet is derived with.
+#[derive(Serialize, Deserialize, Clone, Copy)]
+struct KdfParams {
+    memory_cost: u32,
+    iterations: u32,
+    parallelism: u32,
+}
+
+impl Default for KdfParams {
+    fn default() -> Self {
+        Self {
+            memory_cost: KDF_MEMORY_COST,
+            iterations: KDF_ITERATIONS,
+            parallelism: KDF_PARALLELISM,
+        }
+    }
+}
+
+impl KdfParams {
+    fn check_bounds(&self) -> Result<()> {
+        if self.memory_cost > MAX_KDF_MEMORY_COST
+            || self.iterations > MAX_KDF_ITERATIONS
+            || self.parallelism > MAX_KDF_PARALLELISM
+        {
+            return Err(Error::KeyDerivation(format!(
+                "parameters exceed the maximum supported: memory cost {} KiB, {} iterations, parallelism {}",
+                self.memory_cost, self.iterations, self.parallelism
+            )));
+        }
+        Ok(())
+    }
+}
 const CREATED_DBCS_DIR_NAME: &str = "created_dbcs";
 const RECEIVED_DBCS_DIR_NAME: &str = "received_dbcs";
 
@@ -33,6 +104,14 @@ pub(super) async fn store_wallet(wallet_dir: &Path, wallet: &KeyLessWallet) -> R
     Ok(())
 }
 
+/// Removes the `KeyLessWallet` stored in plaintext at the specified path, if any.
+pub(super) async fn remove_wallet(wallet_dir: &Path) -> Result<()> {
+    match fs::remove_file(wallet_dir.join(WALLET_FILE_NAME)).await {
+        Err(err) if err.kind() != ErrorKind::NotFound => Err(err.into()),
+        _ => Ok(()),
+    }
+}
+
 /// Returns `Some(KeyLessWallet)` or None if file doesn't exist.
 pub(super) async fn get_wallet(wallet_dir: &Path) -> Result<Option<KeyLessWallet>> {
     let path = wallet_dir.join(WALLET_FILE_NAME);
@@ -46,6 +125,102 @@ pub(super) async fn get_wallet(wallet_dir: &Path) -> Result<Option<KeyLessWallet
     Ok(Some(wallet))
 }
 
+/// Encrypts the `MainKey` and `KeyLessWallet` with a key derived from the passphrase,
+/// and writes them to the specified path.
+pub(super) async fn store_encrypted_wallet(
+    wallet_dir: &Path,
+    main_key: &MainKey,
+    wallet: &KeyLessWallet,
+    passphrase: &str,
+) -> Result<()> {
+    let plaintext = bincode::serialize(&(main_key.to_bytes().to_vec(), wallet))?;
+
+    let kdf_params = KdfParams::default();
+    let salt: [u8; 32] = rand::thread_rng().gen();
+    let nonce: [u8; 24] = rand::thread_rng().gen();
+    let ciphertext = cipher(passphrase, salt, kdf_params)
+        .await?
+        .encrypt(XNonce::from_slice(&nonce), plaintext.as_slice())
+        .map_err(|_| Error::EncryptionFailed)?;
+
+    let bytes = bincode::serialize(&EncryptedWallet {
+        kdf_params,
+        salt,
+        nonce,
+        ciphertext,
+    })?;
+    // Written to a tmp file first, so the previous encrypted wallet is only replaced once
+    // the new one is fully on disk.
+    let tmp_path = wallet_dir.join(ENCRYPTED_WALLET_TMP_FILE_NAME);
+    let mut file = fs::File::create(&tmp_path).await?;
+    file.write_all(&bytes).await?;
+    file.sync_all().await?;
+    fs::rename(&tmp_path, wallet_dir.join(ENCRYPTED_WALLET_FILE_NAME)).await?;
+    Ok(())
+}
+
+/// Whether a wallet encrypted with a passphrase is stored at the specified path.
+pub(super) fn encrypted_wallet_exists(wallet_dir: &Path) -> bool {
+    wallet_dir.join(ENCRYPTED_WALLET_FILE_NAME).is_file()
+}
+
+/// Reads the `MainKey` and `KeyLessWallet` from the specified path, decrypting them with
+/// a key derived from the passphrase. A wrong passphrase, as well as a wallet file which
+/// has been tampered with, yields `Error::DecryptionFailed`.
+pub(super) async fn get_encrypted_wallet(
+    wallet_dir: &Path,
+    passphrase: &str,
+) -> Result<(MainKey, KeyLessWallet)> {
+    let bytes = fs::read(wallet_dir.join(ENCRYPTED_WALLET_FILE_NAME)).await?;
+    let EncryptedWallet {
+        kdf_params,
+        salt,
+        nonce,
+        ciphertext,
+    } = bincode::deserialize(&bytes)?;
+
+    let plaintext = cipher(passphrase, salt, kdf_params)
+        .await?
+        .decrypt(XNonce::from_slice(&nonce), ciphertext.as_slice())
+        .map_err(|_| Error::DecryptionFailed)?;
+
+    let (key_bytes, wallet): (Vec<u8>, KeyLessWallet) = bincode::deserialize(&plaintext)?;
+    let key_bytes: [u8; bls::SK_SIZE] = key_bytes
+        .as_slice()
+        .try_into()
+        .map_err(|_| Error::FailedToParseBlsKey)?;
+    let main_key = MainKey::new(bls::SecretKey::from_bytes(key_bytes)?);
+
+    Ok((main_key, wallet))
+}
+
+// Derives the key to encrypt/decrypt a wallet with from the passphrase and salt, with Argon2id.
+// Being deliberately costly, it's derived on a blocking thread, off the async runtime.
+async fn cipher(
+    passphrase: &str,
+    salt: [u8; 32],
+    kdf_params: KdfParams,
+) -> Result<XChaCha20Poly1305> {
+    kdf_params.check_bounds()?;
+    let params = Params::new(
+        kdf_params.memory_cost,
+        kdf_params.iterations,
+        kdf_params.parallelism,
+        Some(32),
+    )
+    .map_err(|err| Error::KeyDerivation(err.to_string()))?;
+    let passphrase = passphrase.to_owned();
+    tokio::task::spawn_blocking(move || {
+        let mut key = [0; 32];
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+            .hash_password_into(passphrase.as_bytes(), &salt, &mut key)
+            .map_err(|err| Error::KeyDerivation(err.to_string()))?;
+        Ok(XChaCha20Poly1305::new(Key::from_slice(&key)))
+    })
+    .await
+    .map_err(|err| Error::KeyDerivation(err.to_string()))?
+}
+
 /// Hex encode and write each `Dbc` to a separate file in respective
 /// recipient public address dir in the created dbcs dir. Each file is named after the dbc id.
 pub(super) async fn store_created_dbcs(created_dbcs: Vec<Dbc>, wallet_dir: &Path) -> Result<()> {
@@ -21,7 +21,7 @@ use tracing_subscriber::{
     fmt::{
         format::Writer,
         time::{FormatTime, SystemTime},
-        FmtContext, FormatEvent, FormatFields,
+        FmtContext, FormatEvent, FormatFields, FormattedFields,
     },
     layer::Filter,
     prelude::*,
@@ -52,7 +52,17 @@ where
         write!(writer, "[")?;
         time.format_time(&mut writer)?;
         write!(writer, " {level} {module}")?;
-        ctx.visit_spans(|span| write!(writer, "/{}", span.name()))?;
+        ctx.visit_spans(|span| {
+            write!(writer, "/{}", span.name())?;
+            // Add the structured fields of the span, e.g. the peer ids it relates to
+            let extensions = span.extensions();
+            if let Some(fields) = extensions.get::<FormattedFields<N>>() {
+                if !fields.is_empty() {
+                    write!(writer, "{{{fields}}}")?;
+                }
+            }
+            Ok(())
+        })?;
         write!(writer, "] ")?;
 
         // Add the log message and any fields associated with the event
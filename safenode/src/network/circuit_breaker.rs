@@ -0,0 +1,157 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use libp2p::PeerId;
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+// Number of consecutive failed requests after which a peer is no longer selected.
+pub(super) const CIRCUIT_BREAKER_FAILURE_THRESHOLD: usize = 3;
+// Duration a peer is kept out of the selection once it has crossed the failure threshold.
+pub(super) const CIRCUIT_BREAKER_COOLDOWN: Duration = Duration::from_secs(60);
+
+/// Tracks the requests failing on each peer, so that the peers which keep failing
+/// are temporarily left out when selecting the recipients of a request.
+/// Once the cooldown has elapsed the peer is re-admitted on probation, i.e. a single
+/// further failure is enough to leave it out again.
+#[derive(Debug)]
+pub(super) struct PeerCircuitBreaker {
+    failure_threshold: usize,
+    cooldown: Duration,
+    peers: HashMap<PeerId, PeerFailures>,
+}
+
+#[derive(Debug, Default)]
+struct PeerFailures {
+    consecutive: usize,
+    tripped_at: Option<Instant>,
+}
+
+impl Default for PeerCircuitBreaker {
+    fn default() -> Self {
+        Self::new(CIRCUIT_BREAKER_FAILURE_THRESHOLD, CIRCUIT_BREAKER_COOLDOWN)
+    }
+}
+
+impl PeerCircuitBreaker {
+    pub(super) fn new(failure_threshold: usize, cooldown: Duration) -> Self {
+        Self {
+            failure_threshold,
+            cooldown,
+            peers: HashMap::new(),
+        }
+    }
+
+    /// A successful response resets the peer's failure count.
+    pub(super) fn record_success(&mut self, peer: &PeerId) {
+        let _ = self.peers.remove(peer);
+    }
+
+    /// Records a failed request, tripping the breaker for the peer if it reaches the threshold.
+    pub(super) fn record_failure(&mut self, peer: PeerId) {
+        let failures = self.peers.entry(peer).or_default();
+        failures.consecutive += 1;
+        if failures.tripped_at.is_none() && failures.consecutive >= self.failure_threshold {
+            warn!(
+                "Peer {peer:?} failed {} consecutive requests, not selecting it for {:?}",
+                failures.consecutive, self.cooldown
+            );
+            failures.tripped_at = Some(Instant::now());
+        }
+    }
+
+    /// Returns whether the peer is currently left out of the selection.
+    pub(super) fn is_tripped(&mut self, peer: &PeerId) -> bool {
+        let failures = match self.peers.get_mut(peer) {
+            Some(failures) => failures,
+            None => return false,
+        };
+        match failures.tripped_at {
+            Some(tripped_at) if tripped_at.elapsed() >= self.cooldown => {
+                debug!("Cooldown elapsed, re-admitting peer {peer:?}");
+                failures.tripped_at = None;
+                failures.consecutive = self.failure_threshold.saturating_sub(1);
+                false
+            }
+            Some(_) => true,
+            None => false,
+        }
+    }
+
+    /// Filters out the peers which are currently tripped.
+    pub(super) fn available_peers(&mut self, peers: Vec<PeerId>) -> Vec<PeerId> {
+        peers
+            .into_iter()
+            .filter(|peer| !self.is_tripped(peer))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FAILURE_THRESHOLD: usize = 3;
+    const COOLDOWN: Duration = Duration::from_millis(200);
+
+    #[test]
+    fn failing_peer_is_not_selected_until_cooldown_elapses() {
+        let mut breaker = PeerCircuitBreaker::new(FAILURE_THRESHOLD, COOLDOWN);
+        let failing_peer = PeerId::random();
+        let peers: Vec<_> = iter_peers(4).chain([failing_peer]).collect();
+
+        // Every attempt which selects the failing peer fails on it, the rest succeed.
+        let mut attempts_selecting_failing_peer = 0;
+        for _ in 0..10 {
+            for peer in breaker.available_peers(peers.clone()) {
+                if peer == failing_peer {
+                    attempts_selecting_failing_peer += 1;
+                    breaker.record_failure(peer);
+                } else {
+                    breaker.record_success(&peer);
+                }
+            }
+        }
+        assert_eq!(attempts_selecting_failing_peer, FAILURE_THRESHOLD);
+        assert_eq!(
+            breaker.available_peers(peers.clone()).len(),
+            peers.len() - 1
+        );
+
+        std::thread::sleep(COOLDOWN);
+        assert_eq!(breaker.available_peers(peers.clone()), peers);
+
+        // Re-admitted on probation, hence a single failure trips it again.
+        breaker.record_failure(failing_peer);
+        assert!(breaker.is_tripped(&failing_peer));
+    }
+
+    #[test]
+    fn success_resets_the_failure_count() {
+        let mut breaker = PeerCircuitBreaker::new(FAILURE_THRESHOLD, COOLDOWN);
+        let peer = PeerId::random();
+
+        for _ in 0..FAILURE_THRESHOLD - 1 {
+            breaker.record_failure(peer);
+        }
+        breaker.record_success(&peer);
+        for _ in 0..FAILURE_THRESHOLD - 1 {
+            breaker.record_failure(peer);
+        }
+        assert!(!breaker.is_tripped(&peer));
+
+        breaker.record_failure(peer);
+        assert!(breaker.is_tripped(&peer));
+    }
+
+    fn iter_peers(count: usize) -> impl Iterator<Item = PeerId> {
+        (0..count).map(|_| PeerId::random())
+    }
+}
@@ -0,0 +1,65 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use libp2p::{Multiaddr, PeerId};
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+/// Tracks the peers whose last connection closed, so they are only removed from the
+/// routing table if they don't reconnect within the grace period, rather than thrashing
+/// the routing table whenever a peer briefly drops out during churn.
+#[derive(Debug, Default)]
+pub(super) struct RecentlyClosedPeers {
+    // How long a peer is given to reconnect, peers are removed right away if zero.
+    grace_period: Duration,
+    // The peers waiting to reconnect, along with the address to remove and when they closed.
+    closed: HashMap<PeerId, (Multiaddr, Instant)>,
+}
+
+impl RecentlyClosedPeers {
+    pub(super) fn set_grace_period(&mut self, grace_period: Duration) {
+        self.grace_period = grace_period;
+    }
+
+    /// Whether peers are to be removed as soon as their last connection closes.
+    pub(super) fn removes_immediately(&self) -> bool {
+        self.grace_period.is_zero()
+    }
+
+    pub(super) fn is_empty(&self) -> bool {
+        self.closed.is_empty()
+    }
+
+    /// Records the last connection with the peer, at the given address, closed at `now`.
+    pub(super) fn insert(&mut self, peer: PeerId, addr: Multiaddr, now: Instant) {
+        let _ = self.closed.insert(peer, (addr, now));
+    }
+
+    /// Stops tracking the peer as it reconnected, returning whether it was pending removal.
+    pub(super) fn reconnected(&mut self, peer: &PeerId) -> bool {
+        self.closed.remove(peer).is_some()
+    }
+
+    /// Takes out the peers which haven't reconnected within the grace period by `now`.
+    pub(super) fn take_expired(&mut self, now: Instant) -> Vec<(PeerId, Multiaddr)> {
+        let grace_period = self.grace_period;
+        let expired: Vec<PeerId> = self
+            .closed
+            .iter()
+            .filter(|(_, (_, closed_at))| now.saturating_duration_since(*closed_at) >= grace_period)
+            .map(|(peer, _)| *peer)
+            .collect();
+
+        expired
+            .into_iter()
+            .filter_map(|peer| self.closed.remove(&peer).map(|(addr, _)| (peer, addr)))
+            .collect()
+    }
+}
@@ -6,22 +6,31 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use super::{error::Error, MsgResponder, NetworkEvent, SwarmDriver};
+use super::{
+    dial_addr, error::Error, ClosestPeersRequester, MsgResponder, NetworkEvent,
+    PendingClosestPeersBatch, SwarmDriver, SwarmMetrics,
+};
 
 use crate::{
     network::error::Result,
     protocol::{
+        error::StorageError,
         messages::{QueryResponse, Request, Response},
+        storage::{registers::EntryHash, Chunk, ChunkAddress, RegisterAddress},
         NetworkAddress,
     },
 };
 
+use bytes::Bytes;
 use libp2p::{
-    kad::{Record, RecordKey},
-    multiaddr::Protocol,
+    kad::{store::RecordStore, Quorum, Record, RecordKey},
     Multiaddr, PeerId,
 };
-use std::collections::{hash_map, HashSet};
+use std::{
+    collections::{hash_map, BTreeMap, BTreeSet, HashSet},
+    num::NonZeroUsize,
+    time::Instant,
+};
 use tokio::sync::oneshot;
 
 /// Commands to send to the Swarm
@@ -43,7 +52,12 @@ pub enum SwarmCmd {
     },
     GetClosestPeers {
         key: NetworkAddress,
-        sender: oneshot::Sender<HashSet<PeerId>>,
+        sender: oneshot::Sender<Result<HashSet<PeerId>>>,
+    },
+    /// Look up the closest peers to each of the keys concurrently, sending them all at once
+    GetClosestPeersBatch {
+        keys: Vec<NetworkAddress>,
+        sender: oneshot::Sender<Result<BTreeMap<NetworkAddress, HashSet<PeerId>>>>,
     },
     SendRequest {
         req: Request,
@@ -55,15 +69,48 @@ pub enum SwarmCmd {
         channel: MsgResponder,
     },
     GetSwarmLocalState(oneshot::Sender<SwarmLocalState>),
-    /// Put data to the Kad network as record
+    GetMetricsSnapshot(oneshot::Sender<SwarmMetrics>),
+    /// Set whether the responses received from peers are rejected unless signed by them
+    SetRequireSignedResponses(bool),
+    /// (Re-)run the Kademlia bootstrap process
+    Bootstrap {
+        sender: oneshot::Sender<Result<()>>,
+    },
+    /// Put data to the Kad network as record, to be stored by at least `min_replicas` peers
     PutProvidedDataAsRecord {
         record: Record,
+        sender: oneshot::Sender<Result<()>>,
+    },
+    /// Put data as record to the given peers, to be stored by all of them
+    PutRecordTo {
+        record: Record,
+        peers: Vec<PeerId>,
+        sender: oneshot::Sender<Result<()>>,
+    },
+    /// Get a chunk only if held in our local record store
+    GetStoredChunk {
+        address: ChunkAddress,
+        sender: oneshot::Sender<QueryResponse>,
+    },
+    /// Check whether a chunk is held in our local record store, without reading it
+    ChunkExists {
+        address: ChunkAddress,
+        sender: oneshot::Sender<QueryResponse>,
+    },
+    /// Emit a `NetworkEvent::RegisterUpdated` with the edits applied to a Register we hold
+    NotifyRegisterUpdated {
+        address: RegisterAddress,
+        new_entries: Vec<EntryHash>,
     },
     /// Get data from the kademlia store
     GetData {
         key: RecordKey,
         sender: oneshot::Sender<Result<QueryResponse>>,
     },
+    /// Pin a chunk in our local record store, so it's never removed until unpinned
+    PinChunk(ChunkAddress),
+    /// Unpin a chunk in our local record store, so it can be removed again
+    UnpinChunk(ChunkAddress),
 }
 
 /// Snapshot of information kept in the Swarm's local state
@@ -73,22 +120,102 @@ pub struct SwarmLocalState {
     pub connected_peers: Vec<PeerId>,
     /// List of aaddresses the node is currently listening on
     pub listeners: Vec<Multiaddr>,
+    /// Number of peers currently in the routing table
+    pub peers_in_routing_table: usize,
 }
 
 impl SwarmDriver {
     pub(crate) async fn handle_cmd(&mut self, cmd: SwarmCmd) -> Result<(), Error> {
         match cmd {
             SwarmCmd::GetData { key, sender } => {
-                let query_id = self.swarm.behaviour_mut().kademlia.get_record(key);
-                let _ = self.pending_query.insert(query_id, sender);
+                // Prefer the record held in our local store, if any, over looking it up
+                // across the network, e.g. when a client runs along with the node holding it.
+                let local_value = self
+                    .swarm
+                    .behaviour_mut()
+                    .kademlia
+                    .store_mut()
+                    .get(&key)
+                    .map(|record| record.value.clone());
+                match local_value {
+                    Some(value) => {
+                        trace!("Record {key:?} found in our local store");
+                        let chunk = Chunk::new(Bytes::from(value));
+                        let _ = sender.send(Ok(QueryResponse::GetChunk(Ok(chunk))));
+                    }
+                    None if self.too_many_pending_queries() => {
+                        let _ = sender.send(Err(Error::TooManyPendingQueries));
+                    }
+                    None => {
+                        self.network_record_lookups += 1;
+                        let query_id = self.swarm.behaviour_mut().kademlia.get_record(key);
+                        let _ = self.pending_query.insert(query_id, sender);
+                    }
+                }
             }
-            SwarmCmd::PutProvidedDataAsRecord { record } => {
+            SwarmCmd::PutProvidedDataAsRecord { record, sender } => {
                 // TODO: when do we remove records. Do we need to?
-                let _ = self
+                let quorum = Quorum::N(self.min_replicas);
+                match self
                     .swarm
                     .behaviour_mut()
                     .kademlia
-                    .put_record(record, libp2p::kad::Quorum::All)?;
+                    .put_record(record, quorum)
+                {
+                    Ok(query_id) => {
+                        let _ = self.pending_put_record.insert(query_id, sender);
+                    }
+                    Err(err) => {
+                        let _ = sender.send(Err(err.into()));
+                    }
+                }
+            }
+            SwarmCmd::PutRecordTo {
+                record,
+                peers,
+                sender,
+            } => match NonZeroUsize::new(peers.len()) {
+                Some(quorum) => {
+                    let query_id = self.swarm.behaviour_mut().kademlia.put_record_to(
+                        record,
+                        peers.into_iter(),
+                        Quorum::N(quorum),
+                    );
+                    let _ = self.pending_put_record.insert(query_id, sender);
+                }
+                None => {
+                    let _ = sender.send(Ok(()));
+                }
+            },
+            SwarmCmd::GetStoredChunk { address, sender } => {
+                let _ = sender.send(self.stored_chunk(&address));
+            }
+            SwarmCmd::ChunkExists { address, sender } => {
+                let _ = sender.send(self.chunk_exists(&address));
+            }
+            SwarmCmd::PinChunk(address) => {
+                self.swarm
+                    .behaviour_mut()
+                    .kademlia
+                    .store_mut()
+                    .pin(&address);
+            }
+            SwarmCmd::UnpinChunk(address) => {
+                self.swarm
+                    .behaviour_mut()
+                    .kademlia
+                    .store_mut()
+                    .unpin(&address);
+            }
+            SwarmCmd::NotifyRegisterUpdated {
+                address,
+                new_entries,
+            } => {
+                self.send_event(NetworkEvent::RegisterUpdated {
+                    address,
+                    new_entries,
+                })
+                .await?;
             }
             SwarmCmd::StartListening { addr, sender } => {
                 let _ = match self.swarm.listen_on(addr) {
@@ -119,10 +246,7 @@ impl SwarmDriver {
                     // immediately write to the pending dial hashmap, as dials can take time,
                     // if we wait until its done more may be in flight
                     let _ = dial_entry.insert(sender);
-                    match self
-                        .swarm
-                        .dial(peer_addr.with(Protocol::P2p(peer_id.into())))
-                    {
+                    match self.swarm.dial(dial_addr(peer_addr, peer_id)) {
                         Ok(()) => {}
                         Err(e) => {
                             dial_error = Some(e);
@@ -140,14 +264,58 @@ impl SwarmDriver {
                 }
             }
             SwarmCmd::GetClosestPeers { key, sender } => {
+                if self.too_many_pending_queries() {
+                    let _ = sender.send(Err(Error::TooManyPendingQueries));
+                    return Ok(());
+                }
                 let query_id = self
                     .swarm
                     .behaviour_mut()
                     .kademlia
                     .get_closest_peers(key.as_bytes());
-                let _ = self
-                    .pending_get_closest_peers
-                    .insert(query_id, (sender, Default::default()));
+                let _ = self.pending_get_closest_peers.insert(
+                    query_id,
+                    (ClosestPeersRequester::Single(sender), Default::default()),
+                );
+            }
+            SwarmCmd::GetClosestPeersBatch { keys, sender } => {
+                let keys: BTreeSet<_> = keys.into_iter().collect();
+                if keys.is_empty() {
+                    let _ = sender.send(Ok(BTreeMap::new()));
+                    return Ok(());
+                }
+                // The whole batch is rejected unless all of its queries can be started.
+                let pending =
+                    self.pending_query.len() + self.pending_get_closest_peers.len() + keys.len();
+                if pending > self.max_pending_queries {
+                    let _ = sender.send(Err(Error::TooManyPendingQueries));
+                    return Ok(());
+                }
+
+                let batch_id = self.next_closest_peers_batch_id;
+                self.next_closest_peers_batch_id += 1;
+                let _ = self.pending_get_closest_peers_batches.insert(
+                    batch_id,
+                    PendingClosestPeersBatch {
+                        sender,
+                        found: BTreeMap::new(),
+                        remaining: keys.len(),
+                    },
+                );
+                for key in keys {
+                    let query_id = self
+                        .swarm
+                        .behaviour_mut()
+                        .kademlia
+                        .get_closest_peers(key.as_bytes());
+                    let _ = self.pending_get_closest_peers.insert(
+                        query_id,
+                        (
+                            ClosestPeersRequester::Batch { batch_id, key },
+                            Default::default(),
+                        ),
+                    );
+                }
             }
             SwarmCmd::SendRequest { req, peer, sender } => {
                 // If `self` is the recipient, forward the request directly to our upper layer to
@@ -155,14 +323,14 @@ impl SwarmDriver {
                 // `self` then handles the request and sends a response back again to itself.
                 if peer == *self.swarm.local_peer_id() {
                     trace!("Sending request to self");
-                    self.event_sender
-                        .send(NetworkEvent::RequestReceived {
-                            req,
-                            channel: MsgResponder::FromSelf(sender),
-                        })
-                        .await?;
+                    self.send_event(NetworkEvent::RequestReceived {
+                        req,
+                        channel: MsgResponder::FromSelf(sender),
+                    })
+                    .await?;
                 } else {
                     trace!("Sending request to peer {peer:?}");
+                    self.idle_peers.record_activity(peer, Instant::now());
                     let request_id = self
                         .swarm
                         .behaviour_mut()
@@ -180,6 +348,7 @@ impl SwarmDriver {
                         .map_err(|_| Error::InternalMsgChannelDropped)?;
                 }
                 MsgResponder::FromPeer(channel) => {
+                    let resp = self.sign_response(resp)?;
                     self.swarm
                         .behaviour_mut()
                         .request_response
@@ -187,17 +356,77 @@ impl SwarmDriver {
                         .map_err(Error::OutgoingResponseDropped)?;
                 }
             },
+            SwarmCmd::Bootstrap { sender } => {
+                match self.swarm.behaviour_mut().kademlia.bootstrap() {
+                    Ok(query_id) => {
+                        let _ = self.pending_bootstrap.insert(query_id, sender);
+                    }
+                    Err(err) => {
+                        let _ = sender.send(Err(err.into()));
+                    }
+                }
+            }
+            SwarmCmd::SetRequireSignedResponses(require_signed_responses) => {
+                self.set_require_signed_responses(require_signed_responses);
+            }
             SwarmCmd::GetSwarmLocalState(sender) => {
                 let current_state = SwarmLocalState {
                     connected_peers: self.swarm.connected_peers().cloned().collect(),
                     listeners: self.swarm.listeners().cloned().collect(),
+                    peers_in_routing_table: self
+                        .swarm
+                        .behaviour_mut()
+                        .kademlia
+                        .kbuckets()
+                        .map(|bucket| bucket.num_entries())
+                        .sum(),
                 };
 
                 sender
                     .send(current_state)
                     .map_err(|_| Error::InternalMsgChannelDropped)?;
             }
+            SwarmCmd::GetMetricsSnapshot(sender) => {
+                sender
+                    .send(self.metrics_snapshot())
+                    .map_err(|_| Error::InternalMsgChannelDropped)?;
+            }
         }
         Ok(())
     }
+
+    // Whether the pending record and closest peers queries have reached the maximum allowed,
+    // i.e. no new query is to be started until some of them complete.
+    fn too_many_pending_queries(&self) -> bool {
+        self.pending_query.len() + self.pending_get_closest_peers.len() >= self.max_pending_queries
+    }
+
+    // Returns the chunk at the given address if held in our local record store,
+    // without looking it up across the network.
+    pub(super) fn stored_chunk(&mut self, address: &ChunkAddress) -> QueryResponse {
+        let key = RecordKey::new(address.name());
+        match self.swarm.behaviour_mut().kademlia.store_mut().get(&key) {
+            Some(record) => {
+                QueryResponse::GetChunk(Ok(Chunk::new(Bytes::from(record.value.clone()))))
+            }
+            None => QueryResponse::GetChunk(Err(StorageError::ChunkNotFound(*address).into())),
+        }
+    }
+
+    // Tells whether the chunk at the given address is held in our local record store,
+    // without reading it.
+    pub(super) fn chunk_exists(&mut self, address: &ChunkAddress) -> QueryResponse {
+        let key = RecordKey::new(address.name());
+        if self
+            .swarm
+            .behaviour_mut()
+            .kademlia
+            .store_mut()
+            .contains(&key)
+        {
+            QueryResponse::ChunkExists(Ok(()))
+        } else {
+            QueryResponse::ChunkExists(Err(StorageError::ChunkNotFound(*address).into()))
+        }
+    }
 }
@@ -38,9 +38,15 @@ pub enum Error {
     #[error("Outgoing response has been dropped due to a conn being closed or timeout: {0}")]
     OutgoingResponseDropped(Response),
 
+    #[error("The request has no dst address to find the closest peers to")]
+    NoRequestDst,
+
     #[error("I/O error: {0}")]
     Io(#[from] io::Error),
 
+    #[error("Noise Error: {0}")]
+    NoiseError(#[from] libp2p::noise::Error),
+
     #[error("Transport Error")]
     TransportError(#[from] TransportError<std::io::Error>),
 
@@ -71,6 +77,24 @@ pub enum Error {
     #[error("Could not get enough peers ({required}) to satisfy the request, found {found}")]
     NotEnoughPeers { found: usize, required: usize },
 
+    #[error("Kademlia has no known peers to bootstrap from: {0}")]
+    NoKnownPeers(#[from] kad::NoKnownPeers),
+
+    #[error("Kademlia bootstrap query failed: {0}")]
+    BootstrapFailed(#[from] kad::BootstrapError),
+
     #[error("Record was not found locally")]
     RecordNotFound,
+
+    #[error("Too many Kademlia queries are pending, try again once some of them complete")]
+    TooManyPendingQueries,
+
+    #[error("Record was stored by {got} peer/s, fewer than the {needed} required")]
+    InsufficientReplication { got: usize, needed: usize },
+
+    #[error("Failed to sign the response: {0}")]
+    ResponseSigning(String),
+
+    #[error("The response received from peer {0:?} has an invalid signature")]
+    InvalidResponseSignature(libp2p::PeerId),
 }
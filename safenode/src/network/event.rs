@@ -7,35 +7,42 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 use super::{
+    dial_addr,
     error::{Error, Result},
+    is_relayed,
     msg::MsgCodec,
-    SwarmDriver,
+    ClosestPeersRequester, SwarmDriver,
 };
 use crate::{
     domain::storage::DiskBackedRecordStore,
     network::IDENTIFY_AGENT_STR,
     protocol::{
+        error::Error as ProtocolError,
         messages::{QueryResponse, Request, Response},
-        storage::Chunk,
+        storage::{registers::EntryHash, Chunk, RegisterAddress},
         NetworkAddress,
     },
 };
 
 #[cfg(feature = "local-discovery")]
-use libp2p::mdns;
+use libp2p::{mdns, swarm::behaviour::toggle::Toggle};
 
 use libp2p::{
-    kad::{GetRecordOk, Kademlia, KademliaEvent, QueryResult, K_VALUE},
-    multiaddr::Protocol,
+    connection_limits,
+    core::ConnectedPoint,
+    kad::{
+        GetRecordError, GetRecordOk, InboundRequest, Kademlia, KademliaEvent, PutRecordError,
+        PutRecordOk, QueryId, QueryResult, K_VALUE,
+    },
     request_response::{self, ResponseChannel as PeerResponseChannel},
-    swarm::{NetworkBehaviour, SwarmEvent},
+    swarm::{ListenError, NetworkBehaviour, SwarmEvent},
     Multiaddr, PeerId,
 };
 #[cfg(feature = "local-discovery")]
 use std::collections::hash_map;
-use std::collections::HashSet;
+use std::{collections::HashSet, time::Instant};
 use tokio::sync::oneshot;
-use tracing::{info, warn};
+use tracing::{field, info, warn, Span};
 
 // Threshold of times of `OutgoingConnectionError` detected within the period.
 // If higher than this number of times detected,
@@ -45,11 +52,13 @@ const DEAD_PEER_DETECTION_THRESHOLD: usize = 3;
 #[derive(NetworkBehaviour)]
 #[behaviour(out_event = "NodeEvent")]
 pub(super) struct NodeBehaviour {
+    pub(super) connection_limits: connection_limits::Behaviour,
     pub(super) request_response: request_response::Behaviour<MsgCodec>,
     pub(super) kademlia: Kademlia<DiskBackedRecordStore>,
     #[cfg(feature = "local-discovery")]
-    pub(super) mdns: mdns::tokio::Behaviour,
+    pub(super) mdns: Toggle<mdns::tokio::Behaviour>,
     pub(super) identify: libp2p::identify::Behaviour,
+    pub(super) relay_client: libp2p::relay::client::Behaviour,
 }
 
 #[derive(Debug)]
@@ -59,6 +68,14 @@ pub(super) enum NodeEvent {
     #[cfg(feature = "local-discovery")]
     Mdns(Box<mdns::Event>),
     Identify(Box<libp2p::identify::Event>),
+    Relay(Box<libp2p::relay::client::Event>),
+}
+
+// The connection limits behaviour never emits any event.
+impl From<void::Void> for NodeEvent {
+    fn from(event: void::Void) -> Self {
+        void::unreachable(event)
+    }
 }
 
 impl From<request_response::Event<Request, Response>> for NodeEvent {
@@ -86,6 +103,12 @@ impl From<libp2p::identify::Event> for NodeEvent {
     }
 }
 
+impl From<libp2p::relay::client::Event> for NodeEvent {
+    fn from(event: libp2p::relay::client::Event) -> Self {
+        NodeEvent::Relay(Box::new(event))
+    }
+}
+
 #[derive(Debug)]
 /// Channel to send the `Response` through.
 pub enum MsgResponder {
@@ -107,8 +130,150 @@ pub enum NetworkEvent {
     },
     /// Emitted when the DHT is updated
     PeerAdded(PeerId),
+    /// Emitted when peers are removed from the DHT, e.g. once the link to them is gone
+    PeersRemoved(Vec<PeerId>),
+    /// Emitted when a peer is evicted from a full bucket of the DHT, replaced by a new one
+    PeerEvicted(PeerId),
+    /// Emitted when a new peer is added to the DHT, which may now be among the closest peers to
+    /// some of the data we hold, so the data it's now responsible for can be pushed to it
+    ReplicationCandidate {
+        /// The peer newly added
+        peer: PeerId,
+    },
+    /// Started listening on a new address
+    NewListenAddr(Multiaddr),
+    /// New edits were applied to a Register we hold, upon its replication
+    RegisterUpdated {
+        /// Address of the Register
+        address: RegisterAddress,
+        /// Hashes of the entries newly applied
+        new_entries: Vec<EntryHash>,
+    },
+    /// A Kademlia request was received from a peer, and served by the DHT itself
+    InboundDhtRequest {
+        /// The kind of request served
+        kind: DhtRequestKind,
+    },
+}
+
+/// The `NetworkEvent`s as broadcast to the subscribers of the `Network`.
+/// The channel to respond to a request through is left out, since only
+/// the main events receiver is in charge of responding to it.
+#[derive(Clone, Debug)]
+pub enum ObservedNetworkEvent {
+    /// Incoming `Request` from a peer
+    RequestReceived(Request),
+    /// Emitted when the DHT is updated
+    PeerAdded(PeerId),
+    /// Emitted when peers are removed from the DHT, e.g. once the link to them is gone
+    PeersRemoved(Vec<PeerId>),
+    /// Emitted when a peer is evicted from a full bucket of the DHT, replaced by a new one
+    PeerEvicted(PeerId),
+    /// Emitted when a new peer is added to the DHT, which may now be among the closest peers to
+    /// some of the data we hold, so the data it's now responsible for can be pushed to it
+    ReplicationCandidate {
+        /// The peer newly added
+        peer: PeerId,
+    },
     /// Started listening on a new address
     NewListenAddr(Multiaddr),
+    /// New edits were applied to a Register we hold, upon its replication
+    RegisterUpdated {
+        /// Address of the Register
+        address: RegisterAddress,
+        /// Hashes of the entries newly applied
+        new_entries: Vec<EntryHash>,
+    },
+    /// A Kademlia request was received from a peer, and served by the DHT itself
+    InboundDhtRequest {
+        /// The kind of request served
+        kind: DhtRequestKind,
+    },
+}
+
+impl From<&NetworkEvent> for ObservedNetworkEvent {
+    fn from(event: &NetworkEvent) -> Self {
+        match event {
+            NetworkEvent::RequestReceived { req, .. } => Self::RequestReceived(req.clone()),
+            NetworkEvent::PeerAdded(peer_id) => Self::PeerAdded(*peer_id),
+            NetworkEvent::PeersRemoved(peer_ids) => Self::PeersRemoved(peer_ids.clone()),
+            NetworkEvent::PeerEvicted(peer_id) => Self::PeerEvicted(*peer_id),
+            NetworkEvent::ReplicationCandidate { peer } => {
+                Self::ReplicationCandidate { peer: *peer }
+            }
+            NetworkEvent::NewListenAddr(addr) => Self::NewListenAddr(addr.clone()),
+            NetworkEvent::RegisterUpdated {
+                address,
+                new_entries,
+            } => Self::RegisterUpdated {
+                address: *address,
+                new_entries: new_entries.clone(),
+            },
+            NetworkEvent::InboundDhtRequest { kind } => Self::InboundDhtRequest { kind: *kind },
+        }
+    }
+}
+
+/// The kinds of Kademlia requests peers can send us, which are served by the DHT itself.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum DhtRequestKind {
+    /// Request for the closest peers to a key.
+    FindNode,
+    /// Request for the providers of a key.
+    GetProvider,
+    /// Request to add a provider of a key.
+    AddProvider,
+    /// Request for a record.
+    GetRecord,
+    /// Request to store a record.
+    PutRecord,
+}
+
+impl From<&InboundRequest> for DhtRequestKind {
+    fn from(request: &InboundRequest) -> Self {
+        match request {
+            InboundRequest::FindNode { .. } => Self::FindNode,
+            InboundRequest::GetProvider { .. } => Self::GetProvider,
+            InboundRequest::AddProvider { .. } => Self::AddProvider,
+            InboundRequest::GetRecord { .. } => Self::GetRecord,
+            InboundRequest::PutRecord { .. } => Self::PutRecord,
+        }
+    }
+}
+
+/// Creates the span the handling of a swarm event is instrumented with. It carries our own
+/// `PeerId`, and the remote peer's one when known, as structured fields of the span.
+pub(super) fn swarm_event_span<EventError>(
+    local_peer_id: PeerId,
+    event: &SwarmEvent<NodeEvent, EventError>,
+) -> Span {
+    let span = info_span!(
+        "swarm_event",
+        local_peer_id = %local_peer_id,
+        peer_id = field::Empty
+    );
+    if let Some(peer_id) = remote_peer_id(event) {
+        let _ = span.record("peer_id", field::display(peer_id));
+    }
+    span
+}
+
+// Returns the `PeerId` of the remote peer the given swarm event relates to, if any.
+fn remote_peer_id<EventError>(event: &SwarmEvent<NodeEvent, EventError>) -> Option<PeerId> {
+    match event {
+        SwarmEvent::Behaviour(NodeEvent::MsgReceived(request_response::Event::Message {
+            peer,
+            ..
+        }))
+        | SwarmEvent::Behaviour(NodeEvent::Kademlia(KademliaEvent::RoutingUpdated {
+            peer, ..
+        })) => Some(*peer),
+        SwarmEvent::ConnectionEstablished { peer_id, .. }
+        | SwarmEvent::ConnectionClosed { peer_id, .. }
+        | SwarmEvent::Dialing(peer_id) => Some(*peer_id),
+        SwarmEvent::OutgoingConnectionError { peer_id, .. } => *peer_id,
+        _ => None,
+    }
 }
 
 impl SwarmDriver {
@@ -117,11 +282,15 @@ impl SwarmDriver {
         &mut self,
         event: SwarmEvent<NodeEvent, EventError>,
     ) -> Result<()> {
-        let span = info_span!("Handling a swarm event");
-        let _ = span.enter();
+        self.metrics.record(&event);
         match event {
             SwarmEvent::Behaviour(NodeEvent::MsgReceived(event)) => {
-                if let Err(e) = self.handle_msg(event).await {
+                if let request_response::Event::Message { peer, .. }
+                | request_response::Event::ResponseSent { peer, .. } = &event
+                {
+                    self.idle_peers.record_activity(*peer, Instant::now());
+                }
+                if let Err(e) = self.handle_msg(event) {
                     warn!("MsgReceivedError: {e:?}");
                 }
             }
@@ -134,7 +303,7 @@ impl SwarmDriver {
                 } => {
                     trace!("Query task {id:?} returned with peers {closest_peers:?}, {stats:?} - {step:?}");
 
-                    let (sender, mut current_closest) =
+                    let (requester, mut current_closest) =
                         self.pending_get_closest_peers.remove(id).ok_or_else(|| {
                             trace!("Can't locate query task {id:?}, shall be completed already.");
                             Error::ReceivedKademliaEventDropped(event.clone())
@@ -148,13 +317,11 @@ impl SwarmDriver {
                         closest_peers.peers.clone().into_iter().collect();
                     current_closest.extend(new_peers);
                     if current_closest.len() >= usize::from(K_VALUE) || step.last {
-                        sender
-                            .send(current_closest)
-                            .map_err(|_| Error::InternalMsgChannelDropped)?;
+                        self.complete_closest_peers_query(requester, current_closest)?;
                     } else {
                         let _ = self
                             .pending_get_closest_peers
-                            .insert(*id, (sender, current_closest));
+                            .insert(*id, (requester, current_closest));
                     }
                 }
                 KademliaEvent::OutboundQueryProgressed {
@@ -164,44 +331,80 @@ impl SwarmDriver {
                     step,
                 } => {
                     trace!("Record query task {id:?} returned with result, {stats:?} - {step:?}");
-                    if let Ok(GetRecordOk::FoundRecord(peer_record)) = result {
-                        trace!(
-                            "Query {id:?} returned with record {:?} from peer {:?}",
-                            peer_record.record.key,
-                            peer_record.peer
-                        );
-                        if let Some(sender) = self.pending_query.remove(id) {
+                    self.handle_get_record_result(*id, result, step.last)?;
+                }
+                KademliaEvent::OutboundQueryProgressed {
+                    id,
+                    result: QueryResult::PutRecord(result),
+                    stats,
+                    step,
+                } => {
+                    trace!("Put record task {id:?} returned with result, {stats:?} - {step:?}");
+                    self.handle_put_record_result(*id, result)?;
+                }
+                KademliaEvent::OutboundQueryProgressed {
+                    id,
+                    result: QueryResult::Bootstrap(result),
+                    stats,
+                    step,
+                } => {
+                    trace!("Bootstrap query task {id:?} progressed with {result:?}, {stats:?} - {step:?}");
+                    // The query is finished once the buckets have all been refreshed
+                    if step.last {
+                        if let Some(sender) = self.pending_bootstrap.remove(id) {
+                            let result = result.clone().map(|_| ()).map_err(Error::from);
                             sender
-                                .send(Ok(QueryResponse::GetChunk(Ok(Chunk::new(
-                                    peer_record.record.value.clone().into(),
-                                )))))
+                                .send(result)
                                 .map_err(|_| Error::InternalMsgChannelDropped)?;
                         }
-                    } else {
-                        warn!("Query {id:?} failed to get record with result {result:?}");
-                        if step.last {
-                            // To avoid the caller wait forever on a non-existring entry
-                            if let Some(sender) = self.pending_query.remove(id) {
-                                sender
-                                    .send(Err(Error::RecordNotFound))
-                                    .map_err(|_| Error::InternalMsgChannelDropped)?;
-                            }
-                        }
-                        // TODO: send an error response back?
+                    }
+                }
+                KademliaEvent::OutboundQueryProgressed {
+                    id,
+                    result: QueryResult::GetProviders(result),
+                    stats,
+                    step,
+                } => {
+                    debug!("GetProviders query task {id:?} progressed with {result:?}, {stats:?} - {step:?}");
+                    if step.last {
+                        self.complete_pending_query(id)?;
+                    }
+                }
+                KademliaEvent::OutboundQueryProgressed {
+                    id,
+                    result: QueryResult::StartProviding(result),
+                    stats,
+                    step,
+                } => {
+                    debug!("StartProviding query task {id:?} progressed with {result:?}, {stats:?} - {step:?}");
+                    if step.last {
+                        self.complete_pending_query(id)?;
                     }
                 }
                 KademliaEvent::RoutingUpdated {
-                    peer, is_new_peer, ..
+                    peer,
+                    is_new_peer,
+                    old_peer,
+                    ..
                 } => {
                     if *is_new_peer {
-                        self.event_sender
-                            .send(NetworkEvent::PeerAdded(*peer))
+                        self.send_event(NetworkEvent::PeerAdded(*peer)).await?;
+                        self.send_event(NetworkEvent::ReplicationCandidate { peer: *peer })
                             .await?;
                         self.try_trigger_replication(peer);
                     }
+                    if let Some(old_peer) = old_peer {
+                        debug!("Peer {old_peer:?} evicted from the routing table by {peer:?}");
+                        self.send_event(NetworkEvent::PeerEvicted(*old_peer))
+                            .await?;
+                    }
                 }
                 KademliaEvent::InboundRequest { request } => {
                     info!("got inbound request: {request:?}");
+                    self.send_event(NetworkEvent::InboundDhtRequest {
+                        kind: request.into(),
+                    })
+                    .await?;
                 }
                 todo => {
                     error!("KademliaEvent has not been implemented: {todo:?}");
@@ -241,10 +444,7 @@ impl SwarmDriver {
                             let (sender, _receiver) = oneshot::channel();
                             let _ = dial_entry.insert(sender);
                             // TODO: Dropping the receiver immediately might get logged as error later.
-                            if let Err(error) = self
-                                .swarm
-                                .dial(multiaddr.with(Protocol::P2p(peer_id.into())))
-                            {
+                            if let Err(error) = self.swarm.dial(dial_addr(multiaddr, peer_id)) {
                                 dial_failed = Some(error);
                             }
                         }
@@ -263,18 +463,37 @@ impl SwarmDriver {
             },
             SwarmEvent::NewListenAddr { address, .. } => {
                 let local_peer_id = *self.swarm.local_peer_id();
-                let address = address.with(Protocol::P2p(local_peer_id.into()));
-                self.event_sender
-                    .send(NetworkEvent::NewListenAddr(address.clone()))
+                let address = dial_addr(address, local_peer_id);
+                self.send_event(NetworkEvent::NewListenAddr(address.clone()))
                     .await?;
                 info!("Local node is listening on {address:?}");
             }
-            SwarmEvent::IncomingConnection { .. } => {}
+            SwarmEvent::IncomingConnection { send_back_addr, .. } => {
+                trace!("Incoming connection from {send_back_addr:?}");
+            }
+            SwarmEvent::Behaviour(NodeEvent::Relay(event)) => {
+                info!("RelayClientEvent: {event:?}");
+            }
             SwarmEvent::ConnectionEstablished {
                 peer_id, endpoint, ..
             } => {
+                if self.recently_closed_peers.reconnected(&peer_id) {
+                    debug!("Peer {peer_id:?} reconnected within the grace period, it's kept in the routing table");
+                }
+                self.idle_peers.record_activity(peer_id, Instant::now());
                 if endpoint.is_dialer() {
                     info!("Connected with {peer_id:?}");
+                    // A peer reached through a relay may not be reachable otherwise,
+                    // so we keep the relayed address it was reached at.
+                    let addr = endpoint.get_remote_address();
+                    if is_relayed(addr) {
+                        info!("Adding relayed address of {peer_id:?} to routing table: {addr:?}");
+                        let _routing_update = self
+                            .swarm
+                            .behaviour_mut()
+                            .kademlia
+                            .add_address(&peer_id, addr.clone());
+                    }
                     if let Some(sender) = self.pending_dial.remove(&peer_id) {
                         let _ = sender.send(Ok(()));
                     }
@@ -283,10 +502,13 @@ impl SwarmDriver {
             SwarmEvent::ConnectionClosed {
                 peer_id,
                 endpoint,
+                num_established,
                 cause,
                 ..
             } => {
                 info!("Connection closed to Peer {peer_id} - {endpoint:?} - {cause:?}");
+                self.handle_connection_closed(peer_id, &endpoint, num_established)
+                    .await?;
 
                 // This is most periodically called due to connection time out.
                 // Hence using it as a point to cleanup the replication cache.
@@ -318,6 +540,13 @@ impl SwarmDriver {
                     }
                 }
             }
+            SwarmEvent::IncomingConnectionError {
+                send_back_addr,
+                error: ListenError::Denied { cause },
+                ..
+            } => {
+                warn!("Rejected incoming connection from {send_back_addr:?}: {cause}");
+            }
             SwarmEvent::IncomingConnectionError { .. } => {}
             SwarmEvent::Dialing(peer_id) => info!("Dialing {peer_id}"),
             todo => error!("SwarmEvent has not been implemented: {todo:?}"),
@@ -325,6 +554,209 @@ impl SwarmDriver {
         Ok(())
     }
 
+    // Completes the pending query with the record found, or with the error
+    // the query failed with, distinguishing when a quorum was not reached.
+    pub(super) fn handle_get_record_result(
+        &mut self,
+        id: QueryId,
+        result: &Result<GetRecordOk, GetRecordError>,
+        last_step: bool,
+    ) -> Result<()> {
+        match result {
+            Ok(GetRecordOk::FoundRecord(peer_record)) => {
+                trace!(
+                    "Query {id:?} returned with record {:?} from peer {:?}",
+                    peer_record.record.key,
+                    peer_record.peer
+                );
+                if let Some(sender) = self.pending_query.remove(&id) {
+                    sender
+                        .send(Ok(QueryResponse::GetChunk(Ok(Chunk::new(
+                            peer_record.record.value.clone().into(),
+                        )))))
+                        .map_err(|_| Error::InternalMsgChannelDropped)?;
+                }
+            }
+            Err(GetRecordError::QuorumFailed { key, quorum, .. }) => {
+                warn!("Query {id:?} failed to reach a quorum of {quorum} for record {key:?}");
+                // Unlike a missing record, the caller may want to retry it.
+                if let Some(sender) = self.pending_query.remove(&id) {
+                    sender
+                        .send(Ok(QueryResponse::GetChunk(Err(
+                            ProtocolError::QuorumFailed,
+                        ))))
+                        .map_err(|_| Error::InternalMsgChannelDropped)?;
+                }
+            }
+            _ => {
+                warn!("Query {id:?} failed to get record with result {result:?}");
+                if last_step {
+                    // To avoid the caller wait forever on a non-existring entry
+                    self.complete_pending_query(&id)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Completes the pending put of a record, reporting whether it was stored
+    // by as many peers as required.
+    pub(super) fn handle_put_record_result(
+        &mut self,
+        id: QueryId,
+        result: &Result<PutRecordOk, PutRecordError>,
+    ) -> Result<()> {
+        let result = match result {
+            Ok(PutRecordOk { key }) => {
+                trace!(
+                    "Record {key:?} stored by at least {} peers",
+                    self.min_replicas
+                );
+                Ok(())
+            }
+            Err(
+                PutRecordError::QuorumFailed {
+                    key,
+                    success,
+                    quorum,
+                }
+                | PutRecordError::Timeout {
+                    key,
+                    success,
+                    quorum,
+                },
+            ) => {
+                warn!(
+                    "Record {key:?} stored by {} peers only, out of the {quorum} required",
+                    success.len()
+                );
+                Err(Error::InsufficientReplication {
+                    got: success.len(),
+                    needed: quorum.get(),
+                })
+            }
+        };
+        if let Some(sender) = self.pending_put_record.remove(&id) {
+            sender
+                .send(result)
+                .map_err(|_| Error::InternalMsgChannelDropped)?;
+        }
+        Ok(())
+    }
+
+    // A connection the peer initiated may be closed for being idle, with no other connection
+    // left to it, in which case the link to it is gone but it would linger in our routing table,
+    // hence the address it connected from is removed from it, along with the peer if it was
+    // its last address. Peers still connected to us through another connection are left alone.
+    pub(super) async fn handle_connection_closed(
+        &mut self,
+        peer_id: PeerId,
+        endpoint: &ConnectedPoint,
+        num_established: u32,
+    ) -> Result<()> {
+        if num_established > 0 {
+            return Ok(());
+        }
+        self.idle_peers.disconnected(&peer_id);
+        if endpoint.is_dialer() {
+            return Ok(());
+        }
+        let addr = endpoint.get_remote_address().clone();
+        if self.recently_closed_peers.removes_immediately() {
+            return self.remove_from_routing_table(vec![(peer_id, addr)]).await;
+        }
+        trace!("Peer {peer_id:?} closed its last connection, it's removed unless it reconnects");
+        self.recently_closed_peers
+            .insert(peer_id, addr, Instant::now());
+        Ok(())
+    }
+
+    // Closes the connections with the peers no msgs were exchanged with within the
+    // idle timeout by `now`.
+    pub(super) fn disconnect_idle_peers(&mut self, now: Instant) {
+        for peer in self.idle_peers.take_idle(now) {
+            debug!("Closing the idle connections with {peer:?}");
+            let _ = self.swarm.disconnect_peer_id(peer);
+        }
+    }
+
+    // Removes the peers which closed their last connection and haven't reconnected
+    // within the grace period by `now`.
+    pub(super) async fn remove_closed_peers(&mut self, now: Instant) -> Result<()> {
+        let expired = self.recently_closed_peers.take_expired(now);
+        self.remove_from_routing_table(expired).await
+    }
+
+    async fn remove_from_routing_table(&mut self, peers: Vec<(PeerId, Multiaddr)>) -> Result<()> {
+        let mut removed = vec![];
+        for (peer_id, addr) in peers {
+            if self
+                .swarm
+                .behaviour_mut()
+                .kademlia
+                .remove_address(&peer_id, &addr)
+                .is_some()
+            {
+                debug!(
+                    "Removed peer {peer_id:?} from the routing table, its last connection closed"
+                );
+                removed.push(peer_id);
+            }
+        }
+        if !removed.is_empty() {
+            self.send_event(NetworkEvent::PeersRemoved(removed)).await?;
+        }
+        Ok(())
+    }
+
+    // Sends the closest peers found by a query to its requester, or, if the query is part
+    // of a batch, sends the closest peers found for all the keys once the last one completes.
+    pub(super) fn complete_closest_peers_query(
+        &mut self,
+        requester: ClosestPeersRequester,
+        closest_peers: HashSet<PeerId>,
+    ) -> Result<()> {
+        let (batch_id, key) = match requester {
+            ClosestPeersRequester::Single(sender) => {
+                return sender
+                    .send(Ok(closest_peers))
+                    .map_err(|_| Error::InternalMsgChannelDropped);
+            }
+            ClosestPeersRequester::Batch { batch_id, key } => (batch_id, key),
+        };
+
+        let batch = match self.pending_get_closest_peers_batches.get_mut(&batch_id) {
+            Some(batch) => batch,
+            None => {
+                trace!("Can't locate closest peers batch {batch_id}, shall be completed already.");
+                return Ok(());
+            }
+        };
+        let _ = batch.found.insert(key, closest_peers);
+        batch.remaining = batch.remaining.saturating_sub(1);
+        if batch.remaining > 0 {
+            return Ok(());
+        }
+        if let Some(batch) = self.pending_get_closest_peers_batches.remove(&batch_id) {
+            batch
+                .sender
+                .send(Ok(batch.found))
+                .map_err(|_| Error::InternalMsgChannelDropped)?;
+        }
+        Ok(())
+    }
+
+    // Completes the pending query, if any, of a finished query which carried no record,
+    // so the caller doesn't wait forever on it.
+    pub(super) fn complete_pending_query(&mut self, id: &QueryId) -> Result<()> {
+        if let Some(sender) = self.pending_query.remove(id) {
+            sender
+                .send(Err(Error::RecordNotFound))
+                .map_err(|_| Error::InternalMsgChannelDropped)?;
+        }
+        Ok(())
+    }
+
     fn try_trigger_replication(&mut self, peer: &PeerId) {
         // Replication is triggered when the newly added peer is among our closest,
         // or the dead peer was among our closest.
@@ -0,0 +1,63 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use libp2p::PeerId;
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+/// Tracks when msgs were last exchanged with each connected peer, so the connections with no
+/// msg flow for longer than the idle timeout are closed by the swarm itself, rather than being
+/// kept alive indefinitely by the periodic traffic of protocols like identify.
+#[derive(Debug)]
+pub(super) struct IdlePeers {
+    // How long a peer can go without msgs exchanged before its connections are closed.
+    idle_timeout: Duration,
+    // The connected peers, along with when msgs were last exchanged with them.
+    last_activity: HashMap<PeerId, Instant>,
+}
+
+impl IdlePeers {
+    pub(super) fn new(idle_timeout: Duration) -> Self {
+        Self {
+            idle_timeout,
+            last_activity: HashMap::new(),
+        }
+    }
+
+    pub(super) fn is_empty(&self) -> bool {
+        self.last_activity.is_empty()
+    }
+
+    /// Records msgs were exchanged with the peer, or a connection established with it, at `now`.
+    pub(super) fn record_activity(&mut self, peer: PeerId, now: Instant) {
+        let _ = self.last_activity.insert(peer, now);
+    }
+
+    /// Stops tracking the peer as its last connection closed.
+    pub(super) fn disconnected(&mut self, peer: &PeerId) {
+        let _ = self.last_activity.remove(peer);
+    }
+
+    /// Takes out the peers with no msgs exchanged within the idle timeout by `now`.
+    pub(super) fn take_idle(&mut self, now: Instant) -> Vec<PeerId> {
+        let idle_timeout = self.idle_timeout;
+        let idle: Vec<PeerId> = self
+            .last_activity
+            .iter()
+            .filter(|(_, last_active)| now.saturating_duration_since(**last_active) >= idle_timeout)
+            .map(|(peer, _)| *peer)
+            .collect();
+
+        for peer in &idle {
+            let _ = self.last_activity.remove(peer);
+        }
+        idle
+    }
+}
@@ -0,0 +1,133 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::event::NodeEvent;
+
+#[cfg(feature = "local-discovery")]
+use libp2p::mdns;
+
+use libp2p::{
+    kad::{GetRecordOk, KademliaEvent, QueryResult},
+    request_response,
+    swarm::SwarmEvent,
+};
+
+/// Counters of the swarm events handled by the `SwarmDriver`, by their type,
+/// giving a picture of what the node has been busy with.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SwarmMetrics {
+    /// Number of requests received from peers.
+    pub requests_received: usize,
+    /// Number of responses received from peers.
+    pub responses_received: usize,
+    /// Number of requests, to or from peers, which failed.
+    pub request_failures: usize,
+    /// Number of connections established with peers.
+    pub connections_established: usize,
+    /// Number of connections to peers closed.
+    pub connections_closed: usize,
+    /// Number of failures to connect to peers, whether we dialed them or they dialed us.
+    pub connection_errors: usize,
+    /// Number of peers dialed.
+    pub dials: usize,
+    /// Number of new addresses listened on.
+    pub new_listen_addrs: usize,
+    /// Number of peers newly added to the routing table.
+    pub peers_added: usize,
+    /// Number of peers evicted from the routing table.
+    pub peers_evicted: usize,
+    /// Number of peers discovered through mDNS.
+    pub mdns_discovered: usize,
+    /// Number of peers discovered through mDNS which expired.
+    pub mdns_expired: usize,
+    /// Number of records found by our record queries.
+    pub records_found: usize,
+    /// Number of record queries which failed to find the record.
+    pub records_missed: usize,
+    /// Number of records put to the network.
+    pub records_put: usize,
+    /// Number of records which failed to be put to the network.
+    pub records_put_failed: usize,
+    /// Number of closest peers queries completed.
+    pub closest_peers_queries: usize,
+    /// Number of Kademlia requests received from peers.
+    pub inbound_dht_requests: usize,
+    /// Number of identify infos received from peers.
+    pub identify_received: usize,
+    /// Number of any other events.
+    pub other_events: usize,
+}
+
+impl SwarmMetrics {
+    // Increments the counter(s) of the given swarm event's type.
+    pub(super) fn record<EventError>(&mut self, event: &SwarmEvent<NodeEvent, EventError>) {
+        match event {
+            SwarmEvent::Behaviour(NodeEvent::MsgReceived(event)) => match event {
+                request_response::Event::Message {
+                    message: request_response::Message::Request { .. },
+                    ..
+                } => self.requests_received += 1,
+                request_response::Event::Message {
+                    message: request_response::Message::Response { .. },
+                    ..
+                } => self.responses_received += 1,
+                request_response::Event::OutboundFailure { .. }
+                | request_response::Event::InboundFailure { .. } => self.request_failures += 1,
+                request_response::Event::ResponseSent { .. } => self.other_events += 1,
+            },
+            SwarmEvent::Behaviour(NodeEvent::Kademlia(event)) => self.record_kademlia(event),
+            #[cfg(feature = "local-discovery")]
+            SwarmEvent::Behaviour(NodeEvent::Mdns(event)) => match event.as_ref() {
+                mdns::Event::Discovered(list) => self.mdns_discovered += list.len(),
+                mdns::Event::Expired(list) => self.mdns_expired += list.len(),
+            },
+            SwarmEvent::Behaviour(NodeEvent::Identify(event)) => {
+                if let libp2p::identify::Event::Received { .. } = event.as_ref() {
+                    self.identify_received += 1;
+                } else {
+                    self.other_events += 1;
+                }
+            }
+            SwarmEvent::ConnectionEstablished { .. } => self.connections_established += 1,
+            SwarmEvent::ConnectionClosed { .. } => self.connections_closed += 1,
+            SwarmEvent::OutgoingConnectionError { .. }
+            | SwarmEvent::IncomingConnectionError { .. } => self.connection_errors += 1,
+            SwarmEvent::Dialing(_) => self.dials += 1,
+            SwarmEvent::NewListenAddr { .. } => self.new_listen_addrs += 1,
+            _ => self.other_events += 1,
+        }
+    }
+
+    fn record_kademlia(&mut self, event: &KademliaEvent) {
+        match event {
+            KademliaEvent::OutboundQueryProgressed { result, step, .. } => match result {
+                QueryResult::GetRecord(Ok(GetRecordOk::FoundRecord(_))) => self.records_found += 1,
+                // A failed query is only reported once, upon its last step.
+                QueryResult::GetRecord(Err(_)) if step.last => self.records_missed += 1,
+                QueryResult::PutRecord(Ok(_)) => self.records_put += 1,
+                QueryResult::PutRecord(Err(_)) => self.records_put_failed += 1,
+                QueryResult::GetClosestPeers(_) if step.last => self.closest_peers_queries += 1,
+                _ => self.other_events += 1,
+            },
+            KademliaEvent::RoutingUpdated {
+                is_new_peer,
+                old_peer,
+                ..
+            } => {
+                if *is_new_peer {
+                    self.peers_added += 1;
+                }
+                if old_peer.is_some() {
+                    self.peers_evicted += 1;
+                }
+            }
+            KademliaEvent::InboundRequest { .. } => self.inbound_dht_requests += 1,
+            _ => self.other_events += 1,
+        }
+    }
+}
@@ -6,57 +6,83 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
+mod circuit_breaker;
+mod closed_peers;
 mod cmd;
 mod error;
 mod event;
+mod idle_peers;
+mod metrics;
 mod msg;
+mod request_queue;
 
 pub use self::{
     cmd::SwarmLocalState,
     error::Error,
-    event::{MsgResponder, NetworkEvent},
+    event::{DhtRequestKind, MsgResponder, NetworkEvent, ObservedNetworkEvent},
+    metrics::SwarmMetrics,
+    request_queue::RequestWeights,
 };
 
 use self::{
+    circuit_breaker::PeerCircuitBreaker,
+    closed_peers::RecentlyClosedPeers,
     cmd::SwarmCmd,
     error::Result,
-    event::NodeBehaviour,
+    event::{swarm_event_span, NodeBehaviour},
+    idle_peers::IdlePeers,
     msg::{MsgCodec, MsgProtocol},
+    request_queue::InboundRequestQueue,
 };
 
+pub(crate) use self::msg::DEFAULT_MAX_MSG_SIZE;
+
 use crate::domain::storage::{
     DiskBackedRecordStore, DiskBackedRecordStoreConfig, REPLICATION_INTERVAL,
 };
 use crate::protocol::{
-    messages::{QueryResponse, Request, Response},
+    messages::{Query, QueryResponse, Request, Response},
+    storage::{registers::EntryHash, ChunkAddress, RegisterAddress},
     NetworkAddress,
 };
 
-use futures::{future::select_all, StreamExt};
+use futures::{
+    future::{join_all, select_all, Either},
+    StreamExt,
+};
 
 #[cfg(feature = "local-discovery")]
 use libp2p::mdns;
 
 use libp2p::{
-    core::muxing::StreamMuxerBox,
+    connection_limits::{self, ConnectionLimits},
+    core::{muxing::StreamMuxerBox, upgrade},
     identity,
     kad::{Kademlia, KademliaConfig, QueryId, Record, RecordKey},
     multiaddr::Protocol,
+    noise, relay,
     request_response::{self, Config as RequestResponseConfig, ProtocolSupport, RequestId},
     swarm::{Swarm, SwarmBuilder},
-    Multiaddr, PeerId, Transport,
+    yamux, Multiaddr, PeerId, Transport,
 };
 use lru_time_cache::LruCache;
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, HashMap, HashSet},
     iter,
     net::SocketAddr,
     num::NonZeroUsize,
     path::{Path, PathBuf},
-    time::Duration,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
 };
-use tokio::sync::{mpsc, oneshot};
-use tracing::warn;
+use tokio::{
+    sync::{broadcast, mpsc, oneshot, Mutex},
+    time::MissedTickBehavior,
+};
+use tracing::{warn, Instrument};
 
 /// The maximum number of peers to return in a `GetClosestPeers` response.
 /// This is the group size used in safe network protocol to be responsible for
@@ -65,8 +91,8 @@ pub(crate) const CLOSE_GROUP_SIZE: usize = 8;
 
 // Timeout for requests sent/received through the request_response behaviour.
 const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
-// Sets the keep-alive timeout of idle connections.
-const CONNECTION_KEEP_ALIVE_TIMEOUT: Duration = Duration::from_secs(10);
+// Default timeout after which connections with no msg flow are closed.
+const IDLE_CONNECTION_TIMEOUT: Duration = Duration::from_secs(10);
 // Duration of the period during which counting the number of times a peer
 // emmits `OutgoingConnectionError` error.
 // This value and the correspendent `DEAD_PEER_DETECTION_THRESHOLD`,
@@ -74,6 +100,18 @@ const CONNECTION_KEEP_ALIVE_TIMEOUT: Duration = Duration::from_secs(10);
 const DEAD_PEER_DETECTION_PERIOD: Duration = Duration::from_secs(10);
 // Number of entries to be held in the dead peer dectection LRU cache.
 const DEAD_PEER_DETECTION_CAPACITY: usize = 50;
+// Interval at which the peers that closed their connection are checked for removal,
+// once they haven't reconnected within the grace period.
+const CLOSED_PEERS_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+// Interval at which the connections with no msg flow for longer than the idle timeout are closed.
+const IDLE_PEERS_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+// Default minimum number of peers a record put to the network is to be stored by.
+const DEFAULT_MIN_REPLICAS: usize = close_group_majority();
+// Default maximum number of Kademlia queries to be in flight at once.
+const DEFAULT_MAX_PENDING_QUERIES: usize = 256;
+// Number of events buffered for each subscriber of the network events,
+// beyond which a lagging subscriber misses the oldest ones.
+const NETWORK_EVENTS_BROADCAST_CAPACITY: usize = 100;
 
 /// Our agent string has as a prefix that we can match against.
 pub const IDENTIFY_AGENT_STR: &str = "safe/node/";
@@ -90,20 +128,78 @@ pub const fn close_group_majority() -> usize {
     CLOSE_GROUP_SIZE / 2 + 1
 }
 
-type PendingGetClosest = HashMap<QueryId, (oneshot::Sender<HashSet<PeerId>>, HashSet<PeerId>)>;
+// Whether the address is that of a peer reached through a relay.
+fn is_relayed(addr: &Multiaddr) -> bool {
+    addr.iter().any(|protocol| protocol == Protocol::P2pCircuit)
+}
+
+// Returns the address to dial the peer at, i.e. ending with its `PeerId`, which relayed
+// addresses may already end with, following the relay's `PeerId` and the circuit.
+fn dial_addr(addr: Multiaddr, peer_id: PeerId) -> Multiaddr {
+    match addr.iter().last() {
+        Some(Protocol::P2p(hash)) if PeerId::from_multihash(hash) == Ok(peer_id) => addr,
+        _ => addr.with(Protocol::P2p(peer_id.into())),
+    }
+}
+
+type PendingGetClosest = HashMap<QueryId, (ClosestPeersRequester, HashSet<PeerId>)>;
+
+type ClosestPeersBatchSender = oneshot::Sender<Result<BTreeMap<NetworkAddress, HashSet<PeerId>>>>;
+
+/// Whom the closest peers found by a `GetClosestPeers` query are to be sent to.
+#[derive(Debug)]
+enum ClosestPeersRequester {
+    /// The requester of a single lookup.
+    Single(oneshot::Sender<Result<HashSet<PeerId>>>),
+    /// One of the lookups of a batch, for the given key, all sent to the requester at once.
+    Batch { batch_id: u64, key: NetworkAddress },
+}
+
+/// A batch of `GetClosestPeers` queries run concurrently, completed once all of them are.
+#[derive(Debug)]
+struct PendingClosestPeersBatch {
+    sender: ClosestPeersBatchSender,
+    // The closest peers found for each of the keys whose query has completed.
+    found: BTreeMap<NetworkAddress, HashSet<PeerId>>,
+    // Number of the queries of the batch yet to complete.
+    remaining: usize,
+}
 
 /// `SwarmDriver` is responsible for managing the swarm of peers, handling
 /// swarm events, processing commands, and maintaining the state of pending
 /// tasks. It serves as the core component for the network functionality.
 pub struct SwarmDriver {
     self_peer_id: PeerId,
+    keypair: identity::Keypair,
+    // Whether the responses sent to peers are signed with our keypair.
+    sign_responses: bool,
+    // Whether the responses received from peers are rejected unless signed by them.
+    require_signed_responses: bool,
     swarm: Swarm<NodeBehaviour>,
     cmd_receiver: mpsc::Receiver<SwarmCmd>,
     event_sender: mpsc::Sender<NetworkEvent>,
+    event_broadcast: broadcast::Sender<ObservedNetworkEvent>,
+    // The requests received from peers waiting to be passed on to the upper layers,
+    // prioritised by their class whenever they come in faster than those handle them.
+    inbound_requests: InboundRequestQueue<NetworkEvent>,
     pending_dial: HashMap<PeerId, oneshot::Sender<Result<()>>>,
     pending_get_closest_peers: PendingGetClosest,
+    pending_get_closest_peers_batches: HashMap<u64, PendingClosestPeersBatch>,
+    next_closest_peers_batch_id: u64,
     pending_requests: HashMap<RequestId, oneshot::Sender<Result<Response>>>,
     pending_query: HashMap<QueryId, oneshot::Sender<Result<QueryResponse>>>,
+    // Maximum number of record and closest peers queries to be in flight at once.
+    max_pending_queries: usize,
+    // Maximum size of the msgs exchanged with peers, shared with the msg codec.
+    max_msg_size: Arc<AtomicUsize>,
+    // Number of records looked up across the network, i.e. not found in our local store.
+    network_record_lookups: usize,
+    // Counters of the swarm events handled, by their type.
+    metrics: SwarmMetrics,
+    pending_put_record: HashMap<QueryId, oneshot::Sender<Result<()>>>,
+    // Minimum number of peers a record put to the network is to be stored by.
+    min_replicas: NonZeroUsize,
+    pending_bootstrap: HashMap<QueryId, oneshot::Sender<Result<()>>>,
     // Kademlia uses a technique called `lazy refreshing` to periodically check
     // the responsiveness of nodes in its routing table, and attempts to
     // replace it with a new node from its list of known nodes.
@@ -120,6 +216,10 @@ pub struct SwarmDriver {
     //        Even with larger network, it still gain something.
     //     2, it ensures a corrected partially targeted replication .
     potential_dead_peers: LruCache<PeerId, usize>,
+    // The peers whose last connection closed, removed unless they reconnect in time.
+    recently_closed_peers: RecentlyClosedPeers,
+    // The connected peers, closed once no msgs are exchanged with them for the idle timeout.
+    idle_peers: IdlePeers,
 }
 
 impl SwarmDriver {
@@ -139,6 +239,36 @@ impl SwarmDriver {
     pub fn new(
         addr: SocketAddr,
         root_dir: &Path,
+    ) -> Result<(Network, mpsc::Receiver<NetworkEvent>, Self)> {
+        Self::with_idle_connection_timeout(addr, root_dir, IDLE_CONNECTION_TIMEOUT)
+    }
+
+    /// Same as `new` API but without discovering peers on the local network through mDNS,
+    /// relying solely on the bootstrap peers, e.g. for deployments reachable over the
+    /// internet only, where LAN discovery is just noise, or a privacy concern.
+    pub fn without_mdns(
+        addr: SocketAddr,
+        root_dir: &Path,
+    ) -> Result<(Network, mpsc::Receiver<NetworkEvent>, Self)> {
+        Self::listening_on(addr, root_dir, IDLE_CONNECTION_TIMEOUT, false)
+    }
+
+    /// Same as `new` API but closes the connections which have been idle,
+    /// i.e. with no msg flow, for longer than the provided `idle_connection_timeout`.
+    pub fn with_idle_connection_timeout(
+        addr: SocketAddr,
+        root_dir: &Path,
+        idle_connection_timeout: Duration,
+    ) -> Result<(Network, mpsc::Receiver<NetworkEvent>, Self)> {
+        Self::listening_on(addr, root_dir, idle_connection_timeout, true)
+    }
+
+    // Private helper to create the network components of a node listening on the provided address.
+    fn listening_on(
+        addr: SocketAddr,
+        root_dir: &Path,
+        idle_connection_timeout: Duration,
+        enable_mdns: bool,
     ) -> Result<(Network, mpsc::Receiver<NetworkEvent>, Self)> {
         let mut kad_cfg = KademliaConfig::default();
         let _ = kad_cfg
@@ -159,8 +289,13 @@ impl SwarmDriver {
             // Records never expire
             .set_record_ttl(None);
 
-        let (network, events_receiver, mut swarm_driver) =
-            Self::with(kad_cfg, false, Some(root_dir.join("record_store")))?;
+        let (network, events_receiver, mut swarm_driver) = Self::with(
+            kad_cfg,
+            false,
+            Some(root_dir.join("record_store")),
+            idle_connection_timeout,
+            enable_mdns,
+        )?;
 
         // Listen on the provided address
         let addr = Multiaddr::from(addr.ip())
@@ -190,14 +325,16 @@ impl SwarmDriver {
                 NonZeroUsize::new(CLOSE_GROUP_SIZE).ok_or_else(|| Error::InvalidCloseGroupSize)?,
             );
 
-        Self::with(kad_cfg, true, None)
+        Self::with(kad_cfg, true, None, IDLE_CONNECTION_TIMEOUT, true)
     }
 
     // Private helper to create the network components with the provided config and req/res behaviour
     fn with(
-        kad_cfg: KademliaConfig,
+        mut kad_cfg: KademliaConfig,
         is_client: bool,
         disk_store_path: Option<PathBuf>,
+        idle_connection_timeout: Duration,
+        enable_mdns: bool,
     ) -> Result<(Network, mpsc::Receiver<NetworkEvent>, Self)> {
         // Create a random key for ourself.
         let keypair = identity::Keypair::generate_ed25519();
@@ -206,11 +343,12 @@ impl SwarmDriver {
         info!("Node (PID: {}) with PeerId: {peer_id}", std::process::id());
 
         // RequestResponse Behaviour
+        let max_msg_size = Arc::new(AtomicUsize::new(DEFAULT_MAX_MSG_SIZE));
         let request_response = {
             let mut cfg = RequestResponseConfig::default();
             let _ = cfg
                 .set_request_timeout(REQUEST_TIMEOUT)
-                .set_connection_keep_alive(CONNECTION_KEEP_ALIVE_TIMEOUT);
+                .set_connection_keep_alive(idle_connection_timeout);
 
             let req_res_protocol = if is_client {
                 ProtocolSupport::Outbound
@@ -218,7 +356,7 @@ impl SwarmDriver {
                 ProtocolSupport::Full
             };
             request_response::Behaviour::new(
-                MsgCodec(),
+                MsgCodec::new(max_msg_size.clone()),
                 iter::once((MsgProtocol(), req_res_protocol)),
                 cfg,
             )
@@ -229,6 +367,7 @@ impl SwarmDriver {
             // Configures the disk_store to store records under the provided path and increase the max record size
             let storage_dir = disk_store_path.unwrap_or(std::env::temp_dir());
             std::fs::create_dir_all(&storage_dir)?;
+            let _ = kad_cfg.set_connection_idle_timeout(idle_connection_timeout);
             let store_cfg = DiskBackedRecordStoreConfig {
                 max_value_bytes: 1024 * 1024,
                 storage_dir,
@@ -251,8 +390,17 @@ impl SwarmDriver {
             ..Default::default()
         };
 
+        // mDNS is left disabled when not enabled, which is then never polled for any events.
         #[cfg(feature = "local-discovery")]
-        let mdns = mdns::tokio::Behaviour::new(mdns_config, peer_id)?;
+        let mdns = if enable_mdns {
+            Some(mdns::tokio::Behaviour::new(mdns_config, peer_id)?)
+        } else {
+            info!("Peer discovery through mDNS is disabled");
+            None
+        }
+        .into();
+        #[cfg(not(feature = "local-discovery"))]
+        let _ = enable_mdns;
 
         // Identify Behaviour
         let identify = {
@@ -266,20 +414,39 @@ impl SwarmDriver {
             libp2p::identify::Behaviour::new(cfg)
         };
 
+        // Relay client Behaviour, along with the transport to reach and be reached by
+        // peers through a relay, e.g. when they are behind a NAT
+        let (relay_transport, relay_client) = relay::client::new(peer_id);
+
         // Transport
         let transport = {
             // use the QUIC Protocol for transport
             let quic_config = libp2p_quic::Config::new(&keypair);
-            let transport = libp2p_quic::tokio::Transport::new(quic_config);
+            let transport = libp2p_quic::tokio::Transport::new(quic_config)
+                .map(|(peer_id, muxer), _| (peer_id, StreamMuxerBox::new(muxer)));
+
+            // connections relayed through a peer are to be secured and multiplexed on top of it
+            let relay_transport = relay_transport
+                .upgrade(upgrade::Version::V1)
+                .authenticate(noise::Config::new(&keypair)?)
+                .multiplex(yamux::Config::default())
+                .map(|(peer_id, muxer), _| (peer_id, StreamMuxerBox::new(muxer)));
+
             transport
-                .map(|(peer_id, muxer), _| (peer_id, StreamMuxerBox::new(muxer)))
+                .or_transport(relay_transport)
+                .map(|output, _| match output {
+                    Either::Left(output) | Either::Right(output) => output,
+                })
                 .boxed()
         };
 
         let behaviour = NodeBehaviour {
+            // no limits until they're set
+            connection_limits: connection_limits::Behaviour::new(ConnectionLimits::default()),
             request_response,
             kademlia,
             identify,
+            relay_client,
             #[cfg(feature = "local-discovery")]
             mdns,
         };
@@ -287,31 +454,149 @@ impl SwarmDriver {
 
         let (swarm_cmd_sender, swarm_cmd_receiver) = mpsc::channel(100);
         let (network_event_sender, network_event_receiver) = mpsc::channel(100);
+        let (event_broadcast, _) = broadcast::channel(NETWORK_EVENTS_BROADCAST_CAPACITY);
         let swarm_driver = Self {
             self_peer_id: peer_id,
+            keypair,
+            sign_responses: false,
+            require_signed_responses: false,
             swarm,
             cmd_receiver: swarm_cmd_receiver,
             event_sender: network_event_sender,
+            event_broadcast: event_broadcast.clone(),
+            inbound_requests: Default::default(),
             pending_dial: Default::default(),
             pending_get_closest_peers: Default::default(),
+            pending_get_closest_peers_batches: Default::default(),
+            next_closest_peers_batch_id: 0,
             pending_requests: Default::default(),
             pending_query: Default::default(),
+            max_pending_queries: DEFAULT_MAX_PENDING_QUERIES,
+            max_msg_size,
+            network_record_lookups: 0,
+            metrics: Default::default(),
+            pending_put_record: Default::default(),
+            min_replicas: NonZeroUsize::new(DEFAULT_MIN_REPLICAS)
+                .ok_or_else(|| Error::InvalidCloseGroupSize)?,
+            pending_bootstrap: Default::default(),
             potential_dead_peers: LruCache::with_expiry_duration_and_capacity(
                 DEAD_PEER_DETECTION_PERIOD,
                 DEAD_PEER_DETECTION_CAPACITY,
             ),
+            recently_closed_peers: Default::default(),
+            idle_peers: IdlePeers::new(idle_connection_timeout),
         };
 
         Ok((
             Network {
                 swarm_cmd_sender,
                 peer_id,
+                peer_circuit_breaker: Default::default(),
+                event_broadcast,
             },
             network_event_receiver,
             swarm_driver,
         ))
     }
 
+    /// Sets whether the responses sent to peers are signed with this node's keypair,
+    /// so the requesters can verify who they have been sent by.
+    pub fn set_sign_responses(&mut self, sign_responses: bool) {
+        self.sign_responses = sign_responses;
+    }
+
+    /// Sets whether the responses received from peers are rejected unless signed by them,
+    /// so a peer can't answer with fabricated data by simply not signing its response.
+    pub fn set_require_signed_responses(&mut self, require_signed_responses: bool) {
+        self.require_signed_responses = require_signed_responses;
+    }
+
+    /// Sets the minimum number of peers a record put to the network is to be stored by,
+    /// for the put to succeed.
+    pub fn set_min_replicas(&mut self, min_replicas: NonZeroUsize) {
+        self.min_replicas = min_replicas;
+    }
+
+    /// Sets the maximum number of record and closest peers queries to be in flight at once,
+    /// beyond which new queries are rejected until some of the pending ones complete.
+    pub fn set_max_pending_queries(&mut self, max_pending_queries: usize) {
+        self.max_pending_queries = max_pending_queries;
+    }
+
+    /// Sets the maximum size of the msgs exchanged with peers, any larger msg received is
+    /// rejected before being decoded, and any larger msg to be sent fails to be sent.
+    pub fn set_max_msg_size(&mut self, max_msg_size: usize) {
+        self.max_msg_size.store(max_msg_size, Ordering::Relaxed);
+    }
+
+    /// Sets the weights by which the requests received from peers are prioritised,
+    /// whenever they come in faster than the upper layers handle them.
+    pub fn set_request_weights(&mut self, weights: RequestWeights) {
+        self.inbound_requests.set_weights(weights);
+    }
+
+    /// Sets the maximum number of inbound connections the node holds at once, unlimited if `None`.
+    /// Incoming connections beyond it are denied by the swarm as they come in, while those
+    /// already held are kept. It's to be set before the driver is run.
+    pub fn set_max_inbound_connections(&mut self, max_inbound_connections: Option<u32>) {
+        let limits =
+            ConnectionLimits::default().with_max_established_incoming(max_inbound_connections);
+        self.swarm.behaviour_mut().connection_limits = connection_limits::Behaviour::new(limits);
+    }
+
+    /// Sets how long a peer whose last connection closed is given to reconnect,
+    /// before being removed from the routing table. Peers are removed right away if zero.
+    pub fn set_dead_peer_grace_period(&mut self, grace_period: Duration) {
+        self.recently_closed_peers.set_grace_period(grace_period);
+    }
+
+    /// Returns a snapshot of the counters of the swarm events handled so far, by their type.
+    pub fn metrics_snapshot(&self) -> SwarmMetrics {
+        self.metrics.clone()
+    }
+
+    /// Sets the relay to be reached through by the peers which cannot connect to us directly,
+    /// e.g. when we are behind a NAT, reserving a slot on it to listen on the relayed address.
+    /// The relay's address is expected to end with its `PeerId`.
+    pub fn set_relay(&mut self, relay_addr: Multiaddr) -> Result<()> {
+        let relayed_addr = relay_addr.with(Protocol::P2pCircuit);
+        info!("Listening through relay at {relayed_addr:?}");
+        let _listener_id = self.swarm.listen_on(relayed_addr)?;
+        Ok(())
+    }
+
+    // Sends the event to the upper layer, and broadcasts it to the subscribers, if any.
+    async fn send_event(&mut self, event: NetworkEvent) -> Result<()> {
+        self.broadcast_event(&event);
+        self.event_sender.send(event).await?;
+        Ok(())
+    }
+
+    fn broadcast_event(&self, event: &NetworkEvent) {
+        // It only errors when there are no subscribers
+        let _ = self.event_broadcast.send(ObservedNetworkEvent::from(event));
+    }
+
+    // Passes the queued inbound requests on to the upper layer, in order of priority,
+    // for as long as there is room for them in the events channel.
+    fn forward_inbound_requests(&mut self) {
+        while !self.inbound_requests.is_empty() {
+            match self.event_sender.try_reserve() {
+                Ok(permit) => {
+                    if let Some(event) = self.inbound_requests.pop() {
+                        self.broadcast_event(&event);
+                        permit.send(event);
+                    }
+                }
+                Err(mpsc::error::TrySendError::Full(())) => break,
+                Err(mpsc::error::TrySendError::Closed(())) => {
+                    warn!("Events channel closed, the inbound requests can't be handled");
+                    break;
+                }
+            }
+        }
+    }
+
     /// Asynchronously drives the swarm event loop, handling events from both
     /// the swarm and command receiver. This function will run indefinitely,
     /// until the command channel is closed.
@@ -320,10 +605,17 @@ impl SwarmDriver {
     /// and command receiver messages, ensuring efficient handling of multiple
     /// asynchronous tasks.
     pub async fn run(mut self) {
+        let mut closed_peers_check = tokio::time::interval(CLOSED_PEERS_CHECK_INTERVAL);
+        closed_peers_check.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        let mut idle_peers_check = tokio::time::interval(IDLE_PEERS_CHECK_INTERVAL);
+        idle_peers_check.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        // Reserving room in the events channel mustn't borrow us while the other events are handled.
+        let event_sender = self.event_sender.clone();
         loop {
             tokio::select! {
                 swarm_event = self.swarm.select_next_some() => {
-                    if let Err(err) = self.handle_swarm_events(swarm_event).await {
+                    let span = swarm_event_span(self.self_peer_id, &swarm_event);
+                    if let Err(err) = self.handle_swarm_events(swarm_event).instrument(span).await {
                         warn!("Error while handling event: {err}");
                     }
                 },
@@ -335,6 +627,24 @@ impl SwarmDriver {
                     },
                     None =>  continue,
                 },
+                // there is room again in the events channel for the queued inbound requests
+                permit = event_sender.reserve(), if !self.inbound_requests.is_empty() => match permit {
+                    Ok(permit) => {
+                        if let Some(event) = self.inbound_requests.pop() {
+                            self.broadcast_event(&event);
+                            permit.send(event);
+                        }
+                    },
+                    Err(err) => warn!("Error while forwarding inbound request: {err}"),
+                },
+                _ = closed_peers_check.tick(), if !self.recently_closed_peers.is_empty() => {
+                    if let Err(err) = self.remove_closed_peers(Instant::now()).await {
+                        warn!("Error while removing closed peers: {err}");
+                    }
+                },
+                _ = idle_peers_check.tick(), if !self.idle_peers.is_empty() => {
+                    self.disconnect_idle_peers(Instant::now());
+                },
             }
         }
     }
@@ -345,9 +655,19 @@ impl SwarmDriver {
 pub struct Network {
     pub(super) swarm_cmd_sender: mpsc::Sender<SwarmCmd>,
     pub(super) peer_id: PeerId,
+    // Keeps track of the peers failing our requests, so we stop selecting them for a while.
+    peer_circuit_breaker: Arc<Mutex<PeerCircuitBreaker>>,
+    event_broadcast: broadcast::Sender<ObservedNetworkEvent>,
 }
 
 impl Network {
+    /// Returns a new receiver of the events emitted by the network, along with the one returned
+    /// upon creating the network, so multiple subsystems can independently observe them.
+    /// A subscriber lagging behind doesn't hold up the network, it misses the oldest events instead.
+    pub fn subscribe(&self) -> broadcast::Receiver<ObservedNetworkEvent> {
+        self.event_broadcast.subscribe()
+    }
+
     ///  Listen for incoming connections on the given address.
     pub async fn start_listening(&self, addr: Multiaddr) -> Result<()> {
         let (sender, receiver) = oneshot::channel();
@@ -380,6 +700,15 @@ impl Network {
         receiver.await?
     }
 
+    /// Re-run the Kademlia bootstrap process, e.g. to rediscover peers after a network partition,
+    /// returning a handle which resolves once the bootstrap query has finished.
+    /// It errors right away if there are no known peers to bootstrap from.
+    pub async fn trigger_bootstrap(&self) -> Result<oneshot::Receiver<Result<()>>> {
+        let (sender, receiver) = oneshot::channel();
+        self.send_swarm_cmd(SwarmCmd::Bootstrap { sender }).await?;
+        Ok(receiver)
+    }
+
     /// Returns the closest peers to the given `XorName`, sorted by their distance to the xor_name.
     /// Excludes the client's `PeerId` while calculating the closest peers.
     pub async fn client_get_closest_peers(&self, key: &NetworkAddress) -> Result<Vec<PeerId>> {
@@ -392,16 +721,36 @@ impl Network {
         self.get_closest_peers(key, false).await
     }
 
+    /// Returns the closest peers to each of the given keys, sorted by their distance to the key,
+    /// looking them all up concurrently. Includes our node's `PeerId` as `node_get_closest_peers`.
+    pub async fn node_get_closest_peers_batch(
+        &self,
+        keys: Vec<NetworkAddress>,
+    ) -> Result<BTreeMap<NetworkAddress, Vec<PeerId>>> {
+        debug!("Getting the closest peers to {} keys", keys.len());
+        let (sender, receiver) = oneshot::channel();
+        self.send_swarm_cmd(SwarmCmd::GetClosestPeersBatch { keys, sender })
+            .await?;
+        let k_bucket_peers = receiver.await??;
+
+        k_bucket_peers
+            .into_iter()
+            .map(|(key, peers)| {
+                let closest_peers = peers.into_iter().chain([self.peer_id]).collect();
+                let closest_peers = self.sort_peers_by_key(closest_peers, &key)?;
+                Ok((key, closest_peers))
+            })
+            .collect()
+    }
+
     /// Send `Request` to the closest peers. If `self` is among the closest_peers, the `Request` is
     /// forwarded to itself and handled. Then a corresponding `Response` is created and is
     /// forwarded to iself. Hence the flow remains the same and there is no branching at the upper
     /// layers.
     pub async fn node_send_to_closest(&self, request: &Request) -> Result<Vec<Result<Response>>> {
-        info!(
-            "Sending {request:?} with dst {:?} to the closest peers.",
-            request.dst()
-        );
-        let closest_peers = self.node_get_closest_peers(&request.dst()).await?;
+        let dst = request.dst().ok_or(Error::NoRequestDst)?;
+        info!("Sending {request:?} with dst {dst:?} to the closest peers.");
+        let closest_peers = self.node_get_closest_peers(&dst).await?;
 
         Ok(self
             .send_and_get_responses(closest_peers, request, true)
@@ -413,11 +762,9 @@ impl Network {
     /// forwarded to iself. Hence the flow remains the same and there is no branching at the upper
     /// layers.
     pub async fn fire_and_forget_to_closest(&self, request: &Request) -> Result<()> {
-        info!(
-            "Sending {request:?} with dst {:?} to the closest peers.",
-            request.dst()
-        );
-        let closest_peers = self.node_get_closest_peers(&request.dst()).await?;
+        let dst = request.dst().ok_or(Error::NoRequestDst)?;
+        info!("Sending {request:?} with dst {dst:?} to the closest peers.");
+        let closest_peers = self.node_get_closest_peers(&dst).await?;
         for peer in closest_peers {
             self.fire_and_forget(request.clone(), peer).await?;
         }
@@ -430,11 +777,9 @@ impl Network {
         request: &Request,
         expect_all_responses: bool,
     ) -> Result<Vec<Result<Response>>> {
-        info!(
-            "Sending {request:?} with dst {:?} to the closest peers.",
-            request.dst()
-        );
-        let closest_peers = self.client_get_closest_peers(&request.dst()).await?;
+        let dst = request.dst().ok_or(Error::NoRequestDst)?;
+        info!("Sending {request:?} with dst {dst:?} to the closest peers.");
+        let closest_peers = self.client_get_closest_peers(&dst).await?;
         Ok(self
             .send_and_get_responses(closest_peers, request, expect_all_responses)
             .await)
@@ -451,15 +796,102 @@ impl Network {
             .map_err(|_e| Error::InternalMsgChannelDropped)
     }
 
-    /// Put data to KAD network as record
+    /// Put data to KAD network as record, returning once it's been stored by as many peers
+    /// as required, or an `Error::InsufficientReplication` if it couldn't.
     pub async fn put_data_as_record(&self, record: Record) -> Result<()> {
         debug!(
             "Putting data as record, for {:?} - length {:?}",
             record.key,
             record.value.len()
         );
-        self.send_swarm_cmd(SwarmCmd::PutProvidedDataAsRecord { record })
+        let (sender, receiver) = oneshot::channel();
+        self.send_swarm_cmd(SwarmCmd::PutProvidedDataAsRecord { record, sender })
+            .await?;
+        receiver.await?
+    }
+
+    /// Returns the chunk at the given address if held by this node itself,
+    /// without looking it up across the network.
+    pub async fn get_stored_chunk(&self, address: ChunkAddress) -> Result<QueryResponse> {
+        let (sender, receiver) = oneshot::channel();
+        self.send_swarm_cmd(SwarmCmd::GetStoredChunk { address, sender })
+            .await?;
+        receiver
+            .await
+            .map_err(|_e| Error::InternalMsgChannelDropped)
+    }
+
+    /// Tells whether the chunk at the given address is held by this node itself,
+    /// without reading it nor looking it up across the network.
+    pub async fn chunk_exists(&self, address: ChunkAddress) -> Result<QueryResponse> {
+        let (sender, receiver) = oneshot::channel();
+        self.send_swarm_cmd(SwarmCmd::ChunkExists { address, sender })
+            .await?;
+        receiver
             .await
+            .map_err(|_e| Error::InternalMsgChannelDropped)
+    }
+
+    /// Pushes a copy of the chunk at the given address, held by this node, to those of the
+    /// closest peers to it which don't hold it, e.g. after churn left it under-replicated.
+    /// Returns how many new replicas were created.
+    pub async fn re_replicate_chunk(&self, address: ChunkAddress) -> Result<usize> {
+        let chunk = match self.get_stored_chunk(address).await? {
+            QueryResponse::GetChunk(Ok(chunk)) => chunk,
+            _ => return Err(Error::RecordNotFound),
+        };
+
+        let dst = NetworkAddress::from_chunk_address(address);
+        let replica_set: Vec<PeerId> = self
+            .node_get_closest_peers(&dst)
+            .await?
+            .into_iter()
+            .filter(|peer| *peer != self.peer_id)
+            .collect();
+
+        let request = Request::Query(Query::ChunkExists(address));
+        let responses = join_all(replica_set.iter().map(|peer| {
+            let request = request.clone();
+            async move { (*peer, self.send_request(request, *peer).await) }
+        }))
+        .await;
+        let missing: Vec<PeerId> = responses
+            .into_iter()
+            .filter(|(_, resp)| {
+                !matches!(
+                    resp,
+                    Ok(Response::Query(QueryResponse::ChunkExists(Ok(()))))
+                )
+            })
+            .map(|(peer, _)| peer)
+            .collect();
+        if missing.is_empty() {
+            trace!("Chunk {address:?} is held by all its closest peers");
+            return Ok(0);
+        }
+
+        debug!(
+            "Pushing chunk {address:?} to {} of its closest peers not holding it",
+            missing.len()
+        );
+        let record = Record {
+            key: RecordKey::new(address.name()),
+            value: chunk.value().to_vec(),
+            publisher: None,
+            expires: None,
+        };
+        let (sender, receiver) = oneshot::channel();
+        self.send_swarm_cmd(SwarmCmd::PutRecordTo {
+            record,
+            peers: missing.clone(),
+            sender,
+        })
+        .await?;
+        match receiver.await? {
+            Ok(()) => Ok(missing.len()),
+            Err(Error::InsufficientReplication { got, .. }) => Ok(got),
+            Err(err) => Err(err),
+        }
     }
 
     /// Send `Request` to the the given `PeerId` and await for the response. If `self` is the recipient,
@@ -479,6 +911,19 @@ impl Network {
             .await
     }
 
+    /// Notify the upper layers, and the subscribers, of the new edits applied to a Register.
+    pub async fn notify_register_updated(
+        &self,
+        address: RegisterAddress,
+        new_entries: Vec<EntryHash>,
+    ) -> Result<()> {
+        self.send_swarm_cmd(SwarmCmd::NotifyRegisterUpdated {
+            address,
+            new_entries,
+        })
+        .await
+    }
+
     /// Send `Request` to the the given `PeerId` and do _not_ await a response.
     pub async fn fire_and_forget(&self, req: Request, peer: PeerId) -> Result<()> {
         let (sender, _) = oneshot::channel();
@@ -486,6 +931,49 @@ impl Network {
         self.send_swarm_cmd(swarm_cmd).await
     }
 
+    /// Sets whether the responses received from peers are rejected unless signed by them.
+    pub async fn set_require_signed_responses(&self, require_signed_responses: bool) -> Result<()> {
+        self.send_swarm_cmd(SwarmCmd::SetRequireSignedResponses(
+            require_signed_responses,
+        ))
+        .await
+    }
+
+    /// Pins the chunk at the given address in our local record store, so it's never removed
+    /// from it, e.g. once out of our range, until it's unpinned.
+    pub async fn pin_chunk(&self, address: ChunkAddress) -> Result<()> {
+        self.send_swarm_cmd(SwarmCmd::PinChunk(address)).await
+    }
+
+    /// Unpins the chunk at the given address in our local record store, so it can be removed again.
+    pub async fn unpin_chunk(&self, address: ChunkAddress) -> Result<()> {
+        self.send_swarm_cmd(SwarmCmd::UnpinChunk(address)).await
+    }
+
+    /// Waits until the routing table holds at least `min_peers` peers, checking it again whenever
+    /// a peer is added to it, for up to the provided `timeout`. Returns the number of peers in the
+    /// routing table by then, which is fewer than `min_peers` if they weren't added in time.
+    pub async fn wait_for_peers(&self, min_peers: usize, timeout: Duration) -> Result<usize> {
+        // Subscribed before checking the routing table, so no peer added in between is missed.
+        let mut events = self.subscribe();
+        let deadline = tokio::time::Instant::now() + timeout;
+        loop {
+            let peers = self.get_swarm_local_state().await?.peers_in_routing_table;
+            if peers >= min_peers {
+                return Ok(peers);
+            }
+            loop {
+                match tokio::time::timeout_at(deadline, events.recv()).await {
+                    // A lagging subscriber may have missed peers being added.
+                    Ok(Ok(ObservedNetworkEvent::PeerAdded(_)))
+                    | Ok(Err(broadcast::error::RecvError::Lagged(_))) => break,
+                    Ok(Ok(_)) => continue,
+                    Ok(Err(broadcast::error::RecvError::Closed)) | Err(_) => return Ok(peers),
+                }
+            }
+        }
+    }
+
     /// Return a `SwarmLocalState` with some information obtained from swarm's local state.
     pub async fn get_swarm_local_state(&self) -> Result<SwarmLocalState> {
         let (sender, receiver) = oneshot::channel();
@@ -495,6 +983,15 @@ impl Network {
         Ok(state)
     }
 
+    /// Returns a snapshot of the counters of the swarm events handled so far, by their type.
+    pub async fn metrics_snapshot(&self) -> Result<SwarmMetrics> {
+        let (sender, receiver) = oneshot::channel();
+        self.send_swarm_cmd(SwarmCmd::GetMetricsSnapshot(sender))
+            .await?;
+        let metrics = receiver.await?;
+        Ok(metrics)
+    }
+
     // Helper to send SwarmCmd
     async fn send_swarm_cmd(&self, cmd: SwarmCmd) -> Result<()> {
         self.swarm_cmd_sender.send(cmd).await?;
@@ -511,11 +1008,23 @@ impl Network {
             sender,
         })
         .await?;
-        let k_bucket_peers = receiver.await?;
+        let k_bucket_peers = receiver.await??;
 
         // Count self in if among the CLOSE_GROUP_SIZE closest and sort the result
         let mut closest_peers: Vec<_> = k_bucket_peers.into_iter().collect();
-        if !client {
+        if client {
+            // Leave out the peers which keep failing, as long as there are enough others to pick from
+            let available_peers = self
+                .peer_circuit_breaker
+                .lock()
+                .await
+                .available_peers(closest_peers.clone());
+            if available_peers.len() >= CLOSE_GROUP_SIZE {
+                closest_peers = available_peers;
+            } else {
+                debug!("Not enough peers left when excluding the failing ones, selecting among all of them");
+            }
+        } else {
             closest_peers.push(self.peer_id);
         }
         self.sort_peers_by_key(closest_peers, key)
@@ -556,12 +1065,22 @@ impl Network {
     ) -> Vec<Result<Response>> {
         let mut list_of_futures = peers
             .iter()
-            .map(|peer| Box::pin(self.send_request(req.clone(), *peer)))
+            .map(|peer| {
+                Box::pin(async move { (*peer, self.send_request(req.clone(), *peer).await) })
+            })
             .collect::<Vec<_>>();
 
         let mut responses = Vec::new();
         while !list_of_futures.is_empty() {
-            let (res, _, remaining_futures) = select_all(list_of_futures).await;
+            let ((peer, res), _, remaining_futures) = select_all(list_of_futures).await;
+            {
+                let mut peer_circuit_breaker = self.peer_circuit_breaker.lock().await;
+                if res.is_ok() {
+                    peer_circuit_breaker.record_success(&peer);
+                } else {
+                    peer_circuit_breaker.record_failure(peer);
+                }
+            }
             let res_string = match &res {
                 Ok(res) => format!("{res}"),
                 Err(err) => format!("{err:?}"),
@@ -580,14 +1099,22 @@ impl Network {
 
 #[cfg(test)]
 mod tests {
-    use super::SwarmDriver;
+    use super::{
+        cmd::SwarmCmd, dial_addr, event::NodeEvent, is_relayed, msg::verify_response,
+        swarm_event_span, ClosestPeersRequester, DhtRequestKind, Error, Network,
+        ObservedNetworkEvent, SwarmDriver, SwarmMetrics, IDLE_CONNECTION_TIMEOUT,
+        NETWORK_EVENTS_BROADCAST_CAPACITY,
+    };
     use crate::{
         log::init_test_logger,
         network::{MsgResponder, NetworkEvent, CLOSE_GROUP_SIZE},
         protocol::{
+            error::Error as ProtocolError,
             NetworkAddress,
             {
-                messages::{Cmd, CmdResponse, Request, Response},
+                messages::{
+                    Cmd, CmdResponse, QueryResponse, Request, Response, MSG_PROTOCOL_VERSION,
+                },
                 storage::{Chunk, ChunkAddress},
             },
         },
@@ -595,21 +1122,40 @@ mod tests {
     use assert_matches::assert_matches;
     use bytes::Bytes;
     use eyre::{eyre, Result};
+    use libp2p::{
+        core::{ConnectedPoint, Endpoint},
+        kad::{
+            kbucket::Key as KBucketKey, store::RecordStore, Addresses, GetRecordError,
+            InboundRequest, KademliaEvent, PutRecordError, Quorum, Record as KadRecord, RecordKey,
+        },
+        multiaddr::Protocol,
+        swarm::SwarmEvent,
+        Multiaddr, PeerId,
+    };
     use rand::{thread_rng, Rng};
-    use std::{net::SocketAddr, time::Duration};
+    use std::{
+        collections::{BTreeMap, HashSet},
+        fmt,
+        net::SocketAddr,
+        num::{NonZeroU32, NonZeroUsize},
+        path::Path,
+        sync::{Arc, Mutex},
+        time::{Duration, Instant},
+    };
+    use tokio::sync::oneshot;
+    use tracing::{
+        field::{Field, Visit},
+        instrument::WithSubscriber,
+        span::{Attributes, Id, Record},
+        Event, Level, Subscriber,
+    };
+    use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+    use xor_name::XorName;
 
     #[cfg(feature = "local-discovery")]
     use libp2p::kad::kbucket::{Entry, InsertResult, KBucketsTable, NodeStatus};
     #[cfg(feature = "local-discovery")]
-    use libp2p::PeerId;
-    #[cfg(feature = "local-discovery")]
-    use std::collections::{BTreeMap, HashMap};
-    #[cfg(feature = "local-discovery")]
-    use std::fmt;
-    #[cfg(feature = "local-discovery")]
-    use xor_name::XorName;
-
-    use std::path::Path;
+    use std::collections::HashMap;
 
     #[tokio::test(flavor = "multi_thread")]
     // Enable mDNS for peer discovery here
@@ -737,6 +1283,853 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn bootstrap_completion_is_signaled() -> Result<()> {
+        init_test_logger();
+        let mut networks = vec![];
+        for _ in 0..2 {
+            networks.push(spawn_listening_node(IDLE_CONNECTION_TIMEOUT).await?);
+        }
+
+        // Bootstrapping with an empty routing table shall fail right away.
+        let (net, _) = &networks[0];
+        let no_peers = net.trigger_bootstrap().await?.await?;
+        assert_matches!(no_peers, Err(Error::NoKnownPeers(_)));
+
+        // Seed the routing table with the other node, and bootstrap off it.
+        let (other_net, other_addr) = &networks[1];
+        net.add_to_routing_table(other_net.peer_id, other_addr.clone())
+            .await?;
+        let handle = net.trigger_bootstrap().await?;
+        let completion = tokio::time::timeout(Duration::from_secs(30), handle).await??;
+        assert_matches!(completion, Ok(()));
+
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn node_without_mdns_only_finds_peers_by_bootstrapping() -> Result<()> {
+        init_test_logger();
+        let mut networks = vec![];
+        for _ in 0..2 {
+            let (net, mut event_rx, driver) =
+                SwarmDriver::without_mdns("127.0.0.1:0".parse::<SocketAddr>()?, Path::new(""))?;
+            #[cfg(feature = "local-discovery")]
+            assert!(!driver.swarm.behaviour().mdns.is_enabled());
+            let _driver_handle = tokio::spawn(driver.run());
+            let listen_addr = loop {
+                match event_rx.recv().await {
+                    Some(NetworkEvent::NewListenAddr(addr)) => break addr,
+                    Some(_) => continue,
+                    None => return Err(eyre!("Network events channel closed")),
+                }
+            };
+            let _events_handle =
+                tokio::spawn(async move { while event_rx.recv().await.is_some() {} });
+            networks.push((net, listen_addr));
+        }
+
+        // The nodes on the same host would have discovered each other within an mDNS query interval.
+        tokio::time::sleep(Duration::from_secs(6)).await;
+        for (net, _) in &networks {
+            assert_eq!(net.get_swarm_local_state().await?.peers_in_routing_table, 0);
+        }
+
+        // Whereas bootstrapping off a known peer still works.
+        let (net, _) = &networks[0];
+        let (other_net, other_addr) = &networks[1];
+        net.add_to_routing_table(other_net.peer_id, other_addr.clone())
+            .await?;
+        let handle = net.trigger_bootstrap().await?;
+        let completion = tokio::time::timeout(Duration::from_secs(30), handle).await??;
+        assert_matches!(completion, Ok(()));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn idle_connection_is_closed_after_timeout() -> Result<()> {
+        init_test_logger();
+        let idle_connection_timeout = Duration::from_secs(1);
+        let mut networks = vec![];
+        for _ in 0..2 {
+            networks.push(spawn_listening_node(idle_connection_timeout).await?);
+        }
+
+        let (net, _) = &networks[0];
+        let (other_net, other_addr) = &networks[1];
+        net.dial(other_net.peer_id, other_addr.clone()).await?;
+        assert!(net
+            .get_swarm_local_state()
+            .await?
+            .connected_peers
+            .contains(&other_net.peer_id));
+
+        // With no msg flow, the connection shall be closed soon after the timeout.
+        let now = tokio::time::Instant::now();
+        loop {
+            tokio::time::sleep(idle_connection_timeout).await;
+            let state = net.get_swarm_local_state().await?;
+            if !state.connected_peers.contains(&other_net.peer_id) {
+                break;
+            }
+            if now.elapsed() > idle_connection_timeout * 10 {
+                return Err(eyre!("Idle connection to {} not closed", other_net.peer_id));
+            }
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn protocol_info_is_answered_by_peers() -> Result<()> {
+        init_test_logger();
+        let (net, _) = spawn_listening_node(IDLE_CONNECTION_TIMEOUT).await?;
+        let (other_net, other_addr) = spawn_listening_node(IDLE_CONNECTION_TIMEOUT).await?;
+        net.dial(other_net.peer_id, other_addr).await?;
+
+        let response = net
+            .send_request(Request::ProtocolInfo, other_net.peer_id)
+            .await?;
+        match response {
+            Response::ProtocolInfo {
+                version,
+                supported_queries,
+            } => {
+                assert_eq!(version, MSG_PROTOCOL_VERSION);
+                assert!(supported_queries.contains(&"GetChunk".to_string()));
+            }
+            other => return Err(eyre!("Unexpected response: {other:?}")),
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn under_replicated_chunk_is_pushed_to_fill_its_replica_set() -> Result<()> {
+        init_test_logger();
+        let mut root_dirs = vec![];
+
+        // The chunk is held only by the first node, before any of them is started.
+        let chunk = Chunk::new(Bytes::from(vec![7u8; 1024]));
+        let address = *chunk.address();
+        let holder_root_dir = assert_fs::TempDir::new()?;
+        let (holder, mut holder_event_rx, mut holder_driver) =
+            SwarmDriver::with_idle_connection_timeout(
+                "127.0.0.1:0"
+                    .parse::<SocketAddr>()
+                    .expect("127.0.0.1:0 should parse into a valid `SocketAddr`"),
+                holder_root_dir.path(),
+                IDLE_CONNECTION_TIMEOUT,
+            )?;
+        holder_driver
+            .swarm
+            .behaviour_mut()
+            .kademlia
+            .store_mut()
+            .put(KadRecord::new(
+                RecordKey::new(address.name()),
+                chunk.value().to_vec(),
+            ))?;
+        let _driver_handle = tokio::spawn(holder_driver.run());
+        let _events_handle =
+            tokio::spawn(async move { while holder_event_rx.recv().await.is_some() {} });
+        root_dirs.push(holder_root_dir);
+
+        // The rest of the close group is made of the nodes known by the holder.
+        for _ in 1..CLOSE_GROUP_SIZE {
+            let root_dir = assert_fs::TempDir::new()?;
+            let (net, addr) =
+                spawn_listening_node_at(IDLE_CONNECTION_TIMEOUT, root_dir.path()).await?;
+            holder.add_to_routing_table(net.peer_id, addr).await?;
+            root_dirs.push(root_dir);
+        }
+
+        let new_replicas =
+            tokio::time::timeout(Duration::from_secs(30), holder.re_replicate_chunk(address))
+                .await??;
+        assert_eq!(new_replicas, CLOSE_GROUP_SIZE - 1);
+
+        // Once the replica set is filled, there is nothing left to push.
+        let new_replicas =
+            tokio::time::timeout(Duration::from_secs(30), holder.re_replicate_chunk(address))
+                .await??;
+        assert_eq!(new_replicas, 0);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn get_providers_result_is_not_logged_as_error() -> Result<()> {
+        let (_net, _event_rx, mut driver) = SwarmDriver::new(
+            "127.0.0.1:0"
+                .parse::<SocketAddr>()
+                .expect("127.0.0.1:0 should parse into a valid `SocketAddr`"),
+            Path::new(""),
+        )?;
+
+        // With no known peers, the query finishes straight away with an empty result.
+        let key = RecordKey::new(&XorName::random(&mut thread_rng()));
+        let _query_id = driver.swarm.behaviour_mut().kademlia.get_providers(key);
+
+        let recorded_events = Arc::new(Mutex::new(vec![]));
+        let subscriber =
+            tracing_subscriber::registry().with(EventsRecorder(recorded_events.clone()));
+        let _driver_handle = tokio::spawn(driver.run().with_subscriber(subscriber));
+        tokio::time::sleep(Duration::from_secs(2)).await;
+
+        let events = recorded_events
+            .lock()
+            .map_err(|_| eyre!("Failed to lock the recorded events"))?;
+        assert!(events
+            .iter()
+            .any(|(level, msg)| *level == Level::DEBUG && msg.contains("GetProviders")));
+        assert!(
+            events.iter().all(|(level, _)| *level != Level::ERROR),
+            "Unexpected error-level logs: {events:?}"
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn get_record_quorum_failure_is_reported_distinctly() -> Result<()> {
+        let (_net, _event_rx, mut driver) = SwarmDriver::new_client()?;
+
+        let key = RecordKey::new(&XorName::random(&mut thread_rng()));
+        let query_id = driver
+            .swarm
+            .behaviour_mut()
+            .kademlia
+            .get_record(key.clone());
+        let (sender, receiver) = oneshot::channel();
+        let _ = driver.pending_query.insert(query_id, sender);
+
+        let quorum = NonZeroUsize::new(CLOSE_GROUP_SIZE).ok_or_else(|| eyre!("Zero quorum"))?;
+        let result = Err(GetRecordError::QuorumFailed {
+            key: key.clone(),
+            records: vec![],
+            quorum,
+        });
+        driver.handle_get_record_result(query_id, &result, true)?;
+        assert_matches!(
+            receiver.await?,
+            Ok(QueryResponse::GetChunk(Err(ProtocolError::QuorumFailed)))
+        );
+
+        // Whereas a record not found is reported as such.
+        let query_id = driver
+            .swarm
+            .behaviour_mut()
+            .kademlia
+            .get_record(key.clone());
+        let (sender, receiver) = oneshot::channel();
+        let _ = driver.pending_query.insert(query_id, sender);
+        let result = Err(GetRecordError::NotFound {
+            key,
+            closest_peers: vec![],
+        });
+        driver.handle_get_record_result(query_id, &result, true)?;
+        assert_matches!(receiver.await?, Err(Error::RecordNotFound));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn put_record_stored_by_fewer_peers_than_min_replicas_is_rejected() -> Result<()> {
+        let (_net, _event_rx, mut driver) = SwarmDriver::new_client()?;
+        let min_replicas = NonZeroUsize::new(3).ok_or_else(|| eyre!("Zero min replicas"))?;
+        driver.set_min_replicas(min_replicas);
+
+        let key = RecordKey::new(&XorName::random(&mut thread_rng()));
+        let record = KadRecord::new(key.clone(), vec![1, 2, 3]);
+        let query_id = driver
+            .swarm
+            .behaviour_mut()
+            .kademlia
+            .put_record(record, Quorum::N(min_replicas))?;
+        let (sender, receiver) = oneshot::channel();
+        let _ = driver.pending_put_record.insert(query_id, sender);
+
+        // Only a single peer, out of the closest ones available, stored the record.
+        let result = Err(PutRecordError::QuorumFailed {
+            key,
+            success: vec![PeerId::random()],
+            quorum: min_replicas,
+        });
+        driver.handle_put_record_result(query_id, &result)?;
+        assert_matches!(
+            receiver.await?,
+            Err(Error::InsufficientReplication { got: 1, needed: 3 })
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn locally_stored_record_is_not_looked_up_across_the_network() -> Result<()> {
+        let (_net, _event_rx, mut driver) = SwarmDriver::new_client()?;
+
+        let chunk = Chunk::new(Bytes::from_static(b"locally stored chunk"));
+        let key = RecordKey::new(chunk.name());
+        driver
+            .swarm
+            .behaviour_mut()
+            .kademlia
+            .store_mut()
+            .put(KadRecord::new(key.clone(), chunk.value().to_vec()))?;
+
+        let (sender, receiver) = oneshot::channel();
+        driver.handle_cmd(SwarmCmd::GetData { key, sender }).await?;
+        assert_matches!(
+            receiver.await?,
+            Ok(QueryResponse::GetChunk(Ok(found))) if found == chunk
+        );
+        assert_eq!(driver.network_record_lookups, 0);
+
+        // A record missing from our local store is looked up across the network.
+        let missing_key = RecordKey::new(&XorName::random(&mut thread_rng()));
+        let (sender, _receiver) = oneshot::channel();
+        driver
+            .handle_cmd(SwarmCmd::GetData {
+                key: missing_key,
+                sender,
+            })
+            .await?;
+        assert_eq!(driver.network_record_lookups, 1);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn inbound_dht_request_is_reported_by_kind() -> Result<()> {
+        let (net, mut event_rx, mut driver) = SwarmDriver::new_client()?;
+        let mut observer = net.subscribe();
+
+        let event = SwarmEvent::Behaviour(NodeEvent::Kademlia(KademliaEvent::InboundRequest {
+            request: InboundRequest::GetRecord {
+                num_closer_peers: 0,
+                present_locally: true,
+            },
+        }));
+        driver.handle_swarm_events::<Error>(event).await?;
+
+        assert_matches!(
+            event_rx.recv().await,
+            Some(NetworkEvent::InboundDhtRequest {
+                kind: DhtRequestKind::GetRecord
+            })
+        );
+        assert_matches!(
+            observer.recv().await?,
+            ObservedNetworkEvent::InboundDhtRequest {
+                kind: DhtRequestKind::GetRecord
+            }
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn peer_evicted_from_the_routing_table_is_reported() -> Result<()> {
+        let (net, mut event_rx, mut driver) = SwarmDriver::new_client()?;
+        let mut observer = net.subscribe();
+
+        let new_peer = PeerId::random();
+        let evicted_peer = PeerId::random();
+        let addr: Multiaddr = "/ip4/127.0.0.1/tcp/12000".parse()?;
+        let distance = KBucketKey::from(new_peer).distance(&KBucketKey::from(evicted_peer));
+        let event = SwarmEvent::Behaviour(NodeEvent::Kademlia(KademliaEvent::RoutingUpdated {
+            peer: new_peer,
+            is_new_peer: true,
+            addresses: Addresses::new(addr),
+            bucket_range: (distance, distance),
+            old_peer: Some(evicted_peer),
+        }));
+        driver.handle_swarm_events::<Error>(event).await?;
+
+        assert_matches!(event_rx.recv().await, Some(NetworkEvent::PeerAdded(peer)) if peer == new_peer);
+        assert_matches!(
+            event_rx.recv().await,
+            Some(NetworkEvent::ReplicationCandidate { peer }) if peer == new_peer
+        );
+        assert_matches!(
+            event_rx.recv().await,
+            Some(NetworkEvent::PeerEvicted(peer)) if peer == evicted_peer
+        );
+        assert_matches!(observer.recv().await?, ObservedNetworkEvent::PeerAdded(_));
+        assert_matches!(
+            observer.recv().await?,
+            ObservedNetworkEvent::ReplicationCandidate { .. }
+        );
+        assert_matches!(
+            observer.recv().await?,
+            ObservedNetworkEvent::PeerEvicted(peer) if peer == evicted_peer
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn swarm_events_are_counted_by_type() -> Result<()> {
+        let (_net, _event_rx, mut driver) = SwarmDriver::new_client()?;
+
+        let peer_id = PeerId::random();
+        let evicted_peer = PeerId::random();
+        let peer_addr: Multiaddr = "/ip4/127.0.0.1/udp/12001/quic-v1".parse()?;
+        let endpoint = ConnectedPoint::Listener {
+            local_addr: "/ip4/127.0.0.1/udp/12000/quic-v1".parse()?,
+            send_back_addr: peer_addr.clone(),
+        };
+        let distance = KBucketKey::from(peer_id).distance(&KBucketKey::from(evicted_peer));
+        let inbound_request = || {
+            SwarmEvent::Behaviour(NodeEvent::Kademlia(KademliaEvent::InboundRequest {
+                request: InboundRequest::FindNode {
+                    num_closer_peers: 0,
+                },
+            }))
+        };
+        let events = vec![
+            SwarmEvent::Dialing(peer_id),
+            SwarmEvent::ConnectionEstablished {
+                peer_id,
+                endpoint: endpoint.clone(),
+                num_established: NonZeroU32::new(1).ok_or_else(|| eyre!("Zero connections"))?,
+                concurrent_dial_errors: None,
+                established_in: Duration::from_millis(10),
+            },
+            SwarmEvent::Behaviour(NodeEvent::Kademlia(KademliaEvent::RoutingUpdated {
+                peer: peer_id,
+                is_new_peer: true,
+                addresses: Addresses::new(peer_addr),
+                bucket_range: (distance, distance),
+                old_peer: Some(evicted_peer),
+            })),
+            inbound_request(),
+            inbound_request(),
+            SwarmEvent::ConnectionClosed {
+                peer_id,
+                endpoint,
+                num_established: 0,
+                cause: None,
+            },
+        ];
+        for event in events {
+            driver.handle_swarm_events::<Error>(event).await?;
+        }
+
+        assert_eq!(
+            driver.metrics_snapshot(),
+            SwarmMetrics {
+                dials: 1,
+                connections_established: 1,
+                peers_added: 1,
+                peers_evicted: 1,
+                inbound_dht_requests: 2,
+                connections_closed: 1,
+                ..Default::default()
+            }
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn wait_for_peers_resolves_once_enough_peers_in_routing_table() -> Result<()> {
+        let (net, _event_rx, driver) = SwarmDriver::new_client()?;
+        let _driver_handle = tokio::spawn(driver.run());
+
+        let net_clone = net.clone();
+        let handle = tokio::spawn(async move {
+            for port in 12001..12004 {
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                let addr: Multiaddr = format!("/ip4/127.0.0.1/udp/{port}/quic-v1").parse()?;
+                net_clone
+                    .add_to_routing_table(PeerId::random(), addr)
+                    .await?;
+            }
+            Ok::<_, eyre::Report>(())
+        });
+
+        assert_eq!(net.wait_for_peers(3, Duration::from_secs(5)).await?, 3);
+        handle.await??;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn wait_for_peers_times_out_with_too_few_peers() -> Result<()> {
+        let (net, _event_rx, driver) = SwarmDriver::new_client()?;
+        let _driver_handle = tokio::spawn(driver.run());
+        let addr: Multiaddr = "/ip4/127.0.0.1/udp/12001/quic-v1".parse()?;
+        net.add_to_routing_table(PeerId::random(), addr).await?;
+
+        assert_eq!(net.wait_for_peers(2, Duration::from_millis(500)).await?, 1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn metrics_snapshot_is_taken_through_the_network_handle() -> Result<()> {
+        init_test_logger();
+        let (net, _) = spawn_listening_node(IDLE_CONNECTION_TIMEOUT).await?;
+        let (other_net, other_addr) = spawn_listening_node(IDLE_CONNECTION_TIMEOUT).await?;
+        net.dial(other_net.peer_id, other_addr).await?;
+        let _ = net
+            .send_request(Request::ProtocolInfo, other_net.peer_id)
+            .await?;
+
+        assert_eq!(net.metrics_snapshot().await?.responses_received, 1);
+        assert_eq!(other_net.metrics_snapshot().await?.requests_received, 1);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn new_peer_is_reported_as_replication_candidate() -> Result<()> {
+        let (_net, mut event_rx, mut driver) = SwarmDriver::new_client()?;
+
+        let peer = PeerId::random();
+        let addr: Multiaddr = "/ip4/127.0.0.1/udp/12000/quic-v1".parse()?;
+        let distance = KBucketKey::from(peer).distance(&KBucketKey::from(PeerId::random()));
+        let routing_updated = |is_new_peer| {
+            SwarmEvent::Behaviour(NodeEvent::Kademlia(KademliaEvent::RoutingUpdated {
+                peer,
+                is_new_peer,
+                addresses: Addresses::new(addr.clone()),
+                bucket_range: (distance, distance),
+                old_peer: None,
+            }))
+        };
+
+        driver
+            .handle_swarm_events::<Error>(routing_updated(true))
+            .await?;
+        assert_matches!(event_rx.recv().await, Some(NetworkEvent::PeerAdded(added)) if added == peer);
+        assert_matches!(
+            event_rx.recv().await,
+            Some(NetworkEvent::ReplicationCandidate { peer: candidate }) if candidate == peer
+        );
+
+        // The routing of an already known peer is updated, e.g. with a new address.
+        driver
+            .handle_swarm_events::<Error>(routing_updated(false))
+            .await?;
+        assert!(event_rx.try_recv().is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn closest_peers_are_looked_up_in_batch() -> Result<()> {
+        let (_net, _event_rx, mut driver) = SwarmDriver::new_client()?;
+
+        let keys: Vec<_> = (0..3)
+            .map(|_| NetworkAddress::from_peer(PeerId::random()))
+            .collect();
+        let (sender, mut receiver) = oneshot::channel();
+        driver
+            .handle_cmd(SwarmCmd::GetClosestPeersBatch {
+                keys: keys.clone(),
+                sender,
+            })
+            .await?;
+        assert_eq!(driver.pending_get_closest_peers.len(), keys.len());
+
+        // The queries complete in any order, each with the peers seeded for its own key.
+        let seeded: BTreeMap<_, HashSet<_>> = keys
+            .iter()
+            .map(|key| (key.clone(), (0..4).map(|_| PeerId::random()).collect()))
+            .collect();
+        let mut queries: Vec<_> = driver
+            .pending_get_closest_peers
+            .iter()
+            .filter_map(|(id, (requester, _))| match requester {
+                ClosestPeersRequester::Batch { key, .. } => Some((*id, key.clone())),
+                ClosestPeersRequester::Single(_) => None,
+            })
+            .collect();
+        assert_eq!(queries.len(), keys.len());
+        queries.sort_by_key(|(_, key)| std::cmp::Reverse(key.clone()));
+        for (i, (id, key)) in queries.into_iter().enumerate() {
+            // Nothing is sent to the requester until all of them complete.
+            assert!(receiver.try_recv().is_err());
+            let (requester, _) = driver
+                .pending_get_closest_peers
+                .remove(&id)
+                .ok_or_else(|| eyre!("No pending query for key {i}"))?;
+            driver.complete_closest_peers_query(requester, seeded[&key].clone())?;
+        }
+
+        assert_eq!(receiver.await??, seeded);
+        assert!(driver.pending_get_closest_peers_batches.is_empty());
+
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn inbound_connections_beyond_the_max_are_rejected() -> Result<()> {
+        init_test_logger();
+        let (net, mut event_rx, mut driver) = SwarmDriver::with_idle_connection_timeout(
+            "127.0.0.1:0"
+                .parse::<SocketAddr>()
+                .expect("127.0.0.1:0 should parse into a valid `SocketAddr`"),
+            Path::new(""),
+            IDLE_CONNECTION_TIMEOUT,
+        )?;
+        driver.set_max_inbound_connections(Some(2));
+        let _driver_handle = tokio::spawn(driver.run());
+        let addr = loop {
+            match event_rx.recv().await {
+                Some(NetworkEvent::NewListenAddr(addr)) => break addr,
+                Some(_) => continue,
+                None => return Err(eyre!("Network events channel closed")),
+            }
+        };
+        let _events_handle = tokio::spawn(async move { while event_rx.recv().await.is_some() {} });
+
+        // The first peers connecting are admitted, and the node answers them.
+        let mut admitted = Vec::new();
+        for _ in 0..2 {
+            let (peer_net, _) = spawn_listening_node(IDLE_CONNECTION_TIMEOUT).await?;
+            peer_net.dial(net.peer_id, addr.clone()).await?;
+            let response = peer_net
+                .send_request(Request::ProtocolInfo, net.peer_id)
+                .await?;
+            assert_matches!(response, Response::ProtocolInfo { .. });
+            admitted.push(peer_net);
+        }
+
+        // Any further peer is rejected, however many times it tries.
+        for _ in 0..3 {
+            let (peer_net, _) = spawn_listening_node(IDLE_CONNECTION_TIMEOUT).await?;
+            let _ = peer_net.dial(net.peer_id, addr.clone()).await;
+            assert!(peer_net
+                .send_request(Request::ProtocolInfo, net.peer_id)
+                .await
+                .is_err());
+        }
+
+        // The node is still responsive to the peers it admitted.
+        for peer_net in &admitted {
+            let response = peer_net
+                .send_request(Request::ProtocolInfo, net.peer_id)
+                .await?;
+            assert_matches!(response, Response::ProtocolInfo { .. });
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn queries_beyond_the_max_pending_are_rejected() -> Result<()> {
+        let (_net, _event_rx, mut driver) = SwarmDriver::new_client()?;
+        driver.set_max_pending_queries(2);
+
+        let get_data = |sender| SwarmCmd::GetData {
+            key: RecordKey::new(&XorName::random(&mut thread_rng())),
+            sender,
+        };
+
+        let (sender, _first_receiver) = oneshot::channel();
+        driver.handle_cmd(get_data(sender)).await?;
+        let (sender, _closest_receiver) = oneshot::channel();
+        driver
+            .handle_cmd(SwarmCmd::GetClosestPeers {
+                key: NetworkAddress::from_peer(PeerId::random()),
+                sender,
+            })
+            .await?;
+
+        // The limit is reached, so the next query is rejected without being started.
+        let (sender, receiver) = oneshot::channel();
+        driver.handle_cmd(get_data(sender)).await?;
+        assert_matches!(receiver.await?, Err(Error::TooManyPendingQueries));
+        assert_eq!(driver.network_record_lookups, 1);
+
+        // Once one of them completes, its slot is freed for a new query.
+        let query_id = *driver
+            .pending_query
+            .keys()
+            .next()
+            .ok_or_else(|| eyre!("No pending query"))?;
+        driver.complete_pending_query(&query_id)?;
+        let (sender, _receiver) = oneshot::channel();
+        driver.handle_cmd(get_data(sender)).await?;
+        assert_eq!(driver.network_record_lookups, 2);
+        assert_eq!(driver.pending_query.len(), 1);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn response_with_invalid_signature_is_rejected() -> Result<()> {
+        let (_net, _event_rx, mut driver) = SwarmDriver::new_client()?;
+        driver.set_sign_responses(true);
+        let peer = driver.self_peer_id;
+
+        let response = Response::Cmd(CmdResponse::StoreChunk(Ok(())));
+        let signed = driver.sign_response(response.clone())?;
+        assert_matches!(signed, Response::Signed(_));
+        assert_eq!(verify_response(signed.clone(), &peer, true)?, response);
+
+        // Signed by a peer other than the one the response is received from.
+        assert_matches!(
+            verify_response(signed.clone(), &PeerId::random(), true),
+            Err(Error::InvalidResponseSignature(_))
+        );
+
+        // The signed response has been tampered with.
+        let mut tampered = match signed {
+            Response::Signed(signed) => signed,
+            other => return Err(eyre!("Expected a signed response, got {other:?}")),
+        };
+        tampered.response = Box::new(Response::Cmd(CmdResponse::StoreChunk(Err(
+            ProtocolError::QuorumFailed,
+        ))));
+        assert_matches!(
+            verify_response(Response::Signed(tampered), &peer, false),
+            Err(Error::InvalidResponseSignature(_))
+        );
+
+        // An unsigned response is only accepted if signed responses aren't required.
+        assert_eq!(verify_response(response.clone(), &peer, false)?, response);
+        assert_matches!(
+            verify_response(response, &peer, true),
+            Err(Error::InvalidResponseSignature(p)) if p == peer
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn network_events_are_broadcast_to_every_subscriber() -> Result<()> {
+        let (net, mut event_rx, mut driver) = SwarmDriver::new_client()?;
+        let mut first = net.subscribe();
+        let mut second = net.subscribe();
+        let mut lagging = net.subscribe();
+
+        let peer = PeerId::random();
+        driver.send_event(NetworkEvent::PeerAdded(peer)).await?;
+        assert_matches!(event_rx.recv().await, Some(NetworkEvent::PeerAdded(p)) if p == peer);
+        assert_matches!(first.recv().await?, ObservedNetworkEvent::PeerAdded(p) if p == peer);
+        assert_matches!(second.recv().await?, ObservedNetworkEvent::PeerAdded(p) if p == peer);
+
+        // A subscriber not keeping up misses the oldest events, without holding up the others.
+        // The broadcast channel rounds its capacity up to the next power of two.
+        for _ in 0..NETWORK_EVENTS_BROADCAST_CAPACITY.next_power_of_two() {
+            driver
+                .send_event(NetworkEvent::PeerAdded(PeerId::random()))
+                .await?;
+            let _ = event_rx.recv().await;
+            let _ = first.recv().await?;
+        }
+        assert_matches!(
+            lagging.recv().await,
+            Err(tokio::sync::broadcast::error::RecvError::Lagged(1))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn swarm_event_span_carries_peer_ids() {
+        let recorded_fields = Arc::new(Mutex::new(BTreeMap::new()));
+        let subscriber =
+            tracing_subscriber::registry().with(SpanFieldsRecorder(recorded_fields.clone()));
+
+        let local_peer_id = PeerId::random();
+        let remote_peer_id = PeerId::random();
+        let event: SwarmEvent<NodeEvent, std::io::Error> = SwarmEvent::Dialing(remote_peer_id);
+
+        tracing::subscriber::with_default(subscriber, || {
+            let span = swarm_event_span(local_peer_id, &event);
+            span.in_scope(|| info!("Handling a connection event"));
+        });
+
+        let fields = recorded_fields
+            .lock()
+            .expect("Failed to lock the recorded fields");
+        assert_eq!(
+            fields.get("local_peer_id"),
+            Some(&local_peer_id.to_string())
+        );
+        assert_eq!(fields.get("peer_id"), Some(&remote_peer_id.to_string()));
+    }
+
+    /// Test utility to record the fields set on spans
+    struct SpanFieldsRecorder(Arc<Mutex<BTreeMap<String, String>>>);
+
+    impl<S: Subscriber> Layer<S> for SpanFieldsRecorder {
+        fn on_new_span(&self, attrs: &Attributes<'_>, _id: &Id, _ctx: Context<'_, S>) {
+            if let Ok(mut fields) = self.0.lock() {
+                attrs.record(&mut FieldsVisitor(&mut fields));
+            }
+        }
+
+        fn on_record(&self, _id: &Id, values: &Record<'_>, _ctx: Context<'_, S>) {
+            if let Ok(mut fields) = self.0.lock() {
+                values.record(&mut FieldsVisitor(&mut fields));
+            }
+        }
+    }
+
+    // Spawns a node listening on localhost, returning its `Network` handle and listening address.
+    // The node's events are drained in the background.
+    async fn spawn_listening_node(
+        idle_connection_timeout: Duration,
+    ) -> Result<(Network, Multiaddr)> {
+        spawn_listening_node_at(idle_connection_timeout, Path::new("")).await
+    }
+
+    // Same as `spawn_listening_node` but storing the node's records within the given `root_dir`.
+    async fn spawn_listening_node_at(
+        idle_connection_timeout: Duration,
+        root_dir: &Path,
+    ) -> Result<(Network, Multiaddr)> {
+        let (net, mut event_rx, driver) = SwarmDriver::with_idle_connection_timeout(
+            "127.0.0.1:0"
+                .parse::<SocketAddr>()
+                .expect("127.0.0.1:0 should parse into a valid `SocketAddr`"),
+            root_dir,
+            idle_connection_timeout,
+        )?;
+        let _driver_handle = tokio::spawn(driver.run());
+
+        let listen_addr = loop {
+            match event_rx.recv().await {
+                Some(NetworkEvent::NewListenAddr(addr)) => break addr,
+                Some(_) => continue,
+                None => return Err(eyre!("Network events channel closed")),
+            }
+        };
+        let _events_handle = tokio::spawn(async move { while event_rx.recv().await.is_some() {} });
+
+        Ok((net, listen_addr))
+    }
+
+    /// Test utility to record the level and message of the logged events
+    struct EventsRecorder(Arc<Mutex<Vec<(Level, String)>>>);
+
+    impl<S: Subscriber> Layer<S> for EventsRecorder {
+        fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+            let mut fields = BTreeMap::new();
+            event.record(&mut FieldsVisitor(&mut fields));
+            let msg = fields.remove("message").unwrap_or_default();
+            if let Ok(mut events) = self.0.lock() {
+                events.push((*event.metadata().level(), msg));
+            }
+        }
+    }
+
+    struct FieldsVisitor<'a>(&'a mut BTreeMap<String, String>);
+
+    impl Visit for FieldsVisitor<'_> {
+        fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+            let _ = self
+                .0
+                .insert(field.name().to_string(), format!("{value:?}"));
+        }
+    }
+
     #[cfg(feature = "local-discovery")]
     /// Test utility
     fn assert_lists<I, J, K>(a: I, b: J)
@@ -761,4 +2154,192 @@ mod tests {
 
         assert_eq!(vec2.len(), 0);
     }
+
+    #[tokio::test]
+    async fn peer_is_removed_once_its_last_inbound_connection_closes() -> Result<()> {
+        let (_net, mut event_rx, mut driver) = SwarmDriver::new_client()?;
+
+        let peer_id = PeerId::random();
+        let peer_addr: Multiaddr = "/ip4/127.0.0.1/udp/12001/quic-v1".parse()?;
+        let (sender, receiver) = oneshot::channel();
+        driver
+            .handle_cmd(SwarmCmd::AddToRoutingTable {
+                peer_id,
+                peer_addr: peer_addr.clone(),
+                sender,
+            })
+            .await?;
+        receiver.await??;
+
+        let in_routing_table = |driver: &mut SwarmDriver| {
+            driver
+                .swarm
+                .behaviour_mut()
+                .kademlia
+                .kbuckets()
+                .any(|bucket| {
+                    bucket
+                        .iter()
+                        .any(|entry| *entry.node.key.preimage() == peer_id)
+                })
+        };
+        let inbound = ConnectedPoint::Listener {
+            local_addr: "/ip4/127.0.0.1/udp/12000/quic-v1".parse()?,
+            send_back_addr: peer_addr.clone(),
+        };
+
+        // Another connection to the peer is still alive.
+        driver
+            .handle_connection_closed(peer_id, &inbound, 1)
+            .await?;
+        assert!(in_routing_table(&mut driver));
+
+        // A connection we dialed is left to be handled on our side.
+        let outbound = ConnectedPoint::Dialer {
+            address: peer_addr,
+            role_override: Endpoint::Dialer,
+        };
+        driver
+            .handle_connection_closed(peer_id, &outbound, 0)
+            .await?;
+        assert!(in_routing_table(&mut driver));
+        assert!(event_rx.try_recv().is_err());
+
+        // The last connection to the peer, which it initiated, is closed.
+        driver
+            .handle_connection_closed(peer_id, &inbound, 0)
+            .await?;
+        assert!(!in_routing_table(&mut driver));
+        assert_matches!(
+            event_rx.recv().await,
+            Some(NetworkEvent::PeersRemoved(peers)) if peers == vec![peer_id]
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn closed_peer_is_only_removed_if_not_reconnecting_within_grace_period() -> Result<()> {
+        let (_net, mut event_rx, mut driver) = SwarmDriver::new_client()?;
+        let grace_period = Duration::from_secs(30);
+        driver.set_dead_peer_grace_period(grace_period);
+
+        let peer_addr: Multiaddr = "/ip4/127.0.0.1/udp/12001/quic-v1".parse()?;
+        let inbound = ConnectedPoint::Listener {
+            local_addr: "/ip4/127.0.0.1/udp/12000/quic-v1".parse()?,
+            send_back_addr: peer_addr.clone(),
+        };
+        let reconnecting_peer = PeerId::random();
+        let dead_peer = PeerId::random();
+        for peer_id in [reconnecting_peer, dead_peer] {
+            let (sender, receiver) = oneshot::channel();
+            driver
+                .handle_cmd(SwarmCmd::AddToRoutingTable {
+                    peer_id,
+                    peer_addr: peer_addr.clone(),
+                    sender,
+                })
+                .await?;
+            receiver.await??;
+        }
+
+        let in_routing_table = |driver: &mut SwarmDriver, peer_id: PeerId| {
+            driver
+                .swarm
+                .behaviour_mut()
+                .kademlia
+                .kbuckets()
+                .any(|bucket| {
+                    bucket
+                        .iter()
+                        .any(|entry| *entry.node.key.preimage() == peer_id)
+                })
+        };
+
+        // Both peers close their last connection, they are kept for the time being.
+        for peer_id in [reconnecting_peer, dead_peer] {
+            driver
+                .handle_connection_closed(peer_id, &inbound, 0)
+                .await?;
+        }
+        assert!(in_routing_table(&mut driver, reconnecting_peer));
+        assert!(in_routing_table(&mut driver, dead_peer));
+        assert!(event_rx.try_recv().is_err());
+
+        // Only one of them reconnects within the grace period.
+        driver
+            .handle_swarm_events::<Error>(SwarmEvent::ConnectionEstablished {
+                peer_id: reconnecting_peer,
+                endpoint: inbound.clone(),
+                num_established: NonZeroU32::new(1).ok_or_else(|| eyre!("Zero connections"))?,
+                concurrent_dial_errors: None,
+                established_in: Duration::from_millis(10),
+            })
+            .await?;
+
+        // Nothing is removed before the grace period elapses.
+        driver.remove_closed_peers(Instant::now()).await?;
+        assert!(in_routing_table(&mut driver, dead_peer));
+        assert!(event_rx.try_recv().is_err());
+
+        // Once it elapses, only the peer which didn't reconnect is removed.
+        driver
+            .remove_closed_peers(Instant::now() + grace_period)
+            .await?;
+        assert!(in_routing_table(&mut driver, reconnecting_peer));
+        assert!(!in_routing_table(&mut driver, dead_peer));
+        assert_matches!(
+            event_rx.recv().await,
+            Some(NetworkEvent::PeersRemoved(peers)) if peers == vec![dead_peer]
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn relayed_peer_address_is_added_to_routing_table() -> Result<()> {
+        let (_network, _event_receiver, mut driver) = SwarmDriver::new_client()?;
+
+        let relay_id = PeerId::random();
+        let peer_id = PeerId::random();
+        let relay_addr: Multiaddr =
+            format!("/ip4/127.0.0.1/udp/12000/quic-v1/p2p/{relay_id}").parse()?;
+        let relayed_addr = relay_addr
+            .clone()
+            .with(Protocol::P2pCircuit)
+            .with(Protocol::P2p(peer_id.into()));
+        assert!(is_relayed(&relayed_addr));
+        assert!(!is_relayed(&relay_addr));
+
+        // The relayed address already ends with the peer's id, which is not to be appended again.
+        assert_eq!(dial_addr(relayed_addr.clone(), peer_id), relayed_addr);
+        assert_eq!(
+            dial_addr(relayed_addr.clone(), PeerId::random())
+                .iter()
+                .count(),
+            relayed_addr.iter().count() + 1
+        );
+
+        let (sender, receiver) = oneshot::channel();
+        driver
+            .handle_cmd(SwarmCmd::AddToRoutingTable {
+                peer_id,
+                peer_addr: relayed_addr.clone(),
+                sender,
+            })
+            .await?;
+        receiver.await??;
+
+        let mut peer_addrs = vec![];
+        for bucket in driver.swarm.behaviour_mut().kademlia.kbuckets() {
+            for entry in bucket.iter() {
+                if *entry.node.key.preimage() == peer_id {
+                    peer_addrs.extend(entry.node.value.iter().cloned());
+                }
+            }
+        }
+        assert_eq!(peer_addrs, vec![relayed_addr]);
+
+        Ok(())
+    }
 }
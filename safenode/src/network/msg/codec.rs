@@ -8,18 +8,42 @@
 
 use crate::protocol::messages::{Request, Response};
 use async_trait::async_trait;
-use futures::{AsyncRead, AsyncWrite, AsyncWriteExt};
+use futures::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
 use libp2p::{
-    core::upgrade::{read_length_prefixed, write_length_prefixed},
+    core::upgrade::{read_varint, write_length_prefixed},
     request_response::{self, ProtocolName},
 };
 use serde::{de::DeserializeOwned, Serialize};
-use std::io;
+use std::{
+    io,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+/// Default maximum size of the msgs exchanged with peers.
+pub(crate) const DEFAULT_MAX_MSG_SIZE: usize = 500_000_000;
 
 #[derive(Debug, Clone)]
 pub(crate) struct MsgProtocol();
+
+/// Codec of the msgs exchanged with peers, rejecting those larger than the max msg size,
+/// which is shared by all its clones so it can be changed once the swarm is built.
 #[derive(Clone)]
-pub(crate) struct MsgCodec();
+pub(crate) struct MsgCodec {
+    max_msg_size: Arc<AtomicUsize>,
+}
+
+impl MsgCodec {
+    pub(crate) fn new(max_msg_size: Arc<AtomicUsize>) -> Self {
+        Self { max_msg_size }
+    }
+
+    fn max_msg_size(&self) -> usize {
+        self.max_msg_size.load(Ordering::Relaxed)
+    }
+}
 
 impl ProtocolName for MsgProtocol {
     fn protocol_name(&self) -> &[u8] {
@@ -37,7 +61,7 @@ impl request_response::Codec for MsgCodec {
     where
         T: AsyncRead + Unpin + Send,
     {
-        read_and_decode(io).await
+        read_and_decode(io, self.max_msg_size()).await
     }
 
     async fn read_response<T>(
@@ -48,7 +72,7 @@ impl request_response::Codec for MsgCodec {
     where
         T: AsyncRead + Unpin + Send,
     {
-        read_and_decode(io).await
+        read_and_decode(io, self.max_msg_size()).await
     }
 
     async fn write_request<T>(
@@ -60,7 +84,7 @@ impl request_response::Codec for MsgCodec {
     where
         T: AsyncWrite + Unpin + Send,
     {
-        encode_and_write(io, req).await
+        encode_and_write(io, req, self.max_msg_size()).await
     }
 
     async fn write_response<T>(
@@ -72,33 +96,167 @@ impl request_response::Codec for MsgCodec {
     where
         T: AsyncWrite + Unpin + Send,
     {
-        encode_and_write(io, res).await
+        encode_and_write(io, res, self.max_msg_size()).await
     }
 }
 
 // Encodes the Response/Response using rmp_serde
-async fn encode_and_write<IO, T>(io: &mut IO, data: T) -> io::Result<()>
+async fn encode_and_write<IO, T>(io: &mut IO, data: T, max_msg_size: usize) -> io::Result<()>
 where
     IO: AsyncWrite + Unpin,
     T: Serialize,
 {
     let bytes = rmp_serde::to_vec(&data)
         .map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))?;
+    if bytes.len() > max_msg_size {
+        return Err(msg_too_large(bytes.len(), max_msg_size));
+    }
     write_length_prefixed(io, bytes).await?;
     io.close().await?;
     Ok(())
 }
 
-// Decodes the Response/Response using rmp_serde
-async fn read_and_decode<IO, T>(io: &mut IO) -> io::Result<T>
+// Decodes the Response/Response using rmp_serde. The size of the msg is checked against
+// the maximum before reading it, so an oversized msg is rejected without buffering it.
+async fn read_and_decode<IO, T>(io: &mut IO, max_msg_size: usize) -> io::Result<T>
 where
     IO: AsyncRead + Unpin,
     T: DeserializeOwned,
 {
-    let vec = read_length_prefixed(io, 500_000_000).await?; // update transfer maximum
-    if vec.is_empty() {
+    let len = read_varint(io).await?;
+    if len > max_msg_size {
+        return Err(msg_too_large(len, max_msg_size));
+    }
+    if len == 0 {
         return Err(io::ErrorKind::UnexpectedEof.into());
     }
+    let mut vec = vec![0; len];
+    io.read_exact(&mut vec).await?;
     rmp_serde::from_slice::<T>(vec.as_slice())
         .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
 }
+
+fn msg_too_large(size: usize, max_msg_size: usize) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("Msg of {size} bytes exceeds the maximum msg size of {max_msg_size} bytes"),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::protocol::{
+        messages::{Cmd, EditRegister, RegisterCmd, SignedRegisterEdit},
+        storage::{
+            registers::{DataAuthority, RegisterOp, User},
+            RegisterAddress,
+        },
+    };
+
+    use crdts::merkle_reg::MerkleReg;
+    use futures::io::Cursor;
+    use libp2p::{core::upgrade::write_varint, request_response::Codec};
+    use std::collections::BTreeSet;
+    use xor_name::XorName;
+
+    const MAX_MSG_SIZE: usize = 64 * 1024;
+
+    fn codec(max_msg_size: usize) -> MsgCodec {
+        MsgCodec::new(Arc::new(AtomicUsize::new(max_msg_size)))
+    }
+
+    fn register_edit_request(entry_size: usize) -> Request {
+        let sk = bls::SecretKey::random();
+        let address = RegisterAddress::new(XorName::default(), 0);
+        let op = EditRegister {
+            address,
+            edit: RegisterOp {
+                address,
+                crdt_op: MerkleReg::new().write(vec![0; entry_size], BTreeSet::new()),
+                source: User::Key(sk.public_key()),
+                signature: None,
+            },
+            expected_size: None,
+        };
+        // the codec doesn't verify the signature
+        let signature = sk.sign(b"edit");
+        Request::Cmd(Cmd::Register(RegisterCmd::Edit(SignedRegisterEdit {
+            op,
+            auth: DataAuthority {
+                public_key: sk.public_key(),
+                signature,
+            },
+        })))
+    }
+
+    fn assert_msg_too_large(result: io::Result<impl std::fmt::Debug>) {
+        match result {
+            Err(err) => {
+                assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+                assert!(
+                    err.to_string()
+                        .contains(&format!("exceeds the maximum msg size of {MAX_MSG_SIZE}")),
+                    "Unexpected error: {err}"
+                );
+            }
+            Ok(decoded) => panic!("Oversized msg should be rejected: {decoded:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn oversized_register_cmd_is_rejected_by_the_codec() -> io::Result<()> {
+        let request = register_edit_request(2 * MAX_MSG_SIZE);
+
+        // it's received from a peer with a higher max msg size
+        let mut io = Cursor::new(Vec::new());
+        codec(DEFAULT_MAX_MSG_SIZE)
+            .write_request(&MsgProtocol(), &mut io, request.clone())
+            .await?;
+        io.set_position(0);
+        assert_msg_too_large(
+            codec(MAX_MSG_SIZE)
+                .read_request(&MsgProtocol(), &mut io)
+                .await,
+        );
+
+        // and we don't send it either
+        let mut io = Cursor::new(Vec::new());
+        assert_msg_too_large(
+            codec(MAX_MSG_SIZE)
+                .write_request(&MsgProtocol(), &mut io, request)
+                .await,
+        );
+        assert!(io.get_ref().is_empty());
+
+        // whereas a cmd within the max msg size goes through
+        let request = register_edit_request(MAX_MSG_SIZE / 2);
+        let mut io = Cursor::new(Vec::new());
+        codec(MAX_MSG_SIZE)
+            .write_request(&MsgProtocol(), &mut io, request.clone())
+            .await?;
+        io.set_position(0);
+        let decoded = codec(MAX_MSG_SIZE)
+            .read_request(&MsgProtocol(), &mut io)
+            .await?;
+        assert_eq!(decoded, request);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn msg_claiming_to_be_oversized_is_rejected_before_reading_it() -> io::Result<()> {
+        // Only the length prefix of a ~1GB msg is sent, it's rejected without waiting for it.
+        let mut io = Cursor::new(Vec::new());
+        write_varint(&mut io, 1_000_000_000).await?;
+        io.set_position(0);
+        assert_msg_too_large(
+            codec(MAX_MSG_SIZE)
+                .read_request(&MsgProtocol(), &mut io)
+                .await,
+        );
+
+        Ok(())
+    }
+}
@@ -8,24 +8,75 @@
 
 mod codec;
 
-pub(crate) use codec::{MsgCodec, MsgProtocol};
+pub(crate) use codec::{MsgCodec, MsgProtocol, DEFAULT_MAX_MSG_SIZE};
 
 use crate::{
-    network::{error::Error, MsgResponder, NetworkEvent, SwarmDriver},
-    protocol::messages::{Request, Response},
+    network::{error::Error, request_queue::RequestClass, MsgResponder, NetworkEvent, SwarmDriver},
+    protocol::messages::{Query, Request, Response, SignedResponse},
 };
 
-use libp2p::request_response::{self, Message};
+use libp2p::{
+    identity::PublicKey,
+    request_response::{self, Message},
+    PeerId,
+};
 use tracing::{trace, warn};
 
 impl SwarmDriver {
     /// Forwards `Request` to the upper layers using `Sender<NetworkEvent>`. Sends `Response` to the peers
-    pub async fn handle_msg(
+    pub fn handle_msg(
         &mut self,
         event: request_response::Event<Request, Response>,
     ) -> Result<(), Error> {
         match event {
-            request_response::Event::Message { message, .. } => match message {
+            request_response::Event::Message { peer, message } => match message {
+                Message::Request {
+                    request: Request::ProtocolInfo,
+                    channel,
+                    request_id,
+                    ..
+                } => {
+                    // Answered right away, as it requires no involvement of the upper layers.
+                    trace!("Received ProtocolInfo request with id: {request_id:?}");
+                    let response = self.sign_response(Response::protocol_info())?;
+                    self.swarm
+                        .behaviour_mut()
+                        .request_response
+                        .send_response(channel, response)
+                        .map_err(Error::OutgoingResponseDropped)?;
+                }
+                Message::Request {
+                    request: Request::Query(Query::GetStoredChunk(address)),
+                    channel,
+                    request_id,
+                    ..
+                } => {
+                    // Answered right away from our local record store.
+                    trace!("Received GetStoredChunk request with id: {request_id:?}");
+                    let response = Response::Query(self.stored_chunk(&address));
+                    let response = self.sign_response(response)?;
+                    self.swarm
+                        .behaviour_mut()
+                        .request_response
+                        .send_response(channel, response)
+                        .map_err(Error::OutgoingResponseDropped)?;
+                }
+                Message::Request {
+                    request: Request::Query(Query::ChunkExists(address)),
+                    channel,
+                    request_id,
+                    ..
+                } => {
+                    // Answered right away from our local record store.
+                    trace!("Received ChunkExists request with id: {request_id:?}");
+                    let response = Response::Query(self.chunk_exists(&address));
+                    let response = self.sign_response(response)?;
+                    self.swarm
+                        .behaviour_mut()
+                        .request_response
+                        .send_response(channel, response)
+                        .map_err(Error::OutgoingResponseDropped)?;
+                }
                 Message::Request {
                     request,
                     channel,
@@ -33,22 +84,33 @@ impl SwarmDriver {
                     ..
                 } => {
                     trace!("Received request with id: {request_id:?}, req: {request:?}");
-                    self.event_sender
-                        .send(NetworkEvent::RequestReceived {
+                    let class = RequestClass::of(&request);
+                    let pushed = self.inbound_requests.push(
+                        class,
+                        NetworkEvent::RequestReceived {
                             req: request,
                             channel: MsgResponder::FromPeer(channel),
-                        })
-                        .await?
+                        },
+                    );
+                    if pushed.is_err() {
+                        // Dropping the response channel lets the peer know its request failed.
+                        warn!("Too many inbound requests queued, rejecting request with id: {request_id:?} from {peer:?}");
+                    }
+                    self.forward_inbound_requests();
                 }
                 Message::Response {
                     request_id,
                     response,
                 } => {
                     trace!("Got response for id: {request_id:?}, res: {response}.");
+                    let response = verify_response(response, &peer, self.require_signed_responses);
+                    if let Err(err) = &response {
+                        warn!("Rejecting response for id: {request_id:?}: {err}");
+                    }
                     self.pending_requests
                         .remove(&request_id)
                         .ok_or(Error::ReceivedResponseDropped(request_id))?
-                        .send(Ok(response))
+                        .send(response)
                         .map_err(|_| Error::InternalMsgChannelDropped)?;
                 }
             },
@@ -74,4 +136,48 @@ impl SwarmDriver {
         }
         Ok(())
     }
+
+    /// Signs the response with our keypair, if we've been set to sign the responses.
+    pub(super) fn sign_response(&self, response: Response) -> Result<Response, Error> {
+        if !self.sign_responses {
+            return Ok(response);
+        }
+        let bytes =
+            bincode::serialize(&response).map_err(|err| Error::ResponseSigning(err.to_string()))?;
+        let signature = self
+            .keypair
+            .sign(&bytes)
+            .map_err(|err| Error::ResponseSigning(err.to_string()))?;
+        Ok(Response::Signed(SignedResponse {
+            response: Box::new(response),
+            public_key: self.keypair.public().encode_protobuf(),
+            signature,
+        }))
+    }
+}
+
+/// Verifies a signed response was signed by the peer it's been received from, returning the
+/// response which was signed. Unsigned responses are rejected if `require_signed` is set,
+/// otherwise they are returned as they are.
+pub(super) fn verify_response(
+    response: Response,
+    peer: &PeerId,
+    require_signed: bool,
+) -> Result<Response, Error> {
+    let signed = match response {
+        Response::Signed(signed) => signed,
+        _ if require_signed => return Err(Error::InvalidResponseSignature(*peer)),
+        response => return Ok(response),
+    };
+    let public_key = PublicKey::try_decode_protobuf(&signed.public_key)
+        .map_err(|_| Error::InvalidResponseSignature(*peer))?;
+    if PeerId::from_public_key(&public_key) != *peer {
+        return Err(Error::InvalidResponseSignature(*peer));
+    }
+    let bytes =
+        bincode::serialize(&signed.response).map_err(|_| Error::InvalidResponseSignature(*peer))?;
+    if !public_key.verify(&bytes, &signed.signature) {
+        return Err(Error::InvalidResponseSignature(*peer));
+    }
+    Ok(*signed.response)
 }
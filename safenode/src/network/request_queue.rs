@@ -0,0 +1,288 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::protocol::messages::{Cmd, Request};
+use std::{collections::VecDeque, str::FromStr};
+
+// Number of classes the inbound requests are prioritised by.
+const NUM_OF_CLASSES: usize = 3;
+
+/// Default maximum number of inbound requests queued, waiting to be handled, at once.
+pub(super) const DEFAULT_MAX_QUEUED_REQUESTS: usize = 1024;
+
+/// Class of an inbound request, by which it's prioritised over the others.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum RequestClass {
+    /// Queries, i.e. reads, along with the other requests which only inspect our state.
+    Read,
+    /// Cmds, i.e. writes.
+    Write,
+    /// Requests to replicate, or sync, the data held by other peers, along with events.
+    Replication,
+}
+
+impl RequestClass {
+    pub(super) fn of(request: &Request) -> Self {
+        match request {
+            Request::Query(_) | Request::ProtocolInfo | Request::ValidateRegisterCmd(_) => {
+                Self::Read
+            }
+            Request::Cmd(Cmd::ReplicateRegister(_))
+            | Request::Cmd(Cmd::ReplicateRegisterLog(_))
+            | Request::RegisterSync { .. }
+            | Request::Event(_) => Self::Replication,
+            Request::Cmd(_) => Self::Write,
+        }
+    }
+
+    fn index(&self) -> usize {
+        match self {
+            Self::Read => 0,
+            Self::Write => 1,
+            Self::Replication => 2,
+        }
+    }
+}
+
+/// Weights of the classes of the inbound requests, i.e. how many requests of each class are
+/// handled in each round over the queued requests, which serves reads, then writes, then
+/// replication. A class with a zero weight is only served when no other class has any queued.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RequestWeights {
+    /// Number of queries to be handled in each round.
+    pub reads: usize,
+    /// Number of cmds to be handled in each round.
+    pub writes: usize,
+    /// Number of replication requests and events to be handled in each round.
+    pub replication: usize,
+}
+
+impl Default for RequestWeights {
+    fn default() -> Self {
+        Self {
+            reads: 4,
+            writes: 2,
+            replication: 1,
+        }
+    }
+}
+
+/// Parses the weights from the number of reads, writes and replication requests to be handled
+/// in each round, in that order, separated by commas, e.g. `4,2,1`.
+impl FromStr for RequestWeights {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let weights = s
+            .split(',')
+            .map(|weight| weight.trim().parse::<usize>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|err| format!("Invalid request weight in {s:?}: {err}"))?;
+        match weights[..] {
+            [reads, writes, replication] => Ok(Self {
+                reads,
+                writes,
+                replication,
+            }),
+            _ => Err(format!(
+                "Expected the weights of reads, writes and replication requests, got {s:?}"
+            )),
+        }
+    }
+}
+
+impl RequestWeights {
+    fn of(&self, class_index: usize) -> usize {
+        match class_index {
+            0 => self.reads,
+            1 => self.writes,
+            _ => self.replication,
+        }
+    }
+}
+
+/// Queues of the inbound requests waiting to be handled, one per class, served by
+/// weighted round robin so no class is starved while the others are prioritised.
+/// The requests coming in once `max_len` of them are queued are rejected.
+#[derive(Debug)]
+pub(super) struct InboundRequestQueue<T> {
+    weights: RequestWeights,
+    max_len: usize,
+    queues: [VecDeque<T>; NUM_OF_CLASSES],
+    // The class being served in the current round, and the number of its requests served so far.
+    current: usize,
+    served: usize,
+}
+
+impl<T> Default for InboundRequestQueue<T> {
+    fn default() -> Self {
+        Self::new(RequestWeights::default(), DEFAULT_MAX_QUEUED_REQUESTS)
+    }
+}
+
+impl<T> InboundRequestQueue<T> {
+    pub(super) fn new(weights: RequestWeights, max_len: usize) -> Self {
+        Self {
+            weights,
+            max_len,
+            queues: Default::default(),
+            current: 0,
+            served: 0,
+        }
+    }
+
+    pub(super) fn set_weights(&mut self, weights: RequestWeights) {
+        self.weights = weights;
+    }
+
+    /// Queues the request, unless `max_len` of them are already queued,
+    /// in which case it's handed back to be rejected.
+    pub(super) fn push(&mut self, class: RequestClass, request: T) -> Result<(), T> {
+        if self.len() >= self.max_len {
+            return Err(request);
+        }
+        self.queues[class.index()].push_back(request);
+        Ok(())
+    }
+
+    pub(super) fn len(&self) -> usize {
+        self.queues.iter().map(VecDeque::len).sum()
+    }
+
+    pub(super) fn is_empty(&self) -> bool {
+        self.queues.iter().all(|queue| queue.is_empty())
+    }
+
+    /// Returns the next request to be handled, if any is queued.
+    pub(super) fn pop(&mut self) -> Option<T> {
+        for _ in 0..NUM_OF_CLASSES {
+            if self.served < self.weights.of(self.current) {
+                if let Some(request) = self.queues[self.current].pop_front() {
+                    self.served += 1;
+                    return Some(request);
+                }
+            }
+            self.current = (self.current + 1) % NUM_OF_CLASSES;
+            self.served = 0;
+        }
+
+        // Only the classes with a zero weight have queued requests.
+        self.queues.iter_mut().find_map(|queue| queue.pop_front())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn drain(queue: &mut InboundRequestQueue<(RequestClass, usize)>) -> Vec<RequestClass> {
+        std::iter::from_fn(|| queue.pop())
+            .map(|(class, _)| class)
+            .collect()
+    }
+
+    #[test]
+    fn higher_priority_requests_are_handled_first() {
+        use RequestClass::*;
+        let mut queue = InboundRequestQueue::new(
+            RequestWeights {
+                reads: 3,
+                writes: 1,
+                replication: 1,
+            },
+            DEFAULT_MAX_QUEUED_REQUESTS,
+        );
+
+        // A backlog of writes and replication requests, which arrived before the reads.
+        let arrivals = [Write, Replication, Write, Write, Replication, Write]
+            .into_iter()
+            .chain([Read; 4]);
+        for (i, class) in arrivals.enumerate() {
+            assert!(queue.push(class, (class, i)).is_ok());
+        }
+
+        assert_eq!(
+            drain(&mut queue),
+            vec![
+                Read,
+                Read,
+                Read,
+                Write,
+                Replication,
+                Read,
+                Write,
+                Replication,
+                Write,
+                Write
+            ]
+        );
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn requests_of_a_class_are_handled_in_arrival_order() {
+        let mut queue = InboundRequestQueue::default();
+        for i in 0..5 {
+            assert!(queue.push(RequestClass::Write, i).is_ok());
+        }
+
+        let handled: Vec<_> = std::iter::from_fn(|| queue.pop()).collect();
+        assert_eq!(handled, vec![0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn zero_weight_class_is_handled_once_the_others_are_drained() {
+        use RequestClass::*;
+        let mut queue = InboundRequestQueue::new(
+            RequestWeights {
+                reads: 1,
+                writes: 1,
+                replication: 0,
+            },
+            DEFAULT_MAX_QUEUED_REQUESTS,
+        );
+        for (i, class) in [Replication, Write, Read, Replication]
+            .into_iter()
+            .enumerate()
+        {
+            assert!(queue.push(class, (class, i)).is_ok());
+        }
+
+        assert_eq!(
+            drain(&mut queue),
+            vec![Read, Write, Replication, Replication]
+        );
+    }
+
+    #[test]
+    fn weights_are_parsed_in_order() {
+        assert_eq!(
+            "3, 2,0".parse::<RequestWeights>(),
+            Ok(RequestWeights {
+                reads: 3,
+                writes: 2,
+                replication: 0,
+            })
+        );
+        assert!("3,2".parse::<RequestWeights>().is_err());
+        assert!("3,2,x".parse::<RequestWeights>().is_err());
+    }
+
+    #[test]
+    fn requests_beyond_the_cap_are_rejected() {
+        let mut queue = InboundRequestQueue::new(RequestWeights::default(), 2);
+        assert!(queue.push(RequestClass::Read, 0).is_ok());
+        assert!(queue.push(RequestClass::Write, 1).is_ok());
+        assert_eq!(queue.push(RequestClass::Read, 2), Err(2));
+
+        // Room is made again as the queued requests are handled.
+        assert_eq!(queue.pop(), Some(0));
+        assert!(queue.push(RequestClass::Read, 2).is_ok());
+        assert_eq!(queue.len(), 2);
+    }
+}
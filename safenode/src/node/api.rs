@@ -7,22 +7,27 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 use super::{
+    close_group::CloseGroupView,
     error::{Error, Result},
     event::NodeEventsChannel,
     Network, Node, NodeEvent,
 };
 
 use crate::{
-    domain::dbc_genesis::is_genesis_parent_tx,
-    network::{close_group_majority, MsgResponder, NetworkEvent, SwarmDriver, SwarmLocalState},
+    domain::{dbc_genesis::is_genesis_parent_tx, storage::register_op_id},
+    network::{
+        close_group_majority, Error as NetworkError, MsgResponder, NetworkEvent, RequestWeights,
+        SwarmDriver, SwarmLocalState, CLOSE_GROUP_SIZE,
+    },
     node::{RegisterStorage, Transfers},
     protocol::{
         error::{Error as ProtocolError, StorageError, TransferError},
         messages::{
-            Cmd, CmdResponse, Event, Query, QueryResponse, RegisterCmd, Request, Response,
-            SpendQuery,
+            Cmd, CmdResponse, Event, Query, QueryResponse, RegisterCmd, ReplicatedRegisterLog,
+            Request, Response, SpendQuery,
         },
-        storage::{registers::User, DbcAddress},
+        storage::{ChunkAddress, DbcAddress, RegisterAddress},
+        NetworkAddress,
     },
 };
 
@@ -32,8 +37,19 @@ use libp2p::{
     kad::{Record, RecordKey},
     Multiaddr, PeerId,
 };
-use std::{collections::BTreeSet, net::SocketAddr, path::Path};
-use tokio::{sync::mpsc, task::spawn};
+use std::{
+    collections::{hash_map, BTreeSet, HashMap},
+    future::Future,
+    net::SocketAddr,
+    num::NonZeroUsize,
+    path::Path,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio::{
+    sync::{mpsc, Semaphore},
+    task::spawn,
+};
 
 #[derive(Debug)]
 pub(super) struct TransferAction {
@@ -43,6 +59,18 @@ pub(super) struct TransferAction {
     response_channel: MsgResponder,
 }
 
+// Number of Registers held which are listed at a time when syncing them with a peer.
+const REGISTER_SYNC_PAGE_SIZE: usize = 100;
+// Minimum time between two syncs of the Registers with the same peer, e.g. when it churns.
+const REGISTER_SYNC_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// Default number of closest peers a `RegisterCmd` accepted by a node is forwarded to.
+pub const DEFAULT_REGISTER_REPLICAS: usize = CLOSE_GROUP_SIZE;
+
+/// Default time a peer whose last connection closed is given to reconnect,
+/// before being removed from the routing table.
+pub const DEFAULT_DEAD_PEER_GRACE_PERIOD: Duration = Duration::from_secs(30);
+
 /// Once a node is started and running, the user obtains
 /// a `NodeRunning` object which can be used to interact with it.
 pub struct RunningNode {
@@ -66,6 +94,19 @@ impl RunningNode {
     pub fn node_events_channel(&self) -> &NodeEventsChannel {
         &self.node_events_channel
     }
+
+    /// Pins the chunk at the given address, so this node never removes it
+    /// from its storage until it's unpinned.
+    pub async fn pin_chunk(&self, address: ChunkAddress) -> Result<()> {
+        self.network.pin_chunk(address).await?;
+        Ok(())
+    }
+
+    /// Unpins the chunk at the given address, so this node can remove it from its storage again.
+    pub async fn unpin_chunk(&self, address: ChunkAddress) -> Result<()> {
+        self.network.unpin_chunk(address).await?;
+        Ok(())
+    }
 }
 
 impl Node {
@@ -73,6 +114,24 @@ impl Node {
     /// creating a data storage, and handling network events. Returns the
     /// created node and a `NodeEventsChannel` for listening to node-related
     /// events.
+    /// If `sign_responses` is set, the responses sent to peers are signed with the node's keypair.
+    /// A chunk is only acknowledged as stored once it's been stored by at least `min_replicas` peers,
+    /// a majority of the close group if not provided.
+    /// If a `relay` is provided, the node can also be reached through it, e.g. when behind a NAT.
+    /// A `RegisterCmd` the node accepts is forwarded to the `register_replicas` closest peers to
+    /// the Register, `DEFAULT_REGISTER_REPLICAS` if not provided.
+    /// If `disable_mdns` is set, peers are not discovered on the local network, relying solely
+    /// on the `initial_peers` to join the network.
+    /// The Registers held in memory are capped to `register_cache_max_bytes`, if provided.
+    /// If `lenient_register_load` is set, Registers are loaded skipping any cmd of their log
+    /// which can't be applied.
+    /// The requests received from peers are prioritised by the `request_weights`, if provided.
+    /// The inbound connections held at once are capped to `max_inbound_connections`, if provided.
+    /// A peer whose last connection closed is given `dead_peer_grace_period` to reconnect before
+    /// being removed from the routing table, `DEFAULT_DEAD_PEER_GRACE_PERIOD` if not provided.
+    /// The record and closest peers queries in flight at once are capped to `max_pending_queries`,
+    /// if provided.
+    /// The msgs exchanged with peers are capped to `max_msg_size` bytes, if provided.
     ///
     /// # Returns
     ///
@@ -81,23 +140,88 @@ impl Node {
     /// # Errors
     ///
     /// Returns an error if there is a problem initializing the `SwarmDriver`.
+    #[allow(clippy::too_many_arguments)]
     pub async fn run(
         addr: SocketAddr,
         initial_peers: Vec<(PeerId, Multiaddr)>,
         root_dir: &Path,
+        sign_responses: bool,
+        min_replicas: Option<NonZeroUsize>,
+        relay: Option<Multiaddr>,
+        register_replicas: Option<NonZeroUsize>,
+        disable_mdns: bool,
+        register_cache_max_bytes: Option<usize>,
+        lenient_register_load: bool,
+        request_weights: Option<RequestWeights>,
+        max_inbound_connections: Option<u32>,
+        dead_peer_grace_period: Option<Duration>,
+        max_pending_queries: Option<usize>,
+        max_msg_size: Option<usize>,
     ) -> Result<RunningNode> {
-        let (network, mut network_event_receiver, swarm_driver) = SwarmDriver::new(addr, root_dir)?;
+        let (network, mut network_event_receiver, mut swarm_driver) = if disable_mdns {
+            SwarmDriver::without_mdns(addr, root_dir)?
+        } else {
+            SwarmDriver::new(addr, root_dir)?
+        };
+        swarm_driver.set_sign_responses(sign_responses);
+        if let Some(min_replicas) = min_replicas {
+            swarm_driver.set_min_replicas(min_replicas);
+        }
+        if let Some(relay_addr) = relay {
+            swarm_driver.set_relay(relay_addr)?;
+        }
+        if let Some(weights) = request_weights {
+            swarm_driver.set_request_weights(weights);
+        }
+        swarm_driver.set_max_inbound_connections(max_inbound_connections);
+        swarm_driver.set_dead_peer_grace_period(
+            dead_peer_grace_period.unwrap_or(DEFAULT_DEAD_PEER_GRACE_PERIOD),
+        );
+        if let Some(max_pending_queries) = max_pending_queries {
+            swarm_driver.set_max_pending_queries(max_pending_queries);
+        }
+        if let Some(max_msg_size) = max_msg_size {
+            swarm_driver.set_max_msg_size(max_msg_size);
+        }
         let node_events_channel = NodeEventsChannel::default();
+        let mut registers = RegisterStorage::new(root_dir);
+        if let Some(max_bytes) = register_cache_max_bytes {
+            registers.set_cache_max_bytes(max_bytes);
+        }
+        registers.set_lenient_load(lenient_register_load);
+        // Fail fast if the store can't be written to, rather than upon storing every cmd.
+        registers
+            .check_writable()
+            .await
+            .map_err(ProtocolError::Storage)?;
+        // Complete any batch of Register cmds whose writing was interrupted, e.g. by a crash.
+        let _ = registers
+            .replay_journals()
+            .await
+            .map_err(ProtocolError::Storage)?;
+        registers.set_network(network.clone());
+        // Only the cmds for Registers among whose closest peers we are get stored.
+        let register_replicas =
+            register_replicas.map_or(DEFAULT_REGISTER_REPLICAS, NonZeroUsize::get);
+        let close_group = CloseGroupView::new(network.peer_id, register_replicas);
+        let view = close_group.clone();
+        registers.set_responsibility(move |address| {
+            view.is_responsible(&NetworkAddress::from_register_address(*address))
+        });
 
         let (transfer_action_sender, mut transfer_action_receiver) = mpsc::channel(100);
 
         let mut node = Self {
             network: network.clone(),
-            registers: RegisterStorage::new(root_dir),
+            registers,
             transfers: Transfers::new(root_dir),
             events_channel: node_events_channel.clone(),
             initial_peers,
             transfer_actor: transfer_action_sender,
+            register_replicas,
+            close_group,
+            register_syncs: HashMap::new(),
+            register_sync_permit: Arc::new(Semaphore::new(1)),
         };
 
         let _handle = spawn(swarm_driver.run());
@@ -135,9 +259,37 @@ impl Node {
             }
             NetworkEvent::PeerAdded(peer_id) => {
                 debug!("PeerAdded: {peer_id}");
+                self.close_group.add(peer_id);
 
                 self.events_channel.broadcast(NodeEvent::ConnectedToNetwork);
             }
+            NetworkEvent::PeersRemoved(peer_ids) => {
+                debug!("PeersRemoved: {peer_ids:?}");
+                self.close_group.remove(&peer_ids);
+            }
+            NetworkEvent::PeerEvicted(peer_id) => {
+                debug!("PeerEvicted: {peer_id}");
+                self.close_group.remove(&[peer_id]);
+            }
+            NetworkEvent::ReplicationCandidate { peer } => {
+                trace!("Peer {peer} is a candidate to replicate the data we hold to");
+                let now = Instant::now();
+                self.register_syncs
+                    .retain(|_, synced_at| now.duration_since(*synced_at) < REGISTER_SYNC_INTERVAL);
+                if let hash_map::Entry::Vacant(entry) = self.register_syncs.entry(peer) {
+                    let _ = entry.insert(now);
+                    let network = self.network.clone();
+                    let registers = self.registers.clone();
+                    let close_group = self.close_group.clone();
+                    let permit = self.register_sync_permit.clone();
+                    let _handle = spawn(async move {
+                        let _permit = permit.acquire_owned().await;
+                        sync_registers_with(network, registers, close_group, peer).await
+                    });
+                } else {
+                    trace!("Registers were synced with peer {peer} recently, skipping it");
+                }
+            }
             NetworkEvent::NewListenAddr(_) => {
                 let network = self.network.clone();
                 let peers = self.initial_peers.clone();
@@ -149,6 +301,18 @@ impl Node {
                     }
                 });
             }
+            NetworkEvent::RegisterUpdated {
+                address,
+                new_entries,
+            } => {
+                debug!(
+                    "Register {address:?} updated with {} new entries",
+                    new_entries.len()
+                );
+            }
+            NetworkEvent::InboundDhtRequest { kind } => {
+                trace!("Served a {kind:?} DHT request");
+            }
         }
     }
 
@@ -157,6 +321,29 @@ impl Node {
         match request {
             Request::Cmd(cmd) => self.handle_cmd(cmd, response_channel).await,
             Request::Query(query) => self.handle_query(query, response_channel).await,
+            // Only reaches us when sent to ourselves, otherwise it's answered by the network layer.
+            Request::ProtocolInfo => {
+                self.send_response(Response::protocol_info(), response_channel)
+                    .await
+            }
+            Request::RegisterSync { address, have } => {
+                let delta = self
+                    .registers
+                    .log_delta(&address, &have)
+                    .await
+                    .map_err(ProtocolError::Storage);
+                self.send_response(Response::RegisterSyncDelta(delta), response_channel)
+                    .await
+            }
+            Request::ValidateRegisterCmd(cmd) => {
+                let result = self
+                    .registers
+                    .validate(&cmd)
+                    .await
+                    .map_err(ProtocolError::Storage);
+                self.send_response(Response::Validation(result), response_channel)
+                    .await
+            }
             Request::Event(event) => {
                 let result = match event {
                     Event::ValidSpendReceived {
@@ -184,7 +371,8 @@ impl Node {
 
     async fn handle_query(&mut self, query: Query, response_channel: MsgResponder) {
         let resp = match query {
-            Query::Register(query) => self.registers.read(&query, User::Anyone).await,
+            // Queries are not signed by the requester, hence it's anonymous to us.
+            Query::Register(query) => self.registers.read(&query, None).await,
             Query::GetChunk(address) => {
                 match self
                     .network
@@ -198,6 +386,22 @@ impl Node {
                     }
                 }
             }
+            // Only reaches us when sent to ourselves, otherwise it's answered by the network layer.
+            Query::GetStoredChunk(address) => match self.network.get_stored_chunk(address).await {
+                Ok(response) => response,
+                Err(err) => {
+                    error!("Error getting chunk from local store: {err}");
+                    QueryResponse::GetChunk(Err(StorageError::ChunkNotFound(address).into()))
+                }
+            },
+            // Only reaches us when sent to ourselves, otherwise it's answered by the network layer.
+            Query::ChunkExists(address) => match self.network.chunk_exists(address).await {
+                Ok(response) => response,
+                Err(err) => {
+                    error!("Error checking chunk in local store: {err}");
+                    QueryResponse::ChunkExists(Err(StorageError::ChunkNotFound(address).into()))
+                }
+            },
             Query::Spend(query) => match query {
                 SpendQuery::GetDbcSpend(address) => {
                     let res = self
@@ -228,20 +432,38 @@ impl Node {
                     expires: None,
                 };
 
-                let resp = match self.network.put_data_as_record(record).await {
-                    Ok(()) => {
-                        self.events_channel.broadcast(NodeEvent::ChunkStored(addr));
-                        CmdResponse::StoreChunk(Ok(()))
-                    }
-                    Err(err) => {
-                        error!("Failed to StoreChunk: {err:?}");
-                        CmdResponse::StoreChunk(Err(
-                            StorageError::ChunkNotStored(*addr.name()).into()
-                        ))
+                // The chunk is only acknowledged once it's been stored by enough peers,
+                // which we don't hold up the handling of other msgs for.
+                let network = self.network.clone();
+                let events_channel = self.events_channel.clone();
+                let _handle = spawn(async move {
+                    let resp = match network.put_data_as_record(record).await {
+                        Ok(()) => {
+                            events_channel.broadcast(NodeEvent::ChunkStored(addr));
+                            CmdResponse::StoreChunk(Ok(()))
+                        }
+                        Err(NetworkError::InsufficientReplication { got, needed }) => {
+                            warn!("Chunk {addr:?} stored by {got} node/s only, out of the {needed} required");
+                            CmdResponse::StoreChunk(Err(StorageError::InsufficientReplication {
+                                got,
+                                needed,
+                            }
+                            .into()))
+                        }
+                        Err(err) => {
+                            error!("Failed to StoreChunk: {err:?}");
+                            CmdResponse::StoreChunk(Err(
+                                StorageError::ChunkNotStored(*addr.name()).into()
+                            ))
+                        }
+                    };
+                    if let Err(err) = network
+                        .send_response(Response::Cmd(resp), response_channel)
+                        .await
+                    {
+                        warn!("Error while sending response: {err:?}");
                     }
-                };
-                self.send_response(Response::Cmd(resp), response_channel)
-                    .await;
+                });
             }
             Cmd::Register(cmd) => {
                 let result = self
@@ -250,6 +472,14 @@ impl Node {
                     .await
                     .map_err(ProtocolError::Storage);
 
+                if result.is_ok() {
+                    let network = self.network.clone();
+                    let cmd = cmd.clone();
+                    let target = self.register_replicas;
+                    let _handle =
+                        spawn(async move { replicate_register_cmd(network, cmd, target).await });
+                }
+
                 let xorname = cmd.dst();
                 let resp = match cmd {
                     RegisterCmd::Create(_) => {
@@ -257,7 +487,7 @@ impl Node {
                             .broadcast(NodeEvent::RegisterCreated(xorname));
                         CmdResponse::CreateRegister(result)
                     }
-                    RegisterCmd::Edit(_) => {
+                    RegisterCmd::Edit(_) | RegisterCmd::SetExpiry(_) => {
                         self.events_channel
                             .broadcast(NodeEvent::RegisterEdited(xorname));
                         CmdResponse::EditRegister(result)
@@ -266,6 +496,41 @@ impl Node {
                 self.send_response(Response::Cmd(resp), response_channel)
                     .await;
             }
+            Cmd::ReplicateRegister(cmd) => {
+                // Forwarded by the peer which accepted it, so it's not forwarded any further.
+                let result = self
+                    .registers
+                    .write(&cmd)
+                    .await
+                    .map_err(ProtocolError::Storage);
+                if let Err(err) = &result {
+                    warn!("Failed to store replicated Register cmd {cmd:?}: {err:?}");
+                }
+                self.send_response(
+                    Response::Cmd(CmdResponse::ReplicateRegister(result)),
+                    response_channel,
+                )
+                .await;
+            }
+            Cmd::ReplicateRegisterLog(log) => {
+                // Pushed by a peer holding the Register, so it's not forwarded any further.
+                let result = self
+                    .registers
+                    .update(&log)
+                    .await
+                    .map_err(ProtocolError::Storage);
+                if let Err(err) = &result {
+                    warn!(
+                        "Failed to store the replicated log of Register {:?}: {err:?}",
+                        log.address
+                    );
+                }
+                self.send_response(
+                    Response::Cmd(CmdResponse::ReplicateRegister(result)),
+                    response_channel,
+                )
+                .await;
+            }
             Cmd::SpendDbc {
                 signed_spend,
                 parent_tx,
@@ -530,3 +795,198 @@ async fn get_spend(network: Network, address: DbcAddress) -> Result<SignedSpend>
     // If there was none of the above, then we had unexpected responses.
     Err(Error::UnexpectedResponses)
 }
+
+// Forwards the `RegisterCmd` to the `target` closest peers to the Register, other than ourselves.
+async fn replicate_register_cmd(network: Network, cmd: RegisterCmd, target: usize) {
+    let addr = cmd.dst();
+    let dst = NetworkAddress::from_register_address(addr);
+    let closest_peers = match network.node_get_closest_peers(&dst).await {
+        Ok(peers) => peers,
+        Err(err) => {
+            warn!("Failed to get the closest peers to replicate Register {addr:?} to: {err:?}");
+            return;
+        }
+    };
+    let our_id = network.peer_id;
+    let peers = closest_peers
+        .into_iter()
+        .filter(|peer| *peer != our_id)
+        .collect();
+
+    let request = Request::Cmd(Cmd::ReplicateRegister(cmd));
+    let replicated = forward_to_peers(peers, target, |peer| {
+        network.send_request(request.clone(), peer)
+    })
+    .await;
+    if replicated < target {
+        warn!("Register {addr:?} cmd replicated to {replicated} peer/s only, out of the {target} targeted");
+    } else {
+        trace!("Register {addr:?} cmd replicated to {replicated} peer/s");
+    }
+}
+
+// Syncs the Registers we hold, which the peer is also responsible for, with the peer's:
+// pushing ours to it, e.g. as it just joined and holds none of them, and bringing ours up
+// to date with the peer's, e.g. with the cmds we missed while the peer was unreachable.
+async fn sync_registers_with(
+    network: Network,
+    registers: RegisterStorage,
+    close_group: CloseGroupView,
+    peer: PeerId,
+) {
+    let mut offset = 0;
+    loop {
+        let addrs = registers.addrs_page(offset, REGISTER_SYNC_PAGE_SIZE).await;
+        if addrs.is_empty() {
+            break;
+        }
+        offset += addrs.len();
+        for address in addrs {
+            if !close_group
+                .is_peer_responsible(peer, &NetworkAddress::from_register_address(address))
+            {
+                continue;
+            }
+            let replica = match registers.get_register_replica(&address).await {
+                Ok(replica) => replica,
+                Err(err) => {
+                    debug!("Failed to read Register {address:?} to sync it: {err:?}");
+                    continue;
+                }
+            };
+            let have = match replica.op_log.iter().map(register_op_id).collect() {
+                Ok(have) => have,
+                Err(err) => {
+                    debug!("Failed to read the cmd ids of Register {address:?}: {err:?}");
+                    continue;
+                }
+            };
+            if let Err(err) = push_register(&network, replica, peer).await {
+                debug!("Failed to push Register {address:?} to peer {peer:?}: {err:?}");
+            }
+            if let Err(err) = sync_register(&network, &registers, address, have, peer).await {
+                debug!("Failed to sync Register {address:?} with peer {peer:?}: {err:?}");
+            }
+        }
+    }
+}
+
+// Pushes our replica of the Register to the given peer, which stores the cmds it's missing.
+async fn push_register(
+    network: &Network,
+    replica: ReplicatedRegisterLog,
+    peer: PeerId,
+) -> Result<()> {
+    let request = Request::Cmd(Cmd::ReplicateRegisterLog(replica));
+    match network.send_request(request, peer).await? {
+        Response::Cmd(CmdResponse::ReplicateRegister(result)) => Ok(result?),
+        other => {
+            warn!("Unexpected response to a ReplicateRegisterLog cmd: {other:?}");
+            Err(Error::UnexpectedResponses)
+        }
+    }
+}
+
+// Brings our replica of the Register, whose cmds we hold are those with the `have` ids,
+// up to date with the one held by the given peer, fetching from it only those we don't hold yet.
+async fn sync_register(
+    network: &Network,
+    registers: &RegisterStorage,
+    address: RegisterAddress,
+    have: BTreeSet<String>,
+    peer: PeerId,
+) -> Result<()> {
+    let request = Request::RegisterSync { address, have };
+    match network.send_request(request, peer).await? {
+        Response::RegisterSyncDelta(delta) => {
+            let delta = delta?;
+            if delta.op_log.is_empty() {
+                return Ok(());
+            }
+            debug!(
+                "Received {} cmd/s of Register {address:?} missing from our replica",
+                delta.op_log.len()
+            );
+            registers
+                .update(&delta)
+                .await
+                .map_err(ProtocolError::Storage)?;
+            Ok(())
+        }
+        other => {
+            warn!("Unexpected response to a RegisterSync request: {other:?}");
+            Err(Error::UnexpectedResponses)
+        }
+    }
+}
+
+// Sends the request to the peers, in the given order, until `target` of them have accepted it.
+// A peer failing to do so is replaced by the next one. Returns how many peers accepted it.
+async fn forward_to_peers<F, Fut>(peers: Vec<PeerId>, target: usize, mut send_request: F) -> usize
+where
+    F: FnMut(PeerId) -> Fut,
+    Fut: Future<Output = std::result::Result<Response, NetworkError>>,
+{
+    let mut accepted = 0;
+    for peer in peers {
+        if accepted == target {
+            break;
+        }
+        match send_request(peer).await {
+            Ok(Response::Cmd(CmdResponse::ReplicateRegister(Ok(())))) => accepted += 1,
+            Ok(resp) => debug!("Peer {peer:?} didn't accept the replicated cmd: {resp:?}"),
+            Err(err) => debug!("Failed to replicate the cmd to peer {peer:?}: {err:?}"),
+        }
+    }
+    accepted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::{Arc, Mutex};
+
+    #[tokio::test]
+    async fn register_cmd_is_forwarded_to_the_configured_number_of_peers() {
+        let peers: Vec<_> = (0..CLOSE_GROUP_SIZE).map(|_| PeerId::random()).collect();
+        let target = 3;
+
+        // The second peer rejects the cmd, so it has to be replaced by the next one.
+        let rejecting_peer = peers[1];
+        let reached = Arc::new(Mutex::new(vec![]));
+        let replicated = forward_to_peers(peers.clone(), target, |peer| {
+            let reached = reached.clone();
+            async move {
+                if let Ok(mut reached) = reached.lock() {
+                    reached.push(peer);
+                }
+                let result = if peer == rejecting_peer {
+                    Err(StorageError::StoreNotWritable {
+                        path: Default::default(),
+                    }
+                    .into())
+                } else {
+                    Ok(())
+                };
+                Ok(Response::Cmd(CmdResponse::ReplicateRegister(result)))
+            }
+        })
+        .await;
+
+        assert_eq!(replicated, target);
+        let reached = reached.lock().map(|r| r.clone()).unwrap_or_default();
+        assert_eq!(reached, peers[..target + 1].to_vec());
+    }
+
+    #[tokio::test]
+    async fn replication_below_target_reports_the_peers_reached() {
+        let peers: Vec<_> = (0..2).map(|_| PeerId::random()).collect();
+        let replicated = forward_to_peers(peers, 3, |_| async {
+            Ok(Response::Cmd(CmdResponse::ReplicateRegister(Ok(()))))
+        })
+        .await;
+
+        assert_eq!(replicated, 2);
+    }
+}
@@ -0,0 +1,111 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::protocol::NetworkAddress;
+
+use libp2p::PeerId;
+use std::{
+    collections::BTreeSet,
+    sync::{Arc, PoisonError, RwLock},
+};
+
+/// The node's view of the peers in its routing table, kept up to date with the peers
+/// added and removed, telling whether the node is among the closest peers to an address,
+/// i.e. whether it's responsible for the data at it.
+#[derive(Clone)]
+pub(super) struct CloseGroupView {
+    self_peer_id: PeerId,
+    // Number of closest peers to an address which are responsible for it.
+    group_size: usize,
+    peers: Arc<RwLock<BTreeSet<PeerId>>>,
+}
+
+impl CloseGroupView {
+    pub(super) fn new(self_peer_id: PeerId, group_size: usize) -> Self {
+        Self {
+            self_peer_id,
+            group_size,
+            peers: Default::default(),
+        }
+    }
+
+    pub(super) fn add(&self, peer: PeerId) {
+        let _ = self
+            .peers
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(peer);
+    }
+
+    pub(super) fn remove(&self, peers: &[PeerId]) {
+        let mut known_peers = self.peers.write().unwrap_or_else(PoisonError::into_inner);
+        for peer in peers {
+            let _ = known_peers.remove(peer);
+        }
+    }
+
+    /// Whether fewer than `group_size` of the peers known are closer to the address than us.
+    /// Until we know enough peers, e.g. upon joining the network, we deem ourselves responsible.
+    pub(super) fn is_responsible(&self, address: &NetworkAddress) -> bool {
+        self.is_peer_responsible(self.self_peer_id, address)
+    }
+
+    /// Whether fewer than `group_size` of the peers known, ourselves included, are closer
+    /// to the address than the given peer.
+    pub(super) fn is_peer_responsible(&self, peer: PeerId, address: &NetworkAddress) -> bool {
+        let peer_distance = address.distance(&NetworkAddress::from_peer(peer));
+        let known_peers = self.peers.read().unwrap_or_else(PoisonError::into_inner);
+        let closer_peers = known_peers
+            .iter()
+            .chain(std::iter::once(&self.self_peer_id))
+            .filter(|known| **known != peer)
+            .filter(|known| address.distance(&NetworkAddress::from_peer(**known)) < peer_distance)
+            .take(self.group_size)
+            .count();
+        closer_peers < self.group_size
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CloseGroupView;
+
+    use crate::protocol::{storage::RegisterAddress, NetworkAddress};
+
+    use libp2p::PeerId;
+
+    #[test]
+    fn responsible_only_if_among_the_closest_peers() {
+        let self_peer_id = PeerId::random();
+        let view = CloseGroupView::new(self_peer_id, 3);
+        let address =
+            NetworkAddress::from_register_address(RegisterAddress::new(rand::random(), 0));
+        assert!(view.is_responsible(&address));
+
+        let mut peers: Vec<_> = (0..10).map(|_| PeerId::random()).collect();
+        peers.push(self_peer_id);
+        peers.sort_by_key(|peer| address.distance(&NetworkAddress::from_peer(*peer)));
+        let self_rank = peers
+            .iter()
+            .position(|peer| *peer == self_peer_id)
+            .unwrap_or_default();
+        for peer in peers.iter().filter(|peer| **peer != self_peer_id) {
+            view.add(*peer);
+        }
+        assert_eq!(view.is_responsible(&address), self_rank < 3);
+
+        // Same for any other peer, with us counted among those closer to the address.
+        let others: Vec<_> = peers.iter().filter(|peer| **peer != self_peer_id).collect();
+        assert!(view.is_peer_responsible(*others[0], &address));
+        assert!(!view.is_peer_responsible(*others[others.len() - 1], &address));
+
+        // Once the peers closer than us are gone, we are responsible.
+        view.remove(&peers[..self_rank]);
+        assert!(view.is_responsible(&address));
+    }
+}
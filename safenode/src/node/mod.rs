@@ -7,15 +7,16 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 mod api;
+mod close_group;
 mod error;
 mod event;
 
 pub use self::{
-    api::RunningNode,
+    api::{RunningNode, DEFAULT_DEAD_PEER_GRACE_PERIOD, DEFAULT_REGISTER_REPLICAS},
     event::{NodeEvent, NodeEventsChannel, NodeEventsReceiver},
 };
 
-use self::api::TransferAction;
+use self::{api::TransferAction, close_group::CloseGroupView};
 
 use crate::{
     domain::{node_transfers::Transfers, storage::RegisterStorage},
@@ -23,7 +24,8 @@ use crate::{
 };
 
 use libp2p::{Multiaddr, PeerId};
-use tokio::sync::mpsc;
+use std::{collections::HashMap, sync::Arc, time::Instant};
+use tokio::sync::{mpsc, Semaphore};
 
 /// `Node` represents a single node in the distributed network. It handles
 /// network events, processes incoming requests, interacts with the data
@@ -36,4 +38,13 @@ pub struct Node {
     /// Peers that are dialed at startup of node.
     initial_peers: Vec<(PeerId, Multiaddr)>,
     transfer_actor: mpsc::Sender<TransferAction>,
+    /// Number of closest peers a `RegisterCmd` accepted by the node is forwarded to.
+    register_replicas: usize,
+    /// The peers in the routing table, telling which Registers the node is responsible for.
+    close_group: CloseGroupView,
+    /// When the Registers were last synced with each peer, so they're synced with the same peer
+    /// at most once within `REGISTER_SYNC_INTERVAL`.
+    register_syncs: HashMap<PeerId, Instant>,
+    /// Lets a single sync of the Registers run at a time, however many peers are added at once.
+    register_sync_permit: Arc<Semaphore>,
 }
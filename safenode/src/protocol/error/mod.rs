@@ -29,4 +29,8 @@ pub enum Error {
     /// Errors in node transfer handling.
     #[error("Transfer error: {0:?}")]
     Transfers(#[from] TransferError),
+    /// The record was found on fewer peers than the quorum required, hence worth retrying,
+    /// unlike when it's not found at all.
+    #[error("Quorum not reached for the record")]
+    QuorumFailed,
 }
@@ -7,7 +7,7 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 use crate::protocol::storage::{
-    registers::{EntryHash, User},
+    registers::{Action, EntryHash, Permissions, User},
     ChunkAddress, DbcAddress, RegisterAddress,
 };
 
@@ -15,6 +15,7 @@ use sn_dbc::SignedSpend;
 use xor_name::XorName;
 
 use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
 use thiserror::Error;
 
 /// Errors related to storage operation on the network.
@@ -27,12 +28,72 @@ pub enum StorageError {
     /// We failed to store chunk
     #[error("Chunk was not stored w/ xorname {0:?}")]
     ChunkNotStored(XorName),
+    /// Chunk was stored by fewer nodes than required.
+    #[error("Chunk was stored by {got} node/s, fewer than the {needed} required")]
+    InsufficientReplication {
+        /// Number of nodes the chunk was stored by.
+        got: usize,
+        /// Number of nodes the chunk is required to be stored by.
+        needed: usize,
+    },
     /// Register not found.
     #[error("Register not found: {0:?}")]
     RegisterNotFound(RegisterAddress),
+    /// The Register has expired, i.e. the expiry set on it by its owner has been reached,
+    /// so it can no longer be read, although its data remains stored until collected.
+    #[error("Register has expired: {0:?}")]
+    RegisterExpired(RegisterAddress),
+    /// The user is not the owner of the Register, and so can't perform an operation
+    /// only its owner is allowed to, e.g. setting its expiry.
+    #[error("User {user:?} is not the owner of Register {address:?}")]
+    NotRegisterOwner {
+        /// Address of the Register.
+        address: RegisterAddress,
+        /// User who is not the owner.
+        user: User,
+    },
     /// Register operation was not stored.
     #[error("Register operation was not stored: {0:?}")]
     RegisterCmdNotStored(RegisterAddress),
+    /// A cmd of a Register's log couldn't be read from storage, e.g. as its file is corrupted.
+    #[error("Register cmd of {address:?} could not be read from {path:?}")]
+    RegisterCmdUnreadable {
+        /// Address of the Register.
+        address: RegisterAddress,
+        /// Path of the file the cmd was read from.
+        path: PathBuf,
+    },
+    /// The store's directory cannot be written to, e.g. its disk is mounted read-only
+    /// or it lacks the required permissions.
+    #[error("The store at {path:?} is not writable, check its disk and permissions")]
+    StoreNotWritable {
+        /// Path of the store's directory.
+        path: PathBuf,
+    },
+    /// The node is not responsible for the Register, i.e. its address falls outside the range
+    /// of addresses the node stores, so a cmd for it was misrouted and is rejected.
+    #[error("Not responsible for the Register at {address:?}")]
+    NotResponsible {
+        /// Address of the Register the cmd was for.
+        address: RegisterAddress,
+    },
+    /// A Registers archive couldn't be exported or imported.
+    #[error("Register archive error: {0}")]
+    RegisterArchive(String),
+    /// Register operation not found in the Register's log.
+    #[error("Register operation {op_id} not found in the log of Register {address:?}")]
+    RegisterOpNotFound {
+        /// Address of the Register.
+        address: RegisterAddress,
+        /// Id of the operation.
+        op_id: String,
+    },
+    /// The entries of a Register couldn't be compressed, or decompressed.
+    #[error("Register entries compression error: {0}")]
+    RegisterCompression(String),
+    /// The verification of a Register's log couldn't be completed.
+    #[error("The verification of the log of Register {0:?} couldn't be completed")]
+    RegisterLogNotVerified(RegisterAddress),
     /// Register operation destination address mistmatch
     #[error(
         "The CRDT operation cannot be applied since the Register operation destination address ({dst_addr:?}) \
@@ -45,8 +106,21 @@ pub enum StorageError {
         reg_addr: RegisterAddress,
     },
     /// Access denied for user
-    #[error("Access denied for user: {0:?}")]
-    AccessDenied(User),
+    #[error(
+        "Access denied for user {user:?} to perform {action:?} on Register {address:?}, \
+        permissions granted to the user: {granted:?}"
+    )]
+    AccessDenied {
+        /// Address of the Register the access was denied to
+        address: RegisterAddress,
+        /// User the access was denied to
+        user: User,
+        /// Action the user was not allowed to perform
+        action: Action,
+        /// Permissions the policy of the Register grants to the user, i.e. those set for
+        /// the user, or else those set for anyone, if any
+        granted: Option<Permissions>,
+    },
     /// Entry is too big to fit inside a register
     #[error("Entry is too big to fit inside a register: {size}, max: {max}")]
     EntryTooBig {
@@ -58,6 +132,14 @@ pub enum StorageError {
     /// Cannot add another entry since the register entry cap has been reached.
     #[error("Cannot add another entry since the register entry cap has been reached: {0}")]
     TooManyEntries(usize),
+    /// The Register size didn't match the one expected by a conditional write.
+    #[error("Register precondition failed, expected size: {expected}, actual size: {actual}")]
+    PreconditionFailed {
+        /// Size of the Register expected by the operation
+        expected: u64,
+        /// Actual size of the Register
+        actual: u64,
+    },
     /// Entry could not be found on the data
     #[error("Requested entry not found {0}")]
     NoSuchEntry(EntryHash),
@@ -11,7 +11,7 @@ use crate::protocol::{
     NetworkAddress,
 };
 
-use super::RegisterCmd;
+use super::{RegisterCmd, ReplicatedRegisterLog};
 
 use sn_dbc::{DbcTransaction, SignedSpend};
 
@@ -34,6 +34,16 @@ pub enum Cmd {
     ///
     /// [`Register`]: crate::protocol::storage::Register
     Register(RegisterCmd),
+    /// [`Register`] write operation forwarded by the node which accepted it,
+    /// so the receiver stores it without forwarding it any further.
+    ///
+    /// [`Register`]: crate::protocol::storage::Register
+    ReplicateRegister(RegisterCmd),
+    /// The whole log of a [`Register`], pushed by a node holding it to a peer which became
+    /// responsible for it, so the receiver stores the cmds it's missing from it.
+    ///
+    /// [`Register`]: crate::protocol::storage::Register
+    ReplicateRegisterLog(ReplicatedRegisterLog),
     /// [`SignedSpend`] write operation.
     ///
     /// [`SignedSpend`]: sn_dbc::SignedSpend
@@ -55,7 +65,10 @@ impl Cmd {
             Cmd::StoreChunk(chunk) => {
                 NetworkAddress::from_chunk_address(ChunkAddress::new(*chunk.name()))
             }
-            Cmd::Register(cmd) => NetworkAddress::from_register_address(cmd.dst()),
+            Cmd::Register(cmd) | Cmd::ReplicateRegister(cmd) => {
+                NetworkAddress::from_register_address(cmd.dst())
+            }
+            Cmd::ReplicateRegisterLog(log) => NetworkAddress::from_register_address(log.address),
             Cmd::SpendDbc { signed_spend, .. } => {
                 NetworkAddress::from_dbc_address(DbcAddress::from_dbc_id(signed_spend.dbc_id()))
             }
@@ -72,6 +85,12 @@ impl std::fmt::Display for Cmd {
             Cmd::Register(cmd) => {
                 write!(f, "Cmd::Register({:?})", cmd.name()) // more qualification needed
             }
+            Cmd::ReplicateRegister(cmd) => {
+                write!(f, "Cmd::ReplicateRegister({:?})", cmd.name())
+            }
+            Cmd::ReplicateRegisterLog(log) => {
+                write!(f, "Cmd::ReplicateRegisterLog({:?})", log.address.name())
+            }
             Cmd::SpendDbc { signed_spend, .. } => {
                 write!(f, "Cmd::SpendDbc({:?})", signed_spend.dbc_id())
             }
@@ -19,17 +19,19 @@ pub use self::{
     cmd::Cmd,
     event::Event,
     node_id::NodeId,
-    query::Query,
+    query::{Query, SUPPORTED_QUERIES},
     register::{
-        CreateRegister, EditRegister, RegisterCmd, RegisterQuery, ReplicatedRegisterLog,
-        SignedRegisterCreate, SignedRegisterEdit,
+        compress_register_entries, decompress_register_entries, CreateRegister, EditRegister,
+        RegisterCmd, RegisterQuery, ReplicatedRegisterLog, SetRegisterExpiry, SignedRegisterCreate,
+        SignedRegisterEdit, SignedRegisterSetExpiry,
     },
     response::{CmdResponse, QueryResponse},
     spend::SpendQuery,
 };
 
 use super::{
-    storage::{Chunk, DbcAddress},
+    error::Result,
+    storage::{Chunk, DbcAddress, RegisterAddress},
     NetworkAddress,
 };
 
@@ -39,6 +41,9 @@ use serde::{Deserialize, Serialize};
 use std::{collections::BTreeSet, fmt::Debug};
 use xor_name::XorName;
 
+/// Version of the request/response messages protocol spoken by this node.
+pub const MSG_PROTOCOL_VERSION: u16 = 1;
+
 /// A request to peers in the network
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Request {
@@ -48,6 +53,20 @@ pub enum Request {
     Query(Query),
     /// A fact sent to peers.
     Event(Event),
+    /// A request for the messages protocol version and the queries supported by a peer.
+    /// It's not related to any address, hence it's meant to be sent to a specific peer.
+    ProtocolInfo,
+    /// A request for the cmds of a Register's log the requester doesn't hold yet,
+    /// which is to be answered with a `Response::RegisterSyncDelta`.
+    RegisterSync {
+        /// Address of the Register.
+        address: RegisterAddress,
+        /// Ids of the cmds of the Register's log the requester already holds.
+        have: BTreeSet<String>,
+    },
+    /// A request to validate a `RegisterCmd`, i.e. its signature, permissions and whether it can
+    /// be applied to the Register, without storing it. To be answered with a `Response::Validation`.
+    ValidateRegisterCmd(RegisterCmd),
 }
 
 /// A response to peers in the network.
@@ -57,6 +76,42 @@ pub enum Response {
     Cmd(CmdResponse),
     /// The response to a query.
     Query(QueryResponse),
+    /// The response to a `Request::ProtocolInfo`.
+    ProtocolInfo {
+        /// Version of the messages protocol spoken by the peer.
+        version: u16,
+        /// Names of the queries supported by the peer.
+        supported_queries: Vec<String>,
+    },
+    /// The response to a `Request::RegisterSync`, with only the cmds the requester was missing.
+    RegisterSyncDelta(Result<ReplicatedRegisterLog>),
+    /// The response to a `Request::ValidateRegisterCmd`, telling whether the cmd would be stored.
+    Validation(Result<()>),
+    /// A response signed by the node which sent it.
+    Signed(SignedResponse),
+}
+
+/// A `Response` along with the signature of the node which sent it, over the serialised response.
+/// It allows the recipient to verify the response was sent by the peer it's been received from,
+/// and that it hasn't been tampered with, which content addressing doesn't cover for e.g. registers.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignedResponse {
+    /// The response which was signed.
+    pub response: Box<Response>,
+    /// The protobuf encoding of the public key of the node which signed the response.
+    pub public_key: Vec<u8>,
+    /// The signature over the serialised response.
+    pub signature: Vec<u8>,
+}
+
+impl Response {
+    /// Returns the `ProtocolInfo` response describing the protocol spoken by this node.
+    pub fn protocol_info() -> Self {
+        Response::ProtocolInfo {
+            version: MSG_PROTOCOL_VERSION,
+            supported_queries: SUPPORTED_QUERIES.iter().map(|q| q.to_string()).collect(),
+        }
+    }
 }
 
 /// Messages to replicated data among nodes on the network
@@ -79,11 +134,19 @@ pub enum ReplicatedData {
 
 impl Request {
     /// Used to send a request to the close group of the address.
-    pub fn dst(&self) -> NetworkAddress {
+    /// Returns `None` for requests which are not related to any address.
+    pub fn dst(&self) -> Option<NetworkAddress> {
         match self {
-            Request::Cmd(cmd) => cmd.dst(),
-            Request::Query(query) => query.dst(),
-            Request::Event(event) => event.dst(),
+            Request::Cmd(cmd) => Some(cmd.dst()),
+            Request::Query(query) => Some(query.dst()),
+            Request::Event(event) => Some(event.dst()),
+            Request::ProtocolInfo => None,
+            Request::RegisterSync { address, .. } => {
+                Some(NetworkAddress::from_register_address(*address))
+            }
+            Request::ValidateRegisterCmd(cmd) => {
+                Some(NetworkAddress::from_register_address(cmd.dst()))
+            }
         }
     }
 }
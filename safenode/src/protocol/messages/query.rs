@@ -12,6 +12,28 @@ use super::{RegisterQuery, SpendQuery};
 
 use serde::{Deserialize, Serialize};
 
+/// Names of the queries supported by this node, as reported in a `ProtocolInfo` response.
+pub const SUPPORTED_QUERIES: &[&str] = &[
+    "GetChunk",
+    "GetStoredChunk",
+    "ChunkExists",
+    "Register::Get",
+    "Register::Read",
+    "Register::ReadCompressed",
+    "Register::ReadWithTips",
+    "Register::ReadWithLog",
+    "Register::ReadBranch",
+    "Register::ReadSince",
+    "Register::GetTips",
+    "Register::GetEntry",
+    "Register::GetPolicy",
+    "Register::GetUserPermissions",
+    "Register::CanPerform",
+    "Register::GetOwner",
+    "Register::GetOwners",
+    "Spend::GetDbcSpend",
+];
+
 /// Data queries - retrieving data and inspecting their structure.
 ///
 /// See the [`protocol`] module documentation for more details of the types supported by the Safe
@@ -28,6 +50,22 @@ pub enum Query {
     /// [`Chunk`]:  crate::protocol::storage::Chunk
     /// [`GetChunk`]: super::QueryResponse::GetChunk
     GetChunk(ChunkAddress),
+    /// Retrieve a [`Chunk`] at the given address only if it's held by the queried node itself,
+    /// i.e. without looking it up across the network, to find out which nodes hold a replica.
+    ///
+    /// This should eventually lead to a [`GetChunk`] response.
+    ///
+    /// [`Chunk`]:  crate::protocol::storage::Chunk
+    /// [`GetChunk`]: super::QueryResponse::GetChunk
+    GetStoredChunk(ChunkAddress),
+    /// Check whether a [`Chunk`] at the given address is held by the queried node itself,
+    /// as `GetStoredChunk` does, but without sending the chunk back.
+    ///
+    /// This should eventually lead to a [`ChunkExists`] response.
+    ///
+    /// [`Chunk`]:  crate::protocol::storage::Chunk
+    /// [`ChunkExists`]: super::QueryResponse::ChunkExists
+    ChunkExists(ChunkAddress),
     /// [`Register`] read operation.
     ///
     /// [`Register`]: crate::protocol::storage::Register
@@ -42,7 +80,9 @@ impl Query {
     /// Used to send a query to the close group of the address.
     pub fn dst(&self) -> NetworkAddress {
         match self {
-            Query::GetChunk(address) => NetworkAddress::from_chunk_address(*address),
+            Query::GetChunk(address)
+            | Query::GetStoredChunk(address)
+            | Query::ChunkExists(address) => NetworkAddress::from_chunk_address(*address),
             Query::Register(query) => NetworkAddress::from_register_address(query.dst()),
             Query::Spend(query) => NetworkAddress::from_dbc_address(query.dst()),
         }
@@ -55,6 +95,12 @@ impl std::fmt::Display for Query {
             Query::GetChunk(address) => {
                 write!(f, "Query::GetChunk({address:?})")
             }
+            Query::GetStoredChunk(address) => {
+                write!(f, "Query::GetStoredChunk({address:?})")
+            }
+            Query::ChunkExists(address) => {
+                write!(f, "Query::ChunkExists({address:?})")
+            }
             Query::Register(query) => {
                 write!(f, "Query::Register({:?})", query.dst()) // more qualification needed
             }
@@ -6,17 +6,32 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use crate::protocol::storage::{
-    registers::{DataAuthority, Entry, EntryHash, Policy, RegisterOp, User},
-    RegisterAddress,
+use crate::protocol::{
+    error::{Result, StorageError},
+    storage::{
+        registers::{Action, DataAuthority, Entry, EntryHash, Policy, RegisterOp, User},
+        RegisterAddress,
+    },
 };
 
 #[allow(unused_imports)] // needed by rustdocs links
 use crate::protocol::{messages::QueryResponse, storage::registers::Register};
 
+use bytes::Bytes;
 use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeSet,
+    io::{Read, Write},
+    time::SystemTime,
+};
 use xor_name::XorName;
 
+// Size of the buffers used to compress and decompress the entries of a Register.
+const COMPRESSION_BUFFER_SIZE: usize = 4096;
+// Quality, i.e. level, and window size the entries of a Register are compressed with.
+const COMPRESSION_QUALITY: u32 = 5;
+const COMPRESSION_WINDOW_SIZE: u32 = 22;
+
 /// Register data exchange among replicas on the network.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ReplicatedRegisterLog {
@@ -37,11 +52,103 @@ pub enum RegisterQuery {
     Get(RegisterAddress),
     /// Retrieve the current entries from the [`Register`] at the given address.
     ///
-    /// Multiple entries occur on concurrent writes. This should eventually lead to a
-    /// [`ReadRegister`] response.
+    /// Multiple entries occur on concurrent writes, which are sorted by their hash, so the
+    /// response is the same for any replica holding the same entries, regardless of the order
+    /// they were applied in. This should eventually lead to a [`ReadRegister`] response.
     ///
     /// [`ReadRegister`]: QueryResponse::ReadRegister
     Read(RegisterAddress),
+    /// Same as [`Read`], but the entries are to be sent compressed, for requesters on slow links
+    /// to transfer less, who are then to decompress them with [`decompress_register_entries`].
+    ///
+    /// This should eventually lead to a [`ReadRegisterCompressed`] response.
+    ///
+    /// [`Read`]: RegisterQuery::Read
+    /// [`ReadRegisterCompressed`]: QueryResponse::ReadRegisterCompressed
+    ReadCompressed(RegisterAddress),
+    /// Same as [`Read`], but along with the entries the hashes of the current tips of the
+    /// [`Register`] are also to be sent, as [`GetTips`] would, so a requester caching the
+    /// entries can later check with [`ChangedSince`] whether it has changed from the tips.
+    ///
+    /// This should eventually lead to a [`ReadRegisterWithTips`] response.
+    ///
+    /// [`Read`]: RegisterQuery::Read
+    /// [`GetTips`]: RegisterQuery::GetTips
+    /// [`ChangedSince`]: RegisterQuery::ChangedSince
+    /// [`ReadRegisterWithTips`]: QueryResponse::ReadRegisterWithTips
+    ReadWithTips(RegisterAddress),
+    /// Same as [`Read`], but along with the entries the whole log of cmds of the [`Register`]
+    /// is also to be sent, for requesters who can't otherwise authenticate the node serving the
+    /// read to reconstruct the Register out of it themselves, verifying every signature, at the
+    /// cost of the bandwidth taken by the log.
+    ///
+    /// This should eventually lead to a [`ReadRegisterWithLog`] response.
+    ///
+    /// [`Read`]: RegisterQuery::Read
+    /// [`ReadRegisterWithLog`]: QueryResponse::ReadRegisterWithLog
+    ReadWithLog(RegisterAddress),
+    /// Retrieve the entries from the [`Register`] at the given address which are part of the
+    /// branch ending at the given `tip`, i.e. the `tip` entry along with all its ancestors.
+    ///
+    /// This should eventually lead to a [`ReadRegisterBranch`] response.
+    ///
+    /// [`ReadRegisterBranch`]: QueryResponse::ReadRegisterBranch
+    ReadBranch {
+        /// Register address.
+        address: RegisterAddress,
+        /// The hash of the entry the branch ends at.
+        tip: EntryHash,
+    },
+    /// Retrieve all the entries from the [`Register`] at the given address which were written
+    /// by the given `author`, i.e. by cmds signed with the `author`'s key.
+    ///
+    /// This should eventually lead to a [`ReadRegisterByAuthor`] response.
+    ///
+    /// [`ReadRegisterByAuthor`]: QueryResponse::ReadRegisterByAuthor
+    ReadByAuthor {
+        /// Register address.
+        address: RegisterAddress,
+        /// The user whose entries are to be retrieved.
+        author: User,
+    },
+    /// Retrieve the entries from the [`Register`] at the given address which were written at or
+    /// after `since`, e.g. for feed-like clients to poll for its recent activity.
+    ///
+    /// The times the entries were written at are those they were stored at by the node serving
+    /// the query, as told by its own clock, so they may be skewed from the requester's clock,
+    /// and differ among replicas, e.g. for entries replicated to a node well after being written.
+    ///
+    /// This should eventually lead to a [`ReadRegisterSince`] response.
+    ///
+    /// [`ReadRegisterSince`]: QueryResponse::ReadRegisterSince
+    ReadSince {
+        /// Register address.
+        address: RegisterAddress,
+        /// The time, as told by the serving node's clock, from which entries are to be retrieved.
+        since: SystemTime,
+    },
+    /// Retrieve the hashes of the current tips of the [`Register`] at the given address, i.e.
+    /// the entries no other entry has as parent, to be referenced by a new entry extending it.
+    ///
+    /// This should eventually lead to a [`GetRegisterTips`] response.
+    ///
+    /// [`GetRegisterTips`]: QueryResponse::GetRegisterTips
+    GetTips {
+        /// Register address.
+        address: RegisterAddress,
+    },
+    /// Check whether the [`Register`] at the given address has changed since the given tip,
+    /// i.e. whether it's no longer its only current entry, without retrieving any entries.
+    ///
+    /// This should eventually lead to a [`RegisterChangedSince`] response.
+    ///
+    /// [`RegisterChangedSince`]: QueryResponse::RegisterChangedSince
+    ChangedSince {
+        /// Register address.
+        address: RegisterAddress,
+        /// The hash of the latest entry known by the requester.
+        known_tip_hash: EntryHash,
+    },
     /// Get an entry from a [`Register`] on the Network by its hash
     ///
     /// This should eventually lead to a [`GetRegisterEntry`] response.
@@ -70,12 +177,36 @@ pub enum RegisterQuery {
         /// User to get permissions for.
         user: User,
     },
+    /// Check whether the given user could perform the given action on the [`Register`] at the
+    /// given address, as per its policy, without performing it, e.g. to find out beforehand
+    /// whether a write would be forbidden.
+    ///
+    /// This should eventually lead to a [`RegisterCanPerform`] response.
+    ///
+    /// [`RegisterCanPerform`]: QueryResponse::RegisterCanPerform
+    CanPerform {
+        /// Register address.
+        address: RegisterAddress,
+        /// User who would perform the action.
+        user: User,
+        /// The action to be checked.
+        action: Action,
+    },
     /// Retrieve the owner of the [`Register`] at the given address.
     ///
     /// This should eventually lead to a [`GetRegisterOwner`] response.
     ///
     /// [`GetRegisterOwner`]: QueryResponse::GetRegisterOwner
     GetOwner(RegisterAddress),
+    /// Retrieve the owners of all the [`Register`]s at the given addresses at once.
+    ///
+    /// This should eventually lead to a [`GetRegisterOwners`] response.
+    ///
+    /// [`GetRegisterOwners`]: QueryResponse::GetRegisterOwners
+    GetOwners {
+        /// Addresses of the Registers.
+        addresses: Vec<RegisterAddress>,
+    },
 }
 
 /// A [`Register`] cmd that is stored in a log on Adults.
@@ -86,6 +217,9 @@ pub enum RegisterCmd {
     Create(SignedRegisterCreate),
     /// Edit the [`Register`].
     Edit(SignedRegisterEdit),
+    /// Set the time the [`Register`] expires at, after which it can no longer be read.
+    /// Only its owner can set it.
+    SetExpiry(SignedRegisterSetExpiry),
 }
 
 ///
@@ -97,6 +231,9 @@ pub struct CreateRegister {
     pub tag: u64,
     /// The policy of the [`Register`].
     pub policy: Policy,
+    /// Entries to be written to the [`Register`] as part of its creation.
+    /// They are authorised by the same signature as the creation cmd itself.
+    pub initial_entries: Vec<RegisterOp<Entry>>,
 }
 
 impl CreateRegister {
@@ -121,6 +258,19 @@ pub struct EditRegister {
     pub address: RegisterAddress,
     /// The operation to perform.
     pub edit: RegisterOp<Entry>,
+    /// If set, the operation is applied only if the [`Register`] currently
+    /// holds exactly this number of entries, enabling compare-and-swap writes.
+    pub expected_size: Option<u64>,
+}
+
+/// Sets the time a [`Register`] expires at.
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
+pub struct SetRegisterExpiry {
+    /// The address of the [`Register`] to set the expiry of.
+    pub address: RegisterAddress,
+    /// The time the [`Register`] expires at, as told by the clock of the nodes storing it.
+    /// If set more than once, the earliest of the times is the one it expires at.
+    pub expires_at: SystemTime,
 }
 
 /// A signed cmd to create a [`Register`].
@@ -145,6 +295,17 @@ pub struct SignedRegisterEdit {
     pub auth: DataAuthority,
 }
 
+/// A signed cmd to set the expiry of a [`Register`].
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
+pub struct SignedRegisterSetExpiry {
+    /// The expiry to set.
+    pub op: SetRegisterExpiry,
+    /// A signature carrying authority to perform the operation.
+    ///
+    /// This will be verified against the Register's owner.
+    pub auth: DataAuthority,
+}
+
 impl SignedRegisterCreate {
     /// Returns the dst address of the register.
     pub fn dst(&self) -> RegisterAddress {
@@ -152,6 +313,13 @@ impl SignedRegisterCreate {
     }
 }
 
+impl SignedRegisterSetExpiry {
+    /// Returns the dst address of the register.
+    pub fn dst(&self) -> RegisterAddress {
+        self.op.address
+    }
+}
+
 impl SignedRegisterEdit {
     /// Returns the dst address of the register.
     pub fn dst(&self) -> RegisterAddress {
@@ -165,14 +333,68 @@ impl RegisterQuery {
         match self {
             Self::Get(ref address)
             | Self::Read(ref address)
+            | Self::ReadCompressed(ref address)
+            | Self::ReadWithTips(ref address)
+            | Self::ReadWithLog(ref address)
+            | Self::ReadBranch { ref address, .. }
+            | Self::ReadByAuthor { ref address, .. }
+            | Self::ReadSince { ref address, .. }
+            | Self::GetTips { ref address }
+            | Self::ChangedSince { ref address, .. }
             | Self::GetPolicy(ref address)
             | Self::GetUserPermissions { ref address, .. }
+            | Self::CanPerform { ref address, .. }
             | Self::GetEntry { ref address, .. }
             | Self::GetOwner(ref address) => *address,
+            // The query is sent to the close group of the first address, any
+            // register not held there will be reported as not found.
+            Self::GetOwners { ref addresses } => addresses
+                .first()
+                .copied()
+                .unwrap_or_else(|| RegisterAddress::new(XorName::default(), 0)),
         }
     }
 }
 
+/// Compresses the serialised entries of a Register, as read from it, to be sent over the wire.
+pub fn compress_register_entries(entries: &BTreeSet<(EntryHash, Entry)>) -> Result<Bytes> {
+    let serialized = bincode::serialize(entries)
+        .map_err(|err| StorageError::RegisterCompression(err.to_string()))?;
+    let mut compressor = brotli::CompressorWriter::new(
+        Vec::new(),
+        COMPRESSION_BUFFER_SIZE,
+        COMPRESSION_QUALITY,
+        COMPRESSION_WINDOW_SIZE,
+    );
+    compressor
+        .write_all(&serialized)
+        .map_err(|err| StorageError::RegisterCompression(err.to_string()))?;
+    Ok(Bytes::from(compressor.into_inner()))
+}
+
+/// Decompresses the entries of a Register compressed with [`compress_register_entries`].
+/// Decompression is aborted once the entries exceed `max_size` bytes, so a peer can't
+/// exhaust our memory answering with a small payload decompressing to a huge one.
+pub fn decompress_register_entries(
+    compressed: &[u8],
+    max_size: usize,
+) -> Result<BTreeSet<(EntryHash, Entry)>> {
+    let mut serialized = Vec::new();
+    let _ = brotli::Decompressor::new(compressed, COMPRESSION_BUFFER_SIZE)
+        .take(max_size as u64 + 1)
+        .read_to_end(&mut serialized)
+        .map_err(|err| StorageError::RegisterCompression(err.to_string()))?;
+    if serialized.len() > max_size {
+        return Err(StorageError::RegisterCompression(format!(
+            "Decompressed entries exceed the maximum size of {max_size} bytes"
+        ))
+        .into());
+    }
+    let entries = bincode::deserialize(&serialized)
+        .map_err(|err| StorageError::RegisterCompression(err.to_string()))?;
+    Ok(entries)
+}
+
 impl RegisterCmd {
     /// Returns the name of the register.
     /// This is not a unique identifier.
@@ -185,6 +407,7 @@ impl RegisterCmd {
         match self {
             Self::Create(cmd) => cmd.dst(),
             Self::Edit(cmd) => cmd.dst(),
+            Self::SetExpiry(cmd) => cmd.dst(),
         }
     }
 }
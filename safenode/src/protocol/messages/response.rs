@@ -10,18 +10,24 @@ use crate::protocol::{
     error::Result,
     storage::{
         registers::{Entry, EntryHash, Permissions, Policy, Register, User},
-        Chunk,
+        Chunk, RegisterAddress,
     },
 };
 
+use super::ReplicatedRegisterLog;
+
 #[allow(unused_imports)] // needed by rustdocs links
 use super::RegisterQuery;
 
 use sn_dbc::SignedSpend;
 
+use bytes::Bytes;
 use serde::{Deserialize, Serialize};
 use std::{collections::BTreeSet, fmt::Debug};
 
+// The entries of a Register, along with the hashes of its current tips.
+type EntriesWithTips = (BTreeSet<(EntryHash, Entry)>, BTreeSet<EntryHash>);
+
 /// The response to a query, containing the query result.
 #[allow(clippy::large_enum_variant)]
 #[derive(Clone, PartialEq, Eq, Serialize, Deserialize, custom_debug::Debug)]
@@ -44,6 +50,10 @@ pub enum QueryResponse {
     ///
     /// [`GetChunk`]: crate::protocol::messages::Query::GetChunk
     GetChunk(Result<Chunk>),
+    /// Response to [`ChunkExists`], erring with `ChunkNotFound` if the chunk isn't held.
+    ///
+    /// [`ChunkExists`]: crate::protocol::messages::Query::ChunkExists
+    ChunkExists(Result<()>),
     //
     // ===== Register Data =====
     //
@@ -53,12 +63,35 @@ pub enum QueryResponse {
     GetRegisterEntry(Result<Entry>),
     /// Response to [`RegisterQuery::GetOwner`].
     GetRegisterOwner(Result<User>),
+    /// Response to [`RegisterQuery::GetOwners`], with the result for each of the addresses queried.
+    GetRegisterOwners(Vec<(RegisterAddress, Result<User>)>),
     /// Response to [`RegisterQuery::Read`].
     ReadRegister(Result<BTreeSet<(EntryHash, Entry)>>),
+    /// Response to [`RegisterQuery::ReadCompressed`], with the entries compressed.
+    ReadRegisterCompressed(Result<Bytes>),
+    /// Response to [`RegisterQuery::ReadWithTips`], with the entries along with the hashes
+    /// of the current tips.
+    ReadRegisterWithTips(Result<EntriesWithTips>),
+    /// Response to [`RegisterQuery::ReadWithLog`], with the entries along with the log of cmds
+    /// they are to be verified against.
+    ReadRegisterWithLog(Result<(BTreeSet<(EntryHash, Entry)>, ReplicatedRegisterLog)>),
+    /// Response to [`RegisterQuery::ReadBranch`].
+    ReadRegisterBranch(Result<BTreeSet<(EntryHash, Entry)>>),
+    /// Response to [`RegisterQuery::ReadByAuthor`].
+    ReadRegisterByAuthor(Result<BTreeSet<(EntryHash, Entry)>>),
+    /// Response to [`RegisterQuery::ReadSince`].
+    ReadRegisterSince(Result<BTreeSet<(EntryHash, Entry)>>),
+    /// Response to [`RegisterQuery::GetTips`].
+    GetRegisterTips(Result<BTreeSet<EntryHash>>),
+    /// Response to [`RegisterQuery::ChangedSince`], with the hash of a current entry other
+    /// than the known tip if the Register has changed, or `None` if it hasn't.
+    RegisterChangedSince(Result<Option<EntryHash>>),
     /// Response to [`RegisterQuery::GetPolicy`].
     GetRegisterPolicy(Result<Policy>),
     /// Response to [`RegisterQuery::GetUserPermissions`].
     GetRegisterUserPermissions(Result<Permissions>),
+    /// Response to [`RegisterQuery::CanPerform`], with whether the user could perform the action.
+    RegisterCanPerform(Result<bool>),
 }
 
 /// The response to a Cmd, containing the query result.
@@ -79,8 +112,10 @@ pub enum CmdResponse {
     //
     /// Response to RegisterCmd::Create.
     CreateRegister(Result<()>),
-    /// Response to RegisterCmd::Edit.
+    /// Response to RegisterCmd::Edit and RegisterCmd::SetExpiry.
     EditRegister(Result<()>),
+    /// Response to Cmd::ReplicateRegister and Cmd::ReplicateRegisterLog.
+    ReplicateRegister(Result<()>),
 }
 
 impl std::fmt::Display for QueryResponse {
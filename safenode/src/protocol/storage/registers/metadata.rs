@@ -10,12 +10,14 @@ use serde::{Deserialize, Serialize};
 use std::fmt::{Debug, Display, Formatter, Result as FmtResult};
 
 /// An action on Register data type.
-#[derive(Clone, Debug, Copy, Eq, PartialEq)]
+#[derive(Clone, Debug, Copy, Eq, PartialEq, PartialOrd, Hash, Serialize, Deserialize)]
 pub enum Action {
     /// Read from the data.
     Read,
     /// Write to the data.
     Write,
+    /// Administer the data, e.g. manage its policy, which only its owner is allowed to.
+    Admin,
 }
 
 /// An entry in a Register (note that the `vec<u8>` is size limited: `MAX_REG_ENTRY_SIZE`)
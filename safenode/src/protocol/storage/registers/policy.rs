@@ -34,6 +34,8 @@ impl Permissions {
         match action {
             Action::Read => Some(true), // It's public data, so it's always allowed to read it.
             Action::Write => self.write,
+            // Only the owner can administer the data, which can't be granted to other users.
+            Action::Admin => Some(false),
         }
     }
 }
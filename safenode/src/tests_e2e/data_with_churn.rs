@@ -9,7 +9,7 @@
 use super::get_client;
 
 use crate::{
-    client::{Client, Error, Files},
+    client::{Client, Error as ClientError, Files},
     protocol::{
         storage::{ChunkAddress, RegisterAddress},
         NetworkAddress,
@@ -20,15 +20,21 @@ use safenode_proto::{safe_node_client::SafeNodeClient, NodeInfoRequest, RestartR
 
 use bytes::Bytes;
 use eyre::{bail, Result};
-use rand::{rngs::OsRng, Rng};
+use libp2p::PeerId;
+use rand::{rngs::OsRng, seq::IteratorRandom, Rng};
 use std::{
     collections::{BTreeMap, VecDeque},
     fmt,
+    future::Future,
     net::{IpAddr, Ipv4Addr, SocketAddr},
     path::Path,
-    sync::Arc,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc,
+    },
     time::{Duration, Instant},
 };
+use thiserror::Error;
 use tokio::{fs::remove_dir_all, sync::RwLock, time::sleep};
 use tonic::Request;
 use xor_name::XorName;
@@ -41,25 +47,71 @@ mod safenode_proto {
 
 const NODE_COUNT: u16 = 25;
 const CHURN_PERIOD_MILLIS: u64 = 30_000;
+// Distribution the delay between node restarts is drawn from, with its mean
+// being `CHURN_PERIOD_MILLIS`, so restarts cluster and gap as real node failures do.
+const CHURN_INTERVAL_DISTRIBUTION: ChurnDistribution = ChurnDistribution::Exponential;
+// How often a paused churn checks whether it's been resumed.
+const CHURN_PAUSE_CHECK_INTERVAL: Duration = Duration::from_millis(100);
 
 const REGISTER_CREATION_RATIO_TO_CHURN: u64 = 10;
+// Entry written to every Register upon creation, which shall be found when querying it.
+const REGISTER_MARKER_ENTRY: &[u8] = b"data_availability_during_churn marker";
 const CHUNK_CREATION_RATIO_TO_CHURN: u64 = 15;
 const CHUNKS_SIZE: usize = 1024;
 
 const CONTENT_QUERY_RATIO_TO_CHURN: u64 = 12;
 const MAX_NUM_OF_QUERY_ATTEMPTS: u8 = 5;
+// Delay before retrying a content which failed once, doubled after each further failure.
+const RETRY_BASE_DELAY_MILLIS: u64 = CHURN_PERIOD_MILLIS / 2;
+// How often the erred content is checked for any of it being due to be retried.
+const RETRY_CHECK_RATIO_TO_CHURN: u64 = 6;
+
+// Number of tracked content addresses probed for their replication level on each check
+const REPLICATION_CHECK_SAMPLE_SIZE: usize = 5;
+// Minimum number of nodes expected to hold each content; less than this is warned about
+const MIN_REPLICATION_LEVEL: usize = 3;
+
+// Maximum rate of the content created/stored which can't be retrieved for the test to pass,
+// e.g. 0.001 for soak testing against lossy networks. Every failure is still reported.
+const MAX_ACCEPTABLE_FAILURE_RATE: f64 = 0.0;
 
 // Total amount of time we run the checks for before reporting the outcome
 const TOTAL_TIME_OF_TEST: Duration = Duration::from_secs(60 * 60); // 1hr
 
 type ContentList = Arc<RwLock<VecDeque<NetworkAddress>>>;
 
+// Flag to pause the churning of nodes while it's set, e.g. to let queries stabilise while
+// inspecting the state of the network when debugging a failure, and to resume it once unset.
+type ChurnPause = Arc<AtomicBool>;
+
+/// Errors found when querying the content created/stored by the test.
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Error)]
+enum Error {
+    #[error("Client error: {0}")]
+    Client(#[from] ClientError),
+
+    #[error("Marker entry not found in Register, current entries: {0:?}")]
+    RegisterMarkerNotFound(Vec<Vec<u8>>),
+
+    #[error("Querying content of type {0:?} is not supported by this test")]
+    UnsupportedContentType(NetworkAddress),
+}
+
 struct ContentError {
     net_addr: NetworkAddress,
     attempts: u8,
+    last_attempt: Instant,
     last_err: Error,
 }
 
+impl ContentError {
+    // Whether enough time has passed since the last attempt for the content to be retried.
+    fn is_due_for_retry(&self, now: Instant) -> bool {
+        now.duration_since(self.last_attempt) >= retry_backoff(self.attempts)
+    }
+}
+
 impl fmt::Debug for ContentError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(
@@ -72,14 +124,66 @@ impl fmt::Debug for ContentError {
 
 type ContentErredList = Arc<RwLock<BTreeMap<NetworkAddress, ContentError>>>;
 
+/// Number of failures by the error they last hit, telling the chunks which couldn't
+/// be found apart from those which were found corrupted.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct FailuresTally {
+    chunks_not_found: usize,
+    chunks_corrupted: usize,
+    others: usize,
+}
+
+/// Type of distribution the churning intervals are sampled from.
+#[derive(Clone, Copy, Debug)]
+enum ChurnDistribution {
+    /// Intervals are uniformly distributed within `[0, 2 * mean]`.
+    Uniform,
+    /// Intervals are exponentially distributed with the given mean,
+    /// i.e. restarts follow a Poisson process.
+    Exponential,
+}
+
+/// Generator of the (jittered) intervals between churning events.
+#[derive(Clone, Copy, Debug)]
+struct ChurnIntervals {
+    mean: Duration,
+    distribution: ChurnDistribution,
+}
+
+impl Default for ChurnIntervals {
+    fn default() -> Self {
+        Self {
+            mean: Duration::from_millis(CHURN_PERIOD_MILLIS),
+            distribution: CHURN_INTERVAL_DISTRIBUTION,
+        }
+    }
+}
+
+impl ChurnIntervals {
+    // Draw the next interval from the configured distribution.
+    fn sample<R: Rng>(&self, rng: &mut R) -> Duration {
+        let mean = self.mean.as_secs_f64();
+        let secs = match self.distribution {
+            ChurnDistribution::Uniform => rng.gen_range(0.0..=2.0 * mean),
+            ChurnDistribution::Exponential => {
+                // Inverse transform sampling; `1 - u` lies in (0, 1], so `ln` is always finite.
+                let u: f64 = rng.gen();
+                -mean * (1.0 - u).ln()
+            }
+        };
+        Duration::from_secs_f64(secs)
+    }
+}
+
 #[tokio::test(flavor = "multi_thread")]
 async fn data_availability_during_churn() -> Result<()> {
     println!("Creating a client...");
     let client = get_client().await;
     println!("Client created with signing key: {:?}", client.signer_pk());
 
-    // Spawn a task to churn nodes
-    churn_nodes_task();
+    // Spawn a task to churn nodes, which can be paused by setting the flag, and resumed by unsetting it
+    let churn_paused = ChurnPause::default();
+    churn_nodes_task(churn_paused.clone());
 
     // Shared bucket where we keep track of content created/stored on the network
     let content = ContentList::default();
@@ -101,6 +205,10 @@ async fn data_availability_during_churn() -> Result<()> {
     // Spawn a task to randomly query/fetch the content we create/store
     query_content_task(client.clone(), content.clone(), content_erred.clone());
 
+    // Spawn a task to periodically probe how many nodes hold a sample of the content,
+    // warning about those which are close to be lost.
+    replication_level_task(client.clone(), content.clone());
+
     // Spawn a task to retry querying the content that failed, up to 'MAX_NUM_OF_QUERY_ATTEMPTS' times,
     // and mark them as failures if they effectivelly cannot be retrieved.
     retry_query_content_task(client.clone(), content_erred.clone(), failures.clone());
@@ -122,8 +230,26 @@ async fn data_availability_during_churn() -> Result<()> {
     println!();
 
     let failed = failures.read().await;
+    let total = content.read().await.len();
     if failed.len() > 0 {
-        bail!("{} failure/s in test: {:?}", failed.len(), failed.values());
+        println!(
+            "{} failure/s out of {total} content created/stored: {:?}",
+            failed.len(),
+            failed.values()
+        );
+        let tally = tally_failures(failed.values().map(|content_error| &content_error.last_err));
+        println!(
+            "Chunks not found: {}, chunks corrupted: {}, other failures: {}",
+            tally.chunks_not_found, tally.chunks_corrupted, tally.others
+        );
+    }
+    let successes = total.saturating_sub(failed.len());
+    if !is_failure_rate_acceptable(successes, failed.len(), MAX_ACCEPTABLE_FAILURE_RATE) {
+        bail!(
+            "{} failure/s in test, above the acceptable rate of {MAX_ACCEPTABLE_FAILURE_RATE}: {:?}",
+            failed.len(),
+            failed.values()
+        );
     }
 
     println!("Test passed after running for {:?}.", start_time.elapsed());
@@ -145,8 +271,13 @@ fn create_registers_task(client: Client, content: ContentList) {
             println!("Creating Register at {addr:?} in {delay:?}");
             sleep(delay).await;
 
-            match client.create_register(xorname, tag).await {
-                Ok(_) => content
+            let result = match client.create_register(xorname, tag).await {
+                Ok(mut register) => register.write(REGISTER_MARKER_ENTRY).await,
+                Err(err) => Err(err),
+            };
+
+            match result {
+                Ok(()) => content
                     .write()
                     .await
                     .push_back(NetworkAddress::RegisterAddress(addr)),
@@ -223,10 +354,14 @@ fn query_content_task(client: Client, content: ContentList, content_erred: Conte
                         .write()
                         .await
                         .entry(net_addr.clone())
-                        .and_modify(|curr| curr.attempts += 1)
+                        .and_modify(|curr| {
+                            curr.attempts += 1;
+                            curr.last_attempt = Instant::now();
+                        })
                         .or_insert(ContentError {
                             net_addr,
                             attempts: 1,
+                            last_attempt: Instant::now(),
                             last_err,
                         });
                 }
@@ -235,40 +370,144 @@ fn query_content_task(client: Client, content: ContentList, content_erred: Conte
     });
 }
 
-// Spawns a task which periodically picks up a random node, and restarts it to cause churn in the network.
-fn churn_nodes_task() {
-    let _handle = tokio::spawn(async {
-        let mut addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 12000);
+// Spawns a task which periodically probes the replication level of a random sample
+// of the content created/stored by other tasks.
+fn replication_level_task(client: Client, content: ContentList) {
+    let _handle = tokio::spawn(async move {
         let delay = Duration::from_millis(CHURN_PERIOD_MILLIS);
         loop {
-            // let's choose a random node to restart
-            let node_index = rand::thread_rng().gen_range(1..=NODE_COUNT);
-            addr.set_port(12000 + node_index);
-
-            println!("Restarting node through its RPC service at {addr} in {delay:?}");
             sleep(delay).await;
 
-            if let Err(err) = node_restart(addr).await {
-                println!("Failed to restart node with RPC endpoint {addr}: {err}");
+            let sample: Vec<NetworkAddress> = {
+                let content = content.read().await;
+                content
+                    .iter()
+                    .cloned()
+                    .choose_multiple(&mut rand::thread_rng(), REPLICATION_CHECK_SAMPLE_SIZE)
+            };
+
+            let mut replication = BTreeMap::new();
+            for net_addr in sample {
+                match client.probe_content_holders(&net_addr).await {
+                    Ok(holders) => {
+                        let _ = replication.insert(net_addr, holders);
+                    }
+                    Err(err) => println!("Failed to probe replication of {net_addr:?}: {err:?}"),
+                }
+            }
+
+            for (net_addr, holders) in under_replicated(&replication, MIN_REPLICATION_LEVEL) {
+                println!(
+                    "WARNING: content at {net_addr:?} is held by only {holders} node/s, \
+                    below the minimum of {MIN_REPLICATION_LEVEL}"
+                );
             }
         }
     });
 }
 
+// Returns the content, along with its replication level, held by less than `threshold` nodes.
+fn under_replicated(
+    replication: &BTreeMap<NetworkAddress, usize>,
+    threshold: usize,
+) -> Vec<(NetworkAddress, usize)> {
+    replication
+        .iter()
+        .filter(|(_, holders)| **holders < threshold)
+        .map(|(net_addr, holders)| (net_addr.clone(), *holders))
+        .collect()
+}
+
+// Returns whether the rate of failures over all the content queried doesn't exceed `max_rate`.
+// No failure at all is always acceptable, whereas with a `max_rate` of zero any failure is not.
+fn is_failure_rate_acceptable(successes: usize, failures: usize, max_rate: f64) -> bool {
+    if failures == 0 {
+        return true;
+    }
+    let rate = failures as f64 / (successes + failures) as f64;
+    rate <= max_rate
+}
+
+// Tallies the given failures' errors by their kind.
+fn tally_failures<'a>(errors: impl Iterator<Item = &'a Error>) -> FailuresTally {
+    let mut tally = FailuresTally::default();
+    for err in errors {
+        match err {
+            Error::Client(ClientError::ChunkNotFound(_)) => tally.chunks_not_found += 1,
+            Error::Client(ClientError::ChunkCorrupted(_)) => tally.chunks_corrupted += 1,
+            _ => tally.others += 1,
+        }
+    }
+    tally
+}
+
+// Spawns a task which periodically picks up a random node, and restarts it to cause churn in the network.
+fn churn_nodes_task(paused: ChurnPause) {
+    let _handle = tokio::spawn(churn_nodes(ChurnIntervals::default(), paused, node_restart));
+}
+
+// Restarts a random node, through the provided `restart` fn, at intervals drawn from the given
+// ones, forever. No node is restarted while the churn is `paused`, until it's resumed.
+async fn churn_nodes<F, Fut>(intervals: ChurnIntervals, paused: ChurnPause, restart: F)
+where
+    F: Fn(SocketAddr) -> Fut,
+    Fut: Future<Output = Result<()>>,
+{
+    let mut addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 12000);
+    loop {
+        // let's choose a random node to restart, and when to do it
+        let (node_index, delay) = {
+            let mut rng = rand::thread_rng();
+            (rng.gen_range(1..=NODE_COUNT), intervals.sample(&mut rng))
+        };
+        addr.set_port(12000 + node_index);
+
+        println!("Restarting node through its RPC service at {addr} in {delay:?}");
+        sleep(delay).await;
+
+        if paused.load(Ordering::SeqCst) {
+            println!("Churn paused, holding the restart of node at {addr} until resumed");
+            while paused.load(Ordering::SeqCst) {
+                sleep(CHURN_PAUSE_CHECK_INTERVAL).await;
+            }
+            println!("Churn resumed");
+        }
+
+        if let Err(err) = restart(addr).await {
+            println!("Failed to restart node with RPC endpoint {addr}: {err}");
+        }
+    }
+}
+
+// Delay to wait since the last failed attempt before retrying a content, backing off
+// exponentially with the number of attempts made so far.
+fn retry_backoff(attempts: u8) -> Duration {
+    let factor = 2u64.saturating_pow(u32::from(attempts.saturating_sub(1)));
+    Duration::from_millis(RETRY_BASE_DELAY_MILLIS.saturating_mul(factor))
+}
+
 // Checks (periodically) for any content that an error was reported either at the moment of its creation or
-// in a later query attempt.
+// in a later query attempt, retrying those whose backoff delay has elapsed.
 fn retry_query_content_task(
     client: Client,
     content_erred: ContentErredList,
     failures: ContentErredList,
 ) {
     let _handle = tokio::spawn(async move {
-        let delay = Duration::from_millis(2 * CHURN_PERIOD_MILLIS);
+        let delay = Duration::from_millis(CHURN_PERIOD_MILLIS / RETRY_CHECK_RATIO_TO_CHURN);
         loop {
             sleep(delay).await;
 
             // let's try to query from the bucket of those that erred upon creation/query
-            let erred = content_erred.write().await.pop_first();
+            let erred = {
+                let mut content_erred = content_erred.write().await;
+                let now = Instant::now();
+                let due = content_erred
+                    .iter()
+                    .find(|(_, content_error)| content_error.is_due_for_retry(now))
+                    .map(|(net_addr, _)| net_addr.clone());
+                due.and_then(|net_addr| content_erred.remove_entry(&net_addr))
+            };
 
             if let Some((net_addr, mut content_error)) = erred {
                 let attempts = content_error.attempts + 1;
@@ -279,6 +518,7 @@ fn retry_query_content_task(
                     // We only keep it to retry 'MAX_NUM_OF_QUERY_ATTEMPTS' times,
                     // otherwise report it effectivelly as failure.
                     content_error.attempts = attempts;
+                    content_error.last_attempt = Instant::now();
                     content_error.last_err = last_err;
 
                     if attempts == MAX_NUM_OF_QUERY_ATTEMPTS {
@@ -315,14 +555,165 @@ async fn node_restart(addr: SocketAddr) -> Result<()> {
 async fn query_content(client: &Client, net_addr: &NetworkAddress) -> Result<(), Error> {
     match net_addr {
         NetworkAddress::RegisterAddress(addr) => {
-            let _ = client.get_register(*addr.name(), addr.tag()).await?;
-            Ok(())
+            let register = client.get_register(*addr.name(), addr.tag()).await?;
+            let entries = register.read();
+            if entries
+                .iter()
+                .any(|(_, entry)| entry.as_slice() == REGISTER_MARKER_ENTRY)
+            {
+                Ok(())
+            } else {
+                Err(Error::RegisterMarkerNotFound(
+                    entries.into_iter().map(|(_, entry)| entry).collect(),
+                ))
+            }
         }
         NetworkAddress::ChunkAddress(addr) => {
             let file_api = Files::new(client.clone());
             let _ = file_api.read_bytes(*addr).await?;
             Ok(())
         }
-        _other => Ok(()), // we don't create/store any other type of content in this test yet
+        // We don't create/store any other type of content in this test yet. Listing the
+        // variants explicitly makes any new content type to be wired up here as well.
+        NetworkAddress::PeerId(_) | NetworkAddress::DbcAddress(_) => {
+            Err(Error::UnsupportedContentType(net_addr.clone()))
+        }
     }
 }
+
+#[tokio::test]
+async fn query_content_errors_on_unsupported_content_type() {
+    let client = get_client().await;
+    let net_addr = NetworkAddress::from_peer(PeerId::random());
+
+    let result = query_content(&client, &net_addr).await;
+    assert!(
+        matches!(result, Err(Error::UnsupportedContentType(ref addr)) if *addr == net_addr),
+        "Unexpected result: {result:?}"
+    );
+}
+
+#[test]
+fn churn_intervals_sampled_mean_is_close_to_configured() {
+    const NUM_OF_DRAWS: u32 = 10_000;
+    let mut rng = rand::thread_rng();
+    for distribution in [ChurnDistribution::Uniform, ChurnDistribution::Exponential] {
+        let intervals = ChurnIntervals {
+            mean: Duration::from_millis(CHURN_PERIOD_MILLIS),
+            distribution,
+        };
+
+        let total: Duration = (0..NUM_OF_DRAWS).map(|_| intervals.sample(&mut rng)).sum();
+        let sampled_mean = total.as_secs_f64() / f64::from(NUM_OF_DRAWS);
+        let expected_mean = intervals.mean.as_secs_f64();
+
+        // The standard deviation of the sampled mean is at most `mean / sqrt(NUM_OF_DRAWS)`,
+        // i.e. 1% of the mean, so a 5% tolerance makes this test practically deterministic.
+        assert!(
+            (sampled_mean - expected_mean).abs() < 0.05 * expected_mean,
+            "{distribution:?}: sampled mean {sampled_mean}s is too far from {expected_mean}s"
+        );
+    }
+}
+
+#[tokio::test]
+async fn no_node_is_restarted_while_churn_is_paused() {
+    let intervals = ChurnIntervals {
+        mean: Duration::from_millis(5),
+        distribution: ChurnDistribution::Uniform,
+    };
+    let paused = ChurnPause::default();
+    let restarts = Arc::new(AtomicUsize::new(0));
+    let restarts_requested = restarts.clone();
+    let churn = tokio::spawn(churn_nodes(intervals, paused.clone(), move |_addr| {
+        let _ = restarts_requested.fetch_add(1, Ordering::SeqCst);
+        async { Ok(()) }
+    }));
+
+    sleep(Duration::from_millis(200)).await;
+    assert!(restarts.load(Ordering::SeqCst) > 0);
+
+    // Let any restart requested right before pausing settle.
+    paused.store(true, Ordering::SeqCst);
+    sleep(Duration::from_millis(50)).await;
+    let restarts_when_paused = restarts.load(Ordering::SeqCst);
+    sleep(Duration::from_millis(500)).await;
+    assert_eq!(restarts.load(Ordering::SeqCst), restarts_when_paused);
+
+    paused.store(false, Ordering::SeqCst);
+    sleep(Duration::from_millis(500)).await;
+    assert!(restarts.load(Ordering::SeqCst) > restarts_when_paused);
+
+    churn.abort();
+}
+
+#[test]
+fn retry_backoff_increases_with_attempts() {
+    let delays: Vec<_> = (1..=MAX_NUM_OF_QUERY_ATTEMPTS).map(retry_backoff).collect();
+    assert_eq!(delays[0], Duration::from_millis(RETRY_BASE_DELAY_MILLIS));
+    assert!(
+        delays.windows(2).all(|pair| pair[0] < pair[1]),
+        "Retry delays are not monotonically increasing: {delays:?}"
+    );
+}
+
+#[test]
+fn under_replicated_content_is_reported() {
+    let addr = |tag| {
+        NetworkAddress::RegisterAddress(RegisterAddress {
+            name: XorName::default(),
+            tag,
+        })
+    };
+    let replication: BTreeMap<_, _> = [(addr(0), 0), (addr(1), 2), (addr(2), 3), (addr(3), 8)]
+        .into_iter()
+        .collect();
+
+    let reported = under_replicated(&replication, 3);
+    assert_eq!(reported, vec![(addr(0), 0), (addr(1), 2)]);
+
+    assert!(under_replicated(&replication, 0).is_empty());
+    assert_eq!(under_replicated(&replication, 9).len(), replication.len());
+}
+
+#[test]
+fn failure_rate_is_checked_against_threshold() {
+    // no failures always pass, even with the strictest threshold
+    assert!(is_failure_rate_acceptable(1000, 0, 0.0));
+    assert!(is_failure_rate_acceptable(0, 0, 0.0));
+
+    // any failure fails with the default threshold
+    assert!(!is_failure_rate_acceptable(
+        999_999,
+        1,
+        MAX_ACCEPTABLE_FAILURE_RATE
+    ));
+
+    // 1 failure out of 1000 is a 0.1% rate
+    assert!(is_failure_rate_acceptable(999, 1, 0.001));
+    assert!(is_failure_rate_acceptable(999, 1, 0.01));
+    assert!(!is_failure_rate_acceptable(999, 1, 0.0005));
+
+    // 2 failures out of 1000 exceed a 0.1% threshold
+    assert!(!is_failure_rate_acceptable(998, 2, 0.001));
+}
+
+#[test]
+fn failures_are_tallied_by_kind() {
+    let addr = ChunkAddress::new(XorName::default());
+    let errors = [
+        Error::Client(ClientError::ChunkNotFound(addr)),
+        Error::Client(ClientError::ChunkCorrupted(addr)),
+        Error::Client(ClientError::ChunkNotFound(addr)),
+        Error::RegisterMarkerNotFound(vec![]),
+    ];
+
+    assert_eq!(
+        tally_failures(errors.iter()),
+        FailuresTally {
+            chunks_not_found: 2,
+            chunks_corrupted: 1,
+            others: 1,
+        }
+    );
+}
@@ -7,21 +7,26 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 mod data_with_churn;
+mod node_rpc;
 
 use std::path::Path;
 
 use crate::{
-    client::Client,
+    client::{Client, Error as ClientError, Files, WalletClient},
     domain::{
         dbc_genesis::{get_tokens_from_faucet, send},
         wallet::{DepositWallet, LocalWallet, VerifyingClient, Wallet},
     },
+    network::CLOSE_GROUP_SIZE,
+    protocol::{storage::ChunkAddress, NetworkAddress},
 };
 
 use sn_dbc::Token;
 
 use assert_fs::TempDir;
+use bytes::Bytes;
 use eyre::Result;
+use rand::Rng;
 
 #[tokio::test(flavor = "multi_thread")]
 #[ignore = "This test is ignored because it is not stable until we have DBCs stored as records."]
@@ -70,6 +75,195 @@ async fn multiple_sequential_transfers_succeed() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test(flavor = "multi_thread")]
+#[ignore = "This test is ignored because it is not stable until we have DBCs stored as records."]
+async fn spent_dbc_is_reported_as_spent() -> Result<()> {
+    let wallet_dir = TempDir::new()?;
+    let balance = Token::from_nano(1_000_000);
+
+    let mut wallet = get_wallet(wallet_dir.path()).await;
+    let client = get_client().await;
+    let faucet_dbc = get_tokens_from_faucet(balance, wallet.address(), &client).await;
+    std::thread::sleep(std::time::Duration::from_secs(5));
+    client.verify(&faucet_dbc).await?;
+    assert!(!client.is_spent(&faucet_dbc.id()).await?);
+    wallet.deposit(vec![faucet_dbc.clone()]);
+
+    // The dbc from the faucet is spent to send tokens to another wallet.
+    let recipient_dir = TempDir::new()?;
+    let recipient = get_wallet(recipient_dir.path()).await;
+    let sent_dbc = send(
+        wallet,
+        Token::from_nano(balance.as_nano() / 2),
+        recipient.address(),
+        &client,
+    )
+    .await;
+    std::thread::sleep(std::time::Duration::from_secs(5));
+
+    assert!(client.is_spent(&faucet_dbc.id()).await?);
+    assert!(!client.is_spent(&sent_dbc.id()).await?);
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn spend_is_confirmed_in_network() -> Result<()> {
+    let wallet_dir = TempDir::new()?;
+    let balance = Token::from_nano(1_000_000);
+
+    let mut wallet = get_wallet(wallet_dir.path()).await;
+    let client = get_client().await;
+    let faucet_dbc = get_tokens_from_faucet(balance, wallet.address(), &client).await;
+    wallet.deposit(vec![faucet_dbc]);
+
+    let recipient_dir = TempDir::new()?;
+    let recipient = get_wallet(recipient_dir.path()).await;
+    let mut wallet_client = WalletClient::new(client.clone(), wallet);
+    let confirmation = wallet_client
+        .send_and_confirm(
+            Token::from_nano(balance.as_nano() / 2),
+            recipient.address(),
+            std::time::Duration::from_secs(30),
+        )
+        .await?;
+
+    assert!(confirmation.confirmed);
+    // The spends having been confirmed, the recipient can verify the dbc straight away.
+    client.verify(&confirmation.dbc).await?;
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn file_is_read_back_from_its_data_map() -> Result<()> {
+    let files = Files::new(get_client().await);
+
+    // A small file is stored as a single chunk, whereas a large one is self-encrypted.
+    for size in [512, 1024 * 1024] {
+        let bytes: Bytes = rand::thread_rng()
+            .sample_iter(&rand::distributions::Standard)
+            .take(size)
+            .collect::<Vec<u8>>()
+            .into();
+
+        let data_map = files.upload_and_verify(bytes.clone()).await?;
+        assert_eq!(data_map.size(), size);
+
+        let read_bytes = files.read_from_datamap(&data_map).await?;
+        assert_eq!(read_bytes, bytes);
+    }
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn file_is_read_back_regardless_of_its_chunk_size() -> Result<()> {
+    let client = get_client().await;
+    let bytes: Bytes = rand::thread_rng()
+        .sample_iter(&rand::distributions::Standard)
+        .take(64 * 1024)
+        .collect::<Vec<u8>>()
+        .into();
+
+    let mut num_of_chunks = vec![];
+    for chunk_size in [1024, 8 * 1024] {
+        let files = Files::with_chunk_size(client.clone(), chunk_size)?;
+        let data_map = files.upload_and_verify(bytes.clone()).await?;
+        num_of_chunks.push(data_map.chunks().len());
+
+        // It's read back the same even by a reader using the default chunk size.
+        let read_bytes = Files::new(client.clone())
+            .read_from_datamap(&data_map)
+            .await?;
+        assert_eq!(read_bytes, bytes);
+        let read_range = files
+            .read_from(
+                *data_map
+                    .address()
+                    .ok_or_else(|| eyre::eyre!("No address"))?,
+                5000,
+                3000,
+            )
+            .await?;
+        assert_eq!(read_range, bytes.slice(5000..8000));
+    }
+
+    // The smaller the chunks, the more of them the file is stored as.
+    assert!(num_of_chunks[0] > num_of_chunks[1]);
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn file_range_is_read_from_its_data_map() -> Result<()> {
+    let files = Files::new(get_client().await);
+    // Self-encrypted into three chunks of 20_000 bytes each.
+    let size = 60_000;
+    let bytes: Bytes = rand::thread_rng()
+        .sample_iter(&rand::distributions::Standard)
+        .take(size)
+        .collect::<Vec<u8>>()
+        .into();
+    let data_map = files.upload_and_verify(bytes.clone()).await?;
+
+    // A range in the middle of the file, spanning the first and second chunks.
+    let read_range = files
+        .read_range_from_datamap(&data_map, 19_000, 2_000)
+        .await?;
+    assert_eq!(read_range, bytes.slice(19_000..21_000));
+
+    // A range going beyond the end of the file is rejected.
+    let result = files
+        .read_range_from_datamap(&data_map, size - 100, 200)
+        .await;
+    assert!(
+        matches!(result, Err(ClientError::RangeOutOfBounds { end, .. }) if end == size + 100),
+        "Unexpected result: {result:?}"
+    );
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn chunk_is_fetched_from_the_peers_holding_it() -> Result<()> {
+    let client = get_client().await;
+    let files = Files::new(client.clone());
+    // A small file is stored as a single chunk.
+    let bytes = Bytes::from(vec![7; 512]);
+    let data_map = files.upload_and_verify(bytes.clone()).await?;
+    let address = *data_map
+        .address()
+        .ok_or_else(|| eyre::eyre!("No address"))?;
+
+    let dst = NetworkAddress::from_chunk_address(address);
+    let peers = client.closest_peers(dst, CLOSE_GROUP_SIZE).await?;
+    let mut holders = 0;
+    for peer in &peers {
+        match client.get_chunk_from(address, *peer).await {
+            Ok(chunk) => {
+                assert_eq!(chunk.address(), &address);
+                holders += 1;
+            }
+            Err(ClientError::ChunkNotFound(addr)) => assert_eq!(addr, address),
+            Err(err) => eyre::bail!("Unexpected error fetching chunk from {peer:?}: {err:?}"),
+        }
+    }
+    assert!(holders > 0, "None of {peers:?} returned the chunk");
+
+    // None of them holds a chunk which was never stored.
+    let missing = ChunkAddress::new(rand::random());
+    for peer in peers {
+        let result = client.get_chunk_from(missing, peer).await;
+        assert!(
+            matches!(result, Err(ClientError::ChunkNotFound(addr)) if addr == missing),
+            "Unexpected result from {peer:?}: {result:?}"
+        );
+    }
+
+    Ok(())
+}
+
 async fn get_client() -> Client {
     let secret_key = bls::SecretKey::random();
     Client::new(secret_key, None)
@@ -0,0 +1,82 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use safenode_proto::{
+    safe_node_client::SafeNodeClient, NodeInfoRequest, NodeInfoResponse, RestartRequest,
+};
+
+use eyre::{bail, Result};
+use std::{
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    time::Duration,
+};
+use tokio::time::sleep;
+use tonic::{transport::Channel, Request};
+
+// this includes code generated from .proto files
+#[allow(unused_qualifications, unreachable_pub, clippy::unwrap_used)]
+mod safenode_proto {
+    tonic::include_proto!("safenode_proto");
+}
+
+// RPC port of the node of the local testnet the test is run against.
+const NODE_RPC_PORT: u16 = 12001;
+// Maximum time to wait for the node to be back up after being restarted.
+const RESTART_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[tokio::test(flavor = "multi_thread")]
+async fn node_uptime_resets_after_restart() -> Result<()> {
+    let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), NODE_RPC_PORT);
+    let endpoint = format!("https://{addr}");
+    let mut client = SafeNodeClient::connect(endpoint.clone()).await?;
+
+    let first = node_info(&mut client).await?;
+    sleep(Duration::from_secs(2)).await;
+    let second = node_info(&mut client).await?;
+    assert!(
+        second.uptime_secs > first.uptime_secs,
+        "Uptime didn't increase: {} then {}",
+        first.uptime_secs,
+        second.uptime_secs
+    );
+    assert!(
+        second.peer_count > 0,
+        "Node has no peers in its routing table"
+    );
+
+    let _response = client
+        .restart(Request::new(RestartRequest { delay_millis: 0 }))
+        .await?;
+
+    // The node's RPC service goes down while restarting, so keep retrying until it's back up.
+    let mut waited = Duration::ZERO;
+    let restarted = loop {
+        sleep(Duration::from_secs(1)).await;
+        waited += Duration::from_secs(1);
+        let info = match SafeNodeClient::connect(endpoint.clone()).await {
+            Ok(mut client) => node_info(&mut client).await.ok(),
+            Err(_) => None,
+        };
+        match info {
+            Some(info) if info.uptime_secs < second.uptime_secs => break info,
+            _ if waited >= RESTART_TIMEOUT => bail!("Node wasn't restarted within {waited:?}"),
+            _ => continue,
+        }
+    };
+    println!(
+        "Node restarted, up for {}s with {} peers",
+        restarted.uptime_secs, restarted.peer_count
+    );
+
+    Ok(())
+}
+
+async fn node_info(client: &mut SafeNodeClient<Channel>) -> Result<NodeInfoResponse> {
+    let response = client.node_info(Request::new(NodeInfoRequest {})).await?;
+    Ok(response.into_inner())
+}